@@ -63,6 +63,8 @@ pub struct Config {
     pub s3_endpoint: String,
     pub s3_access_key: String,
     pub s3_secret_key: String,
+    /// Trading calendars file replacing the built-in one
+    pub trading_calendars_path: Option<String>,
     pub environment: Environment,
 }
 
@@ -153,6 +155,8 @@ impl Config {
             return Err(ConfigError::MissingEnv("S3_SECRET_KEY".to_string()));
         };
 
+        let trading_calendars_path = env::var("TRADING_CALENDARS_PATH").ok();
+
         Ok(Config {
             database_url,
             jwt_private_key_file,
@@ -166,6 +170,7 @@ impl Config {
             s3_endpoint,
             s3_access_key,
             s3_secret_key,
+            trading_calendars_path,
             environment,
         })
     }
@@ -3,6 +3,9 @@ use tokio::net::TcpListener;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use api::{create_router, AppState, Config};
+use chrono::Utc;
+use db::repositories::CompanyRepository;
+use domain::markets::calendar::TradingCalendars;
 // mod auth; // Moved to lib.rs
 // mod config;
 // mod error;
@@ -30,14 +33,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let config = Config::from_env()?;
-    let config = Arc::new(config);
+    let config = Arc::new(Config::from_env()?);
 
     tracing::info!("Starting server in {} mode", config.environment);
 
     // Initialize AppState
     let state = AppState::new(config.clone()).await?;
 
+    // The calendars file (or the built-in one), with the timezone and
+    // schedule of each row in the exchanges table applied over it
+    let mut calendars = match &config.trading_calendars_path {
+        Some(path) => TradingCalendars::from_path(path)?,
+        None => TradingCalendars::builtin(),
+    };
+    for exchange in CompanyRepository::new(state.db.clone())
+        .list_exchanges()
+        .await?
+    {
+        calendars = calendars.with_exchange(
+            &exchange.code,
+            &exchange.timezone,
+            exchange.trading_days.as_ref(),
+        )?;
+    }
+    if let Err(e) = calendars.check_coverage(Utc::now().date_naive()) {
+        tracing::error!("{}; sessions in that year are guessed from weekdays", e);
+    }
+    calendars.install()?;
+
     // Create Router
     let app = create_router(state);
 
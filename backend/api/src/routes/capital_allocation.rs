@@ -0,0 +1,131 @@
+use crate::routes::companies::load_metric_history;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::NaiveDate;
+use db::repositories::CompanyRepository;
+use domain::metrics::capital_allocation::{
+    market_cap, trailing_allocations, CapitalAllocation, ShareholderYield, TTM_QUARTERS,
+};
+use domain::periods::PeriodType;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+// DTOs for API Documentation
+
+#[derive(Deserialize, IntoParams)]
+pub struct CapitalAllocationQueryParams {
+    /// "annual" (default) or "quarterly"
+    #[serde(default = "default_period_type")]
+    pub period_type: String,
+    #[serde(default = "default_period_count")]
+    pub period_count: usize,
+}
+
+fn default_period_type() -> String {
+    "annual".to_string()
+}
+
+fn default_period_count() -> usize {
+    5
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CapitalAllocationResponse {
+    pub company_id: Uuid,
+    pub currency: String,
+    pub period_type: String,
+    /// Oldest first; periods without a cash flow statement are left out
+    pub periods: Vec<CapitalAllocationPeriod>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CapitalAllocationPeriod {
+    pub allocation: CapitalAllocation,
+    /// Date of the close market cap is taken at; null when no price is stored
+    pub price_date: Option<NaiveDate>,
+    /// Over the trailing four quarters for quarterly periods; null yields
+    /// when any of them is missing
+    pub shareholder_yield: ShareholderYield,
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/{id}/capital-allocation",
+    params(
+        ("id" = Uuid, Path, description = "Company ID"),
+        CapitalAllocationQueryParams
+    ),
+    responses(
+        (status = 200, description = "Uses of operating cash flow and shareholder yield per period", body = CapitalAllocationResponse),
+        (status = 404, description = "Company not found")
+    ),
+    tag = "companies"
+)]
+pub async fn get_capital_allocation(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<CapitalAllocationQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let repo = CompanyRepository::new(state.db.clone());
+    let internal = |e: db::DbError| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+
+    let company = repo
+        .find_by_id(id)
+        .await
+        .map_err(internal)?
+        .ok_or((StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+
+    let is_quarterly = params.period_type.eq_ignore_ascii_case("quarterly");
+    let period_type = if is_quarterly {
+        PeriodType::Quarterly
+    } else {
+        PeriodType::Annual
+    };
+
+    // Quarterly yields are trailing-twelve-month, so the oldest displayed
+    // quarter needs the three before it
+    let lookback = if is_quarterly { TTM_QUARTERS - 1 } else { 0 };
+    let history = load_metric_history(
+        &repo,
+        &company,
+        is_quarterly,
+        params.period_count + lookback,
+    )
+    .await
+    .map_err(internal)?;
+
+    // Market cap at each period end, from the close of the exchange's last
+    // session on or before it
+    let display_start = history.incomes.len().saturating_sub(params.period_count);
+    let periods = (display_start..history.incomes.len())
+        .filter_map(|i| {
+            let allocation = CapitalAllocation::from_cash_flow(history.cash_flows[i].as_ref()?);
+            let price = history.prices[i].as_ref();
+            let cap = market_cap(history.balances[i].as_ref(), price);
+            let shareholder_yield = trailing_allocations(&history.cash_flows, i, period_type)
+                .map(|window| ShareholderYield::over_periods(&window, cap))
+                .unwrap_or_else(|| ShareholderYield::over_periods(&[], cap));
+
+            Some(CapitalAllocationPeriod {
+                allocation,
+                price_date: price.map(|p| p.date),
+                shareholder_yield,
+            })
+        })
+        .collect();
+
+    Ok(Json(CapitalAllocationResponse {
+        company_id: id,
+        currency: company.currency.unwrap_or_else(|| "USD".to_string()),
+        period_type: if is_quarterly { "quarterly" } else { "annual" }.to_string(),
+        periods,
+    }))
+}
@@ -1,3 +1,5 @@
+use crate::auth::jwt::Claims;
+use crate::routes::jobs::JobStatusResponse;
 use crate::state::AppState;
 use axum::{
     body::Body,
@@ -7,25 +9,60 @@ use axum::{
     routing::get,
     Json, Router,
 };
+use bigdecimal::ToPrimitive;
 use bytes::Bytes;
-use chrono::Datelike;
 use chrono::{DateTime, NaiveDate, Utc};
-use db::repositories::{CompanyRepository, CreateDocumentParams, DocumentRepository};
+use db::models::background_job::{COMPANY_BACKFILL_JOB, COMPANY_BACKFILL_STEPS};
+use db::repositories::{
+    BackgroundJobRepository, CompanyFilters, CompanyInsert, CompanyRepository,
+    CreateDocumentParams, CustomFormulaRepository, DataEventRepository, DocumentRepository,
+    Pagination, PeerGroup,
+};
+use db::DbError;
 use db::PgPool;
-use domain::metrics::calculator::{MetricsCalculator, ValuationMetrics};
-use domain::periods::{PeriodType, PeriodWindowGenerator};
+use domain::error::AppError;
+use domain::markets::calendar::TradingCalendars;
+use domain::metrics::calculator::MetricsCalculator;
+use domain::metrics::formula::Formula;
+use domain::metrics::registry::{
+    find_metric, HeatMapDirection, MetricInputs, MetricSection, METRIC_REGISTRY,
+};
+use domain::metrics::MetricValue;
+use domain::periods::calendarize::{calendar_periods, Calendarizer};
+use domain::periods::{
+    align_statements, find_period_match, is_prior_year_period, FiscalCalendar, FiscalPeriod,
+    PeriodType, PeriodWindowGenerator, PERIOD_MATCH_TOLERANCE_DAYS,
+};
 use multer::Multipart;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx;
+use std::collections::HashMap;
 use std::time::Duration;
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 pub fn companies_router() -> Router<AppState> {
     Router::new()
+        .route("/", get(list_companies).post(create_company))
+        .route("/search", get(search_companies))
+        .route("/compare", get(super::compare::compare_companies))
         .route("/:id", get(get_company_details))
         .route("/:id/metrics", get(get_company_metrics))
+        .route("/:id/valuation/dcf", get(super::valuation::get_company_dcf))
+        .route(
+            "/:id/capital-allocation",
+            get(super::capital_allocation::get_capital_allocation),
+        )
+        .route(
+            "/:id/price-analytics",
+            get(super::price_analytics::get_price_analytics),
+        )
+        .route("/:id/prices", get(super::prices::get_price_history))
+        .route(
+            "/:id/derived-metrics",
+            get(super::derived_metrics::get_derived_metric_series),
+        )
         .route(
             "/:id/documents",
             get(get_company_documents).post(upload_company_document),
@@ -36,6 +73,285 @@ pub fn companies_router() -> Router<AppState> {
         )
         .route("/:id/verdict", get(get_verdict).put(update_verdict))
         .route("/:id/verdict/history", get(get_verdict_history))
+        .route("/:id/data-events", get(get_company_data_events))
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CompanyListQueryParams {
+    pub exchange: Option<String>,
+    pub country: Option<String>,
+    pub sector_id: Option<Uuid>,
+    pub is_active: Option<bool>,
+    #[serde(default = "default_company_page_size")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_company_page_size() -> i64 {
+    50
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CompanySearchQueryParams {
+    /// Ticker prefix or (part of) a company name
+    pub q: String,
+    #[serde(default = "default_search_limit")]
+    pub limit: i32,
+}
+
+fn default_search_limit() -> i32 {
+    10
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompanySummaryOut {
+    pub id: Uuid,
+    pub symbol: String,
+    pub name: String,
+    pub exchange: String,
+    pub industry: Option<String>,
+    pub country: Option<String>,
+    pub sector_id: Option<Uuid>,
+    pub market_cap: Option<f64>,
+    pub market_cap_formatted: String,
+    pub currency: String,
+    pub is_active: bool,
+}
+
+impl From<db::models::Company> for CompanySummaryOut {
+    fn from(company: db::models::Company) -> Self {
+        let market_cap = company.market_cap.map(|mc| mc as f64);
+        Self {
+            id: company.id,
+            market_cap_formatted: format_market_cap(market_cap, company.currency.as_deref()),
+            symbol: company.symbol,
+            name: company.name,
+            exchange: company.exchange,
+            industry: company.industry,
+            country: company.country,
+            sector_id: company.sector_id,
+            market_cap,
+            currency: company.currency.unwrap_or_else(|| "USD".to_string()),
+            is_active: company.is_active,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompanyListResponse {
+    pub companies: Vec<CompanySummaryOut>,
+    /// Companies matching the filters across all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompanySearchResponse {
+    pub query: String,
+    /// Best matches first
+    pub results: Vec<CompanySummaryOut>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies",
+    params(CompanyListQueryParams),
+    responses(
+        (status = 200, description = "Companies matching the filters, largest first", body = CompanyListResponse)
+    ),
+    tag = "companies"
+)]
+pub async fn list_companies(
+    State(state): State<AppState>,
+    Query(params): Query<CompanyListQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let repo = CompanyRepository::new(state.db.clone());
+    let filters = CompanyFilters {
+        exchange: params.exchange,
+        country: params.country,
+        sector_id: params.sector_id,
+        is_active: params.is_active,
+    };
+    let pagination = Pagination {
+        limit: params.limit.clamp(1, 200),
+        offset: params.offset.max(0),
+    };
+
+    let total = repo
+        .count(&filters)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let companies = repo
+        .list(filters, pagination.clone())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(CompanyListResponse {
+        companies: companies.into_iter().map(CompanySummaryOut::from).collect(),
+        total,
+        limit: pagination.limit,
+        offset: pagination.offset,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/search",
+    params(CompanySearchQueryParams),
+    responses(
+        (status = 200, description = "Companies by ticker prefix, then name match", body = CompanySearchResponse),
+        (status = 400, description = "Empty query")
+    ),
+    tag = "companies"
+)]
+pub async fn search_companies(
+    State(state): State<AppState>,
+    Query(params): Query<CompanySearchQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let query = params.q.trim();
+    if query.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Search query must not be empty".to_string(),
+        ));
+    }
+
+    let companies = CompanyRepository::new(state.db.clone())
+        .search(query, params.limit.clamp(1, 50))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(CompanySearchResponse {
+        query: query.to_string(),
+        results: companies.into_iter().map(CompanySummaryOut::from).collect(),
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateCompanyRequest {
+    pub symbol: String,
+    /// Exchange code, e.g. "NASDAQ"
+    pub exchange: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompanyOnboardingResponse {
+    pub company: CompanySummaryOut,
+    /// Backfill of statements, prices and documents; poll `/jobs/{id}` for progress
+    pub backfill_job: JobStatusResponse,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/companies",
+    request_body = CreateCompanyRequest,
+    responses(
+        (status = 202, description = "Company created and its history backfill queued", body = CompanyOnboardingResponse),
+        (status = 400, description = "Unknown exchange or symbol listed elsewhere"),
+        (status = 404, description = "Symbol not known to the data provider"),
+        (status = 409, description = "Company already exists"),
+        (status = 502, description = "Data provider unavailable")
+    ),
+    tag = "companies"
+)]
+pub async fn create_company(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateCompanyRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let symbol = payload.symbol.trim().to_uppercase();
+    let exchange = payload.exchange.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Symbol is required".to_string()));
+    }
+
+    let repo = CompanyRepository::new(state.db.clone());
+    if !repo
+        .exchange_exists(&exchange)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown exchange '{}'", exchange),
+        ));
+    }
+    let existing = repo
+        .find_by_symbol(&symbol, &exchange)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if existing.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("{} on {} already exists", symbol, exchange),
+        ));
+    }
+
+    let overview = state
+        .market_data
+        .get_company_overview(&symbol)
+        .await
+        .map_err(|e| match e {
+            AppError::NotFound { .. } => (
+                StatusCode::NOT_FOUND,
+                format!("Symbol {} not found", symbol),
+            ),
+            AppError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, e.to_string()),
+            other => (StatusCode::BAD_GATEWAY, other.to_string()),
+        })?;
+    if let Some(listed_on) = overview.exchange.as_deref() {
+        if !listed_on.eq_ignore_ascii_case(&exchange) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("{} is listed on {}, not {}", symbol, listed_on, exchange),
+            ));
+        }
+    }
+
+    let company = repo
+        .create(CompanyInsert {
+            symbol,
+            exchange,
+            name: overview.name,
+            sector: overview.sector,
+            industry: overview.industry,
+            country: overview.country,
+            market_cap: overview.market_capitalization,
+            currency: overview.currency,
+            fiscal_year_end_month: overview
+                .fiscal_year_end
+                .and_then(|month| month.parse::<chrono::Month>().ok())
+                .map(|month| month.number_from_month() as i32),
+            description: overview.description,
+            cik: overview.cik,
+            address: overview.address,
+            latest_quarter: overview.latest_quarter,
+            shares_outstanding: overview.shares_outstanding,
+        })
+        .await
+        .map_err(|e| match e {
+            // Lost a race with a concurrent request for the same listing
+            DbError::DuplicateError(_) => (StatusCode::CONFLICT, "Company already exists".into()),
+            other => (StatusCode::INTERNAL_SERVER_ERROR, other.to_string()),
+        })?;
+
+    let job = BackgroundJobRepository::new(state.db.clone())
+        .enqueue(
+            COMPANY_BACKFILL_JOB,
+            Some(company.id),
+            &COMPANY_BACKFILL_STEPS,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(CompanyOnboardingResponse {
+            company: CompanySummaryOut::from(company),
+            backfill_job: JobStatusResponse::from(job),
+        }),
+    ))
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -49,6 +365,8 @@ pub struct CompanyDetailsResponse {
     pub market_cap_formatted: String,
     pub currency: String,
     pub fiscal_year_end_month: i32,
+    /// Fiscal year end rule, e.g. `month_end_dec` or `last_sat_jan`
+    pub fiscal_calendar: String,
     pub is_active: bool,
     pub last_updated: DateTime<Utc>,
 }
@@ -89,6 +407,11 @@ pub async fn get_company_details(
             market_cap_formatted,
             currency: company.currency.unwrap_or_else(|| "USD".to_string()),
             fiscal_year_end_month: company.fiscal_year_end_month.unwrap_or(0),
+            fiscal_calendar: FiscalCalendar::for_company(
+                company.fiscal_calendar.as_deref(),
+                company.fiscal_year_end_month,
+            )
+            .to_string(),
             is_active: company.is_active,
             last_updated: company.updated_at,
         };
@@ -105,6 +428,14 @@ pub struct MetricsQueryParams {
     pub period_type: String,
     #[serde(default = "default_period_count")]
     pub period_count: usize,
+    /// Rank the latest period against peers: "industry" or "sector"
+    pub peer_group: Option<String>,
+    /// Restate values on calendar quarters/years instead of fiscal periods.
+    /// Peer ranking is calendarized unless this is explicitly false.
+    pub calendarized: Option<bool>,
+    /// Reconstruct the view from the statements as they were known at the
+    /// end of this date. Not combinable with `peer_group`.
+    pub as_of: Option<NaiveDate>,
 }
 
 fn default_period_type() -> String {
@@ -119,8 +450,41 @@ fn default_period_count() -> usize {
 pub struct MetricsResponse {
     pub company_id: Uuid,
     pub period_type: String,
+    /// Whether periods are calendar quarters/years rather than fiscal ones
+    pub calendarized: bool,
+    /// Date the statements were read as of; null for the current view
+    pub as_of: Option<NaiveDate>,
     pub periods: Vec<String>, // period labels
+    /// Fiscal window behind each label and the statement aligned to it
+    pub period_details: Vec<PeriodOut>,
     pub sections: MetricsSections,
+    pub peer_group: Option<PeerGroupOut>,
+    /// The requesting user's saved formulas, evaluated per period
+    pub custom_metrics: Vec<MetricRow>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PeriodOut {
+    pub label: String,
+    pub period_start_date: NaiveDate,
+    pub period_end_date: NaiveDate,
+    /// Period end of the reporting statement; null when the period is a gap
+    pub statement_date: Option<NaiveDate>,
+}
+
+/// Peer group the latest-period percentiles were ranked against
+#[derive(Serialize, ToSchema)]
+pub struct PeerGroupOut {
+    pub basis: String,
+    pub name: String,
+    pub company_count: usize,
+    /// Whether peers were compared on the same calendar period rather than
+    /// each company's latest fiscal period
+    pub calendarized: bool,
+    /// Period end the ranking is for: the calendar period when calendarized,
+    /// otherwise the company's latest fiscal period. Null when the company
+    /// has no statements.
+    pub period_end_date: Option<NaiveDate>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -144,6 +508,8 @@ pub struct MetricValueOut {
     pub value: Option<f64>,
     pub formatted: String,
     pub heat_map_quartile: Option<i32>,
+    /// Percentile rank (0-100) within the requested peer group, latest period only
+    pub peer_percentile: Option<f64>,
 }
 
 #[utoipa::path(
@@ -155,17 +521,28 @@ pub struct MetricValueOut {
     ),
     responses(
         (status = 200, description = "Company metrics", body = MetricsResponse),
+        (status = 400, description = "Invalid peer group, company without that peer group, or peer group with as_of"),
         (status = 404, description = "Company not found")
     ),
     tag = "companies"
 )]
 pub async fn get_company_metrics(
     State(state): State<AppState>,
+    claims: Claims,
     Path(id): Path<Uuid>,
     Query(params): Query<MetricsQueryParams>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let repo = CompanyRepository::new(state.db.clone());
 
+    // Peer metrics are only stored for the current view, so ranking a past
+    // view against them would leak later data into it
+    if params.as_of.is_some() && params.peer_group.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "peer_group cannot be combined with as_of".to_string(),
+        ));
+    }
+
     // 1. Fetch company
     let company = repo
         .find_by_id(id)
@@ -183,239 +560,884 @@ pub async fn get_company_metrics(
         PeriodType::Annual
     };
 
-    // 3. Fetch financial data
-    // Fetch a bit more than requested to have prior year data for YoY calculations
-    // If quarterly, we need 4 quarters back for YoY
-    // If annual, we need 1 year back for YoY
-    let limit = params.period_count + if is_quarterly { 4 } else { 1 };
-
-    let db_incomes = repo
-        .get_income_statements(id, db_period_type, limit as i32)
+    // 3. Fetch financial data, restated on calendar periods if requested and
+    // read as of the end of the requested date
+    let repo = match params.as_of {
+        Some(date) => repo.as_of(
+            date.and_hms_micro_opt(23, 59, 59, 999_999)
+                .expect("valid time")
+                .and_utc(),
+        ),
+        None => repo,
+    };
+    let calendarized = params.calendarized == Some(true);
+    let (fiscal_calendar, fiscal_year_end_month) = repo
+        .fiscal_calendar_of(&company)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let calendar = FiscalCalendar::for_company(fiscal_calendar.as_deref(), fiscal_year_end_month);
+    let (periods, history) = if calendarized {
+        load_calendarized_history(&repo, &company, calendar, is_quarterly, params.period_count)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        let history = load_metric_history(&repo, &company, is_quarterly, params.period_count)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let db_balances = repo
-        .get_balance_sheets(id, db_period_type, limit as i32)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        // 4. Period Window Generation, anchored on the latest statement so a
+        // period that has not been reported yet doesn't show as a gap. Each
+        // window is then aligned to the statement that reports it; labels,
+        // values and the document grid all follow this alignment.
+        let generator = PeriodWindowGenerator::with_calendar(calendar);
+        let as_of = history
+            .incomes
+            .last()
+            .map(|i| i.period_end_date - chrono::Duration::days(PERIOD_MATCH_TOLERANCE_DAYS))
+            .unwrap_or_else(|| params.as_of.unwrap_or_else(|| Utc::now().date_naive()));
+        let mut periods =
+            generator.generate_periods(params.period_count, domain_period_type, as_of);
+        periods.reverse();
+        (periods, history)
+    };
+    let statement_dates: Vec<NaiveDate> =
+        history.incomes.iter().map(|i| i.period_end_date).collect();
+    let alignment = align_statements(&periods, &statement_dates);
+    let period_labels: Vec<String> = periods.iter().map(|p| p.display_label.clone()).collect();
+
+    let currency = company.currency.as_deref().unwrap_or("$");
+
+    // 5. Calculate Metrics
+    let inputs = history.inputs(currency);
+
+    // 6. Format Response
+    let mut sections = build_sections(&alignment, &period_labels, &inputs);
 
-    let db_cashflows = repo
-        .get_cash_flow_statements(id, db_period_type, limit as i32)
+    // 7. User-defined formulas. A stored formula that no longer parses (e.g. it
+    // references a retired metric) renders as N/A rather than failing the page.
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+    let formulas = CustomFormulaRepository::new(state.db.clone())
+        .list_by_user(user_id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let custom_metrics = formulas
+        .iter()
+        .map(|f| {
+            let values = match Formula::parse(&f.expression) {
+                Ok(formula) => formula.evaluate(&inputs),
+                Err(_) => Vec::new(),
+            };
+            MetricRow {
+                metric_name: f.name.clone(),
+                display_name: f.name.clone(),
+                values: aligned_values(&alignment, &period_labels, &values),
+                heat_map_enabled: false,
+            }
+        })
+        .collect();
 
-    // 4. Period Window Generation
-    let generator = PeriodWindowGenerator::new(company.fiscal_year_end_month.unwrap_or(12) as u32);
-    let periods = generator.generate_periods(
-        params.period_count,
-        domain_period_type,
-        Utc::now().date_naive(),
-    );
-    let period_labels: Vec<String> = periods.iter().map(|p| p.display_label.clone()).collect();
-
-    // 5. Map DB models to Domain models for calculations
-    // We need to reverse the db results because they are likely ordered by date DESC
-    let mut db_incomes = db_incomes;
-    db_incomes.sort_by_key(|i| i.period_end_date);
+    // 8. Peer-relative ranking of the latest period
+    let no_peers = |basis: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Company has no {} to rank against peers in", basis),
+        )
+    };
+    let peer_group = match params.peer_group.as_deref() {
+        None => None,
+        Some("industry") => Some(PeerGroup::Industry(
+            company
+                .industry
+                .clone()
+                .ok_or_else(|| no_peers("industry"))?,
+        )),
+        Some("sector") => Some(PeerGroup::Sector(
+            company.sector_id.ok_or_else(|| no_peers("sector"))?,
+        )),
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Invalid peer_group '{}': expected 'industry' or 'sector'",
+                    other
+                ),
+            ))
+        }
+    };
 
-    let mut db_balances = db_balances;
-    db_balances.sort_by_key(|b| b.period_end_date);
+    // Peers report on different fiscal calendars, so by default the company's
+    // latest calendar period is ranked against the same calendar period for
+    // every peer. Otherwise each peer's fiscal period matching the company's
+    // latest one is used.
+    let peer_group_out = match peer_group {
+        Some(group) => {
+            let (latest_values, peer_period) = if params.calendarized.unwrap_or(true) {
+                let reloaded = if calendarized {
+                    None
+                } else {
+                    Some(
+                        load_calendarized_history(&repo, &company, calendar, is_quarterly, 1)
+                            .await
+                            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                            .1,
+                    )
+                };
+                let calendar_history = reloaded.as_ref().unwrap_or(&history);
+                (
+                    latest_metric_values(&calendar_history.inputs(currency)),
+                    calendar_history
+                        .incomes
+                        .last()
+                        .map(|i| PeerPeriod::Calendar(i.period_end_date)),
+                )
+            } else {
+                (
+                    latest_row_values(&sections),
+                    history
+                        .incomes
+                        .last()
+                        .map(|i| PeerPeriod::Fiscal(i.period_end_date)),
+                )
+            };
+            Some(
+                apply_peer_percentiles(
+                    &repo,
+                    id,
+                    &group,
+                    db_period_type,
+                    peer_period,
+                    &latest_values,
+                    &mut sections,
+                )
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+            )
+        }
+        None => None,
+    };
 
-    let mut db_cashflows = db_cashflows;
-    db_cashflows.sort_by_key(|c| c.period_end_date);
+    let period_details = periods
+        .iter()
+        .zip(&alignment)
+        .map(|(p, aligned)| PeriodOut {
+            label: p.display_label.clone(),
+            period_start_date: p.period_start_date,
+            period_end_date: p.period_end_date,
+            statement_date: aligned.map(|i| statement_dates[i]),
+        })
+        .collect();
 
-    // Filter to match the periods we generated as closely as possible,
-    // or just take the most recent available.
-    // Fixed: The calculator expects the main 'incomes' to be the periods we want to display.
-    // And 'prior_year_incomes' to be the income statement from 1 year prior for each period.
+    let response = MetricsResponse {
+        company_id: id,
+        period_type: period_type_str,
+        calendarized,
+        as_of: params.as_of,
+        periods: period_labels,
+        period_details,
+        sections,
+        peer_group: peer_group_out,
+        custom_metrics,
+    };
 
-    let mut domain_incomes = Vec::new();
-    let mut prior_year_incomes = Vec::new();
-    let mut domain_balances = Vec::new();
-    let mut domain_cashflows = Vec::new();
+    Ok(Json(response))
+}
 
-    // Take the last 'params.period_count' statements as the current ones
-    let start_idx = db_incomes.len().saturating_sub(params.period_count);
-    let current_db_incomes = &db_incomes[start_idx..];
-
-    for (i, db_inc) in current_db_incomes.iter().enumerate() {
-        let domain_inc = domain::domain::IncomeStatement {
-            period_end_date: db_inc.period_end_date,
-            revenue: db_inc.total_revenue.clone(),
-            gross_profit: db_inc.gross_profit.clone(),
-            operating_income: db_inc.operating_income.clone(),
-            net_income: db_inc.net_income.clone(),
-            eps: db_inc.basic_eps.clone(),
-        };
-        domain_incomes.push(domain_inc);
+/// Every registered metric as a row over the aligned periods
+pub(crate) fn build_sections(
+    alignment: &[Option<usize>],
+    period_labels: &[String],
+    inputs: &MetricInputs,
+) -> MetricsSections {
+    let mut sections = MetricsSections {
+        growth_and_margins: Vec::new(),
+        cash_and_leverage: Vec::new(),
+        valuation: Vec::new(),
+    };
 
-        // Prior year income for YoY
-        let prior_idx = if is_quarterly {
-            (start_idx + i).checked_sub(4)
-        } else {
-            (start_idx + i).checked_sub(1)
+    for metric in METRIC_REGISTRY {
+        let row = MetricRow {
+            metric_name: metric.name.to_string(),
+            display_name: metric.display_name.to_string(),
+            values: aligned_values(alignment, period_labels, &metric.evaluate(inputs)),
+            heat_map_enabled: metric.heat_map != HeatMapDirection::None,
         };
+        match metric.section {
+            MetricSection::GrowthAndMargins => sections.growth_and_margins.push(row),
+            MetricSection::CashAndLeverage => sections.cash_and_leverage.push(row),
+            MetricSection::Valuation => sections.valuation.push(row),
+        }
+    }
+    sections
+}
 
-        let prior_inc = prior_idx.and_then(|idx| db_incomes.get(idx)).map(|db_inc| {
-            domain::domain::IncomeStatement {
-                period_end_date: db_inc.period_end_date,
-                revenue: db_inc.total_revenue.clone(),
-                gross_profit: db_inc.gross_profit.clone(),
-                operating_income: db_inc.operating_income.clone(),
-                net_income: db_inc.net_income.clone(),
-                eps: db_inc.basic_eps.clone(),
+/// Spread per-statement metric values over the aligned periods; gaps render as N/A
+fn aligned_values(
+    alignment: &[Option<usize>],
+    period_labels: &[String],
+    values: &[MetricValue],
+) -> Vec<MetricValueOut> {
+    alignment
+        .iter()
+        .zip(period_labels)
+        .map(|(aligned, label)| {
+            let value = aligned.and_then(|i| values.get(i));
+            MetricValueOut {
+                period: label.clone(),
+                value: value.and_then(|v| v.value),
+                formatted: value
+                    .map(|v| v.formatted_value.clone())
+                    .unwrap_or_else(|| "N/A".to_string()),
+                heat_map_quartile: value.and_then(|v| v.heat_map_quartile),
+                peer_percentile: None,
             }
-        });
-        prior_year_incomes.push(prior_inc);
+        })
+        .collect()
+}
 
-        // Match balance sheet and cash flow by date
-        let bal = db_balances
-            .iter()
-            .find(|b| b.period_end_date == db_inc.period_end_date)
-            .map(|b| domain::domain::BalanceSheet {
-                period_end_date: b.period_end_date,
-                total_assets: b.total_assets.clone(),
-                total_liabilities: b.total_liabilities.clone(),
-                total_equity: b.total_equity.clone(),
-                cash_and_equivalents: b.cash_and_equivalents.clone(),
-                short_term_investments: b.short_term_investments.clone(),
-                short_term_debt: b.short_term_debt.clone(),
-                long_term_debt: b.long_term_debt.clone(),
-                net_debt: b.net_debt.clone(),
-                common_stock_shares_outstanding: db_inc.shares_outstanding,
-            });
-        domain_balances.push(bal);
+/// Domain statements for the displayed periods plus the annual history the
+/// multi-year metrics look back on, all sorted by period end ascending.
+pub(crate) struct MetricHistory {
+    pub incomes: Vec<domain::domain::IncomeStatement>,
+    pub prior_year_incomes: Vec<Option<domain::domain::IncomeStatement>>,
+    pub balances: Vec<Option<domain::domain::BalanceSheet>>,
+    pub cash_flows: Vec<Option<domain::domain::CashFlowStatement>>,
+    pub prices: Vec<Option<domain::domain::DailyPrice>>,
+    pub annual_incomes: Vec<domain::domain::IncomeStatement>,
+    pub annual_cash_flows: Vec<Option<domain::domain::CashFlowStatement>>,
+    pub period_type: PeriodType,
+}
+
+impl MetricHistory {
+    pub fn inputs<'a>(&'a self, currency: &'a str) -> MetricInputs<'a> {
+        MetricInputs {
+            incomes: &self.incomes,
+            prior_year_incomes: &self.prior_year_incomes,
+            balances: &self.balances,
+            cash_flows: &self.cash_flows,
+            prices: &self.prices,
+            annual_incomes: &self.annual_incomes,
+            annual_cash_flows: &self.annual_cash_flows,
+            currency,
+            period_type: self.period_type,
+        }
+    }
+}
+
+/// Load the last `period_count` periods of statements for a company, with the
+/// price at each period end
+pub(crate) async fn load_metric_history(
+    repo: &CompanyRepository,
+    company: &db::models::Company,
+    is_quarterly: bool,
+    period_count: usize,
+) -> Result<MetricHistory, db::DbError> {
+    let id = company.id;
+    let (db_period_type, limit, annual_limit) = history_limits(is_quarterly, period_count);
+    let rows = StatementRows {
+        incomes: repo
+            .get_income_statements(id, db_period_type, limit)
+            .await?,
+        balances: repo.get_balance_sheets(id, db_period_type, limit).await?,
+        cash_flows: repo
+            .get_cash_flow_statements(id, db_period_type, limit)
+            .await?,
+        annual_incomes: repo
+            .get_income_statements(id, "annual", annual_limit)
+            .await?,
+        annual_cash_flows: repo
+            .get_cash_flow_statements(id, "annual", annual_limit)
+            .await?,
+    };
+
+    let mut history = rows.into_history(is_quarterly, period_count);
+    history.prices = load_period_prices(repo, company, &history.incomes).await?;
+    Ok(history)
+}
 
-        let cf = db_cashflows
+/// [`load_metric_history`] for many companies at once, given each one's id and
+/// exchange, with one query per kind of statement and one for prices.
+/// Companies without income statements are left out.
+pub(crate) async fn load_metric_histories(
+    repo: &CompanyRepository,
+    companies: &[(Uuid, &str)],
+    is_quarterly: bool,
+    period_count: usize,
+) -> Result<HashMap<Uuid, MetricHistory>, db::DbError> {
+    let ids: Vec<Uuid> = companies.iter().map(|(id, _)| *id).collect();
+    let ids = ids.as_slice();
+    let (db_period_type, limit, annual_limit) = history_limits(is_quarterly, period_count);
+    let mut rows: HashMap<Uuid, StatementRows> = HashMap::new();
+    for income in repo
+        .get_income_statements_for_companies(ids, db_period_type, limit)
+        .await?
+    {
+        rows.entry(income.company_id)
+            .or_default()
+            .incomes
+            .push(income);
+    }
+    for balance in repo
+        .get_balance_sheets_for_companies(ids, db_period_type, limit)
+        .await?
+    {
+        if let Some(r) = rows.get_mut(&balance.company_id) {
+            r.balances.push(balance);
+        }
+    }
+    for cash_flow in repo
+        .get_cash_flow_statements_for_companies(ids, db_period_type, limit)
+        .await?
+    {
+        if let Some(r) = rows.get_mut(&cash_flow.company_id) {
+            r.cash_flows.push(cash_flow);
+        }
+    }
+    for income in repo
+        .get_income_statements_for_companies(ids, "annual", annual_limit)
+        .await?
+    {
+        if let Some(r) = rows.get_mut(&income.company_id) {
+            r.annual_incomes.push(income);
+        }
+    }
+    for cash_flow in repo
+        .get_cash_flow_statements_for_companies(ids, "annual", annual_limit)
+        .await?
+    {
+        if let Some(r) = rows.get_mut(&cash_flow.company_id) {
+            r.annual_cash_flows.push(cash_flow);
+        }
+    }
+
+    let mut histories: HashMap<Uuid, MetricHistory> = rows
+        .into_iter()
+        .map(|(id, r)| (id, r.into_history(is_quarterly, period_count)))
+        .collect();
+
+    // Each period end's session on its company's exchange, priced in one
+    // query; a session without a close gets no price rather than an older one
+    let mut requests = Vec::new();
+    for (id, exchange) in companies {
+        let Some(history) = histories.get(id) else {
+            continue;
+        };
+        let sessions = TradingCalendars::global().for_exchange(exchange);
+        requests.extend(
+            history
+                .incomes
+                .iter()
+                .map(|i| (*id, sessions.session_on_or_before(i.period_end_date))),
+        );
+    }
+    let prices: HashMap<(Uuid, NaiveDate), domain::domain::DailyPrice> = repo
+        .get_prices_as_of_for_companies(&requests)
+        .await?
+        .iter()
+        .filter(|p| !p.is_stale())
+        .map(|p| ((p.price.company_id, p.as_of), (&p.price).into()))
+        .collect();
+    for (id, exchange) in companies {
+        let Some(history) = histories.get_mut(id) else {
+            continue;
+        };
+        let sessions = TradingCalendars::global().for_exchange(exchange);
+        history.prices = history
+            .incomes
             .iter()
-            .find(|c| c.period_end_date == db_inc.period_end_date)
-            .map(|c| domain::domain::CashFlowStatement {
-                period_end_date: c.period_end_date,
-                operating_cash_flow: c.operating_cash_flow.clone(),
-                capital_expenditures: c.capital_expenditures.clone(),
-                free_cash_flow: c.free_cash_flow.clone(),
-            });
-        domain_cashflows.push(cf);
+            .map(|i| {
+                prices
+                    .get(&(*id, sessions.session_on_or_before(i.period_end_date)))
+                    .cloned()
+            })
+            .collect();
     }
 
-    let currency = company.currency.as_deref().unwrap_or("$");
+    Ok(histories)
+}
 
-    // 6. Calculate Metrics
-    let (revs, yoy, qoq) = MetricsCalculator::calculate_revenue_metrics(
-        &domain_incomes,
-        &prior_year_incomes,
-        currency,
-    );
-    let (gm, om, nm) = MetricsCalculator::calculate_margin_metrics(&domain_incomes);
-    let (ocf_r, fcf_r) =
-        MetricsCalculator::calculate_cash_metrics(&domain_incomes, &domain_cashflows);
-    let (lev_r, shares) =
-        MetricsCalculator::calculate_leverage_metrics(&domain_incomes, &domain_balances);
-
-    // For valuation, we need prices. For now, we'll return empty/mock or fetch if possible.
-    // The prompt says "compute all metrics".
-    let ValuationMetrics {
-        open_ratios: open_r,
-        high_ratios: high_r,
-        low_ratios: low_r,
-        close_ratios: close_r,
-        pe_ratios: pe_r,
-    } = MetricsCalculator::calculate_valuation_metrics(
-        &domain_incomes,
-        &vec![None; domain_incomes.len()],
-    );
+/// Close of the exchange's last session on or before each period end,
+/// aligned with `incomes`; none where that session's close is missing
+async fn load_period_prices(
+    repo: &CompanyRepository,
+    company: &db::models::Company,
+    incomes: &[domain::domain::IncomeStatement],
+) -> Result<Vec<Option<domain::domain::DailyPrice>>, db::DbError> {
+    let sessions = TradingCalendars::global().for_exchange(&company.exchange);
+    let sessions_by_period: Vec<NaiveDate> = incomes
+        .iter()
+        .map(|i| sessions.session_on_or_before(i.period_end_date))
+        .collect();
+    let prices: HashMap<NaiveDate, domain::domain::DailyPrice> = repo
+        .get_prices_as_of(company.id, &sessions_by_period)
+        .await?
+        .iter()
+        .filter(|p| !p.is_stale())
+        .map(|p| (p.as_of, (&p.price).into()))
+        .collect();
 
-    // 7. Format Response
-    let mut sections = MetricsSections {
-        growth_and_margins: Vec::new(),
-        cash_and_leverage: Vec::new(),
-        valuation: Vec::new(),
-    };
+    Ok(sessions_by_period
+        .iter()
+        .map(|session| prices.get(session).cloned())
+        .collect())
+}
 
-    // Helper to map domain MetricValue to output
-    let to_row = |name: &str,
-                  display: &str,
-                  values: Vec<domain::metrics::MetricValue>,
-                  labels: &[String]|
-     -> MetricRow {
-        MetricRow {
-            metric_name: name.to_string(),
-            display_name: display.to_string(),
-            values: values
-                .into_iter()
-                .enumerate()
-                .map(|(i, v)| MetricValueOut {
-                    period: labels.get(i).cloned().unwrap_or_default(),
-                    value: v.value,
-                    formatted: v.formatted_value,
-                    heat_map_quartile: v.heat_map_quartile,
+/// Period type and statement counts to fetch for `period_count` periods
+fn history_limits(is_quarterly: bool, period_count: usize) -> (&'static str, i32, i32) {
+    let db_period_type = if is_quarterly { "quarterly" } else { "annual" };
+
+    // Fetch a bit more than requested to have prior year data for YoY calculations
+    // If quarterly, we need 4 quarters back for YoY
+    // If annual, we need 1 year back for YoY
+    let limit = period_count + if is_quarterly { 4 } else { 1 };
+
+    // Multi-year growth is always computed from annual statements. Five extra
+    // years are fetched so the oldest displayed period still has a 5y base.
+    let annual_limit = period_count + 5;
+
+    (db_period_type, limit as i32, annual_limit as i32)
+}
+
+/// One company's stored statements, as fetched for a [`MetricHistory`]
+#[derive(Default)]
+struct StatementRows {
+    incomes: Vec<db::models::IncomeStatement>,
+    balances: Vec<db::models::BalanceSheet>,
+    cash_flows: Vec<db::models::CashFlowStatement>,
+    annual_incomes: Vec<db::models::IncomeStatement>,
+    annual_cash_flows: Vec<db::models::CashFlowStatement>,
+}
+
+impl StatementRows {
+    fn into_history(self, is_quarterly: bool, period_count: usize) -> MetricHistory {
+        // Map DB models to Domain models for calculations
+        // We need to reverse the db results because they are likely ordered by date DESC
+        let mut db_incomes = self.incomes;
+        db_incomes.sort_by_key(|i| i.period_end_date);
+
+        let mut db_balances = self.balances;
+        db_balances.sort_by_key(|b| b.period_end_date);
+
+        let mut db_cashflows = self.cash_flows;
+        db_cashflows.sort_by_key(|c| c.period_end_date);
+
+        // Filter to match the periods we generated as closely as possible,
+        // or just take the most recent available.
+        // Fixed: The calculator expects the main 'incomes' to be the periods we want to display.
+        // And 'prior_year_incomes' to be the income statement from 1 year prior for each period.
+
+        let mut domain_incomes = Vec::new();
+        let mut prior_year_incomes = Vec::new();
+        let mut domain_balances = Vec::new();
+        let mut domain_cashflows = Vec::new();
+
+        // Take the last 'period_count' statements as the current ones
+        let start_idx = db_incomes.len().saturating_sub(period_count);
+        let current_db_incomes = &db_incomes[start_idx..];
+
+        for (i, db_inc) in current_db_incomes.iter().enumerate() {
+            domain_incomes.push(domain::domain::IncomeStatement::from(db_inc));
+
+            // Prior year income for YoY, matched by date so a missing period
+            // doesn't pair a quarter with the wrong year-ago quarter. Stub periods
+            // from a fiscal year-end change have no like-for-like comparison.
+            let prior_inc = db_incomes[..start_idx + i]
+                .iter()
+                .rev()
+                .filter(|p| !db_inc.is_transitional && !p.is_transitional)
+                .find(|p| is_prior_year_period(p.period_end_date, db_inc.period_end_date))
+                .map(domain::domain::IncomeStatement::from);
+            prior_year_incomes.push(prior_inc);
+
+            // Match balance sheet and cash flow by date, tolerating a few days'
+            // drift between sources on 52/53-week calendars
+            let bal =
+                find_period_match(&db_balances, db_inc.period_end_date, |b| b.period_end_date).map(
+                    |b| domain::domain::BalanceSheet {
+                        common_stock_shares_outstanding: db_inc.shares_outstanding,
+                        ..b.into()
+                    },
+                );
+            domain_balances.push(bal);
+
+            let cf =
+                find_period_match(&db_cashflows, db_inc.period_end_date, |c| c.period_end_date)
+                    .map(domain::domain::CashFlowStatement::from);
+            domain_cashflows.push(cf);
+        }
+
+        let mut annual_incomes = self.annual_incomes;
+        annual_incomes.retain(|i| !i.is_transitional);
+        annual_incomes.sort_by_key(|i| i.period_end_date);
+
+        let annual_cashflows = self.annual_cash_flows;
+
+        let domain_annual_incomes: Vec<domain::domain::IncomeStatement> =
+            annual_incomes.iter().map(Into::into).collect();
+        let domain_annual_cashflows: Vec<Option<domain::domain::CashFlowStatement>> =
+            annual_incomes
+                .iter()
+                .map(|db_inc| {
+                    find_period_match(&annual_cashflows, db_inc.period_end_date, |c| {
+                        c.period_end_date
+                    })
+                    .map(Into::into)
                 })
-                .collect(),
-            heat_map_enabled: true,
+                .collect();
+
+        // Prices come from the price history, which the caller loads
+        let prices = vec![None; domain_incomes.len()];
+
+        MetricHistory {
+            incomes: domain_incomes,
+            prior_year_incomes,
+            balances: domain_balances,
+            cash_flows: domain_cashflows,
+            prices,
+            annual_incomes: domain_annual_incomes,
+            annual_cash_flows: domain_annual_cashflows,
+            period_type: if is_quarterly {
+                PeriodType::Quarterly
+            } else {
+                PeriodType::Annual
+            },
         }
+    }
+}
+
+/// Load the last `period_count` calendar quarters or years of statements for a
+/// company, restated from its fiscal periods. Calendar periods the fiscal
+/// statements don't fully cover yet are left off the end. Returns the periods
+/// (oldest first) together with the history aligned to them.
+pub(crate) async fn load_calendarized_history(
+    repo: &CompanyRepository,
+    company: &db::models::Company,
+    calendar: FiscalCalendar,
+    is_quarterly: bool,
+    period_count: usize,
+) -> Result<(Vec<FiscalPeriod>, MetricHistory), db::DbError> {
+    let (db_period_type, period_type, lookback) = if is_quarterly {
+        ("quarterly", PeriodType::Quarterly, 4)
+    } else {
+        ("annual", PeriodType::Annual, 1)
     };
 
+    // Calendar periods straddle two fiscal ones, so fetch one extra fiscal
+    // period beyond the year-ago lookback and one for the partial latest period
+    let id = company.id;
+    let limit = (period_count + lookback + 2) as i32;
+    let (incomes, balances, cash_flows) =
+        fetch_domain_statements(repo, id, db_period_type, limit).await?;
+
+    let calendarizer = Calendarizer::new(calendar, period_type);
+    let (targets, restated) = restate_incomes(
+        &calendarizer,
+        &incomes,
+        period_count + lookback,
+        period_type,
+    );
+    let restated_balances = calendarizer.balances(&balances, &targets);
+    let restated_cash_flows = calendarizer.cash_flows(&cash_flows, &targets);
+
+    let display_start = targets.len().saturating_sub(period_count);
+    let mut domain_incomes = Vec::new();
+    let mut prior_year_incomes = Vec::new();
+    let mut domain_balances = Vec::new();
+    let mut domain_cashflows = Vec::new();
+    for i in display_start..targets.len() {
+        let Some(income) = restated[i].clone() else {
+            continue;
+        };
+        domain_incomes.push(income);
+        prior_year_incomes.push(i.checked_sub(lookback).and_then(|j| restated[j].clone()));
+        domain_balances.push(restated_balances[i].clone());
+        domain_cashflows.push(restated_cash_flows[i].clone());
+    }
+
+    // Multi-year growth reads calendar years, restated the same way
+    let annual_count = period_count + 5;
+    let (annual_incomes, _, annual_cash_flows) =
+        fetch_domain_statements(repo, id, "annual", annual_count as i32 + 2).await?;
+    let annual_calendarizer = Calendarizer::new(calendar, PeriodType::Annual);
+    let (annual_targets, restated_annual) = restate_incomes(
+        &annual_calendarizer,
+        &annual_incomes,
+        annual_count,
+        PeriodType::Annual,
+    );
+    let restated_annual_cash_flows =
+        annual_calendarizer.cash_flows(&annual_cash_flows, &annual_targets);
+    let (domain_annual_incomes, domain_annual_cashflows) = restated_annual
+        .into_iter()
+        .zip(restated_annual_cash_flows)
+        .filter_map(|(income, cash_flow)| Some((income?, cash_flow)))
+        .unzip();
+
+    let prices = load_period_prices(repo, company, &domain_incomes).await?;
+
+    Ok((
+        targets[display_start..].to_vec(),
+        MetricHistory {
+            incomes: domain_incomes,
+            prior_year_incomes,
+            balances: domain_balances,
+            cash_flows: domain_cashflows,
+            prices,
+            annual_incomes: domain_annual_incomes,
+            annual_cash_flows: domain_annual_cashflows,
+            period_type,
+        },
+    ))
+}
+
+/// Restate incomes onto the last `count` calendar periods they fully cover
+fn restate_incomes(
+    calendarizer: &Calendarizer,
+    incomes: &[domain::domain::IncomeStatement],
+    count: usize,
+    period_type: PeriodType,
+) -> (
+    Vec<FiscalPeriod>,
+    Vec<Option<domain::domain::IncomeStatement>>,
+) {
+    let as_of = incomes
+        .last()
+        .map(|i| i.period_end_date)
+        .unwrap_or_else(|| Utc::now().date_naive());
+    // The calendar period containing the latest fiscal period end is usually
+    // only partly reported, so generate one extra and drop it if uncovered
+    let mut targets = calendar_periods(count + 1, period_type, as_of);
+    let mut restated = calendarizer.incomes(incomes, &targets);
+    if restated.last().is_some_and(|r| r.is_none()) {
+        targets.pop();
+        restated.pop();
+    }
+    let start = targets.len().saturating_sub(count);
+    (targets.split_off(start), restated.split_off(start))
+}
+
+/// Fetch a company's statements of one period type as domain models, oldest first
+async fn fetch_domain_statements(
+    repo: &CompanyRepository,
+    id: Uuid,
+    period_type: &str,
+    limit: i32,
+) -> Result<
+    (
+        Vec<domain::domain::IncomeStatement>,
+        Vec<domain::domain::BalanceSheet>,
+        Vec<domain::domain::CashFlowStatement>,
+    ),
+    db::DbError,
+> {
+    // Stub periods don't span a whole fiscal period, so they can't be
+    // apportioned like one and are left out
+    let mut db_incomes = repo.get_income_statements(id, period_type, limit).await?;
+    db_incomes.retain(|i| !i.is_transitional);
+    db_incomes.sort_by_key(|i| i.period_end_date);
+    let mut db_balances = repo.get_balance_sheets(id, period_type, limit).await?;
+    db_balances.sort_by_key(|b| b.period_end_date);
+    let mut db_cashflows = repo
+        .get_cash_flow_statements(id, period_type, limit)
+        .await?;
+    db_cashflows.retain(|c| !c.is_transitional);
+    db_cashflows.sort_by_key(|c| c.period_end_date);
+
+    // Share counts are reported on the income statement
+    let balances = db_balances
+        .iter()
+        .map(|b| domain::domain::BalanceSheet {
+            common_stock_shares_outstanding: find_period_match(
+                &db_incomes,
+                b.period_end_date,
+                |i| i.period_end_date,
+            )
+            .and_then(|i| i.shares_outstanding),
+            ..b.into()
+        })
+        .collect();
+
+    Ok((
+        db_incomes.iter().map(Into::into).collect(),
+        balances,
+        db_cashflows.iter().map(Into::into).collect(),
+    ))
+}
+
+/// Which peer values the company's latest period is ranked against
+enum PeerPeriod {
+    /// Each peer's fiscal period matching the one ending on this date
+    Fiscal(NaiveDate),
+    /// Every peer's values for the calendar period ending on this date
+    Calendar(NaiveDate),
+}
+
+/// Latest value of each peer-ranked registry metric, keyed by metric name
+fn latest_metric_values(inputs: &MetricInputs) -> HashMap<String, f64> {
+    METRIC_REGISTRY
+        .iter()
+        .filter(|m| m.heat_map != HeatMapDirection::None && m.stored_name.is_some())
+        .filter_map(|m| {
+            let value = m.evaluate(inputs).last()?.value?;
+            Some((m.name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Value in the latest displayed column of each row, keyed by metric name
+fn latest_row_values(sections: &MetricsSections) -> HashMap<String, f64> {
     sections
         .growth_and_margins
-        .push(to_row("revenue", "Revenue", revs, &period_labels));
-    sections.growth_and_margins.push(to_row(
-        "revenue_growth_yoy",
-        "Revenue Growth (YoY)",
-        yoy,
-        &period_labels,
-    ));
-    sections.growth_and_margins.push(to_row(
-        "revenue_growth_qoq",
-        "Revenue Growth (QoQ)",
-        qoq,
-        &period_labels,
-    ));
-    sections
-        .growth_and_margins
-        .push(to_row("gross_margin", "Gross Margin", gm, &period_labels));
-    sections.growth_and_margins.push(to_row(
-        "operating_margin",
-        "Operating Margin",
-        om,
-        &period_labels,
-    ));
-    sections
-        .growth_and_margins
-        .push(to_row("net_margin", "Net Margin", nm, &period_labels));
+        .iter()
+        .chain(&sections.cash_and_leverage)
+        .chain(&sections.valuation)
+        .filter_map(|row| Some((row.metric_name.clone(), row.values.last()?.value?)))
+        .collect()
+}
 
-    sections
-        .cash_and_leverage
-        .push(to_row("ocf_margin", "OCF Margin", ocf_r, &period_labels));
-    sections
-        .cash_and_leverage
-        .push(to_row("fcf_margin", "FCF Margin", fcf_r, &period_labels));
-    sections.cash_and_leverage.push(to_row(
-        "leverage_ratio",
-        "Leverage Ratio",
-        lev_r,
-        &period_labels,
-    ));
-    sections.cash_and_leverage.push(to_row(
-        "shares_outstanding",
-        "Shares Outstanding",
-        shares,
-        &period_labels,
-    ));
+/// Rank the company's latest value of each peer-ranked row against the peer group,
+/// replacing the latest column's heat map quartile with the peer-relative one.
+async fn apply_peer_percentiles(
+    repo: &CompanyRepository,
+    company_id: Uuid,
+    group: &PeerGroup,
+    period_type: &str,
+    peer_period: Option<PeerPeriod>,
+    latest_values: &HashMap<String, f64>,
+    sections: &mut MetricsSections,
+) -> Result<PeerGroupOut, db::DbError> {
+    let metric_names: Vec<String> = METRIC_REGISTRY
+        .iter()
+        .filter(|m| m.heat_map != HeatMapDirection::None)
+        .filter_map(|m| m.stored_name)
+        .map(|name| name.to_string())
+        .collect();
 
-    sections
-        .valuation
-        .push(to_row("pe_ratio", "P/E Ratio", pe_r, &period_labels));
-    // Prefix unused price metrics with underscore for now as they are empty
-    let _ = (open_r, high_r, low_r, close_r);
+    // (company, stored metric name, value) for every peer value in scope
+    let peer_metrics: Vec<(Uuid, String, Option<f64>)> = match peer_period {
+        // The company has no statement to rank
+        None => Vec::new(),
+        Some(PeerPeriod::Fiscal(period_end_date)) => {
+            let rows = repo
+                .get_fiscal_peer_metrics(
+                    group,
+                    period_type,
+                    period_end_date,
+                    PERIOD_MATCH_TOLERANCE_DAYS,
+                    metric_names,
+                )
+                .await?;
+            let mut by_peer_metric: HashMap<(Uuid, String), Vec<db::models::DerivedMetric>> =
+                HashMap::new();
+            for row in rows {
+                by_peer_metric
+                    .entry((row.company_id, row.metric_name.clone()))
+                    .or_default()
+                    .push(row);
+            }
+            by_peer_metric
+                .into_iter()
+                .filter_map(|((peer_id, metric_name), rows)| {
+                    let matched = find_period_match(&rows, period_end_date, |m| m.period_end_date)?;
+                    let value = matched.metric_value.as_ref().and_then(|v| v.to_f64());
+                    Some((peer_id, metric_name, value))
+                })
+                .collect()
+        }
+        Some(PeerPeriod::Calendar(period_end_date)) => {
+            let domain_period_type = if period_type == "quarterly" {
+                PeriodType::Quarterly
+            } else {
+                PeriodType::Annual
+            };
+            // Calendarized rows exist only for peers whose fiscal periods are
+            // off the calendar; for the rest the fiscal rows are the calendar ones
+            repo.get_calendar_peer_metrics(group, period_type, period_end_date, metric_names)
+                .await?
+                .into_iter()
+                .filter(|m| {
+                    m.period_type != period_type
+                        || FiscalCalendar::for_company(
+                            m.fiscal_calendar.as_deref(),
+                            m.fiscal_year_end_month,
+                        )
+                        .is_calendar_aligned(domain_period_type)
+                })
+                .map(|m| {
+                    let value = m.metric_value.as_ref().and_then(|v| v.to_f64());
+                    (m.company_id, m.metric_name, value)
+                })
+                .collect()
+        }
+    };
 
-    let response = MetricsResponse {
-        company_id: id,
-        period_type: period_type_str,
-        periods: period_labels,
-        sections,
+    let (basis, name) = match group {
+        PeerGroup::Industry(industry) => ("industry", industry.clone()),
+        PeerGroup::Sector(sector_id) => (
+            "sector",
+            repo.find_sector_name(*sector_id)
+                .await?
+                .unwrap_or_else(|| sector_id.to_string()),
+        ),
     };
+    // Peers with any persisted metric, plus the company itself
+    let company_count = peer_metrics
+        .iter()
+        .map(|(peer_id, _, _)| *peer_id)
+        .filter(|peer_id| *peer_id != company_id)
+        .collect::<std::collections::HashSet<_>>()
+        .len()
+        + 1;
+
+    let rows = sections
+        .growth_and_margins
+        .iter_mut()
+        .chain(sections.cash_and_leverage.iter_mut())
+        .chain(sections.valuation.iter_mut());
+    for row in rows {
+        let Some(metric) = find_metric(&row.metric_name) else {
+            continue;
+        };
+        let Some(stored_name) = metric.stored_name else {
+            continue;
+        };
+        if metric.heat_map == HeatMapDirection::None {
+            continue;
+        }
+        let Some(latest) = row.values.last_mut() else {
+            continue;
+        };
+        let Some(&value) = latest_values.get(&row.metric_name) else {
+            continue;
+        };
 
-    Ok(Json(response))
+        let mut peer_values: Vec<f64> = peer_metrics
+            .iter()
+            .filter(|(peer_id, name, _)| name == stored_name && *peer_id != company_id)
+            .filter_map(|(_, _, value)| *value)
+            .collect();
+        peer_values.push(value);
+
+        latest.peer_percentile = MetricsCalculator::calculate_peer_percentile(value, &peer_values);
+        if let Some(percentile) = latest.peer_percentile {
+            latest.heat_map_quartile = metric
+                .heat_map
+                .orient(MetricsCalculator::percentile_to_quartile(percentile));
+        }
+    }
+
+    let (calendarized, period_end_date) = match peer_period {
+        None => (false, None),
+        Some(PeerPeriod::Fiscal(date)) => (false, Some(date)),
+        Some(PeerPeriod::Calendar(date)) => (true, Some(date)),
+    };
+    Ok(PeerGroupOut {
+        basis: basis.to_string(),
+        name,
+        company_count,
+        calendarized,
+        period_end_date,
+    })
 }
 
 #[derive(Deserialize, IntoParams)]
@@ -436,6 +1458,8 @@ pub struct DocumentOut {
     pub period_end_date: Option<NaiveDate>,
     pub fiscal_year: i32,
     pub fiscal_quarter: Option<i32>,
+    /// Column label matching the metrics grid, e.g. "Q3 2024" or "FY2024"
+    pub period_label: Option<String>,
     pub title: String,
     pub source_url: Option<String>,
     pub storage_key: Option<String>,
@@ -498,17 +1522,27 @@ pub async fn get_company_documents(
         tracing::info!("Company {} data is stale, would enqueue refresh", id);
     }
 
-    // 4. Map to response (grouped by type and sorted by date via DB)
+    // 4. Map to response (grouped by type and sorted by date via DB). Periods
+    // are resolved with the company's fiscal calendar, the same way metrics
+    // columns are aligned, so documents line up under the matching column.
+    let generator = PeriodWindowGenerator::with_calendar(FiscalCalendar::for_company(
+        company.fiscal_calendar.as_deref(),
+        company.fiscal_year_end_month,
+    ));
     let documents = docs
         .into_iter()
         .map(|d| {
             let available = d.is_available();
+            let period = d
+                .period_end_date
+                .map(|dt| generator.period_containing(dt, PeriodType::Quarterly));
             DocumentOut {
                 id: d.id,
                 document_type: d.document_type,
                 period_end_date: d.period_end_date,
-                fiscal_year: d.period_end_date.map(|dt| dt.year()).unwrap_or(0),
-                fiscal_quarter: d.period_end_date.map(|dt| (dt.month() - 1) as i32 / 3 + 1),
+                fiscal_year: period.as_ref().map(|p| p.fiscal_year).unwrap_or(0),
+                fiscal_quarter: period.as_ref().and_then(|p| p.fiscal_quarter),
+                period_label: period.map(|p| p.display_label),
                 title: d.title,
                 source_url: d.source_url,
                 storage_key: d.storage_key,
@@ -1361,3 +2395,80 @@ pub async fn get_verdict_history(
         history: history_entries,
     }))
 }
+
+#[derive(Deserialize, IntoParams)]
+pub struct DataEventsQueryParams {
+    #[serde(default = "default_data_event_limit")]
+    pub limit: i64,
+}
+
+fn default_data_event_limit() -> i64 {
+    50
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DataEventsResponse {
+    pub company_id: Uuid,
+    pub events: Vec<DataEventOut>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DataEventOut {
+    pub id: Uuid,
+    /// "restatement" or "fiscal_year_end_change"
+    pub event_type: String,
+    pub statement_type: Option<String>,
+    pub period_end_date: Option<NaiveDate>,
+    pub period_type: Option<String>,
+    /// Changed line items (`{"changes": {column: {"from", "to"}}}`) or the
+    /// old and new fiscal year-end months
+    #[schema(value_type = Object)]
+    pub details: serde_json::Value,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/{id}/data-events",
+    params(
+        ("id" = Uuid, Path, description = "Company ID"),
+        DataEventsQueryParams
+    ),
+    responses(
+        (status = 200, description = "Restatements and fiscal year-end changes detected on ingestion, newest first", body = DataEventsResponse),
+        (status = 404, description = "Company not found")
+    ),
+    tag = "companies"
+)]
+pub async fn get_company_data_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<DataEventsQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    CompanyRepository::new(state.db.clone())
+        .find_by_id(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+
+    let events = DataEventRepository::new(state.db.clone())
+        .list_by_company(id, params.limit.clamp(1, 500))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .map(|e| DataEventOut {
+            id: e.id,
+            event_type: e.event_type,
+            statement_type: e.statement_type,
+            period_end_date: e.period_end_date,
+            period_type: e.period_type,
+            details: e.details,
+            detected_at: e.detected_at,
+        })
+        .collect();
+
+    Ok(Json(DataEventsResponse {
+        company_id: id,
+        events,
+    }))
+}
@@ -0,0 +1,288 @@
+use crate::routes::companies::{build_sections, load_calendarized_history, MetricsSections};
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::ToPrimitive;
+use chrono::{NaiveDate, Utc};
+use db::repositories::CompanyRepository;
+use domain::metrics::calculator::MetricsCalculator;
+use domain::metrics::registry::{find_metric, MetricFormat};
+use domain::periods::calendarize::calendar_periods;
+use domain::periods::{align_statements, FiscalCalendar, PeriodType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Companies a comparison can span
+const MIN_COMPARED_COMPANIES: usize = 2;
+const MAX_COMPARED_COMPANIES: usize = 5;
+
+/// Currency absolute values are normalized to
+const COMPARISON_CURRENCY: &str = "USD";
+
+#[derive(Deserialize, IntoParams)]
+pub struct CompareQueryParams {
+    /// Comma-separated company IDs, 2 to 5
+    pub ids: String,
+    #[serde(default = "default_period_type")]
+    pub period_type: String,
+    #[serde(default = "default_period_count")]
+    pub period_count: usize,
+}
+
+fn default_period_type() -> String {
+    "quarterly".to_string()
+}
+
+fn default_period_count() -> usize {
+    8
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CompareResponse {
+    pub period_type: String,
+    /// Calendar period labels shared by every company, oldest first
+    pub periods: Vec<String>,
+    pub period_end_dates: Vec<NaiveDate>,
+    /// Currency absolute values are normalized to
+    pub currency: String,
+    /// In the order requested
+    pub companies: Vec<ComparedCompany>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ComparedCompany {
+    pub company_id: Uuid,
+    pub symbol: String,
+    pub name: String,
+    /// Currency the company reports in
+    pub reporting_currency: String,
+    /// Rate applied to each period's absolute values; null where no rate was
+    /// available, leaving those values N/A
+    pub fx_rates: Vec<Option<f64>>,
+    /// Heat map quartiles are ranked across all compared companies per row
+    pub sections: MetricsSections,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/compare",
+    params(CompareQueryParams),
+    responses(
+        (status = 200, description = "Metrics for each company on a shared calendarized period axis", body = CompareResponse),
+        (status = 400, description = "Invalid or wrong number of company IDs"),
+        (status = 404, description = "Company not found")
+    ),
+    tag = "companies"
+)]
+pub async fn compare_companies(
+    State(state): State<AppState>,
+    Query(params): Query<CompareQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let mut ids: Vec<Uuid> = Vec::new();
+    for raw in params
+        .ids
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let id = Uuid::parse_str(raw).map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid company ID '{}'", raw),
+            )
+        })?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if !(MIN_COMPARED_COMPANIES..=MAX_COMPARED_COMPANIES).contains(&ids.len()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Compare between {} and {} distinct companies",
+                MIN_COMPARED_COMPANIES, MAX_COMPARED_COMPANIES
+            ),
+        ));
+    }
+
+    let is_quarterly = params.period_type.to_lowercase() == "quarterly";
+    let (period_type_str, period_type) = if is_quarterly {
+        ("quarterly", PeriodType::Quarterly)
+    } else {
+        ("annual", PeriodType::Annual)
+    };
+
+    let repo = CompanyRepository::new(state.db.clone());
+
+    // 1. Each company's history restated on calendar periods
+    let mut loaded = Vec::with_capacity(ids.len());
+    for id in ids {
+        let company = repo
+            .find_by_id(id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .ok_or((StatusCode::NOT_FOUND, format!("Company {} not found", id)))?;
+        let calendar = FiscalCalendar::for_company(
+            company.fiscal_calendar.as_deref(),
+            company.fiscal_year_end_month,
+        );
+        let (_, history) =
+            load_calendarized_history(&repo, &company, calendar, is_quarterly, params.period_count)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        loaded.push((company, history));
+    }
+
+    // 2. One axis ending at the latest calendar period any company covers;
+    // companies that haven't reported it yet show a gap there
+    let latest = loaded
+        .iter()
+        .filter_map(|(_, history)| history.incomes.last().map(|i| i.period_end_date))
+        .max()
+        .unwrap_or_else(|| Utc::now().date_naive());
+    let periods = calendar_periods(params.period_count, period_type, latest);
+    let period_labels: Vec<String> = periods.iter().map(|p| p.display_label.clone()).collect();
+
+    // 3. Rows per company, with absolute values converted at each period
+    // end's rate
+    let mut companies = Vec::with_capacity(loaded.len());
+    for (company, history) in &loaded {
+        let reporting_currency = company
+            .currency
+            .clone()
+            .unwrap_or_else(|| COMPARISON_CURRENCY.to_string());
+        let statement_dates: Vec<NaiveDate> =
+            history.incomes.iter().map(|i| i.period_end_date).collect();
+        let alignment = align_statements(&periods, &statement_dates);
+        let mut sections = build_sections(
+            &alignment,
+            &period_labels,
+            &history.inputs(&reporting_currency),
+        );
+
+        let mut fx_rates = Vec::with_capacity(periods.len());
+        for period in &periods {
+            let rate = if reporting_currency == COMPARISON_CURRENCY {
+                Some(1.0)
+            } else {
+                repo.get_fx_rate(
+                    &reporting_currency,
+                    COMPARISON_CURRENCY,
+                    period.period_end_date,
+                )
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .and_then(|r| r.to_f64())
+            };
+            fx_rates.push(rate);
+        }
+        normalize_currency_rows(&mut sections, &fx_rates);
+
+        companies.push(ComparedCompany {
+            company_id: company.id,
+            symbol: company.symbol.clone(),
+            name: company.name.clone(),
+            reporting_currency,
+            fx_rates,
+            sections,
+        });
+    }
+
+    // 4. Heat maps rank every company's values of a row together
+    apply_cross_company_quartiles(&mut companies);
+
+    Ok(Json(CompareResponse {
+        period_type: period_type_str.to_string(),
+        periods: period_labels,
+        period_end_dates: periods.iter().map(|p| p.period_end_date).collect(),
+        currency: COMPARISON_CURRENCY.to_string(),
+        companies,
+    }))
+}
+
+/// Convert currency-formatted rows at each period's rate. Ratios, percentages
+/// and counts are currency-independent and left alone.
+fn normalize_currency_rows(sections: &mut MetricsSections, fx_rates: &[Option<f64>]) {
+    let rows = sections
+        .growth_and_margins
+        .iter_mut()
+        .chain(sections.cash_and_leverage.iter_mut())
+        .chain(sections.valuation.iter_mut());
+    for row in rows {
+        if find_metric(&row.metric_name).map(|m| m.format) != Some(MetricFormat::Currency) {
+            continue;
+        }
+        for (value, rate) in row.values.iter_mut().zip(fx_rates) {
+            let Some(raw) = value.value else {
+                continue;
+            };
+            value.value = rate.map(|rate| raw * rate);
+            value.formatted = match value.value {
+                Some(converted) => {
+                    MetricsCalculator::format_currency_value(converted, COMPARISON_CURRENCY)
+                }
+                None => "N/A".to_string(),
+            };
+        }
+    }
+}
+
+/// Replace each company's own-history quartiles with quartiles over every
+/// compared company's values in the row
+fn apply_cross_company_quartiles(companies: &mut [ComparedCompany]) {
+    let mut pooled: HashMap<String, Vec<Option<f64>>> = HashMap::new();
+    for company in companies.iter() {
+        let sections = &company.sections;
+        let rows = sections
+            .growth_and_margins
+            .iter()
+            .chain(&sections.cash_and_leverage)
+            .chain(&sections.valuation)
+            .filter(|row| row.heat_map_enabled);
+        for row in rows {
+            pooled
+                .entry(row.metric_name.clone())
+                .or_default()
+                .extend(row.values.iter().map(|v| v.value));
+        }
+    }
+    // Handed out in the same company and period order they were pooled in
+    let mut quartiles: HashMap<String, std::vec::IntoIter<Option<i32>>> = pooled
+        .into_iter()
+        .map(|(name, values)| {
+            (
+                name,
+                MetricsCalculator::calculate_quartiles(&values).into_iter(),
+            )
+        })
+        .collect();
+
+    for company in companies.iter_mut() {
+        let sections = &mut company.sections;
+        let rows = sections
+            .growth_and_margins
+            .iter_mut()
+            .chain(sections.cash_and_leverage.iter_mut())
+            .chain(sections.valuation.iter_mut());
+        for row in rows {
+            let (Some(metric), Some(row_quartiles)) = (
+                find_metric(&row.metric_name),
+                quartiles.get_mut(&row.metric_name),
+            ) else {
+                continue;
+            };
+            for value in &mut row.values {
+                value.heat_map_quartile = row_quartiles
+                    .next()
+                    .flatten()
+                    .and_then(|q| metric.heat_map.orient(q));
+            }
+        }
+    }
+}
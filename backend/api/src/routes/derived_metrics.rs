@@ -0,0 +1,169 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::ToPrimitive;
+use chrono::NaiveDate;
+use db::repositories::{CompanyRepository, DerivedMetricFilters};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Most metrics one request can chart
+pub const MAX_SERIES_METRICS: usize = 20;
+
+/// Period types derived metrics are stored under
+const PERIOD_TYPES: [&str; 4] = [
+    "quarterly",
+    "annual",
+    "calendar_quarterly",
+    "calendar_annual",
+];
+
+// DTOs for API Documentation
+
+#[derive(Deserialize, IntoParams)]
+pub struct DerivedMetricsQueryParams {
+    /// Comma-separated metric names, e.g. "pe_ratio_ttm,gross_margin_pct"
+    pub names: String,
+    /// "quarterly" (default), "annual", "calendar_quarterly" or
+    /// "calendar_annual"
+    #[serde(default = "default_period_type")]
+    pub period_type: String,
+    /// First snapshot date, inclusive
+    pub from: Option<NaiveDate>,
+    /// Last snapshot date, inclusive
+    pub to: Option<NaiveDate>,
+}
+
+fn default_period_type() -> String {
+    "quarterly".to_string()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DerivedMetricsResponse {
+    pub company_id: Uuid,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// One per requested name, in request order
+    pub series: Vec<DerivedMetricSeries>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DerivedMetricSeries {
+    pub metric_name: String,
+    /// Oldest snapshot first; empty when nothing was recorded in the range
+    pub points: Vec<DerivedMetricPoint>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DerivedMetricPoint {
+    /// Day the value was recorded
+    pub date: NaiveDate,
+    /// Period the value was computed for
+    pub period_end_date: NaiveDate,
+    pub period_type: String,
+    pub value: Option<f64>,
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/{id}/derived-metrics",
+    params(
+        ("id" = Uuid, Path, description = "Company ID"),
+        DerivedMetricsQueryParams
+    ),
+    responses(
+        (status = 200, description = "Every value recorded for each derived metric, as a time series", body = DerivedMetricsResponse),
+        (status = 400, description = "Missing names, unknown period type or invalid date range"),
+        (status = 404, description = "Company not found")
+    ),
+    tag = "companies"
+)]
+pub async fn get_derived_metric_series(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<DerivedMetricsQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let repo = CompanyRepository::new(state.db.clone());
+    let internal = |e: db::DbError| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+
+    let mut names: Vec<String> = Vec::new();
+    for name in params
+        .names
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    if names.is_empty() || names.len() > MAX_SERIES_METRICS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Request between 1 and {} metric names", MAX_SERIES_METRICS),
+        ));
+    }
+    if !PERIOD_TYPES.contains(&params.period_type.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("period_type must be one of: {}", PERIOD_TYPES.join(", ")),
+        ));
+    }
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from > to {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "from must not be after to".to_string(),
+            ));
+        }
+    }
+
+    repo.find_by_id(id)
+        .await
+        .map_err(internal)?
+        .ok_or((StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+
+    let snapshots = repo
+        .get_derived_metric_history(
+            id,
+            &DerivedMetricFilters {
+                metric_names: names.clone(),
+                period_type: Some(params.period_type),
+                from: params.from,
+                to: params.to,
+            },
+        )
+        .await
+        .map_err(internal)?;
+
+    let series = names
+        .into_iter()
+        .map(|metric_name| DerivedMetricSeries {
+            points: snapshots
+                .iter()
+                .filter(|s| s.metric_name == metric_name)
+                .map(|s| DerivedMetricPoint {
+                    date: s.snapshot_date,
+                    period_end_date: s.period_end_date,
+                    period_type: s.period_type.clone(),
+                    value: s.metric_value.as_ref().and_then(|v| v.to_f64()),
+                })
+                .collect(),
+            metric_name,
+        })
+        .collect();
+
+    Ok(Json(DerivedMetricsResponse {
+        company_id: id,
+        from: params.from,
+        to: params.to,
+        series,
+    }))
+}
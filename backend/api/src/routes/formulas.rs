@@ -0,0 +1,260 @@
+use crate::auth::jwt::Claims;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, put},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use db::models::CustomFormula;
+use db::repositories::{CreateCustomFormula, CustomFormulaRepository, UpdateCustomFormula};
+use db::DbError;
+use domain::metrics::formula::{Formula, STATEMENT_FIELDS};
+use domain::metrics::registry::find_metric;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub fn formulas_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_formulas).post(create_formula))
+        .route("/:id", put(update_formula).delete(delete_formula))
+}
+
+// DTOs for API Documentation
+
+#[derive(Serialize, ToSchema)]
+pub struct FormulaResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub expression: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CustomFormula> for FormulaResponse {
+    fn from(f: CustomFormula) -> Self {
+        Self {
+            id: f.id,
+            name: f.name,
+            expression: f.expression,
+            description: f.description,
+            created_at: f.created_at,
+            updated_at: f.updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateFormulaRequest {
+    pub name: String,
+    pub expression: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateFormulaRequest {
+    pub name: Option<String>,
+    pub expression: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Formula names become metric row names and screener keys, so they follow
+/// identifier rules and cannot shadow a built-in field or metric.
+fn validate_name(name: &str) -> Result<(), (StatusCode, String)> {
+    let valid = name.len() <= 64
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Formula name must be 1-64 lowercase letters, digits or underscores, starting with a letter"
+                .to_string(),
+        ));
+    }
+    if STATEMENT_FIELDS.contains(&name) || find_metric(name).is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Formula name '{}' is reserved by a built-in metric", name),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_expression(expression: &str) -> Result<(), (StatusCode, String)> {
+    Formula::parse(expression)
+        .map(|_| ())
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid formula: {}", e)))
+}
+
+fn map_db_error(e: DbError) -> (StatusCode, String) {
+    match e {
+        DbError::DuplicateError(_) => (
+            StatusCode::CONFLICT,
+            "A formula with this name already exists".to_string(),
+        ),
+        other => (StatusCode::INTERNAL_SERVER_ERROR, other.to_string()),
+    }
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/formulas",
+    responses(
+        (status = 200, description = "List user formulas", body = Vec<FormulaResponse>),
+    ),
+    tag = "formulas"
+)]
+pub async fn list_formulas(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let repo = CustomFormulaRepository::new(state.db.clone());
+    let formulas = repo
+        .list_by_user(user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let response: Vec<FormulaResponse> = formulas.into_iter().map(Into::into).collect();
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/formulas",
+    request_body = CreateFormulaRequest,
+    responses(
+        (status = 201, description = "Formula created", body = FormulaResponse),
+        (status = 400, description = "Invalid name or expression"),
+        (status = 409, description = "Formula name already in use")
+    ),
+    tag = "formulas"
+)]
+pub async fn create_formula(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(payload): Json<CreateFormulaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    validate_name(&payload.name)?;
+    validate_expression(&payload.expression)?;
+
+    let repo = CustomFormulaRepository::new(state.db.clone());
+    let formula = repo
+        .create(
+            user_id,
+            CreateCustomFormula {
+                name: payload.name,
+                expression: payload.expression,
+                description: payload.description,
+            },
+        )
+        .await
+        .map_err(map_db_error)?;
+
+    Ok((StatusCode::CREATED, Json(FormulaResponse::from(formula))))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/formulas/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Formula ID")
+    ),
+    request_body = UpdateFormulaRequest,
+    responses(
+        (status = 200, description = "Formula updated", body = FormulaResponse),
+        (status = 400, description = "Invalid name or expression"),
+        (status = 404, description = "Formula not found"),
+        (status = 409, description = "Formula name already in use")
+    ),
+    tag = "formulas"
+)]
+pub async fn update_formula(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateFormulaRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    if let Some(name) = &payload.name {
+        validate_name(name)?;
+    }
+    if let Some(expression) = &payload.expression {
+        validate_expression(expression)?;
+    }
+
+    let repo = CustomFormulaRepository::new(state.db.clone());
+
+    let existing = repo
+        .find_by_id(id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if existing.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Formula not found".to_string()));
+    }
+
+    let updated = repo
+        .update(
+            id,
+            user_id,
+            UpdateCustomFormula {
+                name: payload.name,
+                expression: payload.expression,
+                description: payload.description,
+            },
+        )
+        .await
+        .map_err(map_db_error)?;
+
+    Ok(Json(FormulaResponse::from(updated)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/formulas/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Formula ID")
+    ),
+    responses(
+        (status = 204, description = "Formula deleted"),
+        (status = 404, description = "Formula not found")
+    ),
+    tag = "formulas"
+)]
+pub async fn delete_formula(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let repo = CustomFormulaRepository::new(state.db.clone());
+    let deleted = repo
+        .delete(id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if deleted {
+        Ok((StatusCode::NO_CONTENT, ()))
+    } else {
+        Err((StatusCode::NOT_FOUND, "Formula not found".to_string()))
+    }
+}
@@ -0,0 +1,97 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use db::models::{BackgroundJob, JobStep};
+use db::repositories::BackgroundJobRepository;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub fn jobs_router() -> Router<AppState> {
+    Router::new().route("/:id", get(get_job_status))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JobStatusResponse {
+    pub id: Uuid,
+    pub job_type: String,
+    pub company_id: Option<Uuid>,
+    /// "pending", "running", "completed", "failed" or "cancelled"
+    pub status: String,
+    /// In the order they run
+    pub steps: Vec<JobStepOut>,
+    /// Rows written across all steps so far
+    pub records_updated: i32,
+    pub error_message: Option<String>,
+    pub scheduled_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JobStepOut {
+    pub step: String,
+    /// "pending", "running", "completed" or "failed"
+    pub status: String,
+    pub records: i32,
+    pub message: Option<String>,
+}
+
+impl From<JobStep> for JobStepOut {
+    fn from(step: JobStep) -> Self {
+        Self {
+            step: step.step,
+            status: step.status,
+            records: step.records,
+            message: step.message,
+        }
+    }
+}
+
+impl From<BackgroundJob> for JobStatusResponse {
+    fn from(job: BackgroundJob) -> Self {
+        Self {
+            steps: job.steps().into_iter().map(JobStepOut::from).collect(),
+            id: job.id,
+            job_type: job.job_type,
+            company_id: job.company_id,
+            status: job.status,
+            records_updated: job.records_updated.unwrap_or(0),
+            error_message: job.error_message,
+            scheduled_at: job.scheduled_at,
+            started_at: job.started_at,
+            completed_at: job.completed_at,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Job ID")
+    ),
+    responses(
+        (status = 200, description = "Job status with per-step progress", body = JobStatusResponse),
+        (status = 404, description = "Job not found")
+    ),
+    tag = "jobs"
+)]
+pub async fn get_job_status(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let job = BackgroundJobRepository::new(state.db.clone())
+        .find_by_id(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Job not found".to_string()))?;
+
+    Ok(Json(JobStatusResponse::from(job)))
+}
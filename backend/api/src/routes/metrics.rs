@@ -0,0 +1,63 @@
+use crate::state::AppState;
+use axum::{response::IntoResponse, routing::get, Json, Router};
+use domain::metrics::price_analytics::PRICE_ANALYTICS_METRICS;
+use domain::metrics::registry::{HeatMapDirection, MetricFormat, MetricSection, METRIC_REGISTRY};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+pub fn metrics_router() -> Router<AppState> {
+    Router::new().route("/catalog", get(get_metric_catalog))
+}
+
+// DTOs for API Documentation
+
+#[derive(Serialize, ToSchema)]
+pub struct MetricCatalogResponse {
+    pub metrics: Vec<MetricCatalogEntry>,
+    /// Stored price analytics, which the screener's `technical_filters` accept
+    pub technical_metrics: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MetricCatalogEntry {
+    pub name: String,
+    pub stored_name: Option<String>,
+    pub display_name: String,
+    pub section: MetricSection,
+    pub unit: String,
+    pub format: MetricFormat,
+    pub heat_map_direction: HeatMapDirection,
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/metrics/catalog",
+    responses(
+        (status = 200, description = "Registered metrics", body = MetricCatalogResponse),
+    ),
+    tag = "metrics"
+)]
+pub async fn get_metric_catalog() -> impl IntoResponse {
+    let metrics = METRIC_REGISTRY
+        .iter()
+        .map(|m| MetricCatalogEntry {
+            name: m.name.to_string(),
+            stored_name: m.stored_name.map(|s| s.to_string()),
+            display_name: m.display_name.to_string(),
+            section: m.section,
+            unit: m.unit.to_string(),
+            format: m.format,
+            heat_map_direction: m.heat_map,
+        })
+        .collect();
+
+    Json(MetricCatalogResponse {
+        metrics,
+        technical_metrics: PRICE_ANALYTICS_METRICS
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+    })
+}
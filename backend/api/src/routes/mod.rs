@@ -9,11 +9,20 @@ use utoipa_swagger_ui::SwaggerUi;
 use crate::state::AppState;
 
 pub mod auth;
+pub mod capital_allocation;
 pub mod companies;
+pub mod compare;
+pub mod derived_metrics;
+pub mod formulas;
 pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod price_analytics;
+pub mod prices;
 pub mod screeners;
 pub mod tracker;
 pub mod users;
+pub mod valuation;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -22,6 +31,9 @@ pub mod users;
         auth::login,
         auth::refresh_token,
         auth::logout,
+        companies::list_companies,
+        companies::search_companies,
+        companies::create_company,
         companies::get_company_details,
         companies::get_company_metrics,
         companies::get_company_documents,
@@ -29,6 +41,18 @@ pub mod users;
         companies::get_document_download_url,
         companies::get_verdict,
         companies::update_verdict,
+        companies::get_company_data_events,
+        capital_allocation::get_capital_allocation,
+        price_analytics::get_price_analytics,
+        prices::get_price_history,
+        derived_metrics::get_derived_metric_series,
+        compare::compare_companies,
+        jobs::get_job_status,
+        metrics::get_metric_catalog,
+        formulas::list_formulas,
+        formulas::create_formula,
+        formulas::update_formula,
+        formulas::delete_formula,
         screeners::list_screeners,
         screeners::create_screener,
         screeners::get_screener,
@@ -37,6 +61,11 @@ pub mod users;
         screeners::run_screener,
         tracker::list_verdicts,
         tracker::get_summary,
+        valuation::get_company_dcf,
+        valuation::list_assumption_sets,
+        valuation::create_assumption_set,
+        valuation::update_assumption_set,
+        valuation::delete_assumption_set,
         // More paths added as we implement them
     ),
     components(schemas(
@@ -47,11 +76,18 @@ pub mod users;
         auth::RefreshRequest,
         auth::RefreshResponse,
         auth::LogoutRequest,
+        companies::CompanyListResponse,
+        companies::CompanySearchResponse,
+        companies::CompanySummaryOut,
+        companies::CreateCompanyRequest,
+        companies::CompanyOnboardingResponse,
         companies::CompanyDetailsResponse,
         companies::MetricsResponse,
         companies::MetricsSections,
         companies::MetricRow,
         companies::MetricValueOut,
+        companies::PeerGroupOut,
+        companies::PeriodOut,
         companies::DocumentsResponse,
         companies::DocumentOut,
         companies::FreshnessMetadata,
@@ -60,6 +96,20 @@ pub mod users;
         companies::VerdictResponse,
         companies::VerdictUpdateRequest,
         companies::LinkedReport,
+        companies::DataEventsResponse,
+        companies::DataEventOut,
+        compare::CompareResponse,
+        compare::ComparedCompany,
+        jobs::JobStatusResponse,
+        jobs::JobStepOut,
+        formulas::FormulaResponse,
+        formulas::CreateFormulaRequest,
+        formulas::UpdateFormulaRequest,
+        metrics::MetricCatalogResponse,
+        metrics::MetricCatalogEntry,
+        domain::metrics::registry::MetricSection,
+        domain::metrics::registry::MetricFormat,
+        domain::metrics::registry::HeatMapDirection,
         domain::periods::FiscalPeriod,
         domain::periods::PeriodType,
         screeners::ScreenerResponse,
@@ -69,18 +119,51 @@ pub mod users;
         screeners::RunScreenerRequest,
         domain::services::screener_service::ScreenerResult,
         domain::services::screener_service::FilterCriteria,
+        domain::services::screener_service::FormulaFilter,
+        domain::services::screener_service::TechnicalFilter,
         tracker::TrackerSummaryResponse,
         tracker::RecentActivityOut,
         tracker::VerdictListResponse,
         tracker::TrackerItemOut,
+        capital_allocation::CapitalAllocationResponse,
+        capital_allocation::CapitalAllocationPeriod,
+        domain::metrics::capital_allocation::CapitalAllocation,
+        domain::metrics::capital_allocation::AllocationLine,
+        domain::metrics::capital_allocation::ShareholderYield,
+        price_analytics::PriceAnalyticsResponse,
+        domain::metrics::price_analytics::PriceAnalytics,
+        prices::PriceHistoryResponse,
+        prices::PriceMarker,
+        prices::PriceMarkerKind,
+        domain::markets::candles::Candle,
+        domain::markets::candles::CandleInterval,
+        derived_metrics::DerivedMetricsResponse,
+        derived_metrics::DerivedMetricSeries,
+        derived_metrics::DerivedMetricPoint,
+        valuation::DcfResponse,
+        valuation::ReverseDcfOut,
+        valuation::AssumptionSetResponse,
+        valuation::CreateAssumptionSetRequest,
+        valuation::UpdateAssumptionSetRequest,
+        domain::valuation::dcf::DcfBase,
+        domain::valuation::dcf::DcfAssumptions,
+        domain::valuation::dcf::DcfAssumptionOverrides,
+        domain::valuation::dcf::DcfProjectionYear,
+        domain::valuation::dcf::DcfResult,
+        domain::valuation::dcf::SensitivityGrid,
+        domain::valuation::dcf::DerivedPaths,
     )),
     tags(
         (name = "health", description = "Health check endpoints"),
         (name = "auth", description = "Authentication endpoints"),
         (name = "companies", description = "Company data endpoints"),
+        (name = "jobs", description = "Background job status endpoints"),
+        (name = "metrics", description = "Metric catalog endpoints"),
+        (name = "formulas", description = "Custom formula endpoints"),
         (name = "screeners", description = "Screener endpoints"),
         (name = "verdicts", description = "Verdict endpoints"),
         (name = "tracker", description = "Results tracker endpoints"),
+        (name = "valuation", description = "Intrinsic value endpoints"),
     )
 )]
 pub struct ApiDoc;
@@ -105,9 +188,13 @@ fn protected_routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/auth/logout", post(auth::logout))
         .nest("/companies", companies::companies_router())
+        .nest("/jobs", jobs::jobs_router())
+        .nest("/metrics", metrics::metrics_router())
+        .nest("/formulas", formulas::formulas_router())
         .nest("/screeners", screeners::screeners_router())
         .nest("/tracker", tracker::tracker_router())
         .nest("/users/me", users::user_router())
+        .nest("/valuation", valuation::valuation_router())
         .layer(axum::middleware::from_fn_with_state(
             state,
             crate::middleware::auth::auth_middleware,
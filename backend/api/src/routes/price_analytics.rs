@@ -0,0 +1,92 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::Duration;
+use db::repositories::CompanyRepository;
+use domain::domain::DailyPrice;
+use domain::metrics::price_analytics::{
+    benchmark_symbol, price_series, PriceAnalytics, LOOKBACK_DAYS, YEAR_DAYS,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// DTOs for API Documentation
+
+#[derive(Serialize, ToSchema)]
+pub struct PriceAnalyticsResponse {
+    pub company_id: Uuid,
+    pub currency: String,
+    /// Symbol beta is measured against
+    pub benchmark_symbol: String,
+    /// Null when no prices are stored
+    pub analytics: Option<PriceAnalytics>,
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/{id}/price-analytics",
+    params(
+        ("id" = Uuid, Path, description = "Company ID")
+    ),
+    responses(
+        (status = 200, description = "Moving averages, 52-week range, volatility, drawdown, beta and relative strength as of the latest close", body = PriceAnalyticsResponse),
+        (status = 404, description = "Company not found")
+    ),
+    tag = "companies"
+)]
+pub async fn get_price_analytics(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let repo = CompanyRepository::new(state.db.clone());
+    let internal = |e: db::DbError| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+
+    let company = repo
+        .find_by_id(id)
+        .await
+        .map_err(internal)?
+        .ok_or((StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+
+    let benchmark_symbol = benchmark_symbol();
+    let analytics = match repo.get_latest_price(id).await.map_err(internal)? {
+        Some(latest) => {
+            let end = latest.price_date;
+            let start = end - Duration::days(LOOKBACK_DAYS);
+            let prices = repo
+                .get_daily_prices(id, start, end)
+                .await
+                .map_err(internal)?;
+            let benchmark = repo
+                .get_daily_prices_by_symbol(&benchmark_symbol, start, end)
+                .await
+                .map_err(internal)?;
+            let peer_returns = repo
+                .get_sector_peer_returns(id, end - Duration::days(YEAR_DAYS), end)
+                .await
+                .map_err(internal)?;
+
+            let prices: Vec<DailyPrice> = prices.iter().map(DailyPrice::from).collect();
+            let benchmark: Vec<DailyPrice> = benchmark.iter().map(DailyPrice::from).collect();
+            PriceAnalytics::compute(
+                &price_series(&prices),
+                &price_series(&benchmark),
+                &peer_returns,
+            )
+        }
+        None => None,
+    };
+
+    Ok(Json(PriceAnalyticsResponse {
+        company_id: id,
+        currency: company.currency.unwrap_or_else(|| "USD".to_string()),
+        benchmark_symbol,
+        analytics,
+    }))
+}
@@ -0,0 +1,152 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use db::repositories::CompanyRepository;
+use domain::domain::DailyPrice;
+use domain::markets::candles::{resample, Candle, CandleInterval};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+// DTOs for API Documentation
+
+#[derive(Deserialize, IntoParams)]
+pub struct PriceHistoryQueryParams {
+    /// First date, inclusive; defaults to a year before `to`
+    pub from: Option<NaiveDate>,
+    /// Last date, inclusive; defaults to today
+    pub to: Option<NaiveDate>,
+    /// "day" (default), "week" or "month"
+    #[serde(default)]
+    #[param(inline)]
+    pub interval: CandleInterval,
+    /// Scale prices by the adjusted close so splits and dividends don't show
+    /// as gaps; rejected when a session in the range has no adjusted close
+    #[serde(default)]
+    pub adjusted: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PriceHistoryResponse {
+    pub company_id: Uuid,
+    pub currency: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub interval: CandleInterval,
+    pub adjusted: bool,
+    /// Oldest first
+    pub candles: Vec<Candle>,
+    /// Period ends and earnings reports within the range, oldest first
+    pub markers: Vec<PriceMarker>,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceMarkerKind {
+    PeriodEnd,
+    Earnings,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PriceMarker {
+    pub date: NaiveDate,
+    pub kind: PriceMarkerKind,
+    /// e.g. "Q3 FY2024", "FY2024" or "Earnings"
+    pub label: String,
+    /// "annual" or "quarterly" for period ends
+    pub period_type: Option<String>,
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/{id}/prices",
+    params(
+        ("id" = Uuid, Path, description = "Company ID"),
+        PriceHistoryQueryParams
+    ),
+    responses(
+        (status = 200, description = "OHLCV candles with period end and earnings markers", body = PriceHistoryResponse),
+        (status = 400, description = "Invalid date range, or adjusted candles for a range without adjusted closes"),
+        (status = 404, description = "Company not found")
+    ),
+    tag = "companies"
+)]
+pub async fn get_price_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<PriceHistoryQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let repo = CompanyRepository::new(state.db.clone());
+    let internal = |e: db::DbError| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params.from.unwrap_or(to - Duration::days(365));
+    if from > to {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "from must not be after to".to_string(),
+        ));
+    }
+
+    let company = repo
+        .find_by_id(id)
+        .await
+        .map_err(internal)?
+        .ok_or((StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+
+    let prices: Vec<DailyPrice> = repo
+        .get_daily_prices(id, from, to)
+        .await
+        .map_err(internal)?
+        .iter()
+        .map(DailyPrice::from)
+        .collect();
+    let candles = resample(&prices, params.interval, params.adjusted).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Adjusted closes are missing for part of the range; request adjusted=false".to_string(),
+    ))?;
+
+    let mut markers: Vec<PriceMarker> = repo
+        .get_statement_periods(id, from, to)
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|p| PriceMarker {
+            date: p.period_end_date,
+            kind: PriceMarkerKind::PeriodEnd,
+            label: p.label(),
+            period_type: Some(p.period_type),
+        })
+        .collect();
+    markers.extend(
+        repo.get_earnings_events(id, from, to)
+            .await
+            .map_err(internal)?
+            .into_iter()
+            .map(|e| PriceMarker {
+                date: e.report_date,
+                kind: PriceMarkerKind::Earnings,
+                label: "Earnings".to_string(),
+                period_type: None,
+            }),
+    );
+    markers.sort_by(|a, b| (a.date, &a.kind).cmp(&(b.date, &b.kind)));
+
+    Ok(Json(PriceHistoryResponse {
+        company_id: id,
+        currency: company.currency.unwrap_or_else(|| "USD".to_string()),
+        from,
+        to,
+        interval: params.interval,
+        adjusted: params.adjusted,
+        candles,
+        markers,
+    }))
+}
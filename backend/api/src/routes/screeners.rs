@@ -1,4 +1,5 @@
 use crate::auth::jwt::Claims;
+use crate::routes::companies::load_metric_histories;
 use crate::state::AppState;
 use axum::{
     extract::{Path, State},
@@ -10,7 +11,11 @@ use axum::{
 use chrono::{DateTime, Utc};
 use db::models::screener::Screener;
 use db::repositories::screener_repository::{CreateScreener, ScreenerRepository, UpdateScreener};
-use domain::services::screener_service::{FilterCriteria, ScreenerResult, ScreenerService};
+use db::repositories::{CompanyRepository, CustomFormulaRepository};
+use domain::metrics::formula::{Formula, MAX_FORMULA_WINDOW};
+use domain::services::screener_service::{
+    FilterCriteria, FormulaFilter, ScreenerResult, ScreenerService, SCREENER_RESULT_LIMIT,
+};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
@@ -291,6 +296,7 @@ pub async fn delete_screener(
     request_body = Option<RunScreenerRequest>,
     responses(
         (status = 200, description = "Screener results", body = ScreenerResultsResponse),
+        (status = 400, description = "Unknown formula or technical metric"),
         (status = 404, description = "Screener not found")
     ),
     tag = "screeners"
@@ -337,12 +343,31 @@ pub async fn run_screener(
         })?
     };
 
-    // 3. Execute screener
+    criteria
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    // 3. Execute screener. Formula filters apply to every candidate before
+    // the result limit, so matches outside the largest companies aren't lost.
+    let formula_filters = criteria.formula_filters.clone().unwrap_or_default();
     let service = ScreenerService::new(state.db.clone());
-    let results = service
-        .execute(criteria)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let results = if formula_filters.is_empty() {
+        service
+            .execute(criteria)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        let candidates = service
+            .candidates(&criteria)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        // 4. Apply custom formula filters
+        let mut matched =
+            apply_formula_filters(&state, user_id, &formula_filters, candidates).await?;
+        matched.truncate(SCREENER_RESULT_LIMIT);
+        matched
+    };
 
     let response = ScreenerResultsResponse {
         screener_id: id,
@@ -353,3 +378,67 @@ pub async fn run_screener(
 
     Ok(Json(response))
 }
+
+/// Evaluate each filter's saved formula on every candidate's latest quarter
+/// and keep the companies where all of them fall within bounds, in order.
+/// Companies without quarterly statements have no values and never match.
+async fn apply_formula_filters(
+    state: &AppState,
+    user_id: Uuid,
+    filters: &[FormulaFilter],
+    results: Vec<ScreenerResult>,
+) -> Result<Vec<ScreenerResult>, (StatusCode, String)> {
+    let saved = CustomFormulaRepository::new(state.db.clone())
+        .list_by_user(user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut formulas = Vec::new();
+    for filter in filters {
+        let stored = saved.iter().find(|f| f.name == filter.name).ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown formula '{}'", filter.name),
+        ))?;
+        let formula = Formula::parse(&stored.expression).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Formula '{}' is invalid: {}", filter.name, e),
+            )
+        })?;
+        formulas.push((filter, formula));
+    }
+
+    // Enough history for the widest lag/avg window, for every candidate at once
+    let companies: Vec<(Uuid, &str)> = results
+        .iter()
+        .map(|r| (r.company_id, r.exchange.as_str()))
+        .collect();
+    let histories = load_metric_histories(
+        &CompanyRepository::new(state.db.clone()),
+        &companies,
+        true,
+        MAX_FORMULA_WINDOW + 1,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut matched = Vec::new();
+    for mut result in results {
+        let Some(history) = histories.get(&result.company_id) else {
+            continue;
+        };
+        let inputs = history.inputs("$");
+
+        let mut passes = true;
+        for (filter, formula) in &formulas {
+            let latest = formula.evaluate(&inputs).last().and_then(|v| v.value);
+            passes &= filter.matches(latest);
+            result.formula_values.insert(filter.name.clone(), latest);
+        }
+        if passes {
+            matched.push(result);
+        }
+    }
+
+    Ok(matched)
+}
@@ -0,0 +1,491 @@
+use crate::auth::jwt::Claims;
+use crate::routes::companies::load_metric_history;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, put},
+    Json, Router,
+};
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, NaiveDate, Utc};
+use db::models::DcfAssumptionSet;
+use db::repositories::{
+    CompanyRepository, CreateDcfAssumptionSet, DcfAssumptionSetRepository, UpdateDcfAssumptionSet,
+};
+use db::DbError;
+use domain::periods::same_period;
+use domain::valuation::dcf::{
+    derive_paths, reverse_dcf, run_dcf, sensitivity_grid, DcfAssumptionOverrides, DcfAssumptions,
+    DcfBase, DcfResult, DerivedPaths, SensitivityGrid,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Annual periods loaded for the DCF: enough for the 5y CAGR behind the
+/// derived growth path and the 3y average FCF margin.
+const DCF_HISTORY_YEARS: usize = 5;
+
+pub fn valuation_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/assumptions",
+            get(list_assumption_sets).post(create_assumption_set),
+        )
+        .route(
+            "/assumptions/:id",
+            put(update_assumption_set).delete(delete_assumption_set),
+        )
+}
+
+// DTOs for API Documentation
+
+#[derive(Deserialize, IntoParams)]
+pub struct DcfQueryParams {
+    /// Saved assumption set to start from
+    pub assumption_set_id: Option<Uuid>,
+    pub projection_years: Option<usize>,
+    /// Discount rate, % (e.g. 9.0)
+    pub wacc: Option<f64>,
+    /// Perpetual growth after the projection, %
+    pub terminal_growth: Option<f64>,
+    /// Comma-separated revenue growth path, % per year (e.g. "12,10,8")
+    pub revenue_growth: Option<String>,
+    /// Comma-separated FCF margin path, % of revenue
+    pub fcf_margin: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DcfResponse {
+    pub company_id: Uuid,
+    pub currency: String,
+    /// Fiscal year end of the statements the projection starts from
+    pub base_period_end: NaiveDate,
+    pub base: DcfBase,
+    /// Assumptions after layering request, saved set and derived values
+    pub assumptions: DcfAssumptions,
+    /// Paths derived from history; null when history is too short
+    pub derived: Option<DerivedPaths>,
+    pub result: DcfResult,
+    pub sensitivity: SensitivityGrid,
+    pub reverse_dcf: Option<ReverseDcfOut>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReverseDcfOut {
+    pub price: f64,
+    pub price_date: NaiveDate,
+    /// Constant revenue growth that justifies the price, %; null if none in [-50%, 100%]
+    pub implied_revenue_growth: Option<f64>,
+    /// Intrinsic value relative to price, %
+    pub upside: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AssumptionSetResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub assumptions: DcfAssumptionOverrides,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<DcfAssumptionSet> for AssumptionSetResponse {
+    type Error = (StatusCode, String);
+
+    /// Stored assumptions that no longer parse are a server-side fault;
+    /// valuing with defaults instead would silently ignore the user's set
+    fn try_from(s: DcfAssumptionSet) -> Result<Self, Self::Error> {
+        let assumptions = serde_json::from_value(s.assumptions).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Assumption set {} has malformed assumptions: {}", s.id, e),
+            )
+        })?;
+        Ok(Self {
+            id: s.id,
+            name: s.name,
+            description: s.description,
+            assumptions,
+            created_at: s.created_at,
+            updated_at: s.updated_at,
+        })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateAssumptionSetRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub assumptions: DcfAssumptionOverrides,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateAssumptionSetRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub assumptions: Option<DcfAssumptionOverrides>,
+}
+
+fn parse_path(param: &str, raw: &str) -> Result<Vec<f64>, (StatusCode, String)> {
+    raw.split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("{} must be a comma-separated list of numbers", param),
+            )
+        })
+}
+
+fn validate_assumptions(assumptions: &DcfAssumptionOverrides) -> Result<(), (StatusCode, String)> {
+    assumptions
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+fn map_db_error(e: DbError) -> (StatusCode, String) {
+    match e {
+        DbError::DuplicateError(_) => (
+            StatusCode::CONFLICT,
+            "An assumption set with this name already exists".to_string(),
+        ),
+        other => (StatusCode::INTERNAL_SERVER_ERROR, other.to_string()),
+    }
+}
+
+// Handlers
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/companies/{id}/valuation/dcf",
+    params(
+        ("id" = Uuid, Path, description = "Company ID"),
+        DcfQueryParams
+    ),
+    responses(
+        (status = 200, description = "DCF valuation with sensitivity grid", body = DcfResponse),
+        (status = 400, description = "Invalid assumptions"),
+        (status = 404, description = "Company or assumption set not found"),
+        (status = 422, description = "Not enough stored history to value the company"),
+        (status = 500, description = "Saved assumption set is malformed")
+    ),
+    tag = "valuation"
+)]
+pub async fn get_company_dcf(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<Uuid>,
+    Query(params): Query<DcfQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let repo = CompanyRepository::new(state.db.clone());
+
+    // 1. Fetch company
+    let company = repo
+        .find_by_id(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Company not found".to_string()))?;
+
+    // 2. Layer assumptions: request params over the saved set
+    let requested = DcfAssumptionOverrides {
+        projection_years: params.projection_years,
+        revenue_growth: params
+            .revenue_growth
+            .as_deref()
+            .map(|raw| parse_path("revenue_growth", raw))
+            .transpose()?,
+        fcf_margin: params
+            .fcf_margin
+            .as_deref()
+            .map(|raw| parse_path("fcf_margin", raw))
+            .transpose()?,
+        wacc: params.wacc,
+        terminal_growth: params.terminal_growth,
+    };
+
+    let saved = match params.assumption_set_id {
+        Some(set_id) => {
+            let set = DcfAssumptionSetRepository::new(state.db.clone())
+                .find_by_id(set_id, user_id)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .ok_or((
+                    StatusCode::NOT_FOUND,
+                    "Assumption set not found".to_string(),
+                ))?;
+            AssumptionSetResponse::try_from(set)?.assumptions
+        }
+        None => DcfAssumptionOverrides::default(),
+    };
+
+    // 3. Starting point from the latest annual statements
+    let insufficient = |what: &str| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("No {} in the latest annual statements", what),
+        )
+    };
+
+    let latest_income = repo
+        .get_income_statements(id, "annual", 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| insufficient("income statement"))?;
+    let latest_balance = repo
+        .get_balance_sheets(id, "annual", 1)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .find(|b| same_period(b.period_end_date, latest_income.period_end_date));
+
+    let revenue = latest_income
+        .total_revenue
+        .as_ref()
+        .and_then(|v| v.to_f64())
+        .ok_or_else(|| insufficient("revenue"))?;
+    let shares_outstanding = latest_income
+        .shares_outstanding
+        .map(|s| s as f64)
+        .ok_or_else(|| insufficient("shares outstanding"))?;
+    // Missing balance sheet data is treated as zero net debt
+    let net_debt = latest_balance
+        .and_then(|b| {
+            b.net_debt.as_ref().and_then(|v| v.to_f64()).or_else(|| {
+                let total_debt = b.total_debt.as_ref().and_then(|v| v.to_f64())?;
+                let cash = b.cash_and_equivalents.as_ref().and_then(|v| v.to_f64());
+                Some(total_debt - cash.unwrap_or(0.0))
+            })
+        })
+        .unwrap_or(0.0);
+
+    let base = DcfBase {
+        revenue,
+        net_debt,
+        shares_outstanding,
+    };
+
+    // 4. Resolve remaining assumptions from history and run the model
+    let history = load_metric_history(&repo, &company, false, DCF_HISTORY_YEARS)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let derived = derive_paths(&history.annual_incomes, &history.annual_cash_flows);
+    let assumptions = requested
+        .or(saved)
+        .resolve(derived.as_ref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let result =
+        run_dcf(&base, &assumptions).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let sensitivity = sensitivity_grid(&base, &assumptions);
+
+    // 5. Reverse DCF against the latest close, when there is one
+    let latest_price = repo
+        .get_latest_price(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let reverse = latest_price.and_then(|p| {
+        let price = p.close.as_ref().and_then(|v| v.to_f64())?;
+        if price <= 0.0 {
+            return None;
+        }
+        Some(ReverseDcfOut {
+            price,
+            price_date: p.price_date,
+            implied_revenue_growth: reverse_dcf(&base, &assumptions, price),
+            upside: (result.intrinsic_value_per_share / price - 1.0) * 100.0,
+        })
+    });
+
+    let response = DcfResponse {
+        company_id: id,
+        currency: company.currency.unwrap_or_else(|| "USD".to_string()),
+        base_period_end: latest_income.period_end_date,
+        base,
+        assumptions,
+        derived,
+        result,
+        sensitivity,
+        reverse_dcf: reverse,
+    };
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/valuation/assumptions",
+    responses(
+        (status = 200, description = "List saved DCF assumption sets", body = Vec<AssumptionSetResponse>),
+        (status = 500, description = "A saved assumption set is malformed")
+    ),
+    tag = "valuation"
+)]
+pub async fn list_assumption_sets(
+    State(state): State<AppState>,
+    claims: Claims,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let repo = DcfAssumptionSetRepository::new(state.db.clone());
+    let sets = repo
+        .list_by_user(user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let response = sets
+        .into_iter()
+        .map(AssumptionSetResponse::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/valuation/assumptions",
+    request_body = CreateAssumptionSetRequest,
+    responses(
+        (status = 201, description = "Assumption set created", body = AssumptionSetResponse),
+        (status = 400, description = "Invalid assumptions"),
+        (status = 409, description = "Assumption set name already in use")
+    ),
+    tag = "valuation"
+)]
+pub async fn create_assumption_set(
+    State(state): State<AppState>,
+    claims: Claims,
+    Json(payload): Json<CreateAssumptionSetRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    validate_assumptions(&payload.assumptions)?;
+
+    let repo = DcfAssumptionSetRepository::new(state.db.clone());
+    let set = repo
+        .create(
+            user_id,
+            CreateDcfAssumptionSet {
+                name: payload.name,
+                description: payload.description,
+                assumptions: serde_json::to_value(&payload.assumptions)
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+            },
+        )
+        .await
+        .map_err(map_db_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(AssumptionSetResponse::try_from(set)?),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/valuation/assumptions/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Assumption set ID")
+    ),
+    request_body = UpdateAssumptionSetRequest,
+    responses(
+        (status = 200, description = "Assumption set updated", body = AssumptionSetResponse),
+        (status = 400, description = "Invalid assumptions"),
+        (status = 404, description = "Assumption set not found"),
+        (status = 409, description = "Assumption set name already in use")
+    ),
+    tag = "valuation"
+)]
+pub async fn update_assumption_set(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateAssumptionSetRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    if let Some(assumptions) = &payload.assumptions {
+        validate_assumptions(assumptions)?;
+    }
+
+    let repo = DcfAssumptionSetRepository::new(state.db.clone());
+
+    let existing = repo
+        .find_by_id(id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if existing.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "Assumption set not found".to_string(),
+        ));
+    }
+
+    let updated = repo
+        .update(
+            id,
+            user_id,
+            UpdateDcfAssumptionSet {
+                name: payload.name,
+                description: payload.description,
+                assumptions: payload
+                    .assumptions
+                    .map(|a| serde_json::to_value(&a))
+                    .transpose()
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+            },
+        )
+        .await
+        .map_err(map_db_error)?;
+
+    Ok(Json(AssumptionSetResponse::try_from(updated)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/valuation/assumptions/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Assumption set ID")
+    ),
+    responses(
+        (status = 204, description = "Assumption set deleted"),
+        (status = 404, description = "Assumption set not found")
+    ),
+    tag = "valuation"
+)]
+pub async fn delete_assumption_set(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let repo = DcfAssumptionSetRepository::new(state.db.clone());
+    let deleted = repo
+        .delete(id, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if deleted {
+        Ok((StatusCode::NO_CONTENT, ()))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            "Assumption set not found".to_string(),
+        ))
+    }
+}
@@ -1,5 +1,9 @@
 use api::auth::password::hash_password;
 use api::{create_router, AppState, Config};
+use bigdecimal::BigDecimal;
+use db::repositories::{
+    CompanyRepository, DataEventRepository, DerivedMetricFilters, IncomeStatementInsert,
+};
 use reqwest::{Client, StatusCode};
 use serde_json::{json, Value};
 use sqlx::postgres::PgPoolOptions;
@@ -56,6 +60,7 @@ async fn spawn_app() -> (String, sqlx::PgPool) {
         s3_endpoint: "http://localhost:9000".to_string(),
         s3_access_key: "minioadmin".to_string(),
         s3_secret_key: "minioadmin".to_string(),
+        trading_calendars_path: None,
         environment: api::config::Environment::Development,
     });
 
@@ -274,6 +279,185 @@ async fn test_get_nonexistent_company_returns_404() {
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_list_companies_applies_filters_and_pagination() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (active_id, _) = setup_company(&pool).await;
+    let (inactive_id, _) = setup_company(&pool).await;
+
+    // A country no other company has, so the filter isolates these two
+    let country = format!("T{}", &Uuid::new_v4().simple().to_string()[..8]);
+    sqlx::query("UPDATE companies SET country = $1, market_cap = 2000 WHERE id = $2")
+        .bind(&country)
+        .bind(active_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "UPDATE companies SET country = $1, market_cap = 1000, is_active = false WHERE id = $2",
+    )
+    .bind(&country)
+    .bind(inactive_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let list = |query: String| {
+        client
+            .get(format!("{}/api/v1/companies?{}", base_url, query))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    // Country alone used to bind into a missing positional parameter
+    let resp = list(format!("country={}&limit=1", country)).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["companies"].as_array().unwrap().len(), 1);
+    assert_eq!(body["companies"][0]["id"], active_id.to_string());
+
+    let body: Value = list(format!("country={}&limit=1&offset=1", country))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["companies"][0]["id"], inactive_id.to_string());
+
+    let body: Value = list(format!(
+        "exchange=NASDAQ&country={}&is_active=false",
+        country
+    ))
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["companies"][0]["is_active"], false);
+
+    cleanup_test_company(&pool, active_id).await;
+    cleanup_test_company(&pool, inactive_id).await;
+}
+
+#[tokio::test]
+async fn test_search_companies_by_ticker_prefix_and_similar_name() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, symbol) = setup_company(&pool).await;
+
+    let name = format!("Quizzical Widgets {}", &symbol[5..]);
+    sqlx::query("UPDATE companies SET name = $1 WHERE id = $2")
+        .bind(&name)
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let search = |q: String| {
+        client
+            .get(format!("{}/api/v1/companies/search", base_url))
+            .query(&[("q", q)])
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    // Lowercase ticker prefix
+    let resp = search(symbol[..9].to_lowercase()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["results"][0]["id"], company_id.to_string());
+
+    // Misspelled name falls back to trigram similarity
+    let body: Value = search(name.replace("Quizzical", "Quizzicle"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<&str> = body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["id"].as_str().unwrap())
+        .collect();
+    assert!(ids.contains(&company_id.to_string().as_str()));
+
+    let resp = search("  ".to_string()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_create_company_queues_backfill_with_job_status() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let symbol = format!("ONB{}", &Uuid::new_v4().simple().to_string()[..8]);
+
+    let create = |exchange: &str| {
+        client
+            .post(format!("{}/api/v1/companies", base_url))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "symbol": symbol, "exchange": exchange }))
+            .send()
+    };
+
+    let resp = create("nyse").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let body: Value = resp.json().await.unwrap();
+    let company_id = Uuid::parse_str(body["company"]["id"].as_str().unwrap()).unwrap();
+    assert_eq!(body["company"]["symbol"], symbol.to_uppercase());
+    assert_eq!(body["company"]["exchange"], "NYSE");
+    let job_id = body["backfill_job"]["id"].as_str().unwrap().to_string();
+
+    // Overview fields land on the company row
+    let (fiscal_year_end_month, sector_id): (Option<i32>, Option<Uuid>) =
+        sqlx::query_as("SELECT fiscal_year_end_month, sector_id FROM companies WHERE id = $1")
+            .bind(company_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(fiscal_year_end_month, Some(12));
+    assert!(sector_id.is_some());
+
+    let resp = client
+        .get(format!("{}/api/v1/jobs/{}", base_url, job_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let job: Value = resp.json().await.unwrap();
+    assert_eq!(job["job_type"], "company_backfill");
+    assert_eq!(job["company_id"], company_id.to_string());
+    assert_eq!(job["status"], "pending");
+    let steps: Vec<&str> = job["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["step"].as_str().unwrap())
+        .collect();
+    assert_eq!(steps, vec!["statements", "prices", "documents"]);
+
+    let resp = create("NYSE").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // The provider lists the symbol on NYSE
+    let resp = create("NASDAQ").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = create("NOWHERE").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
 // -----------------------------------------------------------------------------
 // Metrics Tests
 // -----------------------------------------------------------------------------
@@ -341,6 +525,1563 @@ async fn test_get_metrics_returns_annual_data() {
     cleanup_test_company(&pool, company_id).await;
 }
 
+#[tokio::test]
+async fn test_get_metrics_matches_statements_on_week_based_calendar() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    sqlx::query(
+        "UPDATE companies SET fiscal_year_end_month = 9, fiscal_calendar = 'last_sat_sep' WHERE id = $1",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    // The cash flow statement is dated a day after the income statement
+    sqlx::query(
+        r#"
+        INSERT INTO income_statements (id, company_id, period_end_date, period_type, fiscal_year, total_revenue, created_at)
+        VALUES ($1, $2, '2023-09-30', 'annual', 2023, 1000000, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO cash_flow_statements (id, company_id, period_end_date, period_type, fiscal_year, operating_cash_flow, capital_expenditures, created_at)
+        VALUES ($1, $2, '2023-10-01', 'annual', 2023, 300000, 50000, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!("{}/api/v1/companies/{}", base_url, company_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["fiscal_calendar"], "last_sat_sep");
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=annual",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let fcf_margin = body["sections"]["cash_and_leverage"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "fcf_margin")
+        .expect("fcf_margin row");
+    let latest = fcf_margin["values"].as_array().unwrap().last().unwrap();
+    assert_eq!(latest["value"], 25.0);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_get_metrics_aligns_values_to_periods_with_gaps() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    // Q1 is reported, Q2 is missing, Q3 and Q4 come from setup_company
+    sqlx::query(
+        r#"
+        INSERT INTO income_statements (id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter, total_revenue, created_at)
+        VALUES ($1, $2, '2023-03-31', 'quarterly', 2023, 1, 800000, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly&period_count=4",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+
+    assert_eq!(
+        body["periods"],
+        json!(["Q1 2023", "Q2 2023", "Q3 2023", "FY2023"])
+    );
+    assert!(body["period_details"][1]["statement_date"].is_null());
+    assert_eq!(body["period_details"][2]["statement_date"], "2023-09-30");
+
+    let revenue = body["sections"]["growth_and_margins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "revenue")
+        .expect("revenue row");
+    let values: Vec<Value> = revenue["values"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["value"].clone())
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            json!(800000.0),
+            Value::Null,
+            json!(900000.0),
+            json!(1000000.0)
+        ]
+    );
+    assert_eq!(revenue["values"][1]["formatted"], "N/A");
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_get_metrics_ranks_latest_period_against_industry_peers() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+    let (peer_id, _) = setup_company(&pool).await;
+
+    // Isolate both companies in an industry of their own
+    let industry = format!("Peer Test {}", Uuid::new_v4());
+    sqlx::query("UPDATE companies SET industry = $1 WHERE id = ANY($2)")
+        .bind(&industry)
+        .bind(vec![company_id, peer_id])
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO derived_metrics (company_id, period_end_date, period_type, metric_name, metric_value)
+        VALUES ($1, '2023-12-31', 'quarterly', 'net_margin_pct', 10.0)
+        "#,
+    )
+    .bind(peer_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly&peer_group=industry",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["peer_group"]["basis"], "industry");
+    assert_eq!(body["peer_group"]["name"], industry.as_str());
+    assert_eq!(body["peer_group"]["company_count"], 2);
+
+    // Net margin of 20% ranks above the single peer at 10%
+    let net_margin = body["sections"]["growth_and_margins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "net_margin")
+        .expect("net_margin row");
+    let latest = net_margin["values"].as_array().unwrap().last().unwrap();
+    assert_eq!(latest["peer_percentile"], 75.0);
+    assert_eq!(latest["heat_map_quartile"], 3);
+
+    // On fiscal periods the peer's value for the company's latest period is
+    // used, not a later one the peer has already reported
+    sqlx::query(
+        r#"
+        INSERT INTO derived_metrics (company_id, period_end_date, period_type, metric_name, metric_value)
+        VALUES ($1, '2024-03-31', 'quarterly', 'net_margin_pct', 30.0)
+        "#,
+    )
+    .bind(peer_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly&peer_group=industry&calendarized=false",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["peer_group"]["calendarized"], false);
+    assert_eq!(body["peer_group"]["period_end_date"], "2023-12-31");
+    let net_margin = body["sections"]["growth_and_margins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "net_margin")
+        .expect("net_margin row");
+    let latest = net_margin["values"].as_array().unwrap().last().unwrap();
+    assert_eq!(latest["peer_percentile"], 75.0);
+
+    for peer_group in ["country", "sector"] {
+        let resp = client
+            .get(format!(
+                "{}/api/v1/companies/{}/metrics?peer_group={}",
+                base_url, company_id, peer_group
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    cleanup_test_company(&pool, company_id).await;
+    cleanup_test_company(&pool, peer_id).await;
+}
+
+#[tokio::test]
+async fn test_get_metrics_calendarizes_off_calendar_fiscal_quarters() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+    let (peer_id, _) = setup_company(&pool).await;
+
+    let industry = format!("Calendar Test {}", Uuid::new_v4());
+    sqlx::query("UPDATE companies SET industry = $1 WHERE id = ANY($2)")
+        .bind(&industry)
+        .bind(vec![company_id, peer_id])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // January fiscal year end: quarters end Apr/Jul/Oct/Jan. Revenue runs at
+    // 10, 20 and 30 a day over three 92-day quarters, net margin is 25%.
+    sqlx::query("UPDATE companies SET fiscal_year_end_month = 1 WHERE id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM income_statements WHERE company_id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO income_statements (id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter, total_revenue, net_income, created_at)
+        VALUES
+        (gen_random_uuid(), $1, '2023-07-31', 'quarterly', 2024, 2, 920, 230, NOW()),
+        (gen_random_uuid(), $1, '2023-10-31', 'quarterly', 2024, 3, 1840, 460, NOW()),
+        (gen_random_uuid(), $1, '2024-01-31', 'quarterly', 2024, 4, 2760, 690, NOW())
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Calendar-year peer, ranked on its fiscal Q4 which is CQ4
+    sqlx::query(
+        r#"
+        INSERT INTO derived_metrics (company_id, period_end_date, period_type, metric_name, metric_value)
+        VALUES ($1, '2023-12-31', 'quarterly', 'net_margin_pct', 10.0)
+        "#,
+    )
+    .bind(peer_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly&period_count=2&calendarized=true&peer_group=industry",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["calendarized"], true);
+
+    // CQ1 2024 is only reported through January, so it is left off
+    assert_eq!(body["periods"], json!(["CQ3 2023", "CQ4 2023"]));
+    assert_eq!(body["period_details"][1]["period_start_date"], "2023-10-01");
+    assert_eq!(body["period_details"][1]["period_end_date"], "2023-12-31");
+
+    // CQ3: Jul at 10/day + Aug-Sep at 20/day; CQ4: Oct at 20/day + Nov-Dec at 30/day
+    let revenue = body["sections"]["growth_and_margins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "revenue")
+        .expect("revenue row");
+    let values: Vec<f64> = revenue["values"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["value"].as_f64().unwrap())
+        .collect();
+    assert!(
+        (values[0] - 1530.0).abs() < 1e-6,
+        "CQ3 revenue {}",
+        values[0]
+    );
+    assert!(
+        (values[1] - 2450.0).abs() < 1e-6,
+        "CQ4 revenue {}",
+        values[1]
+    );
+
+    // Ranked against the peer's CQ4 rather than whatever its latest period is
+    assert_eq!(body["peer_group"]["calendarized"], true);
+    assert_eq!(body["peer_group"]["period_end_date"], "2023-12-31");
+    let net_margin = body["sections"]["growth_and_margins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "net_margin")
+        .expect("net_margin row");
+    let latest = net_margin["values"].as_array().unwrap().last().unwrap();
+    assert_eq!(latest["peer_percentile"], 75.0);
+
+    // Without the option the fiscal quarters are shown as reported
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly&period_count=2",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["calendarized"], false);
+    assert_eq!(body["period_details"][1]["period_end_date"], "2024-01-31");
+
+    cleanup_test_company(&pool, company_id).await;
+    cleanup_test_company(&pool, peer_id).await;
+}
+
+fn income_insert(
+    company_id: Uuid,
+    period_end_date: &str,
+    period_type: &str,
+    revenue: i64,
+) -> IncomeStatementInsert {
+    IncomeStatementInsert {
+        company_id,
+        period_end_date: period_end_date.parse().unwrap(),
+        period_type: period_type.to_string(),
+        fiscal_year: None,
+        fiscal_quarter: None,
+        total_revenue: Some(BigDecimal::from(revenue)),
+        cost_of_revenue: None,
+        gross_profit: None,
+        operating_expenses: None,
+        operating_income: None,
+        interest_income: None,
+        interest_expense: None,
+        income_before_tax: None,
+        income_tax_expense: None,
+        net_income: Some(BigDecimal::from(revenue / 5)),
+        depreciation_amortization: None,
+        ebit: None,
+        ebitda: None,
+        basic_eps: None,
+        diluted_eps: None,
+        shares_outstanding: None,
+    }
+}
+
+#[tokio::test]
+async fn test_ingestion_detects_restatements_and_fiscal_year_end_changes() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+    let repo = CompanyRepository::new(pool.clone());
+
+    sqlx::query("UPDATE companies SET fiscal_year_end_month = 12 WHERE id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Q4 2023 revenue restated from 1,000,000 to 950,000; re-ingesting the
+    // same numbers again is not another restatement
+    for _ in 0..2 {
+        repo.upsert_income_statement(income_insert(
+            company_id,
+            "2023-12-31",
+            "quarterly",
+            950_000,
+        ))
+        .await
+        .unwrap();
+    }
+
+    // A line item missing from the feed is a gap: it neither supersedes the
+    // period nor erases the stored value
+    let mut gap = income_insert(company_id, "2023-12-31", "quarterly", 950_000);
+    gap.total_revenue = None;
+    let stored = repo.upsert_income_statement(gap).await.unwrap();
+    assert_eq!(stored.total_revenue, Some(BigDecimal::from(950_000)));
+    let versions: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM income_statements WHERE company_id = $1 AND period_end_date = '2023-12-31'",
+    )
+    .bind(company_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(versions, 2);
+
+    // A line item arriving for the first time is a new version, so views as
+    // of before it arrived don't see it
+    let before_arrival = chrono::Utc::now();
+    let mut arrival = income_insert(company_id, "2023-12-31", "quarterly", 950_000);
+    arrival.operating_income = Some(BigDecimal::from(120_000));
+    repo.upsert_income_statement(arrival).await.unwrap();
+    let q4_operating_income = |statements: Vec<db::models::IncomeStatement>| {
+        statements
+            .into_iter()
+            .find(|s| s.period_end_date == "2023-12-31".parse().unwrap())
+            .unwrap()
+            .operating_income
+    };
+    let known_before = repo
+        .as_of(before_arrival)
+        .get_income_statements(company_id, "quarterly", 10)
+        .await
+        .unwrap();
+    assert_eq!(q4_operating_income(known_before), None);
+    let known_now = repo
+        .get_income_statements(company_id, "quarterly", 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        q4_operating_income(known_now),
+        Some(BigDecimal::from(120_000))
+    );
+
+    let revisions = DataEventRepository::new(pool.clone())
+        .list_statement_revisions(
+            company_id,
+            "income_statement",
+            "2023-12-31".parse().unwrap(),
+            "quarterly",
+        )
+        .await
+        .unwrap();
+    let kinds: Vec<&str> = revisions.iter().map(|r| r.revision_type.as_str()).collect();
+    assert_eq!(kinds, ["as_reported", "restated"]);
+    assert_eq!(revisions[0].line_items["total_revenue"], 1_000_000.0);
+    assert_eq!(revisions[1].line_items["total_revenue"], 950_000.0);
+
+    // A one-month quarter after Q4 is a stub and gets no YoY comparison, even
+    // though a statement a year earlier exists
+    sqlx::query(
+        r#"
+        INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue, created_at)
+        VALUES (gen_random_uuid(), $1, '2023-01-31', 'quarterly', 500000, NOW())
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let stub = repo
+        .upsert_income_statement(income_insert(
+            company_id,
+            "2024-01-31",
+            "quarterly",
+            400_000,
+        ))
+        .await
+        .unwrap();
+    assert!(stub.is_transitional);
+
+    // A March annual period after the December one moves the fiscal year end
+    let transition_year = repo
+        .upsert_income_statement(income_insert(company_id, "2023-03-31", "annual", 900_000))
+        .await
+        .unwrap();
+    assert!(transition_year.is_transitional);
+    let fiscal_year_end_month: Option<i32> =
+        sqlx::query_scalar("SELECT fiscal_year_end_month FROM companies WHERE id = $1")
+            .bind(company_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(fiscal_year_end_month, Some(3));
+
+    // Views as of before the change keep the December year end
+    let company = repo.find_by_id(company_id).await.unwrap().unwrap();
+    let (_, month_before) = repo
+        .as_of(chrono::Utc::now() - chrono::Duration::hours(1))
+        .fiscal_calendar_of(&company)
+        .await
+        .unwrap();
+    assert_eq!(month_before, Some(12));
+    let (_, month_now) = repo.fiscal_calendar_of(&company).await.unwrap();
+    assert_eq!(month_now, Some(3));
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/data-events",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let events = body["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["event_type"], "fiscal_year_end_change");
+    assert_eq!(events[0]["details"]["from_month"], 12);
+    assert_eq!(events[0]["details"]["to_month"], 3);
+    assert_eq!(events[1]["event_type"], "restatement");
+    assert_eq!(events[1]["period_end_date"], "2023-12-31");
+    let change = &events[1]["details"]["changes"]["total_revenue"];
+    assert_eq!(change["from"], 1_000_000.0);
+    assert_eq!(change["to"], 950_000.0);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly&period_count=2&calendarized=false",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    let yoy = body["sections"]["growth_and_margins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == "revenue_growth_yoy")
+        .expect("revenue_growth_yoy row");
+    let latest = yoy["values"].as_array().unwrap().last().unwrap();
+    assert_eq!(body["period_details"][1]["statement_date"], "2024-01-31");
+    assert!(latest["value"].is_null());
+
+    // Backfilling a period a month before an existing one makes that one a
+    // stub too
+    let later = repo
+        .upsert_income_statement(income_insert(
+            company_id,
+            "2024-12-31",
+            "quarterly",
+            300_000,
+        ))
+        .await
+        .unwrap();
+    assert!(!later.is_transitional);
+    let backfilled = repo
+        .upsert_income_statement(income_insert(
+            company_id,
+            "2024-11-30",
+            "quarterly",
+            100_000,
+        ))
+        .await
+        .unwrap();
+    assert!(!backfilled.is_transitional);
+    let later_is_stub: bool =
+        sqlx::query_scalar("SELECT is_transitional FROM income_statements WHERE id = $1")
+            .bind(later.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(later_is_stub);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_get_metrics_as_of_reads_statements_known_on_that_date() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+    let repo = CompanyRepository::new(pool.clone());
+
+    // Restating Q4 2023 keeps the original as a closed version
+    let restated = repo
+        .upsert_income_statement(income_insert(
+            company_id,
+            "2023-12-31",
+            "quarterly",
+            950_000,
+        ))
+        .await
+        .unwrap();
+    let versions: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM income_statements WHERE company_id = $1 AND period_end_date = '2023-12-31'",
+    )
+    .bind(company_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(versions, 2);
+
+    // Backdate the history: everything known from January 2024, restated in March
+    sqlx::query("UPDATE income_statements SET known_from = '2024-01-15' WHERE company_id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "UPDATE income_statements SET known_to = '2024-03-01' WHERE company_id = $1 AND known_to IS NOT NULL",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("UPDATE income_statements SET known_from = '2024-03-01' WHERE id = $1")
+        .bind(restated.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let latest_revenue = |body: &Value| {
+        body["sections"]["growth_and_margins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|row| row["metric_name"] == "revenue")
+            .expect("revenue row")["values"]
+            .as_array()
+            .unwrap()
+            .last()
+            .unwrap()["value"]
+            .clone()
+    };
+    let get_metrics = |query: &'static str| {
+        client
+            .get(format!(
+                "{}/api/v1/companies/{}/metrics?period_type=quarterly&period_count=2{}",
+                base_url, company_id, query
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let body: Value = get_metrics("").await.unwrap().json().await.unwrap();
+    assert!(body["as_of"].is_null());
+    assert_eq!(latest_revenue(&body), 950_000.0);
+
+    let body: Value = get_metrics("&as_of=2024-02-01")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["as_of"], "2024-02-01");
+    assert_eq!(latest_revenue(&body), 1_000_000.0);
+
+    // Nothing had been ingested yet
+    let body: Value = get_metrics("&as_of=2024-01-01")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(latest_revenue(&body).is_null());
+
+    let resp = get_metrics("&as_of=2024-02-01&peer_group=industry")
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_compare_companies_normalizes_to_usd_on_shared_axis() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (usd_id, _) = setup_company(&pool).await;
+    let (eur_id, _) = setup_company(&pool).await;
+
+    // Same revenue, reported in EUR at 2 USD/EUR, with a lower net margin
+    sqlx::query("UPDATE companies SET currency = 'EUR' WHERE id = $1")
+        .bind(eur_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE income_statements SET net_income = net_income / 2 WHERE company_id = $1")
+        .bind(eur_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM fx_rates WHERE from_currency = 'USD' AND to_currency = 'EUR'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO fx_rates (from_currency, to_currency, rate, rate_date)
+        VALUES ('USD', 'EUR', 0.5, '2023-01-01')
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/compare?ids={},{}&period_type=quarterly&period_count=2",
+            base_url, usd_id, eur_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["periods"], json!(["CQ3 2023", "CQ4 2023"]));
+    assert_eq!(body["currency"], "USD");
+
+    let companies = body["companies"].as_array().unwrap();
+    assert_eq!(companies[0]["company_id"], usd_id.to_string());
+    assert_eq!(companies[1]["reporting_currency"], "EUR");
+    assert_eq!(companies[1]["fx_rates"], json!([2.0, 2.0]));
+
+    let row = |company: &Value, name: &str| {
+        company["sections"]["growth_and_margins"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|row| row["metric_name"] == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("{} row", name))
+    };
+    let usd_revenue = row(&companies[0], "revenue");
+    let eur_revenue = row(&companies[1], "revenue");
+    assert_eq!(usd_revenue["values"][1]["value"], 1_000_000.0);
+    assert_eq!(eur_revenue["values"][1]["value"], 2_000_000.0);
+
+    // Margins are ranked across both companies, not each company's history
+    let usd_margin = &row(&companies[0], "net_margin")["values"][1];
+    let eur_margin = &row(&companies[1], "net_margin")["values"][1];
+    assert!(
+        usd_margin["heat_map_quartile"].as_i64().unwrap()
+            > eur_margin["heat_map_quartile"].as_i64().unwrap()
+    );
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/compare?ids={}",
+            base_url, usd_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    sqlx::query("DELETE FROM fx_rates WHERE from_currency = 'USD' AND to_currency = 'EUR'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    cleanup_test_company(&pool, usd_id).await;
+    cleanup_test_company(&pool, eur_id).await;
+}
+
+#[tokio::test]
+async fn test_metric_catalog_lists_registered_metrics() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+
+    let resp = client
+        .get(format!("{}/api/v1/metrics/catalog", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let pe = body["metrics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["name"] == "pe_ratio")
+        .expect("pe_ratio in catalog");
+    assert_eq!(pe["stored_name"], "pe_ratio_historical");
+    assert_eq!(pe["section"], "valuation");
+    assert_eq!(pe["format"], "multiple");
+    assert_eq!(pe["heat_map_direction"], "lower_is_better");
+    assert!(body["technical_metrics"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("volatility_1y_pct")));
+}
+
+#[tokio::test]
+async fn test_custom_formula_is_evaluated_by_metrics_endpoint() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+    let name = format!("npm_{}", &Uuid::new_v4().simple().to_string()[..8]);
+
+    let resp = client
+        .post(format!("{}/api/v1/formulas", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": name, "expression": "net_income / revenue * 100" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let formula: Value = resp.json().await.unwrap();
+    let formula_id = formula["id"].as_str().unwrap().to_string();
+
+    // Same name again conflicts; unknown fields are rejected up front
+    let resp = client
+        .post(format!("{}/api/v1/formulas", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": name, "expression": "revenue" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let resp = client
+        .post(format!("{}/api/v1/formulas", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": "bad_formula", "expression": "ebitdar / revenue" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/metrics?period_type=quarterly",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let row = body["custom_metrics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|row| row["metric_name"] == name.as_str())
+        .expect("custom formula row");
+    let latest = row["values"].as_array().unwrap().last().unwrap();
+    assert_eq!(latest["value"], 20.0);
+
+    let resp = client
+        .delete(format!("{}/api/v1/formulas/{}", base_url, formula_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_dcf_valuation_with_saved_assumption_set() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    sqlx::query(
+        "UPDATE income_statements SET shares_outstanding = 100000 WHERE company_id = $1 AND period_type = 'annual'",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO daily_prices (id, company_id, price_date, close, created_at) VALUES ($1, $2, '2023-12-29', 103.25, NOW())",
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // No cash flow history, so the FCF margin cannot be derived
+    let dcf_url = format!("{}/api/v1/companies/{}/valuation/dcf", base_url, company_id);
+    let resp = client
+        .get(&dcf_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = client
+        .post(format!("{}/api/v1/valuation/assumptions", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": "bad", "assumptions": { "wacc": 2.0, "terminal_growth": 3.0 } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let name = format!("dcf_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let resp = client
+        .post(format!("{}/api/v1/valuation/assumptions", base_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": name,
+            "assumptions": {
+                "projection_years": 2,
+                "revenue_growth": [10.0],
+                "fcf_margin": [20.0],
+                "wacc": 10.0,
+                "terminal_growth": 2.0
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let set: Value = resp.json().await.unwrap();
+    let set_id = set["id"].as_str().unwrap().to_string();
+
+    // Revenue 3.5M grows 10% for two years at a 20% FCF margin; each year's PV
+    // is 700k and the discounted terminal value 8.925M over 100k shares.
+    let resp = client
+        .get(format!("{}?assumption_set_id={}", dcf_url, set_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let per_share = body["result"]["intrinsic_value_per_share"]
+        .as_f64()
+        .unwrap();
+    assert!((per_share - 103.25).abs() < 1e-6);
+    assert_eq!(
+        body["sensitivity"]["values"][2][2].as_f64(),
+        Some(per_share)
+    );
+    let implied = body["reverse_dcf"]["implied_revenue_growth"]
+        .as_f64()
+        .unwrap();
+    assert!((implied - 10.0).abs() < 1e-3);
+
+    // Request parameters override the saved set
+    let resp = client
+        .get(format!("{}?assumption_set_id={}&wacc=12", dcf_url, set_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["assumptions"]["wacc"], 12.0);
+    assert!(
+        body["result"]["intrinsic_value_per_share"]
+            .as_f64()
+            .unwrap()
+            < per_share
+    );
+
+    // A stored set that no longer parses fails loudly instead of valuing on
+    // default assumptions
+    sqlx::query(
+        "UPDATE dcf_assumption_sets SET assumptions = '{\"wacc\": \"high\"}' WHERE id = $1",
+    )
+    .bind(Uuid::parse_str(&set_id).unwrap())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let resp = client
+        .get(format!("{}?assumption_set_id={}", dcf_url, set_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(resp.text().await.unwrap().contains("malformed assumptions"));
+
+    let resp = client
+        .delete(format!(
+            "{}/api/v1/valuation/assumptions/{}",
+            base_url, set_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_capital_allocation_breakdown_and_shareholder_yield() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    sqlx::query(
+        "UPDATE income_statements SET shares_outstanding = 100000 WHERE company_id = $1 AND period_type = 'annual'",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO balance_sheets (company_id, period_end_date, period_type, total_assets) VALUES ($1, '2022-12-31', 'annual', 9000000)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO cash_flow_statements (
+            company_id, period_end_date, period_type, operating_cash_flow,
+            capital_expenditures, dividend_payout, stock_repurchase
+        )
+        VALUES ($1, '2022-12-31', 'annual', 1000000, -250000, 100000, -150000)
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    // Market cap comes from the last close on or before the period end
+    sqlx::query(
+        r#"
+        INSERT INTO daily_prices (id, company_id, price_date, close, created_at)
+        VALUES ($1, $2, '2022-12-30', 50, NOW()), ($3, $2, '2023-01-03', 80, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .bind(Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/capital-allocation",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["period_type"], "annual");
+    let periods = body["periods"].as_array().unwrap();
+    assert_eq!(periods.len(), 1);
+
+    let allocation = &periods[0]["allocation"];
+    assert_eq!(allocation["period_end_date"], "2022-12-31");
+    assert_eq!(allocation["capital_expenditures"]["amount"], 250000.0);
+    assert_eq!(allocation["capital_expenditures"]["pct_of_ocf"], 25.0);
+    assert_eq!(allocation["buybacks"]["pct_of_ocf"], 15.0);
+    assert_eq!(allocation["dividends"]["pct_of_ocf"], 10.0);
+    assert!(allocation["investments"]["amount"].is_null());
+
+    // 100k shares at 50
+    assert_eq!(periods[0]["price_date"], "2022-12-30");
+    let yields = &periods[0]["shareholder_yield"];
+    assert_eq!(yields["market_cap"], 5000000.0);
+    assert_eq!(yields["dividend_yield"], 2.0);
+    assert_eq!(yields["buyback_yield"], 3.0);
+    assert_eq!(yields["shareholder_yield"], 5.0);
+    assert_eq!(body["currency"], "USD");
+
+    // Quarterly yields sum the trailing four quarters' dividends
+    sqlx::query(
+        r#"
+        INSERT INTO income_statements (company_id, period_end_date, period_type, total_revenue)
+        VALUES ($1, '2023-03-31', 'quarterly', 800000), ($1, '2023-06-30', 'quarterly', 850000)
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "UPDATE income_statements SET shares_outstanding = 100000 WHERE company_id = $1 AND period_type = 'quarterly'",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO cash_flow_statements (company_id, period_end_date, period_type, operating_cash_flow, dividend_payout)
+        SELECT $1, d, 'quarterly', 250000, 25000
+        FROM unnest('{2023-03-31,2023-06-30,2023-09-30,2023-12-31}'::date[]) AS d
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO balance_sheets (company_id, period_end_date, period_type, total_assets) VALUES ($1, '2023-12-31', 'quarterly', 9000000)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO daily_prices (company_id, price_date, close) VALUES ($1, '2023-12-29', 50)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/capital-allocation?period_type=quarterly&period_count=4",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let periods = body["periods"].as_array().unwrap();
+    assert_eq!(periods.len(), 4);
+    // Too few earlier quarters for a trailing year
+    assert!(periods[0]["shareholder_yield"]["dividend_yield"].is_null());
+    let yields = &periods[3]["shareholder_yield"];
+    assert_eq!(periods[3]["price_date"], "2023-12-29");
+    assert_eq!(yields["market_cap"], 5000000.0);
+    assert_eq!(yields["dividend_yield"], 2.0);
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/capital-allocation",
+            base_url,
+            Uuid::new_v4()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_price_analytics_from_stored_closes() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    // 260 daily closes rising from 100 to 359
+    sqlx::query(
+        r#"
+        INSERT INTO daily_prices (company_id, price_date, close)
+        SELECT $1, DATE '2024-01-01' + i, 100 + i
+        FROM generate_series(0, 259) AS i
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/price-analytics",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["benchmark_symbol"], "SPY");
+
+    let analytics = &body["analytics"];
+    assert_eq!(analytics["as_of"], "2024-09-16");
+    assert_eq!(analytics["close"], 359.0);
+    assert_eq!(analytics["sma_50"], 334.5);
+    assert_eq!(analytics["sma_200"], 259.5);
+    assert_eq!(analytics["high_52w"], 359.0);
+    assert_eq!(analytics["low_52w"], 100.0);
+    assert_eq!(analytics["pct_from_high_52w"], 0.0);
+    assert_eq!(analytics["max_drawdown_1y"], 0.0);
+    assert!(analytics["volatility_1y"].as_f64().unwrap() > 0.0);
+    // Less than a year of history and no sector
+    assert!(analytics["return_1y"].is_null());
+    assert!(analytics["relative_strength_1y"].is_null());
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/price-analytics",
+            base_url,
+            Uuid::new_v4()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_price_analytics_leaves_out_peers_without_recent_closes() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+    let (fresh_id, _) = setup_company(&pool).await;
+    let (stale_id, _) = setup_company(&pool).await;
+
+    let sector_id: Uuid = sqlx::query_scalar("INSERT INTO sectors (name) VALUES ($1) RETURNING id")
+        .bind(format!("Peer Sector {}", Uuid::new_v4()))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE companies SET sector_id = $1 WHERE id = ANY($2)")
+        .bind(sector_id)
+        .bind(vec![company_id, fresh_id, stale_id])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // The company trades through 2024-01-31. One peer is up 20% over the
+    // same year; the other stopped trading in October and has no close near
+    // the end of the window.
+    sqlx::query(
+        r#"
+        INSERT INTO daily_prices (company_id, price_date, close)
+        SELECT $1, DATE '2023-01-02' + i, 100
+        FROM generate_series(0, 394) AS i
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO daily_prices (company_id, price_date, close)
+        VALUES ($1, '2023-01-31', 100), ($1, '2024-01-31', 120),
+               ($2, '2023-01-31', 100), ($2, '2023-10-02', 50)
+        "#,
+    )
+    .bind(fresh_id)
+    .bind(stale_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let resp = client
+        .get(format!(
+            "{}/api/v1/companies/{}/price-analytics",
+            base_url, company_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["currency"], "USD");
+    let analytics = &body["analytics"];
+    assert_eq!(analytics["as_of"], "2024-01-31");
+    assert_eq!(analytics["sector_return_1y"], 20.0);
+
+    for id in [company_id, fresh_id, stale_id] {
+        cleanup_test_company(&pool, id).await;
+    }
+    sqlx::query("DELETE FROM sectors WHERE id = $1")
+        .bind(sector_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_price_history_resamples_candles_with_markers() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    // A 2-for-1 split between December and January
+    sqlx::query(
+        r#"
+        INSERT INTO daily_prices (company_id, price_date, open, high, low, close, adjusted_close, volume)
+        VALUES ($1, '2023-12-28', 200, 210, 190, 204, 102, 10),
+               ($1, '2023-12-29', 204, 220, 200, 216, 108, 20),
+               ($1, '2024-01-02', 108, 112, 100, 110, 110, 30),
+               ($1, '2024-01-03', 110, 111, 104, 105, 105, 40)
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO earnings_events (company_id, report_date, fiscal_date_ending) VALUES ($1, '2024-01-25', '2023-12-31')",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let get = |query: &'static str| {
+        let client = client.clone();
+        let url = format!(
+            "{}/api/v1/companies/{}/prices?{}",
+            base_url, company_id, query
+        );
+        let token = token.clone();
+        async move {
+            client
+                .get(url)
+                .header("Authorization", format!("Bearer {}", token))
+                .send()
+                .await
+                .unwrap()
+        }
+    };
+
+    let resp = get("from=2023-12-01&to=2024-01-31&interval=month&adjusted=true").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["interval"], "month");
+    assert_eq!(body["currency"], "USD");
+    let candles = body["candles"].as_array().unwrap();
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[0]["date"], "2023-12-01");
+    assert_eq!(candles[0]["open"], 100.0);
+    assert_eq!(candles[0]["high"], 110.0);
+    assert_eq!(candles[0]["low"], 95.0);
+    assert_eq!(candles[0]["close"], 108.0);
+    assert_eq!(candles[0]["volume"], 60);
+    assert_eq!(candles[1]["date"], "2024-01-01");
+    assert_eq!(candles[1]["close"], 105.0);
+
+    // The Q1 FY2024 period end, then its earnings report
+    let markers = body["markers"].as_array().unwrap();
+    assert_eq!(markers.len(), 2);
+    assert_eq!(markers[0]["date"], "2023-12-31");
+    assert_eq!(markers[0]["kind"], "period_end");
+    assert_eq!(markers[0]["label"], "Q1 FY2024");
+    assert_eq!(markers[1]["date"], "2024-01-25");
+    assert_eq!(markers[1]["kind"], "earnings");
+
+    // Unadjusted daily candles keep the pre-split prices
+    let body: Value = get("from=2023-12-28&to=2023-12-29")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let candles = body["candles"].as_array().unwrap();
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[1]["close"], 216.0);
+
+    // A session without an adjusted close can't be charted adjusted
+    sqlx::query(
+        "INSERT INTO daily_prices (company_id, price_date, close, volume) VALUES ($1, '2024-01-04', 106, 50)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        get("from=2023-12-01&to=2024-01-31&adjusted=true")
+            .await
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        get("from=2023-12-01&to=2024-01-31").await.status(),
+        StatusCode::OK
+    );
+
+    assert_eq!(
+        get("from=2024-02-01&to=2024-01-01").await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(get("interval=hour").await.status(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
+#[tokio::test]
+async fn test_derived_metric_series_keeps_overwritten_values() {
+    let (base_url, pool) = spawn_app().await;
+    let client = get_client().await;
+    let token = login(&client, &base_url, &pool).await;
+    let (company_id, _) = setup_company(&pool).await;
+
+    // Recorded last year, then recalculated today: once with a new value and
+    // once with the same one
+    sqlx::query(
+        r#"
+        INSERT INTO derived_metrics_history
+            (company_id, period_end_date, period_type, metric_name, metric_value, snapshot_date)
+        VALUES ($1, '2023-12-31', 'quarterly', 'pe_ratio_ttm', 15, '2024-01-02'),
+               ($1, '2023-12-31', 'annual', 'pe_ratio_ttm', 16, '2024-01-02')
+        "#,
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    for value in [20, 22, 22] {
+        sqlx::query(
+            r#"
+            INSERT INTO derived_metrics (company_id, period_end_date, period_type, metric_name, metric_value)
+            VALUES ($1, '2023-12-31', 'quarterly', 'pe_ratio_ttm', $2)
+            ON CONFLICT (company_id, period_end_date, period_type, metric_name)
+            DO UPDATE SET metric_value = EXCLUDED.metric_value
+            "#,
+        )
+        .bind(company_id)
+        .bind(value)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    let today: String = sqlx::query_scalar("SELECT CURRENT_DATE::text")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    let get = |query: &'static str| {
+        let client = client.clone();
+        let url = format!(
+            "{}/api/v1/companies/{}/derived-metrics?{}",
+            base_url, company_id, query
+        );
+        let token = token.clone();
+        async move {
+            client
+                .get(url)
+                .header("Authorization", format!("Bearer {}", token))
+                .send()
+                .await
+                .unwrap()
+        }
+    };
+
+    let resp = get("names=pe_ratio_ttm,gross_margin_pct").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = resp.json().await.unwrap();
+    let series = body["series"].as_array().unwrap();
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0]["metric_name"], "pe_ratio_ttm");
+    let points = series[0]["points"].as_array().unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0]["date"], "2024-01-02");
+    assert_eq!(points[0]["period_end_date"], "2023-12-31");
+    assert_eq!(points[0]["value"], 15.0);
+    assert_eq!(points[1]["date"], today.as_str());
+    assert_eq!(points[1]["value"], 22.0);
+    assert_eq!(series[1]["metric_name"], "gross_margin_pct");
+    assert!(series[1]["points"].as_array().unwrap().is_empty());
+
+    let body: Value = get("names=pe_ratio_ttm&to=2024-12-31")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["series"][0]["points"].as_array().unwrap().len(), 1);
+
+    // Series stay on one period type, quarterly unless asked otherwise
+    let body: Value = get("names=pe_ratio_ttm&period_type=annual")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let points = body["series"][0]["points"].as_array().unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0]["period_type"], "annual");
+    assert_eq!(points[0]["value"], 16.0);
+
+    // The current values filter on the period end instead
+    let repo = CompanyRepository::new(pool.clone());
+    let current = repo
+        .get_derived_metrics(
+            company_id,
+            &DerivedMetricFilters {
+                metric_names: vec!["pe_ratio_ttm".to_string()],
+                from: Some("2023-12-31".parse().unwrap()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0].metric_value, Some(BigDecimal::from(22)));
+
+    assert_eq!(get("names=").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        get("names=pe_ratio_ttm&period_type=monthly").await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        get("names=pe_ratio_ttm&from=2024-02-01&to=2024-01-01")
+            .await
+            .status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    cleanup_test_company(&pool, company_id).await;
+}
+
 // -----------------------------------------------------------------------------
 // Documents Tests
 // -----------------------------------------------------------------------------
@@ -23,6 +23,7 @@ async fn spawn_app() -> String {
             s3_endpoint: "http://localhost:9000".to_string(),
             s3_access_key: "minioadmin".to_string(),
             s3_secret_key: "minioadmin".to_string(),
+            trading_calendars_path: None,
             environment: api::config::Environment::Development,
         }
     });
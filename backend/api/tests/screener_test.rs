@@ -153,3 +153,212 @@ async fn test_run_screener_returns_results() {
     let results = body["results"].as_array().unwrap();
     assert!(results.iter().any(|r| r["symbol"] == "AAPL"));
 }
+
+#[tokio::test]
+async fn test_run_screener_applies_formula_filters() {
+    let app = TestApp::spawn().await;
+    setup_test_db(&app.db_pool).await;
+
+    // Two companies with 20% and 10% net margins in the latest quarter
+    for (symbol, net_income) in [("HIGHM", 200), ("LOWM", 100)] {
+        let company_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO companies (id, symbol, name, exchange, industry) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(company_id)
+        .bind(symbol)
+        .bind(format!("{} Corp", symbol))
+        .bind("NASDAQ")
+        .bind("Formula Testing")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO income_statements (id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter, total_revenue, net_income)
+            VALUES ($1, $2, '2024-03-31', 'quarterly', 2024, 1, 1000, $3)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(company_id)
+        .bind(net_income)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    }
+
+    // A full page of larger companies that don't match, so the match ranks
+    // past the result limit by market cap
+    sqlx::query(
+        r#"
+        INSERT INTO companies (id, symbol, name, exchange, industry, market_cap)
+        SELECT gen_random_uuid(), 'BIG' || n, 'Big ' || n, 'NASDAQ', 'Formula Testing', 1000000000 + n
+        FROM generate_series(1, 100) n
+        "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO income_statements (id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter, total_revenue, net_income)
+        SELECT gen_random_uuid(), id, '2024-03-31', 'quarterly', 2024, 1, 1000, 100
+        FROM companies WHERE symbol LIKE 'BIG%'
+        "#,
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let client = reqwest::Client::new();
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, created_at, updated_at) VALUES ($1, $2, $3, $4, NOW(), NOW())",
+    )
+    .bind(user_id)
+    .bind(format!("scr_formula_{}", user_id))
+    .bind(format!("scr_formula_{}@example.com", user_id))
+    .bind("hash")
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let token = app.generate_token(user_id);
+
+    let formula_resp = client
+        .post(format!("{}/api/v1/formulas", app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": "net_margin_ratio", "expression": "net_income / revenue" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(formula_resp.status(), StatusCode::CREATED);
+
+    let create_resp = client
+        .post(format!("{}/api/v1/screeners", app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "title": "Margin Screener",
+            "filter_criteria": {
+                "industries": ["Formula Testing"],
+                "formula_filters": [{ "name": "net_margin_ratio", "min": 0.15 }]
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    let screener: Value = create_resp.json().await.unwrap();
+    let screener_id = screener["id"].as_str().unwrap();
+
+    let run_resp = client
+        .post(format!(
+            "{}/api/v1/screeners/{}/run",
+            app.address, screener_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(run_resp.status(), StatusCode::OK);
+    let body: Value = run_resp.json().await.unwrap();
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["symbol"], "HIGHM");
+    assert_eq!(results[0]["formula_values"]["net_margin_ratio"], 0.2);
+}
+
+#[tokio::test]
+async fn test_run_screener_applies_technical_filters() {
+    let app = TestApp::spawn().await;
+    setup_test_db(&app.db_pool).await;
+
+    // Latest one-year volatility 25% and 45%; CALM was volatile a year earlier
+    for (symbol, older, latest) in [("CALM", 60, 25), ("WILD", 20, 45)] {
+        let company_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO companies (id, symbol, name, exchange, industry) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(company_id)
+        .bind(symbol)
+        .bind(format!("{} Corp", symbol))
+        .bind("NASDAQ")
+        .bind("Technical Testing")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO derived_metrics (company_id, period_end_date, period_type, metric_name, metric_value)
+            VALUES ($1, '2023-12-31', 'quarterly', 'volatility_1y_pct', $2),
+                   ($1, '2024-03-31', 'quarterly', 'volatility_1y_pct', $3)
+            "#,
+        )
+        .bind(company_id)
+        .bind(older)
+        .bind(latest)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    }
+
+    let client = reqwest::Client::new();
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, created_at, updated_at) VALUES ($1, $2, $3, $4, NOW(), NOW())",
+    )
+    .bind(user_id)
+    .bind(format!("scr_technical_{}", user_id))
+    .bind(format!("scr_technical_{}@example.com", user_id))
+    .bind("hash")
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    let token = app.generate_token(user_id);
+
+    let create_resp = client
+        .post(format!("{}/api/v1/screeners", app.address))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "title": "Low Volatility",
+            "filter_criteria": {
+                "industries": ["Technical Testing"],
+                "technical_filters": [{ "metric": "volatility_1y_pct", "max": 30 }]
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    let screener: Value = create_resp.json().await.unwrap();
+    let screener_id = screener["id"].as_str().unwrap();
+
+    let run_resp = client
+        .post(format!(
+            "{}/api/v1/screeners/{}/run",
+            app.address, screener_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(run_resp.status(), StatusCode::OK);
+    let body: Value = run_resp.json().await.unwrap();
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["symbol"], "CALM");
+
+    let run_resp = client
+        .post(format!(
+            "{}/api/v1/screeners/{}/run",
+            app.address, screener_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "override_criteria": {
+                "technical_filters": [{ "metric": "pe_ratio_ttm", "min": 0 }]
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(run_resp.status(), StatusCode::BAD_REQUEST);
+}
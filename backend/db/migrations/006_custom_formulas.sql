@@ -0,0 +1,21 @@
+-- Migration: 006_custom_formulas.sql
+-- Description: Per-user named formulas evaluated by the metrics endpoint and screeners
+-- Date: 2026-10-18
+
+CREATE TABLE IF NOT EXISTS custom_formulas (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Definition
+    name                VARCHAR(64) NOT NULL,
+    expression          TEXT NOT NULL,
+    description         TEXT,
+
+    -- Audit
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_custom_formulas_user ON custom_formulas(user_id);
@@ -0,0 +1,22 @@
+-- Migration: 007_dcf_assumption_sets.sql
+-- Description: Per-user saved DCF assumptions for the valuation endpoint
+-- Date: 2026-10-18
+
+CREATE TABLE IF NOT EXISTS dcf_assumption_sets (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id             UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+
+    -- Definition
+    name                VARCHAR(100) NOT NULL,
+    description         TEXT,
+    -- {"projection_years": 5, "revenue_growth": [8.0, 6.0], "fcf_margin": [15.0], "wacc": 9.0, "terminal_growth": 2.5}
+    assumptions         JSONB NOT NULL,
+
+    -- Audit
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dcf_assumption_sets_user ON dcf_assumption_sets(user_id);
@@ -0,0 +1,7 @@
+-- Migration: 008_fiscal_calendars.sql
+-- Description: Fiscal calendar rules for 52/53-week and non-month-end filers
+-- Date: 2026-10-18
+
+-- e.g. 'month_end_dec', 'last_sat_jan', 'nearest_sat_sep'. NULL means month-end
+-- of fiscal_year_end_month.
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS fiscal_calendar VARCHAR(32);
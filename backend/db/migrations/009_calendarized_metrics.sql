@@ -0,0 +1,9 @@
+-- Migration: 009_calendarized_metrics.sql
+-- Description: Store metrics restated on calendar quarters/years for companies
+-- whose fiscal periods don't line up with the calendar
+-- Date: 2026-10-18
+
+ALTER TABLE derived_metrics ALTER COLUMN period_type TYPE VARCHAR(20);
+ALTER TABLE derived_metrics DROP CONSTRAINT IF EXISTS derived_metrics_period_type_check;
+ALTER TABLE derived_metrics ADD CONSTRAINT derived_metrics_period_type_check
+    CHECK (period_type IN ('quarterly', 'annual', 'calendar_quarterly', 'calendar_annual'));
@@ -0,0 +1,40 @@
+-- Migration: 010_restatements_and_fiscal_year_changes.sql
+-- Description: Keep as-reported vs restated statement history and log data
+-- events (restatements, fiscal year-end changes) detected on ingestion
+-- Date: 2026-10-18
+
+-- Stub periods reported while a company moves its fiscal year end. They are
+-- shorter than a normal quarter/year and excluded from YoY comparisons.
+ALTER TABLE income_statements ADD COLUMN IF NOT EXISTS is_transitional BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE cash_flow_statements ADD COLUMN IF NOT EXISTS is_transitional BOOLEAN NOT NULL DEFAULT false;
+
+-- Snapshots of a period's line items. The first restatement of a period also
+-- records the values it replaced as 'as_reported'.
+CREATE TABLE IF NOT EXISTS statement_revisions (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    company_id          UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    statement_type      VARCHAR(20) NOT NULL
+                        CHECK (statement_type IN ('income_statement', 'balance_sheet', 'cash_flow_statement')),
+    period_end_date     DATE NOT NULL,
+    period_type         VARCHAR(10) NOT NULL,
+    revision_type       VARCHAR(20) NOT NULL CHECK (revision_type IN ('as_reported', 'restated')),
+    line_items          JSONB NOT NULL,
+    recorded_at         TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_statement_revisions_period
+    ON statement_revisions(company_id, statement_type, period_end_date, period_type);
+
+CREATE TABLE IF NOT EXISTS data_events (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    company_id          UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    event_type          VARCHAR(30) NOT NULL
+                        CHECK (event_type IN ('restatement', 'fiscal_year_end_change')),
+    statement_type      VARCHAR(20),
+    period_end_date     DATE,
+    period_type         VARCHAR(10),
+    details             JSONB NOT NULL DEFAULT '{}',
+    detected_at         TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_events_company ON data_events(company_id, detected_at DESC);
@@ -0,0 +1,32 @@
+-- Migration: 011_bitemporal_statements.sql
+-- Description: Keep every version of a statement period with the time range it
+-- was known, so the analyzer view can be reconstructed as of a past date
+-- Date: 2026-10-18
+
+-- A version is current from known_from until it is superseded (known_to).
+-- Existing rows are taken to have been known since they were created.
+ALTER TABLE income_statements ADD COLUMN IF NOT EXISTS known_from TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE income_statements ADD COLUMN IF NOT EXISTS known_to TIMESTAMPTZ;
+UPDATE income_statements SET known_from = created_at;
+
+ALTER TABLE balance_sheets ADD COLUMN IF NOT EXISTS known_from TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE balance_sheets ADD COLUMN IF NOT EXISTS known_to TIMESTAMPTZ;
+UPDATE balance_sheets SET known_from = created_at;
+
+ALTER TABLE cash_flow_statements ADD COLUMN IF NOT EXISTS known_from TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE cash_flow_statements ADD COLUMN IF NOT EXISTS known_to TIMESTAMPTZ;
+UPDATE cash_flow_statements SET known_from = created_at;
+
+-- Superseded versions share their period with the current one, so a period
+-- is only unique among current versions
+ALTER TABLE income_statements DROP CONSTRAINT IF EXISTS uq_income_company_period;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_income_company_period_current
+    ON income_statements(company_id, period_end_date, period_type) WHERE known_to IS NULL;
+
+ALTER TABLE balance_sheets DROP CONSTRAINT IF EXISTS uq_balance_company_period;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_balance_company_period_current
+    ON balance_sheets(company_id, period_end_date, period_type) WHERE known_to IS NULL;
+
+ALTER TABLE cash_flow_statements DROP CONSTRAINT IF EXISTS uq_cashflow_company_period;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_cashflow_company_period_current
+    ON cash_flow_statements(company_id, period_end_date, period_type) WHERE known_to IS NULL;
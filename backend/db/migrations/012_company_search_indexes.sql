@@ -0,0 +1,10 @@
+-- Migration: 012_company_search_indexes.sql
+-- Description: Indexes behind company autocomplete: ticker prefix matching and
+-- trigram similarity on names
+-- Date: 2026-10-18
+
+-- LIKE 'AB%' can only use a btree index with pattern ops under non-C collations
+CREATE INDEX IF NOT EXISTS idx_companies_symbol_prefix ON companies (symbol text_pattern_ops);
+
+-- Typo-tolerant name matching (pg_trgm is enabled in 001)
+CREATE INDEX IF NOT EXISTS idx_companies_name_trgm ON companies USING GIN (name gin_trgm_ops);
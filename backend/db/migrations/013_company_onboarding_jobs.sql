@@ -0,0 +1,11 @@
+-- Migration: 013_company_onboarding_jobs.sql
+-- Description: Tie background jobs to a company and record per-step progress,
+-- so an onboarding backfill can be queued and polled
+-- Date: 2026-10-18
+
+ALTER TABLE background_jobs ADD COLUMN IF NOT EXISTS company_id UUID REFERENCES companies(id) ON DELETE CASCADE;
+
+-- Ordered steps, e.g. [{"step": "prices", "status": "completed", "records": 5000, "message": null}]
+ALTER TABLE background_jobs ADD COLUMN IF NOT EXISTS progress JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX IF NOT EXISTS idx_jobs_company ON background_jobs(company_id);
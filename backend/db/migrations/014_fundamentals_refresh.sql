@@ -0,0 +1,9 @@
+-- Migration: 014_fundamentals_refresh.sql
+-- Description: Track when each company's statements were last fetched from the
+-- provider, so the fundamentals refresh only refetches new or stale data
+-- Date: 2026-10-18
+
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS fundamentals_refreshed_at TIMESTAMPTZ;
+
+-- latest_quarter at the last refresh; a later latest_quarter means a new report
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS fundamentals_quarter DATE;
@@ -0,0 +1,10 @@
+-- Migration: 015_daily_price_partitions.sql
+-- Description: Turn the daily_prices catch-all into a DEFAULT partition so
+-- prices for any year can be stored, including history before 2020. The
+-- worker's price_partitions job creates yearly partitions ahead of time and
+-- moves rows out of the default partition as their year gets one.
+-- Date: 2026-10-18
+
+ALTER TABLE daily_prices DETACH PARTITION daily_prices_future;
+ALTER TABLE daily_prices_future RENAME TO daily_prices_default;
+ALTER TABLE daily_prices ATTACH PARTITION daily_prices_default DEFAULT;
@@ -0,0 +1,30 @@
+-- Migration: 016_earnings_events.sql
+-- Description: Keep each earnings report the earnings poll sees, so report
+-- dates can be overlaid on price charts. companies.latest_quarter only holds
+-- the most recent fiscal period. Reports are keyed on the fiscal period they
+-- cover, so a rescheduled report moves its date instead of leaving a second
+-- event behind; reports whose period isn't known yet are keyed by date.
+-- Date: 2026-10-19
+
+CREATE TABLE IF NOT EXISTS earnings_events (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    company_id          UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    report_date         DATE NOT NULL,
+    fiscal_date_ending  DATE,
+    eps_estimate        DECIMAL(15, 4),
+    currency            VARCHAR(10),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_earnings_event_fiscal_period
+    ON earnings_events(company_id, fiscal_date_ending)
+    WHERE fiscal_date_ending IS NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_earnings_event_undated
+    ON earnings_events(company_id, report_date)
+    WHERE fiscal_date_ending IS NULL;
+
+-- Chart overlays read by report date
+CREATE INDEX IF NOT EXISTS idx_earnings_events_company_report_date
+    ON earnings_events(company_id, report_date);
@@ -0,0 +1,65 @@
+-- Migration: 017_derived_metrics_history.sql
+-- Description: Keep every value a derived metric has taken. derived_metrics
+-- only holds the latest value per period, which the metrics recalculation
+-- overwrites; a trigger copies each new or changed value here, keyed by the
+-- day it was recorded, so price-based metrics such as pe_ratio_ttm can be
+-- charted over time.
+-- Date: 2026-10-19
+
+CREATE TABLE derived_metrics_history (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    company_id              UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    period_end_date         DATE NOT NULL,
+    period_type             VARCHAR(20) NOT NULL,
+    metric_name             VARCHAR(100) NOT NULL,
+    metric_value            DECIMAL(20, 6),
+
+    -- Day the value was recorded; a later change on the same day replaces it
+    snapshot_date           DATE NOT NULL,
+
+    -- Audit
+    recorded_at             TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Constraints
+    CONSTRAINT uq_derived_history_snapshot
+        UNIQUE (company_id, metric_name, period_type, period_end_date, snapshot_date)
+);
+
+CREATE INDEX idx_derived_history_company_metric_snapshot
+    ON derived_metrics_history(company_id, metric_name, snapshot_date);
+
+COMMENT ON TABLE derived_metrics_history IS 'Every value recorded for a derived metric, one per day';
+
+CREATE OR REPLACE FUNCTION record_derived_metric_history()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO derived_metrics_history (
+        company_id, period_end_date, period_type, metric_name, metric_value, snapshot_date
+    )
+    VALUES (
+        NEW.company_id, NEW.period_end_date, NEW.period_type, NEW.metric_name,
+        NEW.metric_value, CURRENT_DATE
+    )
+    ON CONFLICT (company_id, metric_name, period_type, period_end_date, snapshot_date)
+    DO UPDATE SET metric_value = EXCLUDED.metric_value, recorded_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_derived_metrics_history_insert AFTER INSERT ON derived_metrics
+    FOR EACH ROW EXECUTE FUNCTION record_derived_metric_history();
+
+-- Recalculations that leave a value unchanged don't add a snapshot
+CREATE TRIGGER record_derived_metrics_history_update AFTER UPDATE ON derived_metrics
+    FOR EACH ROW
+    WHEN (OLD.metric_value IS DISTINCT FROM NEW.metric_value)
+    EXECUTE FUNCTION record_derived_metric_history();
+
+-- Seed the history with the values stored today. Recalculations overwrite
+-- derived_metrics in place, so a row's created_at doesn't date its value.
+INSERT INTO derived_metrics_history (
+    company_id, period_end_date, period_type, metric_name, metric_value, snapshot_date
+)
+SELECT company_id, period_end_date, period_type, metric_name, metric_value, CURRENT_DATE
+FROM derived_metrics
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,64 @@
+-- Migration: 018_metrics_recalc_queue.sql
+-- Description: Track which companies' derived metrics are out of date, so the
+-- metrics recalculation only recomputes companies whose statements or prices
+-- changed since it last ran. Triggers mark a company whenever its statements
+-- or prices are written, whatever the writer.
+-- Date: 2026-10-19
+
+CREATE TABLE metrics_recalc_queue (
+    company_id              UUID PRIMARY KEY REFERENCES companies(id) ON DELETE CASCADE,
+
+    -- First change not yet recomputed
+    marked_at               TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Bumped on every change; the recalculation only clears the revision it
+    -- read, so changes committed while a company is processed aren't lost
+    revision                BIGINT NOT NULL DEFAULT 1
+);
+
+COMMENT ON TABLE metrics_recalc_queue IS 'Companies whose derived metrics need recomputing';
+
+-- Statement-level, so a bulk price load marks each company once
+CREATE OR REPLACE FUNCTION mark_metrics_dirty()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO metrics_recalc_queue (company_id)
+    SELECT DISTINCT company_id FROM changed_rows
+    ON CONFLICT (company_id) DO UPDATE
+    SET revision = metrics_recalc_queue.revision + 1;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER mark_metrics_dirty_income_insert AFTER INSERT ON income_statements
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+CREATE TRIGGER mark_metrics_dirty_income_update AFTER UPDATE ON income_statements
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+
+CREATE TRIGGER mark_metrics_dirty_balance_insert AFTER INSERT ON balance_sheets
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+CREATE TRIGGER mark_metrics_dirty_balance_update AFTER UPDATE ON balance_sheets
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+
+CREATE TRIGGER mark_metrics_dirty_cash_flow_insert AFTER INSERT ON cash_flow_statements
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+CREATE TRIGGER mark_metrics_dirty_cash_flow_update AFTER UPDATE ON cash_flow_statements
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+
+CREATE TRIGGER mark_metrics_dirty_price_insert AFTER INSERT ON daily_prices
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+CREATE TRIGGER mark_metrics_dirty_price_update AFTER UPDATE ON daily_prices
+    REFERENCING NEW TABLE AS changed_rows
+    FOR EACH STATEMENT EXECUTE FUNCTION mark_metrics_dirty();
+
+-- Nothing has been tracked so far: the first run recomputes everyone
+INSERT INTO metrics_recalc_queue (company_id)
+SELECT id FROM companies
+ON CONFLICT DO NOTHING;
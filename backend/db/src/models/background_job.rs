@@ -0,0 +1,76 @@
+/// Background job model
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Job type of a newly added company's history backfill
+pub const COMPANY_BACKFILL_JOB: &str = "company_backfill";
+
+/// Steps of a company backfill, in the order they run
+pub const COMPANY_BACKFILL_STEPS: [&str; 3] = ["statements", "prices", "documents"];
+
+/// Background job entity
+///
+/// Queued work picked up by the worker, e.g. a company backfill.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BackgroundJob {
+    pub id: Uuid,
+    pub job_type: String,
+
+    /// Company the job works on, if it is company-specific
+    pub company_id: Option<Uuid>,
+
+    // Job timing
+    pub scheduled_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// "pending", "running", "completed", "failed" or "cancelled"
+    pub status: String,
+
+    // Results
+    pub companies_processed: Option<i32>,
+    pub records_updated: Option<i32>,
+    pub error_message: Option<String>,
+    pub error_details: Option<serde_json::Value>,
+
+    /// Ordered [`JobStep`]s as JSON
+    pub progress: serde_json::Value,
+
+    // Audit
+    pub created_at: DateTime<Utc>,
+}
+
+impl BackgroundJob {
+    /// Progress steps, in order
+    pub fn steps(&self) -> Vec<JobStep> {
+        serde_json::from_value(self.progress.clone()).unwrap_or_default()
+    }
+}
+
+/// Progress of one step of a background job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStep {
+    pub step: String,
+
+    /// "pending", "running", "completed" or "failed"
+    pub status: String,
+
+    /// Rows written by the step so far
+    pub records: i32,
+
+    /// Why the step failed or was skipped
+    pub message: Option<String>,
+}
+
+impl JobStep {
+    pub fn pending(step: &str) -> Self {
+        Self {
+            step: step.to_string(),
+            status: "pending".to_string(),
+            records: 0,
+            message: None,
+        }
+    }
+}
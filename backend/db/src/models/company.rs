@@ -17,6 +17,8 @@ pub struct Company {
     pub market_cap: Option<i64>,
     pub currency: Option<String>,
     pub fiscal_year_end_month: Option<i32>,
+    /// Fiscal year end rule, e.g. `last_sat_jan`; month-end when absent
+    pub fiscal_calendar: Option<String>,
     pub description: Option<String>,
     pub cik: Option<String>,
     pub address: Option<String>,
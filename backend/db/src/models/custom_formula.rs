@@ -0,0 +1,25 @@
+/// Custom formula model
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Custom formula entity
+///
+/// A user-defined metric written in the formula language, e.g.
+/// `(operating_cash_flow - capital_expenditures) / total_equity`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CustomFormula {
+    pub id: Uuid,
+    pub user_id: Uuid,
+
+    // Definition
+    /// Unique per user; used as the metric row name and in screener criteria
+    pub name: String,
+    pub expression: String,
+    pub description: Option<String>,
+
+    // Audit
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -1,8 +1,11 @@
-use bigdecimal::BigDecimal;
+use crate::repositories::DailyPriceInsert;
+use bigdecimal::{BigDecimal, ToPrimitive};
 /// Daily price model
 use chrono::{DateTime, NaiveDate, Utc};
+use domain::domain::DailyPrice as DomainPrice;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Daily price entity (OHLCV data)
@@ -27,3 +30,54 @@ pub struct DailyPrice {
     // Audit
     pub created_at: DateTime<Utc>,
 }
+
+impl From<&DailyPrice> for DomainPrice {
+    fn from(row: &DailyPrice) -> Self {
+        let value = |v: &Option<BigDecimal>| v.as_ref().and_then(|v| v.to_f64());
+        Self {
+            date: row.price_date,
+            open: value(&row.open).unwrap_or(0.0),
+            high: value(&row.high).unwrap_or(0.0),
+            low: value(&row.low).unwrap_or(0.0),
+            close: value(&row.close).unwrap_or(0.0),
+            adjusted_close: value(&row.adjusted_close),
+            volume: row.volume,
+        }
+    }
+}
+
+impl DailyPriceInsert {
+    /// Insert data for a price reported by a provider
+    pub fn from_domain(company_id: Uuid, p: DomainPrice) -> Self {
+        // Through the decimal string so e.g. 182.35 isn't stored as its binary expansion
+        let decimal = |v: f64| BigDecimal::from_str(&v.to_string()).ok();
+        Self {
+            company_id,
+            price_date: p.date,
+            open: decimal(p.open),
+            high: decimal(p.high),
+            low: decimal(p.low),
+            close: decimal(p.close),
+            adjusted_close: p.adjusted_close.and_then(decimal),
+            volume: p.volume,
+            dividend_amount: None,
+            split_coefficient: None,
+        }
+    }
+}
+
+/// The latest price with a close on or before a requested date
+#[derive(Debug, Clone, FromRow)]
+pub struct PriceAsOf {
+    pub as_of: NaiveDate,
+    #[sqlx(flatten)]
+    pub price: DailyPrice,
+}
+
+impl PriceAsOf {
+    /// Whether the price is from before the requested date. When that date
+    /// is a session, its close is missing and the price found is out of date.
+    pub fn is_stale(&self) -> bool {
+        self.price.price_date < self.as_of
+    }
+}
@@ -0,0 +1,51 @@
+/// Data event and statement revision models
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Data event entity
+///
+/// A change in a company's reported data detected on ingestion: a restated
+/// period or a fiscal year-end change.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DataEvent {
+    pub id: Uuid,
+    pub company_id: Uuid,
+
+    /// "restatement" or "fiscal_year_end_change"
+    pub event_type: String,
+
+    // Affected statement, when the event concerns one period
+    pub statement_type: Option<String>,
+    pub period_end_date: Option<NaiveDate>,
+    pub period_type: Option<String>,
+
+    /// Event specifics as JSON
+    /// Example: {"changes": {"total_revenue": {"from": "100.00", "to": "95.00"}}}
+    /// or {"from_month": 9, "to_month": 12, "previous_fiscal_calendar": null}
+    pub details: serde_json::Value,
+
+    // Audit
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Statement revision entity
+///
+/// One version of a period's line items: the values first reported
+/// ("as_reported") or a later restatement ("restated").
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StatementRevision {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub statement_type: String,
+    pub period_end_date: NaiveDate,
+    pub period_type: String,
+    pub revision_type: String,
+
+    /// Line items by column name
+    pub line_items: serde_json::Value,
+
+    // Audit
+    pub recorded_at: DateTime<Utc>,
+}
@@ -0,0 +1,26 @@
+/// DCF assumption set model
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// DCF assumption set entity
+///
+/// A named set of valuation inputs a user can apply to any company
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DcfAssumptionSet {
+    pub id: Uuid,
+    pub user_id: Uuid,
+
+    // Definition
+    pub name: String,
+    pub description: Option<String>,
+
+    /// Assumptions as JSON
+    /// Example: {"projection_years": 5, "revenue_growth": [8.0, 6.0], "fcf_margin": [15.0], "wacc": 9.0, "terminal_growth": 2.5}
+    pub assumptions: serde_json::Value,
+
+    // Audit
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
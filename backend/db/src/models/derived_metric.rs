@@ -24,3 +24,20 @@ pub struct DerivedMetric {
     // Audit
     pub created_at: DateTime<Utc>,
 }
+
+/// A value a derived metric was recorded with
+///
+/// One row per metric, period and day the value changed; see
+/// `derived_metrics_history`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DerivedMetricSnapshot {
+    pub company_id: Uuid,
+    pub period_end_date: NaiveDate,
+    pub period_type: String,
+    pub metric_name: String,
+    pub metric_value: Option<BigDecimal>,
+
+    /// Day the value was recorded
+    pub snapshot_date: NaiveDate,
+    pub recorded_at: DateTime<Utc>,
+}
@@ -0,0 +1,22 @@
+use bigdecimal::BigDecimal;
+/// Earnings event model
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A scheduled or past earnings report, as seen by the earnings poll
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EarningsEvent {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub report_date: NaiveDate,
+    /// Fiscal period the report covers
+    pub fiscal_date_ending: Option<NaiveDate>,
+    pub eps_estimate: Option<BigDecimal>,
+    pub currency: Option<String>,
+
+    // Audit
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
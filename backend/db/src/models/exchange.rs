@@ -0,0 +1,20 @@
+/// Exchange model
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Exchange entity
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Exchange {
+    pub code: String,
+    pub name: String,
+    pub country: String,
+    /// IANA timezone the exchange's sessions are held in
+    pub timezone: String,
+    pub currency: String,
+    /// Session schedule in the trading calendar format
+    /// Example: {"days": ["Monday", ...], "market_close": "16:00", "holidays": [...]}
+    pub trading_days: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -1,6 +1,14 @@
 /// Financial statement models
+///
+/// The conversions between these rows, their insert data and the
+/// `domain::domain` statement models all live at the bottom of this file.
+use crate::repositories::{BalanceSheetInsert, CashFlowStatementInsert, IncomeStatementInsert};
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, NaiveDate, Utc};
+use domain::domain::{
+    BalanceSheet as DomainBalance, CashFlowStatement as DomainCashFlow,
+    IncomeStatement as DomainIncome,
+};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -41,6 +49,14 @@ pub struct IncomeStatement {
     pub diluted_eps: Option<BigDecimal>,
     pub shares_outstanding: Option<i64>,
 
+    /// Stub period reported while the fiscal year end changed
+    pub is_transitional: bool,
+
+    // Validity: when these values became known and, once superseded by a
+    // later version of the period, when they stopped being current
+    pub known_from: DateTime<Utc>,
+    pub known_to: Option<DateTime<Utc>>,
+
     // Audit
     pub created_at: DateTime<Utc>,
 }
@@ -84,6 +100,11 @@ pub struct BalanceSheet {
     pub total_debt: Option<BigDecimal>,
     pub net_debt: Option<BigDecimal>,
 
+    // Validity: when these values became known and, once superseded by a
+    // later version of the period, when they stopped being current
+    pub known_from: DateTime<Utc>,
+    pub known_to: Option<DateTime<Utc>>,
+
     // Audit
     pub created_at: DateTime<Utc>,
 }
@@ -120,6 +141,199 @@ pub struct CashFlowStatement {
     // Computed fields (from database)
     pub free_cash_flow: Option<BigDecimal>,
 
+    /// Stub period reported while the fiscal year end changed
+    pub is_transitional: bool,
+
+    // Validity: when these values became known and, once superseded by a
+    // later version of the period, when they stopped being current
+    pub known_from: DateTime<Utc>,
+    pub known_to: Option<DateTime<Utc>>,
+
     // Audit
     pub created_at: DateTime<Utc>,
 }
+
+impl From<&IncomeStatement> for DomainIncome {
+    fn from(row: &IncomeStatement) -> Self {
+        Self {
+            period_end_date: row.period_end_date,
+            revenue: row.total_revenue.clone(),
+            cost_of_revenue: row.cost_of_revenue.clone(),
+            gross_profit: row.gross_profit.clone(),
+            operating_expenses: row.operating_expenses.clone(),
+            operating_income: row.operating_income.clone(),
+            interest_income: row.interest_income.clone(),
+            interest_expense: row.interest_expense.clone(),
+            income_before_tax: row.income_before_tax.clone(),
+            income_tax_expense: row.income_tax_expense.clone(),
+            net_income: row.net_income.clone(),
+            depreciation_amortization: row.depreciation_amortization.clone(),
+            ebit: row.ebit.clone(),
+            ebitda: row.ebitda.clone(),
+            eps: row.basic_eps.clone(),
+            diluted_eps: row.diluted_eps.clone(),
+            shares_outstanding: row.shares_outstanding,
+        }
+    }
+}
+
+/// Share counts are stored on the income statement, so the domain balance
+/// sheet's `common_stock_shares_outstanding` is left for the caller to fill
+impl From<&BalanceSheet> for DomainBalance {
+    fn from(row: &BalanceSheet) -> Self {
+        Self {
+            period_end_date: row.period_end_date,
+            total_assets: row.total_assets.clone(),
+            current_assets: row.current_assets.clone(),
+            cash_and_equivalents: row.cash_and_equivalents.clone(),
+            short_term_investments: row.short_term_investments.clone(),
+            inventory: row.inventory.clone(),
+            accounts_receivable: row.accounts_receivable.clone(),
+            non_current_assets: row.non_current_assets.clone(),
+            property_plant_equipment: row.property_plant_equipment.clone(),
+            goodwill: row.goodwill.clone(),
+            intangible_assets: row.intangible_assets.clone(),
+            total_liabilities: row.total_liabilities.clone(),
+            current_liabilities: row.current_liabilities.clone(),
+            accounts_payable: row.accounts_payable.clone(),
+            short_term_debt: row.short_term_debt.clone(),
+            non_current_liabilities: row.non_current_liabilities.clone(),
+            long_term_debt: row.long_term_debt.clone(),
+            total_equity: row.total_equity.clone(),
+            retained_earnings: row.retained_earnings.clone(),
+            common_stock: row.common_stock.clone(),
+            total_debt: row.total_debt.clone(),
+            net_debt: row.net_debt.clone(),
+            common_stock_shares_outstanding: None,
+        }
+    }
+}
+
+impl From<&CashFlowStatement> for DomainCashFlow {
+    fn from(row: &CashFlowStatement) -> Self {
+        Self {
+            period_end_date: row.period_end_date,
+            operating_cash_flow: row.operating_cash_flow.clone(),
+            net_income: row.net_income.clone(),
+            depreciation_depletion: row.depreciation_depletion.clone(),
+            change_in_receivables: row.change_in_receivables.clone(),
+            change_in_inventory: row.change_in_inventory.clone(),
+            change_in_payables: row.change_in_payables.clone(),
+            investing_cash_flow: row.investing_cash_flow.clone(),
+            capital_expenditures: row.capital_expenditures.clone(),
+            investments: row.investments.clone(),
+            financing_cash_flow: row.financing_cash_flow.clone(),
+            dividend_payout: row.dividend_payout.clone(),
+            stock_repurchase: row.stock_repurchase.clone(),
+            debt_repayment: row.debt_repayment.clone(),
+            free_cash_flow: row.free_cash_flow.clone(),
+        }
+    }
+}
+
+impl IncomeStatementInsert {
+    /// Insert data for a domain income statement reported for the given period
+    pub fn from_domain(
+        company_id: Uuid,
+        period_type: &str,
+        fiscal_year: Option<i32>,
+        fiscal_quarter: Option<i32>,
+        s: DomainIncome,
+    ) -> Self {
+        Self {
+            company_id,
+            period_end_date: s.period_end_date,
+            period_type: period_type.to_string(),
+            fiscal_year,
+            fiscal_quarter,
+            total_revenue: s.revenue,
+            cost_of_revenue: s.cost_of_revenue,
+            gross_profit: s.gross_profit,
+            operating_expenses: s.operating_expenses,
+            operating_income: s.operating_income,
+            interest_income: s.interest_income,
+            interest_expense: s.interest_expense,
+            income_before_tax: s.income_before_tax,
+            income_tax_expense: s.income_tax_expense,
+            net_income: s.net_income,
+            depreciation_amortization: s.depreciation_amortization,
+            ebit: s.ebit,
+            ebitda: s.ebitda,
+            basic_eps: s.eps,
+            diluted_eps: s.diluted_eps,
+            shares_outstanding: s.shares_outstanding,
+        }
+    }
+}
+
+impl BalanceSheetInsert {
+    /// Insert data for a domain balance sheet reported for the given period.
+    /// The database computes total and net debt itself.
+    pub fn from_domain(
+        company_id: Uuid,
+        period_type: &str,
+        fiscal_year: Option<i32>,
+        fiscal_quarter: Option<i32>,
+        s: DomainBalance,
+    ) -> Self {
+        Self {
+            company_id,
+            period_end_date: s.period_end_date,
+            period_type: period_type.to_string(),
+            fiscal_year,
+            fiscal_quarter,
+            total_assets: s.total_assets,
+            current_assets: s.current_assets,
+            cash_and_equivalents: s.cash_and_equivalents,
+            short_term_investments: s.short_term_investments,
+            inventory: s.inventory,
+            accounts_receivable: s.accounts_receivable,
+            non_current_assets: s.non_current_assets,
+            property_plant_equipment: s.property_plant_equipment,
+            goodwill: s.goodwill,
+            intangible_assets: s.intangible_assets,
+            total_liabilities: s.total_liabilities,
+            current_liabilities: s.current_liabilities,
+            accounts_payable: s.accounts_payable,
+            short_term_debt: s.short_term_debt,
+            non_current_liabilities: s.non_current_liabilities,
+            long_term_debt: s.long_term_debt,
+            total_equity: s.total_equity,
+            retained_earnings: s.retained_earnings,
+            common_stock: s.common_stock,
+        }
+    }
+}
+
+impl CashFlowStatementInsert {
+    /// Insert data for a domain cash flow statement reported for the given
+    /// period. The database computes free cash flow itself.
+    pub fn from_domain(
+        company_id: Uuid,
+        period_type: &str,
+        fiscal_year: Option<i32>,
+        fiscal_quarter: Option<i32>,
+        s: DomainCashFlow,
+    ) -> Self {
+        Self {
+            company_id,
+            period_end_date: s.period_end_date,
+            period_type: period_type.to_string(),
+            fiscal_year,
+            fiscal_quarter,
+            operating_cash_flow: s.operating_cash_flow,
+            net_income: s.net_income,
+            depreciation_depletion: s.depreciation_depletion,
+            change_in_receivables: s.change_in_receivables,
+            change_in_inventory: s.change_in_inventory,
+            change_in_payables: s.change_in_payables,
+            investing_cash_flow: s.investing_cash_flow,
+            capital_expenditures: s.capital_expenditures,
+            investments: s.investments,
+            financing_cash_flow: s.financing_cash_flow,
+            dividend_payout: s.dividend_payout,
+            stock_repurchase: s.stock_repurchase,
+            debt_repayment: s.debt_repayment,
+        }
+    }
+}
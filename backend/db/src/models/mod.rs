@@ -1,7 +1,13 @@
+pub mod background_job;
 pub mod company;
+pub mod custom_formula;
 pub mod daily_price;
+pub mod data_event;
+pub mod dcf_assumption_set;
 pub mod derived_metric;
 pub mod document;
+pub mod earnings_event;
+pub mod exchange;
 pub mod financials;
 pub mod screener;
 /// Database models
@@ -12,10 +18,16 @@ pub mod user;
 pub mod verdict;
 
 // Re-export commonly used models
+pub use background_job::{BackgroundJob, JobStep};
 pub use company::Company;
-pub use daily_price::DailyPrice;
-pub use derived_metric::DerivedMetric;
+pub use custom_formula::CustomFormula;
+pub use daily_price::{DailyPrice, PriceAsOf};
+pub use data_event::{DataEvent, StatementRevision};
+pub use dcf_assumption_set::DcfAssumptionSet;
+pub use derived_metric::{DerivedMetric, DerivedMetricSnapshot};
 pub use document::{AnalysisReport, Document};
+pub use earnings_event::EarningsEvent;
+pub use exchange::Exchange;
 pub use financials::{BalanceSheet, CashFlowStatement, IncomeStatement};
 pub use screener::Screener;
 pub use user::{RefreshToken, User, UserPreferences};
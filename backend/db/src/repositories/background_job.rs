@@ -0,0 +1,124 @@
+use crate::error::{DbError, DbResult};
+use crate::models::background_job::{BackgroundJob, JobStep};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct BackgroundJobRepository {
+    pool: PgPool,
+}
+
+impl BackgroundJobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Queue a job to run as soon as a worker picks it up, with every step pending
+    pub async fn enqueue(
+        &self,
+        job_type: &str,
+        company_id: Option<Uuid>,
+        steps: &[&str],
+    ) -> DbResult<BackgroundJob> {
+        let progress: Vec<JobStep> = steps.iter().map(|step| JobStep::pending(step)).collect();
+        let progress =
+            serde_json::to_value(progress).map_err(|e| DbError::QueryError(e.to_string()))?;
+
+        let job = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            INSERT INTO background_jobs (job_type, company_id, scheduled_at, status, progress)
+            VALUES ($1, $2, NOW(), 'pending', $3)
+            RETURNING *
+            "#,
+        )
+        .bind(job_type)
+        .bind(company_id)
+        .bind(progress)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(job)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> DbResult<Option<BackgroundJob>> {
+        let job = sqlx::query_as::<_, BackgroundJob>("SELECT * FROM background_jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(job)
+    }
+
+    /// Mark the oldest due pending job of a type running and return it. Jobs
+    /// already claimed by another worker are skipped rather than waited on.
+    pub async fn claim_next(&self, job_type: &str) -> DbResult<Option<BackgroundJob>> {
+        let job = sqlx::query_as::<_, BackgroundJob>(
+            r#"
+            UPDATE background_jobs
+            SET status = 'running', started_at = NOW()
+            WHERE id = (
+                SELECT id FROM background_jobs
+                WHERE job_type = $1 AND status = 'pending' AND scheduled_at <= NOW()
+                ORDER BY scheduled_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(job_type)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(job)
+    }
+
+    /// Record step progress; `records_updated` is the total over all steps
+    pub async fn update_progress(&self, id: Uuid, steps: &[JobStep]) -> DbResult<()> {
+        let records: i32 = steps.iter().map(|s| s.records).sum();
+        let progress =
+            serde_json::to_value(steps).map_err(|e| DbError::QueryError(e.to_string()))?;
+
+        sqlx::query("UPDATE background_jobs SET progress = $2, records_updated = $3 WHERE id = $1")
+            .bind(id)
+            .bind(progress)
+            .bind(records)
+            .execute(&self.pool)
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(())
+    }
+
+    pub async fn complete(&self, id: Uuid) -> DbResult<()> {
+        sqlx::query(
+            "UPDATE background_jobs SET status = 'completed', completed_at = NOW() WHERE id = $1",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(())
+    }
+
+    pub async fn fail(&self, id: Uuid, error_message: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE background_jobs
+            SET status = 'failed', completed_at = NOW(), error_message = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error_message)
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(())
+    }
+}
@@ -1,14 +1,21 @@
 /// Company repository for company and financial data
 use crate::models::{
-    BalanceSheet, CashFlowStatement, Company, DailyPrice, DerivedMetric, IncomeStatement,
+    BalanceSheet, CashFlowStatement, Company, DailyPrice, DerivedMetric, DerivedMetricSnapshot,
+    EarningsEvent, Exchange, IncomeStatement, PriceAsOf,
 };
+use crate::repositories::data_event::{insert_data_event, insert_statement_revision};
 use crate::{DbError, DbResult};
-use bigdecimal::BigDecimal;
-use chrono::NaiveDate;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// Calendar days a peer's close may trail a return's start or end date before
+/// the peer is left out of sector returns
+pub const PEER_CLOSE_MAX_AGE_DAYS: i32 = 7;
+
 // =============================================================================
 // DTOs (Data Transfer Objects)
 // =============================================================================
@@ -38,6 +45,82 @@ pub struct CompanyFilters {
     pub is_active: Option<bool>,
 }
 
+/// Derived metric filters; unset fields and an empty name list match all
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DerivedMetricFilters {
+    pub metric_names: Vec<String>,
+    pub period_type: Option<String>,
+    /// First date, inclusive
+    pub from: Option<NaiveDate>,
+    /// Last date, inclusive
+    pub to: Option<NaiveDate>,
+}
+
+/// Peer group used for cross-sectional metric ranking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PeerGroup {
+    /// Companies sharing the same `companies.industry` value
+    Industry(String),
+    /// Companies sharing the same `companies.sector_id`
+    Sector(Uuid),
+}
+
+/// A peer's stored metric value along with the fiscal calendar it was reported on
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PeerMetric {
+    pub company_id: Uuid,
+    pub period_type: String,
+    pub metric_name: String,
+    pub metric_value: Option<BigDecimal>,
+    pub fiscal_calendar: Option<String>,
+    pub fiscal_year_end_month: Option<i32>,
+}
+
+/// A reported period's end date, e.g. for marking it on a price chart
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StatementPeriod {
+    pub period_end_date: NaiveDate,
+    pub period_type: String,
+    pub fiscal_year: Option<i32>,
+    pub fiscal_quarter: Option<i32>,
+}
+
+impl StatementPeriod {
+    /// "Q3 FY2024" or "FY2024", falling back to the period type
+    pub fn label(&self) -> String {
+        match (
+            self.period_type.as_str(),
+            self.fiscal_year,
+            self.fiscal_quarter,
+        ) {
+            ("quarterly", Some(year), Some(quarter)) => format!("Q{} FY{}", quarter, year),
+            ("annual", Some(year), _) => format!("FY{}", year),
+            (period_type, _, _) => period_type.to_string(),
+        }
+    }
+}
+
+/// New company data, e.g. from a provider's company overview
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompanyInsert {
+    pub symbol: String,
+    pub exchange: String,
+    pub name: String,
+    /// Matched case-insensitively against sector names; unknown sectors are left unset
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub country: Option<String>,
+    pub market_cap: Option<i64>,
+    /// Left unset if not a known currency code
+    pub currency: Option<String>,
+    pub fiscal_year_end_month: Option<i32>,
+    pub description: Option<String>,
+    pub cik: Option<String>,
+    pub address: Option<String>,
+    pub latest_quarter: Option<NaiveDate>,
+    pub shares_outstanding: Option<i64>,
+}
+
 /// Income statement insert data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IncomeStatementInsert {
@@ -138,12 +221,66 @@ pub struct DailyPriceInsert {
 /// Company repository
 pub struct CompanyRepository {
     pool: PgPool,
+    /// Read statements as they were known at this time instead of the
+    /// current versions
+    as_of: Option<DateTime<Utc>>,
 }
 
 impl CompanyRepository {
     /// Create a new company repository
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { pool, as_of: None }
+    }
+
+    /// A repository whose statement queries return the versions that were
+    /// current at `as_of`, ignoring anything ingested or restated later.
+    /// Upserts always write the current version.
+    pub fn as_of(&self, as_of: DateTime<Utc>) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            as_of: Some(as_of),
+        }
+    }
+
+    /// The company's fiscal calendar rule and year-end month as they stood at
+    /// this repository's `as_of`: the first fiscal year-end change detected
+    /// after it recorded the values it replaced. The current values otherwise.
+    pub async fn fiscal_calendar_of(
+        &self,
+        company: &Company,
+    ) -> DbResult<(Option<String>, Option<i32>)> {
+        let current = (
+            company.fiscal_calendar.clone(),
+            company.fiscal_year_end_month,
+        );
+        let Some(as_of) = self.as_of else {
+            return Ok(current);
+        };
+
+        let details = sqlx::query_scalar::<_, serde_json::Value>(
+            r#"
+            SELECT details FROM data_events
+            WHERE company_id = $1 AND event_type = 'fiscal_year_end_change'
+              AND detected_at > $2
+            ORDER BY detected_at
+            LIMIT 1
+            "#,
+        )
+        .bind(company.id)
+        .bind(as_of)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(match details {
+            Some(details) => (
+                details["previous_fiscal_calendar"]
+                    .as_str()
+                    .map(str::to_string),
+                details["from_month"].as_i64().map(|month| month as i32),
+            ),
+            None => current,
+        })
     }
 
     // =========================================================================
@@ -155,8 +292,9 @@ impl CompanyRepository {
         let company = sqlx::query_as::<_, Company>(
             r#"
             SELECT id, symbol, exchange, name, sector_id, industry, country,
-                   market_cap, currency, fiscal_year_end_month, description, cik,
-                   address, latest_quarter, is_active, created_at, updated_at
+                   market_cap, currency, fiscal_year_end_month, fiscal_calendar,
+                   description, cik, address, latest_quarter, is_active, created_at,
+                   updated_at
             FROM companies
             WHERE id = $1
             "#,
@@ -174,8 +312,9 @@ impl CompanyRepository {
         let company = sqlx::query_as::<_, Company>(
             r#"
             SELECT id, symbol, exchange, name, sector_id, industry, country,
-                   market_cap, currency, fiscal_year_end_month, description, cik,
-                   address, latest_quarter, is_active, created_at, updated_at
+                   market_cap, currency, fiscal_year_end_month, fiscal_calendar,
+                   description, cik, address, latest_quarter, is_active, created_at,
+                   updated_at
             FROM companies
             WHERE symbol = $1 AND exchange = $2
             "#,
@@ -189,73 +328,85 @@ impl CompanyRepository {
         Ok(company)
     }
 
-    /// List companies with filters and pagination
+    /// List companies with filters and pagination, largest first
     pub async fn list(
         &self,
         filters: CompanyFilters,
         pagination: Pagination,
     ) -> DbResult<Vec<Company>> {
-        let mut query = String::from(
+        let mut query_builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
             r#"
             SELECT id, symbol, exchange, name, sector_id, industry, country,
-                   market_cap, currency, fiscal_year_end_month, description, cik,
-                   address, latest_quarter, is_active, created_at, updated_at
+                   market_cap, currency, fiscal_year_end_month, fiscal_calendar,
+                   description, cik, address, latest_quarter, is_active, created_at,
+                   updated_at
             FROM companies
-            WHERE 1=1
-            "#,
+            WHERE 1=1"#,
         );
+        push_company_filters(&mut query_builder, &filters);
+        query_builder.push(" ORDER BY market_cap DESC NULLS LAST, symbol ASC LIMIT ");
+        query_builder.push_bind(pagination.limit);
+        query_builder.push(" OFFSET ");
+        query_builder.push_bind(pagination.offset);
 
-        // Build dynamic WHERE clause
-        if filters.exchange.is_some() {
-            query.push_str(" AND exchange = $3");
-        }
-        if filters.country.is_some() {
-            query.push_str(" AND country = $4");
-        }
-        if filters.sector_id.is_some() {
-            query.push_str(" AND sector_id = $5");
-        }
-        if let Some(is_active) = filters.is_active {
-            if is_active {
-                query.push_str(" AND is_active = true");
-            }
-        }
-
-        query.push_str(" ORDER BY market_cap DESC NULLS LAST LIMIT $1 OFFSET $2");
+        let companies = query_builder
+            .build_query_as::<Company>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::from)?;
 
-        let mut q = sqlx::query_as::<_, Company>(&query)
-            .bind(pagination.limit)
-            .bind(pagination.offset);
+        Ok(companies)
+    }
 
-        if let Some(ref exchange) = filters.exchange {
-            q = q.bind(exchange);
-        }
-        if let Some(ref country) = filters.country {
-            q = q.bind(country);
-        }
-        if let Some(sector_id) = filters.sector_id {
-            q = q.bind(sector_id);
-        }
+    /// Count the companies matching `list` filters
+    pub async fn count(&self, filters: &CompanyFilters) -> DbResult<i64> {
+        let mut query_builder =
+            sqlx::QueryBuilder::<sqlx::Postgres>::new("SELECT COUNT(*) FROM companies WHERE 1=1");
+        push_company_filters(&mut query_builder, filters);
 
-        let companies = q.fetch_all(&self.pool).await.map_err(DbError::from)?;
+        let total: (i64,) = query_builder
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(DbError::from)?;
 
-        Ok(companies)
+        Ok(total.0)
     }
 
-    /// Full-text search companies
+    /// Autocomplete search: ticker matches first (exact, then prefix), then
+    /// full-text matches on name and description, then names that are
+    /// merely similar (trigram), so typos still find the company
     pub async fn search(&self, query: &str, limit: i32) -> DbResult<Vec<Company>> {
+        let ticker = query.trim().to_uppercase();
+        let ticker_prefix = format!(
+            "{}%",
+            ticker
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
         let companies = sqlx::query_as::<_, Company>(
             r#"
             SELECT id, symbol, exchange, name, sector_id, industry, country,
-                   market_cap, currency, fiscal_year_end_month, description, cik,
-                   address, latest_quarter, is_active, created_at, updated_at
+                   market_cap, currency, fiscal_year_end_month, fiscal_calendar,
+                   description, cik, address, latest_quarter, is_active, created_at,
+                   updated_at
             FROM companies
-            WHERE search_vector @@ plainto_tsquery('english', $1)
-            ORDER BY ts_rank(search_vector, plainto_tsquery('english', $1)) DESC
-            LIMIT $2
+            WHERE symbol LIKE $2
+               OR search_vector @@ plainto_tsquery('english', $1)
+               OR name % $1
+            ORDER BY symbol = $3 DESC,
+                     symbol LIKE $2 DESC,
+                     ts_rank(search_vector, plainto_tsquery('english', $1)) DESC,
+                     similarity(name, $1) DESC,
+                     market_cap DESC NULLS LAST
+            LIMIT $4
             "#,
         )
-        .bind(query)
+        .bind(query.trim())
+        .bind(ticker_prefix)
+        .bind(&ticker)
         .bind(limit)
         .fetch_all(&self.pool)
         .await
@@ -281,9 +432,12 @@ impl CompanyRepository {
                    total_revenue, cost_of_revenue, gross_profit, operating_expenses,
                    operating_income, interest_income, interest_expense, income_before_tax,
                    income_tax_expense, net_income, depreciation_amortization, ebit, ebitda,
-                   basic_eps, diluted_eps, shares_outstanding, created_at
+                   basic_eps, diluted_eps, shares_outstanding, is_transitional,
+                   known_from, known_to, created_at
             FROM income_statements
             WHERE company_id = $1 AND period_type = $2
+              AND known_from <= COALESCE($4, 'infinity')
+              AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
             ORDER BY period_end_date DESC
             LIMIT $3
             "#,
@@ -291,6 +445,7 @@ impl CompanyRepository {
         .bind(company_id)
         .bind(period_type)
         .bind(limit)
+        .bind(self.as_of)
         .fetch_all(&self.pool)
         .await
         .map_err(DbError::from)?;
@@ -312,9 +467,12 @@ impl CompanyRepository {
                    inventory, accounts_receivable, non_current_assets, property_plant_equipment,
                    goodwill, intangible_assets, total_liabilities, current_liabilities,
                    accounts_payable, short_term_debt, non_current_liabilities, long_term_debt,
-                   total_equity, retained_earnings, common_stock, total_debt, net_debt, created_at
+                   total_equity, retained_earnings, common_stock, total_debt, net_debt,
+                   known_from, known_to, created_at
             FROM balance_sheets
             WHERE company_id = $1 AND period_type = $2
+              AND known_from <= COALESCE($4, 'infinity')
+              AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
             ORDER BY period_end_date DESC
             LIMIT $3
             "#,
@@ -322,6 +480,7 @@ impl CompanyRepository {
         .bind(company_id)
         .bind(period_type)
         .bind(limit)
+        .bind(self.as_of)
         .fetch_all(&self.pool)
         .await
         .map_err(DbError::from)?;
@@ -342,9 +501,12 @@ impl CompanyRepository {
                    operating_cash_flow, net_income, depreciation_depletion, change_in_receivables,
                    change_in_inventory, change_in_payables, investing_cash_flow,
                    capital_expenditures, investments, financing_cash_flow, dividend_payout,
-                   stock_repurchase, debt_repayment, free_cash_flow, created_at
+                   stock_repurchase, debt_repayment, free_cash_flow, is_transitional,
+                   known_from, known_to, created_at
             FROM cash_flow_statements
             WHERE company_id = $1 AND period_type = $2
+              AND known_from <= COALESCE($4, 'infinity')
+              AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
             ORDER BY period_end_date DESC
             LIMIT $3
             "#,
@@ -352,6 +514,128 @@ impl CompanyRepository {
         .bind(company_id)
         .bind(period_type)
         .bind(limit)
+        .bind(self.as_of)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(statements)
+    }
+
+    /// Get the latest `limit` income statements of one period type for
+    /// each of several companies, newest first within each company
+    pub async fn get_income_statements_for_companies(
+        &self,
+        company_ids: &[Uuid],
+        period_type: &str,
+        limit: i32,
+    ) -> DbResult<Vec<IncomeStatement>> {
+        let statements = sqlx::query_as::<_, IncomeStatement>(
+            r#"
+            SELECT id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+                   total_revenue, cost_of_revenue, gross_profit, operating_expenses,
+                   operating_income, interest_income, interest_expense, income_before_tax,
+                   income_tax_expense, net_income, depreciation_amortization, ebit, ebitda,
+                   basic_eps, diluted_eps, shares_outstanding, is_transitional,
+                   known_from, known_to, created_at
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY company_id ORDER BY period_end_date DESC
+                ) AS recency
+                FROM income_statements
+                WHERE company_id = ANY($1) AND period_type = $2
+                  AND known_from <= COALESCE($4, 'infinity')
+                  AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
+            ) latest
+            WHERE recency <= $3
+            ORDER BY company_id, period_end_date DESC
+            "#,
+        )
+        .bind(company_ids)
+        .bind(period_type)
+        .bind(limit)
+        .bind(self.as_of)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(statements)
+    }
+
+    /// Get the latest `limit` balance sheets of one period type for
+    /// each of several companies, newest first within each company
+    pub async fn get_balance_sheets_for_companies(
+        &self,
+        company_ids: &[Uuid],
+        period_type: &str,
+        limit: i32,
+    ) -> DbResult<Vec<BalanceSheet>> {
+        let statements = sqlx::query_as::<_, BalanceSheet>(
+            r#"
+            SELECT id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+                   total_assets, current_assets, cash_and_equivalents, short_term_investments,
+                   inventory, accounts_receivable, non_current_assets, property_plant_equipment,
+                   goodwill, intangible_assets, total_liabilities, current_liabilities,
+                   accounts_payable, short_term_debt, non_current_liabilities, long_term_debt,
+                   total_equity, retained_earnings, common_stock, total_debt, net_debt,
+                   known_from, known_to, created_at
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY company_id ORDER BY period_end_date DESC
+                ) AS recency
+                FROM balance_sheets
+                WHERE company_id = ANY($1) AND period_type = $2
+                  AND known_from <= COALESCE($4, 'infinity')
+                  AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
+            ) latest
+            WHERE recency <= $3
+            ORDER BY company_id, period_end_date DESC
+            "#,
+        )
+        .bind(company_ids)
+        .bind(period_type)
+        .bind(limit)
+        .bind(self.as_of)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(statements)
+    }
+
+    /// Get the latest `limit` cash flow statements of one period type for
+    /// each of several companies, newest first within each company
+    pub async fn get_cash_flow_statements_for_companies(
+        &self,
+        company_ids: &[Uuid],
+        period_type: &str,
+        limit: i32,
+    ) -> DbResult<Vec<CashFlowStatement>> {
+        let statements = sqlx::query_as::<_, CashFlowStatement>(
+            r#"
+            SELECT id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+                   operating_cash_flow, net_income, depreciation_depletion, change_in_receivables,
+                   change_in_inventory, change_in_payables, investing_cash_flow,
+                   capital_expenditures, investments, financing_cash_flow, dividend_payout,
+                   stock_repurchase, debt_repayment, free_cash_flow, is_transitional,
+                   known_from, known_to, created_at
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY company_id ORDER BY period_end_date DESC
+                ) AS recency
+                FROM cash_flow_statements
+                WHERE company_id = ANY($1) AND period_type = $2
+                  AND known_from <= COALESCE($4, 'infinity')
+                  AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
+            ) latest
+            WHERE recency <= $3
+            ORDER BY company_id, period_end_date DESC
+            "#,
+        )
+        .bind(company_ids)
+        .bind(period_type)
+        .bind(limit)
+        .bind(self.as_of)
         .fetch_all(&self.pool)
         .await
         .map_err(DbError::from)?;
@@ -359,6 +643,91 @@ impl CompanyRepository {
         Ok(statements)
     }
 
+    /// Get the most recent daily price with a close for a company
+    pub async fn get_latest_price(&self, company_id: Uuid) -> DbResult<Option<DailyPrice>> {
+        let price = sqlx::query_as::<_, DailyPrice>(
+            r#"
+            SELECT id, company_id, price_date, open, high, low, close, adjusted_close,
+                   volume, dividend_amount, split_coefficient, created_at
+            FROM daily_prices
+            WHERE company_id = $1 AND close IS NOT NULL
+            ORDER BY price_date DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(company_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(price)
+    }
+
+    /// Get the latest price with a close on or before each of `dates`, in one
+    /// query. Dates with no earlier price are left out.
+    pub async fn get_prices_as_of(
+        &self,
+        company_id: Uuid,
+        dates: &[NaiveDate],
+    ) -> DbResult<Vec<PriceAsOf>> {
+        let prices = sqlx::query_as::<_, PriceAsOf>(
+            r#"
+            SELECT d.as_of, p.id, p.company_id, p.price_date, p.open, p.high, p.low, p.close,
+                   p.adjusted_close, p.volume, p.dividend_amount, p.split_coefficient,
+                   p.created_at
+            FROM unnest($2::date[]) AS d(as_of)
+            JOIN LATERAL (
+                SELECT *
+                FROM daily_prices
+                WHERE company_id = $1 AND price_date <= d.as_of AND close IS NOT NULL
+                ORDER BY price_date DESC
+                LIMIT 1
+            ) p ON true
+            ORDER BY d.as_of
+            "#,
+        )
+        .bind(company_id)
+        .bind(dates)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(prices)
+    }
+
+    /// [`Self::get_prices_as_of`] for many companies at once: the latest price
+    /// with a close on or before each (company, date) pair. Pairs with no
+    /// earlier price are left out.
+    pub async fn get_prices_as_of_for_companies(
+        &self,
+        requests: &[(Uuid, NaiveDate)],
+    ) -> DbResult<Vec<PriceAsOf>> {
+        let (company_ids, dates): (Vec<Uuid>, Vec<NaiveDate>) = requests.iter().copied().unzip();
+        let prices = sqlx::query_as::<_, PriceAsOf>(
+            r#"
+            SELECT d.as_of, p.id, p.company_id, p.price_date, p.open, p.high, p.low, p.close,
+                   p.adjusted_close, p.volume, p.dividend_amount, p.split_coefficient,
+                   p.created_at
+            FROM unnest($1::uuid[], $2::date[]) AS d(company_id, as_of)
+            JOIN LATERAL (
+                SELECT *
+                FROM daily_prices
+                WHERE company_id = d.company_id AND price_date <= d.as_of AND close IS NOT NULL
+                ORDER BY price_date DESC
+                LIMIT 1
+            ) p ON true
+            ORDER BY p.company_id, d.as_of
+            "#,
+        )
+        .bind(&company_ids)
+        .bind(&dates)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(prices)
+    }
+
     /// Get daily prices for a company within a date range
     pub async fn get_daily_prices(
         &self,
@@ -387,259 +756,574 @@ impl CompanyRepository {
         Ok(prices)
     }
 
-    // =========================================================================
-    // Derived Metrics Query Methods
-    // =========================================================================
+    /// Get daily prices within a date range for the company listed under
+    /// `symbol`, preferring an active listing, e.g. a benchmark index fund
+    pub async fn get_daily_prices_by_symbol(
+        &self,
+        symbol: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> DbResult<Vec<DailyPrice>> {
+        let prices = sqlx::query_as::<_, DailyPrice>(
+            r#"
+            SELECT id, company_id, price_date, open, high, low, close, adjusted_close,
+                   volume, dividend_amount, split_coefficient, created_at
+            FROM daily_prices
+            WHERE company_id = (
+                    SELECT id FROM companies
+                    WHERE symbol = $1
+                    ORDER BY is_active DESC, created_at
+                    LIMIT 1
+                )
+              AND price_date >= $2
+              AND price_date <= $3
+            ORDER BY price_date ASC
+            "#,
+        )
+        .bind(symbol)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
 
-    /// Get derived metrics for a company
-    pub async fn get_derived_metrics(
+        Ok(prices)
+    }
+
+    /// Get the return, as a fraction, of each active company in the same
+    /// sector from its last close on or before `start_date` to its last close
+    /// on or before `end_date`. Adjusted closes are used when stored at both
+    /// ends, raw closes otherwise. Peers without a close at both ends within
+    /// [`PEER_CLOSE_MAX_AGE_DAYS`] are left out, so delisted or stale peers
+    /// don't count as flat.
+    pub async fn get_sector_peer_returns(
         &self,
         company_id: Uuid,
-        period_type: &str,
-        metric_names: Vec<String>,
-    ) -> DbResult<Vec<DerivedMetric>> {
-        let metrics = sqlx::query_as::<_, DerivedMetric>(
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> DbResult<Vec<f64>> {
+        let returns = sqlx::query_scalar::<_, f64>(
             r#"
-            SELECT id, company_id, period_end_date, period_type, metric_name, metric_value, created_at
-            FROM derived_metrics
-            WHERE company_id = $1
-              AND period_type = $2
-              AND metric_name = ANY($3)
-            ORDER BY period_end_date DESC, metric_name ASC
+            SELECT (
+                CASE
+                    WHEN first.adjusted_close > 0 AND last.adjusted_close IS NOT NULL
+                        THEN last.adjusted_close / first.adjusted_close
+                    ELSE last.close / first.close
+                END - 1
+            )::float8
+            FROM companies c
+            JOIN LATERAL (
+                SELECT close, adjusted_close
+                FROM daily_prices
+                WHERE company_id = c.id AND close IS NOT NULL
+                  AND price_date <= $2 AND price_date > $2 - $4::int
+                ORDER BY price_date DESC
+                LIMIT 1
+            ) first ON true
+            JOIN LATERAL (
+                SELECT close, adjusted_close
+                FROM daily_prices
+                WHERE company_id = c.id AND close IS NOT NULL
+                  AND price_date <= $3 AND price_date > $3 - $4::int
+                ORDER BY price_date DESC
+                LIMIT 1
+            ) last ON true
+            WHERE c.sector_id = (SELECT sector_id FROM companies WHERE id = $1)
+              AND c.id <> $1
+              AND c.is_active = true
+              AND first.close > 0
             "#,
         )
         .bind(company_id)
-        .bind(period_type)
-        .bind(&metric_names)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(PEER_CLOSE_MAX_AGE_DAYS)
         .fetch_all(&self.pool)
         .await
         .map_err(DbError::from)?;
 
-        Ok(metrics)
+        Ok(returns)
     }
 
-    // =========================================================================
-    // Upsert Methods (for background job data insertion)
-    // =========================================================================
-
-    /// Upsert income statement
-    pub async fn upsert_income_statement(
+    /// Get the end date of every reported income statement period within a
+    /// date range, oldest first
+    pub async fn get_statement_periods(
         &self,
-        data: IncomeStatementInsert,
-    ) -> DbResult<IncomeStatement> {
-        let id = Uuid::new_v4();
-
-        let statement = sqlx::query_as::<_, IncomeStatement>(
+        company_id: Uuid,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> DbResult<Vec<StatementPeriod>> {
+        let periods = sqlx::query_as::<_, StatementPeriod>(
             r#"
-            INSERT INTO income_statements (
-                id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
-                total_revenue, cost_of_revenue, gross_profit, operating_expenses,
-                operating_income, interest_income, interest_expense, income_before_tax,
-                income_tax_expense, net_income, depreciation_amortization, ebit, ebitda,
-                basic_eps, diluted_eps, shares_outstanding, created_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, NOW())
-            ON CONFLICT (company_id, period_end_date, period_type) DO UPDATE SET
-                fiscal_year = EXCLUDED.fiscal_year,
-                fiscal_quarter = EXCLUDED.fiscal_quarter,
-                total_revenue = EXCLUDED.total_revenue,
-                cost_of_revenue = EXCLUDED.cost_of_revenue,
-                gross_profit = EXCLUDED.gross_profit,
-                operating_expenses = EXCLUDED.operating_expenses,
-                operating_income = EXCLUDED.operating_income,
-                interest_income = EXCLUDED.interest_income,
-                interest_expense = EXCLUDED.interest_expense,
-                income_before_tax = EXCLUDED.income_before_tax,
-                income_tax_expense = EXCLUDED.income_tax_expense,
-                net_income = EXCLUDED.net_income,
-                depreciation_amortization = EXCLUDED.depreciation_amortization,
-                ebit = EXCLUDED.ebit,
-                ebitda = EXCLUDED.ebitda,
-                basic_eps = EXCLUDED.basic_eps,
-                diluted_eps = EXCLUDED.diluted_eps,
-                shares_outstanding = EXCLUDED.shares_outstanding
-            RETURNING id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
-                      total_revenue, cost_of_revenue, gross_profit, operating_expenses,
-                      operating_income, interest_income, interest_expense, income_before_tax,
-                      income_tax_expense, net_income, depreciation_amortization, ebit, ebitda,
-                      basic_eps, diluted_eps, shares_outstanding, created_at
+            SELECT period_end_date, period_type, fiscal_year, fiscal_quarter
+            FROM income_statements
+            WHERE company_id = $1
+              AND period_end_date >= $2
+              AND period_end_date <= $3
+              AND known_from <= COALESCE($4, 'infinity')
+              AND (known_to IS NULL OR known_to > COALESCE($4, 'infinity'))
+            ORDER BY period_end_date ASC, period_type ASC
             "#,
         )
-        .bind(id)
-        .bind(data.company_id)
-        .bind(data.period_end_date)
-        .bind(data.period_type)
-        .bind(data.fiscal_year)
-        .bind(data.fiscal_quarter)
-        .bind(data.total_revenue)
-        .bind(data.cost_of_revenue)
-        .bind(data.gross_profit)
-        .bind(data.operating_expenses)
-        .bind(data.operating_income)
-        .bind(data.interest_income)
-        .bind(data.interest_expense)
-        .bind(data.income_before_tax)
-        .bind(data.income_tax_expense)
-        .bind(data.net_income)
-        .bind(data.depreciation_amortization)
-        .bind(data.ebit)
-        .bind(data.ebitda)
-        .bind(data.basic_eps)
-        .bind(data.diluted_eps)
-        .bind(data.shares_outstanding)
-        .fetch_one(&self.pool)
+        .bind(company_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(self.as_of)
+        .fetch_all(&self.pool)
         .await
         .map_err(DbError::from)?;
 
-        Ok(statement)
+        Ok(periods)
     }
 
-    /// Upsert balance sheet
-    pub async fn upsert_balance_sheet(&self, data: BalanceSheetInsert) -> DbResult<BalanceSheet> {
-        let id = Uuid::new_v4();
-
-        let sheet = sqlx::query_as::<_, BalanceSheet>(
+    /// Get earnings reports dated within a range, oldest first
+    pub async fn get_earnings_events(
+        &self,
+        company_id: Uuid,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> DbResult<Vec<EarningsEvent>> {
+        let events = sqlx::query_as::<_, EarningsEvent>(
             r#"
-            INSERT INTO balance_sheets (
-                id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
-                total_assets, current_assets, cash_and_equivalents, short_term_investments,
-                inventory, accounts_receivable, non_current_assets, property_plant_equipment,
-                goodwill, intangible_assets, total_liabilities, current_liabilities,
-                accounts_payable, short_term_debt, non_current_liabilities, long_term_debt,
-                total_equity, retained_earnings, common_stock, created_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, NOW())
-            ON CONFLICT (company_id, period_end_date, period_type) DO UPDATE SET
-                fiscal_year = EXCLUDED.fiscal_year,
-                fiscal_quarter = EXCLUDED.fiscal_quarter,
-                total_assets = EXCLUDED.total_assets,
-                current_assets = EXCLUDED.current_assets,
-                cash_and_equivalents = EXCLUDED.cash_and_equivalents,
-                short_term_investments = EXCLUDED.short_term_investments,
-                inventory = EXCLUDED.inventory,
-                accounts_receivable = EXCLUDED.accounts_receivable,
-                non_current_assets = EXCLUDED.non_current_assets,
-                property_plant_equipment = EXCLUDED.property_plant_equipment,
-                goodwill = EXCLUDED.goodwill,
-                intangible_assets = EXCLUDED.intangible_assets,
-                total_liabilities = EXCLUDED.total_liabilities,
-                current_liabilities = EXCLUDED.current_liabilities,
-                accounts_payable = EXCLUDED.accounts_payable,
-                short_term_debt = EXCLUDED.short_term_debt,
-                non_current_liabilities = EXCLUDED.non_current_liabilities,
-                long_term_debt = EXCLUDED.long_term_debt,
-                total_equity = EXCLUDED.total_equity,
-                retained_earnings = EXCLUDED.retained_earnings,
-                common_stock = EXCLUDED.common_stock
-            RETURNING id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
-                      total_assets, current_assets, cash_and_equivalents, short_term_investments,
-                      inventory, accounts_receivable, non_current_assets, property_plant_equipment,
-                      goodwill, intangible_assets, total_liabilities, current_liabilities,
-                      accounts_payable, short_term_debt, non_current_liabilities, long_term_debt,
-                      total_equity, retained_earnings, common_stock, total_debt, net_debt, created_at
+            SELECT id, company_id, report_date, fiscal_date_ending, eps_estimate, currency,
+                   created_at, updated_at
+            FROM earnings_events
+            WHERE company_id = $1
+              AND report_date >= $2
+              AND report_date <= $3
+            ORDER BY report_date ASC
             "#,
         )
-        .bind(id)
-        .bind(data.company_id)
-        .bind(data.period_end_date)
-        .bind(data.period_type)
-        .bind(data.fiscal_year)
-        .bind(data.fiscal_quarter)
-        .bind(data.total_assets)
-        .bind(data.current_assets)
-        .bind(data.cash_and_equivalents)
-        .bind(data.short_term_investments)
-        .bind(data.inventory)
-        .bind(data.accounts_receivable)
-        .bind(data.non_current_assets)
-        .bind(data.property_plant_equipment)
-        .bind(data.goodwill)
-        .bind(data.intangible_assets)
-        .bind(data.total_liabilities)
-        .bind(data.current_liabilities)
-        .bind(data.accounts_payable)
-        .bind(data.short_term_debt)
-        .bind(data.non_current_liabilities)
-        .bind(data.long_term_debt)
-        .bind(data.total_equity)
-        .bind(data.retained_earnings)
-        .bind(data.common_stock)
-        .fetch_one(&self.pool)
+        .bind(company_id)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
         .await
         .map_err(DbError::from)?;
 
-        Ok(sheet)
+        Ok(events)
     }
 
-    /// Upsert cash flow statement
-    pub async fn upsert_cash_flow_statement(
+    /// Get the latest rate converting one unit of `from_currency` into
+    /// `to_currency` on or before a date. A stored rate for the inverse pair is
+    /// inverted when the direct pair is missing.
+    pub async fn get_fx_rate(
         &self,
-        data: CashFlowStatementInsert,
-    ) -> DbResult<CashFlowStatement> {
-        let id = Uuid::new_v4();
-
-        let statement = sqlx::query_as::<_, CashFlowStatement>(
+        from_currency: &str,
+        to_currency: &str,
+        on: NaiveDate,
+    ) -> DbResult<Option<BigDecimal>> {
+        let rate = sqlx::query_scalar::<_, BigDecimal>(
             r#"
-            INSERT INTO cash_flow_statements (
-                id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
-                operating_cash_flow, net_income, depreciation_depletion, change_in_receivables,
-                change_in_inventory, change_in_payables, investing_cash_flow,
-                capital_expenditures, investments, financing_cash_flow, dividend_payout,
-                stock_repurchase, debt_repayment, created_at
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, NOW())
-            ON CONFLICT (company_id, period_end_date, period_type) DO UPDATE SET
-                fiscal_year = EXCLUDED.fiscal_year,
-                fiscal_quarter = EXCLUDED.fiscal_quarter,
-                operating_cash_flow = EXCLUDED.operating_cash_flow,
-                net_income = EXCLUDED.net_income,
-                depreciation_depletion = EXCLUDED.depreciation_depletion,
-                change_in_receivables = EXCLUDED.change_in_receivables,
-                change_in_inventory = EXCLUDED.change_in_inventory,
-                change_in_payables = EXCLUDED.change_in_payables,
-                investing_cash_flow = EXCLUDED.investing_cash_flow,
-                capital_expenditures = EXCLUDED.capital_expenditures,
-                investments = EXCLUDED.investments,
-                financing_cash_flow = EXCLUDED.financing_cash_flow,
-                dividend_payout = EXCLUDED.dividend_payout,
-                stock_repurchase = EXCLUDED.stock_repurchase,
-                debt_repayment = EXCLUDED.debt_repayment
-            RETURNING id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
-                      operating_cash_flow, net_income, depreciation_depletion, change_in_receivables,
-                      change_in_inventory, change_in_payables, investing_cash_flow,
-                      capital_expenditures, investments, financing_cash_flow, dividend_payout,
-                      stock_repurchase, debt_repayment, free_cash_flow, created_at
+            SELECT rate FROM (
+                SELECT rate, rate_date, 0 AS preference
+                FROM fx_rates
+                WHERE from_currency = $1 AND to_currency = $2 AND rate_date <= $3
+                UNION ALL
+                SELECT 1 / rate, rate_date, 1 AS preference
+                FROM fx_rates
+                WHERE from_currency = $2 AND to_currency = $1 AND rate_date <= $3 AND rate <> 0
+            ) rates
+            ORDER BY rate_date DESC, preference ASC
+            LIMIT 1
             "#,
         )
-        .bind(id)
-        .bind(data.company_id)
-        .bind(data.period_end_date)
-        .bind(data.period_type)
-        .bind(data.fiscal_year)
-        .bind(data.fiscal_quarter)
-        .bind(data.operating_cash_flow)
-        .bind(data.net_income)
-        .bind(data.depreciation_depletion)
-        .bind(data.change_in_receivables)
-        .bind(data.change_in_inventory)
-        .bind(data.change_in_payables)
-        .bind(data.investing_cash_flow)
-        .bind(data.capital_expenditures)
-        .bind(data.investments)
-        .bind(data.financing_cash_flow)
-        .bind(data.dividend_payout)
-        .bind(data.stock_repurchase)
-        .bind(data.debt_repayment)
-        .fetch_one(&self.pool)
+        .bind(from_currency)
+        .bind(to_currency)
+        .bind(on)
+        .fetch_optional(&self.pool)
         .await
         .map_err(DbError::from)?;
 
-        Ok(statement)
+        Ok(rate)
     }
 
-    /// Upsert daily price
-    pub async fn upsert_daily_price(&self, data: DailyPriceInsert) -> DbResult<DailyPrice> {
-        let id = Uuid::new_v4();
+    // =========================================================================
+    // Derived Metrics Query Methods
+    // =========================================================================
+
+    /// Get the current derived metrics for a company with a period end date
+    /// in the filtered range
+    pub async fn get_derived_metrics(
+        &self,
+        company_id: Uuid,
+        filters: &DerivedMetricFilters,
+    ) -> DbResult<Vec<DerivedMetric>> {
+        let mut query_builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+            r#"
+            SELECT id, company_id, period_end_date, period_type, metric_name, metric_value, created_at
+            FROM derived_metrics
+            WHERE company_id = "#,
+        );
+        query_builder.push_bind(company_id);
+        push_derived_metric_filters(&mut query_builder, "period_end_date", filters);
+        query_builder.push(" ORDER BY period_end_date DESC, metric_name ASC");
+
+        let metrics = query_builder
+            .build_query_as::<DerivedMetric>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(metrics)
+    }
+
+    /// Get every value recorded for a company's derived metrics on a day in
+    /// the filtered range, oldest first
+    pub async fn get_derived_metric_history(
+        &self,
+        company_id: Uuid,
+        filters: &DerivedMetricFilters,
+    ) -> DbResult<Vec<DerivedMetricSnapshot>> {
+        let mut query_builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+            r#"
+            SELECT company_id, period_end_date, period_type, metric_name, metric_value,
+                   snapshot_date, recorded_at
+            FROM derived_metrics_history
+            WHERE company_id = "#,
+        );
+        query_builder.push_bind(company_id);
+        push_derived_metric_filters(&mut query_builder, "snapshot_date", filters);
+        query_builder.push(" ORDER BY metric_name ASC, snapshot_date ASC, period_end_date ASC");
+
+        let snapshots = query_builder
+            .build_query_as::<DerivedMetricSnapshot>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(snapshots)
+    }
+
+    /// Get each peer's metrics for fiscal periods ending within `tolerance_days`
+    /// of `period_end_date`
+    ///
+    /// Peers' fiscal periods drift by a few days from one another, so this may
+    /// return more than one row per (company, metric); callers pick the
+    /// period closest to the date.
+    pub async fn get_fiscal_peer_metrics(
+        &self,
+        peer_group: &PeerGroup,
+        period_type: &str,
+        period_end_date: NaiveDate,
+        tolerance_days: i64,
+        metric_names: Vec<String>,
+    ) -> DbResult<Vec<DerivedMetric>> {
+        let peer_filter = match peer_group {
+            PeerGroup::Industry(_) => "c.industry = $5",
+            PeerGroup::Sector(_) => "c.sector_id = $5",
+        };
+
+        let query = format!(
+            r#"
+            SELECT dm.id, dm.company_id, dm.period_end_date, dm.period_type,
+                   dm.metric_name, dm.metric_value, dm.created_at
+            FROM derived_metrics dm
+            JOIN companies c ON c.id = dm.company_id
+            WHERE dm.period_type = $1
+              AND dm.period_end_date BETWEEN $2::date - $3::int AND $2::date + $3::int
+              AND dm.metric_name = ANY($4)
+              AND c.is_active = true
+              AND {}
+            ORDER BY dm.company_id, dm.metric_name, dm.period_end_date
+            "#,
+            peer_filter
+        );
+
+        let q = sqlx::query_as::<_, DerivedMetric>(&query)
+            .bind(period_type)
+            .bind(period_end_date)
+            .bind(tolerance_days as i32)
+            .bind(&metric_names);
+        let q = match peer_group {
+            PeerGroup::Industry(industry) => q.bind(industry.clone()),
+            PeerGroup::Sector(sector_id) => q.bind(*sector_id),
+        };
+
+        let metrics = q.fetch_all(&self.pool).await.map_err(DbError::from)?;
+
+        Ok(metrics)
+    }
+
+    /// Get each peer's metrics for one calendar period
+    ///
+    /// Returns both the calendarized rows (`calendar_<period_type>`) and the
+    /// fiscal rows for the period end; callers keep the fiscal rows only for
+    /// peers whose fiscal periods already coincide with the calendar.
+    pub async fn get_calendar_peer_metrics(
+        &self,
+        peer_group: &PeerGroup,
+        period_type: &str,
+        period_end_date: NaiveDate,
+        metric_names: Vec<String>,
+    ) -> DbResult<Vec<PeerMetric>> {
+        let peer_filter = match peer_group {
+            PeerGroup::Industry(_) => "c.industry = $4",
+            PeerGroup::Sector(_) => "c.sector_id = $4",
+        };
+
+        let query = format!(
+            r#"
+            SELECT dm.company_id, dm.period_type, dm.metric_name, dm.metric_value,
+                   c.fiscal_calendar, c.fiscal_year_end_month
+            FROM derived_metrics dm
+            JOIN companies c ON c.id = dm.company_id
+            WHERE dm.period_type IN ($1, 'calendar_' || $1)
+              AND dm.period_end_date = $2
+              AND dm.metric_name = ANY($3)
+              AND c.is_active = true
+              AND {}
+            "#,
+            peer_filter
+        );
+
+        let q = sqlx::query_as::<_, PeerMetric>(&query)
+            .bind(period_type)
+            .bind(period_end_date)
+            .bind(&metric_names);
+        let q = match peer_group {
+            PeerGroup::Industry(industry) => q.bind(industry.clone()),
+            PeerGroup::Sector(sector_id) => q.bind(*sector_id),
+        };
+
+        let metrics = q.fetch_all(&self.pool).await.map_err(DbError::from)?;
+
+        Ok(metrics)
+    }
+
+    /// Get a sector's display name
+    pub async fn find_sector_name(&self, sector_id: Uuid) -> DbResult<Option<String>> {
+        let name = sqlx::query_scalar::<_, String>("SELECT name FROM sectors WHERE id = $1")
+            .bind(sector_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(DbError::from)?;
+
+        Ok(name)
+    }
+
+    /// Every known exchange
+    pub async fn list_exchanges(&self) -> DbResult<Vec<Exchange>> {
+        let exchanges = sqlx::query_as::<_, Exchange>(
+            r#"
+            SELECT code, name, country, timezone, currency, trading_days, created_at, updated_at
+            FROM exchanges
+            ORDER BY code
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(exchanges)
+    }
+
+    /// Whether an exchange code is known
+    pub async fn exchange_exists(&self, code: &str) -> DbResult<bool> {
+        let exists =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM exchanges WHERE code = $1)")
+                .bind(code)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(DbError::from)?;
+
+        Ok(exists)
+    }
+
+    /// Create a company. A symbol already listed on the exchange is a
+    /// [`DbError::DuplicateError`].
+    pub async fn create(&self, data: CompanyInsert) -> DbResult<Company> {
+        let company = sqlx::query_as::<_, Company>(
+            r#"
+            INSERT INTO companies (
+                symbol, exchange, name, sector_id, industry, country, market_cap,
+                currency, fiscal_year_end_month, description, cik, address,
+                latest_quarter, shares_outstanding
+            )
+            VALUES (
+                $1, $2, $3,
+                (SELECT id FROM sectors WHERE LOWER(name) = LOWER($4)),
+                $5, $6, $7,
+                (SELECT code FROM currencies WHERE code = $8),
+                $9, $10, $11, $12, $13, $14
+            )
+            RETURNING id, symbol, exchange, name, sector_id, industry, country,
+                      market_cap, currency, fiscal_year_end_month, fiscal_calendar,
+                      description, cik, address, latest_quarter, is_active, created_at,
+                      updated_at
+            "#,
+        )
+        .bind(&data.symbol)
+        .bind(&data.exchange)
+        .bind(&data.name)
+        .bind(&data.sector)
+        .bind(&data.industry)
+        .bind(&data.country)
+        .bind(data.market_cap)
+        .bind(&data.currency)
+        .bind(data.fiscal_year_end_month)
+        .bind(&data.description)
+        .bind(&data.cik)
+        .bind(&data.address)
+        .bind(data.latest_quarter)
+        .bind(data.shares_outstanding)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(company)
+    }
+
+    // =========================================================================
+    // Upsert Methods (for background job data insertion)
+    // =========================================================================
+
+    /// Upsert income statement
+    ///
+    /// Re-ingesting a period with different values closes the stored version
+    /// and inserts a new current one, recorded as a restatement. A new annual
+    /// period can move the fiscal year end; see [`supersede_changed_version`],
+    /// [`record_restatement`] and [`detect_fiscal_year_end_change`].
+    pub async fn upsert_income_statement(
+        &self,
+        data: IncomeStatementInsert,
+    ) -> DbResult<IncomeStatement> {
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        let statement = upsert_income_statement_in(&mut tx, data).await?;
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(statement)
+    }
+
+    /// Upsert balance sheet
+    ///
+    /// Versions, restatements and fiscal year-end changes are handled as for
+    /// [`Self::upsert_income_statement`]. Balance sheets are point-in-time, so
+    /// they are never stub periods.
+    pub async fn upsert_balance_sheet(&self, data: BalanceSheetInsert) -> DbResult<BalanceSheet> {
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        let sheet = upsert_balance_sheet_in(&mut tx, data).await?;
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(sheet)
+    }
+
+    /// Upsert cash flow statement
+    ///
+    /// Versions, restatements, stub periods and fiscal year-end changes are
+    /// handled as for [`Self::upsert_income_statement`].
+    pub async fn upsert_cash_flow_statement(
+        &self,
+        data: CashFlowStatementInsert,
+    ) -> DbResult<CashFlowStatement> {
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        let statement = upsert_cash_flow_statement_in(&mut tx, data).await?;
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(statement)
+    }
+
+    /// Upsert a batch of a company's statements in one transaction, so a
+    /// failure part-way leaves none of them applied. Returns rows written.
+    pub async fn upsert_statements(
+        &self,
+        income_statements: Vec<IncomeStatementInsert>,
+        balance_sheets: Vec<BalanceSheetInsert>,
+        cash_flow_statements: Vec<CashFlowStatementInsert>,
+    ) -> DbResult<usize> {
+        let written = income_statements.len() + balance_sheets.len() + cash_flow_statements.len();
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        for data in income_statements {
+            upsert_income_statement_in(&mut tx, data).await?;
+        }
+        for data in balance_sheets {
+            upsert_balance_sheet_in(&mut tx, data).await?;
+        }
+        for data in cash_flow_statements {
+            upsert_cash_flow_statement_in(&mut tx, data).await?;
+        }
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(written)
+    }
+
+    /// Upsert a batch of daily prices: rows are streamed into a staging table
+    /// with `COPY` and merged in one statement, so a full history is a single
+    /// round-trip. A date repeated within the batch keeps its last row.
+    /// Returns rows written.
+    pub async fn upsert_daily_prices(&self, rows: &[DailyPriceInsert]) -> DbResult<u64> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        sqlx::query(
+            r#"
+            CREATE TEMP TABLE daily_prices_staging (
+                seq BIGSERIAL,
+                company_id UUID NOT NULL,
+                price_date DATE NOT NULL,
+                open NUMERIC,
+                high NUMERIC,
+                low NUMERIC,
+                close NUMERIC,
+                adjusted_close NUMERIC,
+                volume BIGINT,
+                dividend_amount NUMERIC,
+                split_coefficient NUMERIC
+            ) ON COMMIT DROP
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(DbError::from)?;
+
+        let mut copy = tx
+            .copy_in_raw(
+                "COPY daily_prices_staging (company_id, price_date, open, high, low, close, \
+                 adjusted_close, volume, dividend_amount, split_coefficient) FROM STDIN",
+            )
+            .await
+            .map_err(DbError::from)?;
+        copy.send(copy_rows(rows).into_bytes())
+            .await
+            .map_err(DbError::from)?;
+        copy.finish().await.map_err(DbError::from)?;
+
+        let written = sqlx::query(
+            r#"
+            INSERT INTO daily_prices (
+                company_id, price_date, open, high, low, close, adjusted_close,
+                volume, dividend_amount, split_coefficient
+            )
+            SELECT DISTINCT ON (company_id, price_date)
+                   company_id, price_date, open, high, low, close, adjusted_close,
+                   volume, dividend_amount, split_coefficient
+            FROM daily_prices_staging
+            ORDER BY company_id, price_date, seq DESC
+            ON CONFLICT (company_id, price_date) DO UPDATE SET
+                open = EXCLUDED.open,
+                high = EXCLUDED.high,
+                low = EXCLUDED.low,
+                close = EXCLUDED.close,
+                adjusted_close = EXCLUDED.adjusted_close,
+                volume = EXCLUDED.volume,
+                dividend_amount = EXCLUDED.dividend_amount,
+                split_coefficient = EXCLUDED.split_coefficient
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(DbError::from)?
+        .rows_affected();
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(written)
+    }
+
+    /// Upsert daily price
+    pub async fn upsert_daily_price(&self, data: DailyPriceInsert) -> DbResult<DailyPrice> {
+        let id = Uuid::new_v4();
 
         let price = sqlx::query_as::<_, DailyPrice>(
             r#"
@@ -678,4 +1362,952 @@ impl CompanyRepository {
 
         Ok(price)
     }
+
+    /// Upsert an earnings report from a provider's calendar, keyed by the
+    /// fiscal period it covers so a rescheduled report moves its date rather
+    /// than adding a second event. Reports without a fiscal period are keyed
+    /// by report date, and replaced once the period is known.
+    pub async fn upsert_earnings_event(
+        &self,
+        company_id: Uuid,
+        event: &domain::domain::EarningsEvent,
+    ) -> DbResult<EarningsEvent> {
+        let estimate = event
+            .estimate
+            .and_then(|v| BigDecimal::from_str(&v.to_string()).ok());
+
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        let conflict = if event.fiscal_date_ending.is_some() {
+            sqlx::query(
+                r#"
+                DELETE FROM earnings_events
+                WHERE company_id = $1 AND report_date = $2 AND fiscal_date_ending IS NULL
+                "#,
+            )
+            .bind(company_id)
+            .bind(event.report_date)
+            .execute(&mut *tx)
+            .await
+            .map_err(DbError::from)?;
+            "(company_id, fiscal_date_ending) WHERE fiscal_date_ending IS NOT NULL"
+        } else {
+            "(company_id, report_date) WHERE fiscal_date_ending IS NULL"
+        };
+
+        let query = format!(
+            r#"
+            INSERT INTO earnings_events (
+                company_id, report_date, fiscal_date_ending, eps_estimate, currency
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT {} DO UPDATE SET
+                report_date = EXCLUDED.report_date,
+                eps_estimate = EXCLUDED.eps_estimate,
+                currency = EXCLUDED.currency,
+                updated_at = NOW()
+            RETURNING id, company_id, report_date, fiscal_date_ending, eps_estimate, currency,
+                      created_at, updated_at
+            "#,
+            conflict
+        );
+        let stored = sqlx::query_as::<_, EarningsEvent>(&query)
+            .bind(company_id)
+            .bind(event.report_date)
+            .bind(event.fiscal_date_ending)
+            .bind(estimate)
+            .bind(&event.currency)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DbError::from)?;
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(stored)
+    }
+}
+
+/// Append derived metric filters to a query ending in a WHERE clause, with
+/// the date range applied to `date_column`
+fn push_derived_metric_filters(
+    query_builder: &mut sqlx::QueryBuilder<sqlx::Postgres>,
+    date_column: &str,
+    filters: &DerivedMetricFilters,
+) {
+    if !filters.metric_names.is_empty() {
+        query_builder.push(" AND metric_name = ANY(");
+        query_builder.push_bind(filters.metric_names.clone());
+        query_builder.push(")");
+    }
+    if let Some(period_type) = &filters.period_type {
+        query_builder.push(" AND period_type = ");
+        query_builder.push_bind(period_type.clone());
+    }
+    if let Some(from) = filters.from {
+        query_builder.push(format!(" AND {} >= ", date_column));
+        query_builder.push_bind(from);
+    }
+    if let Some(to) = filters.to {
+        query_builder.push(format!(" AND {} <= ", date_column));
+        query_builder.push_bind(to);
+    }
+}
+
+/// Append the `list` filters to a query ending in a WHERE clause
+fn push_company_filters(
+    query_builder: &mut sqlx::QueryBuilder<sqlx::Postgres>,
+    filters: &CompanyFilters,
+) {
+    if let Some(exchange) = &filters.exchange {
+        query_builder.push(" AND exchange = ");
+        query_builder.push_bind(exchange.clone());
+    }
+    if let Some(country) = &filters.country {
+        query_builder.push(" AND country = ");
+        query_builder.push_bind(country.clone());
+    }
+    if let Some(sector_id) = filters.sector_id {
+        query_builder.push(" AND sector_id = ");
+        query_builder.push_bind(sector_id);
+    }
+    if let Some(is_active) = filters.is_active {
+        query_builder.push(" AND is_active = ");
+        query_builder.push_bind(is_active);
+    }
+}
+
+// =============================================================================
+// Ingestion change detection
+// =============================================================================
+
+/// Statements covering fewer days than this since the previous period end are
+/// stub periods from a fiscal year-end change. Longer gaps are left alone:
+/// they are far more often a missing period than a long transition period.
+const MIN_QUARTER_DAYS: i64 = 80;
+const MIN_ANNUAL_DAYS: i64 = 350;
+
+/// Period ends this close together are the same period reported with drift,
+/// not a stub (mirrors `domain::periods::PERIOD_MATCH_TOLERANCE_DAYS`)
+const PERIOD_DRIFT_DAYS: i64 = 7;
+
+/// Columns that identify a statement or are derived, rather than reported
+const NON_LINE_ITEM_COLUMNS: [&str; 13] = [
+    "id",
+    "company_id",
+    "period_end_date",
+    "period_type",
+    "fiscal_year",
+    "fiscal_quarter",
+    "is_transitional",
+    "known_from",
+    "known_to",
+    "total_debt",
+    "net_debt",
+    "free_cash_flow",
+    "created_at",
+];
+
+/// [`CompanyRepository::upsert_income_statement`] within the caller's transaction
+async fn upsert_income_statement_in(
+    conn: &mut PgConnection,
+    data: IncomeStatementInsert,
+) -> DbResult<IncomeStatement> {
+    let id = Uuid::new_v4();
+    let table = "income_statements";
+    let existing = find_stored_statement(
+        &mut *conn,
+        table,
+        data.company_id,
+        data.period_end_date,
+        &data.period_type,
+    )
+    .await?;
+    let is_transitional = match &existing {
+        Some(row) => row["is_transitional"].as_bool().unwrap_or(false),
+        None => {
+            mark_following_stub(
+                &mut *conn,
+                table,
+                data.company_id,
+                data.period_end_date,
+                &data.period_type,
+            )
+            .await?;
+            is_stub_period(
+                &mut *conn,
+                table,
+                data.company_id,
+                data.period_end_date,
+                &data.period_type,
+            )
+            .await?
+        }
+    };
+    let data = match &existing {
+        Some(stored) => fill_feed_gaps(data, stored),
+        None => data,
+    };
+    let reported = serde_json::to_value(&data).unwrap_or_default();
+    if let Some(stored) = &existing {
+        supersede_changed_version(&mut *conn, table, stored, &reported).await?;
+    }
+
+    let statement = sqlx::query_as::<_, IncomeStatement>(
+        r#"
+        INSERT INTO income_statements (
+            id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+            total_revenue, cost_of_revenue, gross_profit, operating_expenses,
+            operating_income, interest_income, interest_expense, income_before_tax,
+            income_tax_expense, net_income, depreciation_amortization, ebit, ebitda,
+            basic_eps, diluted_eps, shares_outstanding, is_transitional, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, NOW())
+        ON CONFLICT (company_id, period_end_date, period_type) WHERE known_to IS NULL DO UPDATE SET
+            fiscal_year = EXCLUDED.fiscal_year,
+            fiscal_quarter = EXCLUDED.fiscal_quarter,
+            total_revenue = EXCLUDED.total_revenue,
+            cost_of_revenue = EXCLUDED.cost_of_revenue,
+            gross_profit = EXCLUDED.gross_profit,
+            operating_expenses = EXCLUDED.operating_expenses,
+            operating_income = EXCLUDED.operating_income,
+            interest_income = EXCLUDED.interest_income,
+            interest_expense = EXCLUDED.interest_expense,
+            income_before_tax = EXCLUDED.income_before_tax,
+            income_tax_expense = EXCLUDED.income_tax_expense,
+            net_income = EXCLUDED.net_income,
+            depreciation_amortization = EXCLUDED.depreciation_amortization,
+            ebit = EXCLUDED.ebit,
+            ebitda = EXCLUDED.ebitda,
+            basic_eps = EXCLUDED.basic_eps,
+            diluted_eps = EXCLUDED.diluted_eps,
+            shares_outstanding = EXCLUDED.shares_outstanding
+        RETURNING id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+                  total_revenue, cost_of_revenue, gross_profit, operating_expenses,
+                  operating_income, interest_income, interest_expense, income_before_tax,
+                  income_tax_expense, net_income, depreciation_amortization, ebit, ebitda,
+                  basic_eps, diluted_eps, shares_outstanding, is_transitional,
+                  known_from, known_to, created_at
+        "#,
+    )
+    .bind(id)
+    .bind(data.company_id)
+    .bind(data.period_end_date)
+    .bind(&data.period_type)
+    .bind(data.fiscal_year)
+    .bind(data.fiscal_quarter)
+    .bind(data.total_revenue)
+    .bind(data.cost_of_revenue)
+    .bind(data.gross_profit)
+    .bind(data.operating_expenses)
+    .bind(data.operating_income)
+    .bind(data.interest_income)
+    .bind(data.interest_expense)
+    .bind(data.income_before_tax)
+    .bind(data.income_tax_expense)
+    .bind(data.net_income)
+    .bind(data.depreciation_amortization)
+    .bind(data.ebit)
+    .bind(data.ebitda)
+    .bind(data.basic_eps)
+    .bind(data.diluted_eps)
+    .bind(data.shares_outstanding)
+    .bind(is_transitional)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DbError::from)?;
+
+    let period = (
+        "income_statement",
+        statement.period_end_date,
+        statement.period_type.as_str(),
+    );
+    if let Some(stored) = existing {
+        record_restatement(&mut *conn, statement.company_id, period, &stored, &reported).await?;
+    }
+    if statement.period_type == "annual" {
+        detect_fiscal_year_end_change(
+            &mut *conn,
+            table,
+            statement.company_id,
+            statement.period_end_date,
+        )
+        .await?;
+    }
+
+    Ok(statement)
+}
+
+/// [`CompanyRepository::upsert_balance_sheet`] within the caller's transaction
+async fn upsert_balance_sheet_in(
+    conn: &mut PgConnection,
+    data: BalanceSheetInsert,
+) -> DbResult<BalanceSheet> {
+    let id = Uuid::new_v4();
+    let table = "balance_sheets";
+    let existing = find_stored_statement(
+        &mut *conn,
+        table,
+        data.company_id,
+        data.period_end_date,
+        &data.period_type,
+    )
+    .await?;
+    let data = match &existing {
+        Some(stored) => fill_feed_gaps(data, stored),
+        None => data,
+    };
+    let reported = serde_json::to_value(&data).unwrap_or_default();
+    if let Some(stored) = &existing {
+        supersede_changed_version(&mut *conn, table, stored, &reported).await?;
+    }
+
+    let sheet = sqlx::query_as::<_, BalanceSheet>(
+        r#"
+        INSERT INTO balance_sheets (
+            id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+            total_assets, current_assets, cash_and_equivalents, short_term_investments,
+            inventory, accounts_receivable, non_current_assets, property_plant_equipment,
+            goodwill, intangible_assets, total_liabilities, current_liabilities,
+            accounts_payable, short_term_debt, non_current_liabilities, long_term_debt,
+            total_equity, retained_earnings, common_stock, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, NOW())
+        ON CONFLICT (company_id, period_end_date, period_type) WHERE known_to IS NULL DO UPDATE SET
+            fiscal_year = EXCLUDED.fiscal_year,
+            fiscal_quarter = EXCLUDED.fiscal_quarter,
+            total_assets = EXCLUDED.total_assets,
+            current_assets = EXCLUDED.current_assets,
+            cash_and_equivalents = EXCLUDED.cash_and_equivalents,
+            short_term_investments = EXCLUDED.short_term_investments,
+            inventory = EXCLUDED.inventory,
+            accounts_receivable = EXCLUDED.accounts_receivable,
+            non_current_assets = EXCLUDED.non_current_assets,
+            property_plant_equipment = EXCLUDED.property_plant_equipment,
+            goodwill = EXCLUDED.goodwill,
+            intangible_assets = EXCLUDED.intangible_assets,
+            total_liabilities = EXCLUDED.total_liabilities,
+            current_liabilities = EXCLUDED.current_liabilities,
+            accounts_payable = EXCLUDED.accounts_payable,
+            short_term_debt = EXCLUDED.short_term_debt,
+            non_current_liabilities = EXCLUDED.non_current_liabilities,
+            long_term_debt = EXCLUDED.long_term_debt,
+            total_equity = EXCLUDED.total_equity,
+            retained_earnings = EXCLUDED.retained_earnings,
+            common_stock = EXCLUDED.common_stock
+        RETURNING id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+                  total_assets, current_assets, cash_and_equivalents, short_term_investments,
+                  inventory, accounts_receivable, non_current_assets, property_plant_equipment,
+                  goodwill, intangible_assets, total_liabilities, current_liabilities,
+                  accounts_payable, short_term_debt, non_current_liabilities, long_term_debt,
+                  total_equity, retained_earnings, common_stock, total_debt, net_debt,
+                  known_from, known_to, created_at
+        "#,
+    )
+    .bind(id)
+    .bind(data.company_id)
+    .bind(data.period_end_date)
+    .bind(&data.period_type)
+    .bind(data.fiscal_year)
+    .bind(data.fiscal_quarter)
+    .bind(data.total_assets)
+    .bind(data.current_assets)
+    .bind(data.cash_and_equivalents)
+    .bind(data.short_term_investments)
+    .bind(data.inventory)
+    .bind(data.accounts_receivable)
+    .bind(data.non_current_assets)
+    .bind(data.property_plant_equipment)
+    .bind(data.goodwill)
+    .bind(data.intangible_assets)
+    .bind(data.total_liabilities)
+    .bind(data.current_liabilities)
+    .bind(data.accounts_payable)
+    .bind(data.short_term_debt)
+    .bind(data.non_current_liabilities)
+    .bind(data.long_term_debt)
+    .bind(data.total_equity)
+    .bind(data.retained_earnings)
+    .bind(data.common_stock)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DbError::from)?;
+
+    let period = (
+        "balance_sheet",
+        sheet.period_end_date,
+        sheet.period_type.as_str(),
+    );
+    if let Some(stored) = existing {
+        record_restatement(&mut *conn, sheet.company_id, period, &stored, &reported).await?;
+    }
+    if sheet.period_type == "annual" {
+        detect_fiscal_year_end_change(&mut *conn, table, sheet.company_id, sheet.period_end_date)
+            .await?;
+    }
+
+    Ok(sheet)
+}
+
+/// [`CompanyRepository::upsert_cash_flow_statement`] within the caller's transaction
+async fn upsert_cash_flow_statement_in(
+    conn: &mut PgConnection,
+    data: CashFlowStatementInsert,
+) -> DbResult<CashFlowStatement> {
+    let id = Uuid::new_v4();
+    let table = "cash_flow_statements";
+    let existing = find_stored_statement(
+        &mut *conn,
+        table,
+        data.company_id,
+        data.period_end_date,
+        &data.period_type,
+    )
+    .await?;
+    let is_transitional = match &existing {
+        Some(row) => row["is_transitional"].as_bool().unwrap_or(false),
+        None => {
+            mark_following_stub(
+                &mut *conn,
+                table,
+                data.company_id,
+                data.period_end_date,
+                &data.period_type,
+            )
+            .await?;
+            is_stub_period(
+                &mut *conn,
+                table,
+                data.company_id,
+                data.period_end_date,
+                &data.period_type,
+            )
+            .await?
+        }
+    };
+    let data = match &existing {
+        Some(stored) => fill_feed_gaps(data, stored),
+        None => data,
+    };
+    let reported = serde_json::to_value(&data).unwrap_or_default();
+    if let Some(stored) = &existing {
+        supersede_changed_version(&mut *conn, table, stored, &reported).await?;
+    }
+
+    let statement = sqlx::query_as::<_, CashFlowStatement>(
+        r#"
+        INSERT INTO cash_flow_statements (
+            id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+            operating_cash_flow, net_income, depreciation_depletion, change_in_receivables,
+            change_in_inventory, change_in_payables, investing_cash_flow,
+            capital_expenditures, investments, financing_cash_flow, dividend_payout,
+            stock_repurchase, debt_repayment, is_transitional, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, NOW())
+        ON CONFLICT (company_id, period_end_date, period_type) WHERE known_to IS NULL DO UPDATE SET
+            fiscal_year = EXCLUDED.fiscal_year,
+            fiscal_quarter = EXCLUDED.fiscal_quarter,
+            operating_cash_flow = EXCLUDED.operating_cash_flow,
+            net_income = EXCLUDED.net_income,
+            depreciation_depletion = EXCLUDED.depreciation_depletion,
+            change_in_receivables = EXCLUDED.change_in_receivables,
+            change_in_inventory = EXCLUDED.change_in_inventory,
+            change_in_payables = EXCLUDED.change_in_payables,
+            investing_cash_flow = EXCLUDED.investing_cash_flow,
+            capital_expenditures = EXCLUDED.capital_expenditures,
+            investments = EXCLUDED.investments,
+            financing_cash_flow = EXCLUDED.financing_cash_flow,
+            dividend_payout = EXCLUDED.dividend_payout,
+            stock_repurchase = EXCLUDED.stock_repurchase,
+            debt_repayment = EXCLUDED.debt_repayment
+        RETURNING id, company_id, period_end_date, period_type, fiscal_year, fiscal_quarter,
+                  operating_cash_flow, net_income, depreciation_depletion, change_in_receivables,
+                  change_in_inventory, change_in_payables, investing_cash_flow,
+                  capital_expenditures, investments, financing_cash_flow, dividend_payout,
+                  stock_repurchase, debt_repayment, free_cash_flow, is_transitional,
+                  known_from, known_to, created_at
+        "#,
+    )
+    .bind(id)
+    .bind(data.company_id)
+    .bind(data.period_end_date)
+    .bind(&data.period_type)
+    .bind(data.fiscal_year)
+    .bind(data.fiscal_quarter)
+    .bind(data.operating_cash_flow)
+    .bind(data.net_income)
+    .bind(data.depreciation_depletion)
+    .bind(data.change_in_receivables)
+    .bind(data.change_in_inventory)
+    .bind(data.change_in_payables)
+    .bind(data.investing_cash_flow)
+    .bind(data.capital_expenditures)
+    .bind(data.investments)
+    .bind(data.financing_cash_flow)
+    .bind(data.dividend_payout)
+    .bind(data.stock_repurchase)
+    .bind(data.debt_repayment)
+    .bind(is_transitional)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DbError::from)?;
+
+    let period = (
+        "cash_flow_statement",
+        statement.period_end_date,
+        statement.period_type.as_str(),
+    );
+    if let Some(stored) = existing {
+        record_restatement(&mut *conn, statement.company_id, period, &stored, &reported).await?;
+    }
+    if statement.period_type == "annual" {
+        detect_fiscal_year_end_change(
+            &mut *conn,
+            table,
+            statement.company_id,
+            statement.period_end_date,
+        )
+        .await?;
+    }
+
+    Ok(statement)
+}
+
+/// The current version of a statement period as JSON, locked for the upsert
+async fn find_stored_statement(
+    conn: &mut PgConnection,
+    table: &str,
+    company_id: Uuid,
+    period_end_date: NaiveDate,
+    period_type: &str,
+) -> DbResult<Option<serde_json::Value>> {
+    let query = format!(
+        r#"
+        SELECT to_jsonb(s) FROM {} s
+        WHERE company_id = $1 AND period_end_date = $2 AND period_type = $3
+          AND known_to IS NULL
+        FOR UPDATE
+        "#,
+        table
+    );
+    let row = sqlx::query_scalar::<_, serde_json::Value>(&query)
+        .bind(company_id)
+        .bind(period_end_date)
+        .bind(period_type)
+        .fetch_optional(conn)
+        .await
+        .map_err(DbError::from)?;
+
+    Ok(row)
+}
+
+/// Whether a new statement is a stub: shorter than a normal period since the
+/// company's previous statement of the same type
+async fn is_stub_period(
+    conn: &mut PgConnection,
+    table: &str,
+    company_id: Uuid,
+    period_end_date: NaiveDate,
+    period_type: &str,
+) -> DbResult<bool> {
+    let query = format!(
+        r#"
+        SELECT MAX(period_end_date) FROM {}
+        WHERE company_id = $1 AND period_type = $2 AND period_end_date < $3
+        "#,
+        table
+    );
+    let previous = sqlx::query_scalar::<_, Option<NaiveDate>>(&query)
+        .bind(company_id)
+        .bind(period_type)
+        .bind(period_end_date)
+        .fetch_one(conn)
+        .await
+        .map_err(DbError::from)?;
+
+    Ok(previous.is_some_and(|previous| is_stub_gap(previous, period_end_date, period_type)))
+}
+
+/// A period backfilled before an existing one shortens the gap to the period
+/// after it, which can make that one a stub; mark it when it does
+async fn mark_following_stub(
+    conn: &mut PgConnection,
+    table: &str,
+    company_id: Uuid,
+    period_end_date: NaiveDate,
+    period_type: &str,
+) -> DbResult<()> {
+    let query = format!(
+        r#"
+        SELECT id, period_end_date FROM {}
+        WHERE company_id = $1 AND period_type = $2 AND period_end_date > $3
+          AND known_to IS NULL
+        ORDER BY period_end_date
+        LIMIT 1
+        "#,
+        table
+    );
+    let next = sqlx::query_as::<_, (Uuid, NaiveDate)>(&query)
+        .bind(company_id)
+        .bind(period_type)
+        .bind(period_end_date)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DbError::from)?;
+
+    let Some((id, next_end)) = next else {
+        return Ok(());
+    };
+    if !is_stub_gap(period_end_date, next_end, period_type) {
+        return Ok(());
+    }
+    let query = format!("UPDATE {} SET is_transitional = true WHERE id = $1", table);
+    sqlx::query(&query)
+        .bind(id)
+        .execute(conn)
+        .await
+        .map_err(DbError::from)?;
+
+    Ok(())
+}
+
+/// Whether a period ending `period_end_date` is too short to be a full one
+/// after a period ending `previous`
+fn is_stub_gap(previous: NaiveDate, period_end_date: NaiveDate, period_type: &str) -> bool {
+    let min_days = if period_type == "annual" {
+        MIN_ANNUAL_DAYS
+    } else {
+        MIN_QUARTER_DAYS
+    };
+    let days = (period_end_date - previous).num_days();
+    days > PERIOD_DRIFT_DAYS && days < min_days
+}
+
+/// Reported line items of a statement, keyed by column name
+fn line_items(row: &serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    row.as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter(|(column, _)| !NON_LINE_ITEM_COLUMNS.contains(&column.as_str()))
+                .map(|(column, value)| (column.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Compare values numerically where both are numbers, since stored decimals
+/// come back with the column's scale ("100.00" vs "100")
+fn same_value(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    let as_decimal = |v: &serde_json::Value| match v {
+        serde_json::Value::Number(n) => BigDecimal::from_str(&n.to_string()).ok(),
+        serde_json::Value::String(s) => BigDecimal::from_str(s).ok(),
+        _ => None,
+    };
+    match (as_decimal(a), as_decimal(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// A line item as a JSON number. Stored rows (read with `to_jsonb`) carry
+/// numbers but inserts serialize their decimals as strings, so values logged
+/// from either side are normalized before they're written out.
+fn as_number(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => BigDecimal::from_str(s)
+            .ok()
+            .and_then(|d| d.to_f64())
+            .map(serde_json::Value::from)
+            .unwrap_or_else(|| value.clone()),
+        _ => value.clone(),
+    }
+}
+
+/// [`line_items`] with every value as a JSON number
+fn numeric_line_items(row: &serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    line_items(row)
+        .into_iter()
+        .map(|(column, value)| (column, as_number(&value)))
+        .collect()
+}
+
+/// Whether any reported line item differs from the stored version, including
+/// one that has just arrived. A value missing from the report is a gap in the
+/// feed, not a change.
+fn has_new_line_items(stored: &serde_json::Value, reported: &serde_json::Value) -> bool {
+    let stored = line_items(stored);
+    line_items(reported).into_iter().any(|(column, to)| {
+        !to.is_null()
+            && stored
+                .get(&column)
+                .is_some_and(|from| from.is_null() || !same_value(from, &to))
+    })
+}
+
+/// Line items whose newly reported value differs from the stored one. A value
+/// appearing or disappearing is a gap in the feed, not a restatement.
+fn changed_line_items(
+    stored: &serde_json::Value,
+    reported: &serde_json::Value,
+) -> serde_json::Map<String, serde_json::Value> {
+    let stored = line_items(stored);
+    line_items(reported)
+        .into_iter()
+        .filter_map(|(column, to)| {
+            let from = stored.get(&column)?;
+            if from.is_null() || to.is_null() || same_value(from, &to) {
+                return None;
+            }
+            Some((
+                column,
+                serde_json::json!({ "from": as_number(from), "to": as_number(&to) }),
+            ))
+        })
+        .collect()
+}
+
+/// The newly reported statement with line items missing from the feed carried
+/// over from the stored version, so a gap neither erases a known value nor
+/// leaves it out of a new version
+fn fill_feed_gaps<T>(data: T, stored: &serde_json::Value) -> T
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(&data) else {
+        return data;
+    };
+    let stored = line_items(stored);
+    let mut filled = false;
+    for (column, value) in fields.iter_mut() {
+        if !value.is_null() || NON_LINE_ITEM_COLUMNS.contains(&column.as_str()) {
+            continue;
+        }
+        match stored.get(column) {
+            // Decimals go back as strings so they keep their exact value
+            Some(serde_json::Value::Number(n)) if n.is_i64() => {
+                *value = serde_json::Value::Number(n.clone());
+            }
+            Some(serde_json::Value::Number(n)) => {
+                *value = serde_json::Value::String(n.to_string());
+            }
+            Some(serde_json::Value::String(s)) => {
+                *value = serde_json::Value::String(s.clone());
+            }
+            _ => continue,
+        }
+        filled = true;
+    }
+    if !filled {
+        return data;
+    }
+    serde_json::from_value(serde_json::Value::Object(fields)).unwrap_or(data)
+}
+
+/// Close the current version of a period when a newly reported line item
+/// differs from it or fills one it lacked, so the upsert inserts a new version
+/// rather than overwriting what was known until now. Both versions share the
+/// transaction timestamp as their boundary.
+async fn supersede_changed_version(
+    conn: &mut PgConnection,
+    table: &str,
+    stored: &serde_json::Value,
+    reported: &serde_json::Value,
+) -> DbResult<()> {
+    if !has_new_line_items(stored, reported) {
+        return Ok(());
+    }
+    let Some(id) = stored["id"]
+        .as_str()
+        .and_then(|id| Uuid::parse_str(id).ok())
+    else {
+        return Ok(());
+    };
+
+    let query = format!("UPDATE {} SET known_to = NOW() WHERE id = $1", table);
+    sqlx::query(&query)
+        .bind(id)
+        .execute(conn)
+        .await
+        .map_err(DbError::from)?;
+
+    Ok(())
+}
+
+/// Keep both versions of a restated period and log the restatement. The first
+/// restatement of a period also records the values it replaced as reported.
+async fn record_restatement(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    period: (&str, NaiveDate, &str),
+    stored: &serde_json::Value,
+    reported: &serde_json::Value,
+) -> DbResult<()> {
+    let changes = changed_line_items(stored, reported);
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let (statement_type, period_end_date, period_type) = period;
+    let has_history = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM statement_revisions
+            WHERE company_id = $1 AND statement_type = $2
+              AND period_end_date = $3 AND period_type = $4
+        )
+        "#,
+    )
+    .bind(company_id)
+    .bind(statement_type)
+    .bind(period_end_date)
+    .bind(period_type)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DbError::from)?;
+
+    if !has_history {
+        insert_statement_revision(
+            conn,
+            company_id,
+            period,
+            "as_reported",
+            serde_json::Value::Object(numeric_line_items(stored)),
+        )
+        .await?;
+    }
+    insert_statement_revision(
+        conn,
+        company_id,
+        period,
+        "restated",
+        serde_json::Value::Object(numeric_line_items(reported)),
+    )
+    .await?;
+    insert_data_event(
+        conn,
+        company_id,
+        "restatement",
+        Some(period),
+        serde_json::json!({ "changes": changes }),
+    )
+    .await
+}
+
+/// Month a fiscal year ending on `date` is named after. 52/53-week years can
+/// end in the first days of the following month.
+fn fiscal_year_end_month(date: NaiveDate) -> i32 {
+    let anchor = if date.day() <= PERIOD_DRIFT_DAYS as u32 {
+        date - chrono::Duration::days(date.day() as i64)
+    } else {
+        date
+    };
+    anchor.month() as i32
+}
+
+/// Move the company's fiscal year end when its latest annual period ends in a
+/// different month, and log the change. Back-filled older years don't count.
+/// A week-based `fiscal_calendar` rule names the old month, so it is cleared.
+async fn detect_fiscal_year_end_change(
+    conn: &mut PgConnection,
+    table: &str,
+    company_id: Uuid,
+    period_end_date: NaiveDate,
+) -> DbResult<()> {
+    let query = format!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM {}
+            WHERE company_id = $1 AND period_type = 'annual' AND period_end_date > $2
+        )
+        "#,
+        table
+    );
+    let has_later_year = sqlx::query_scalar::<_, bool>(&query)
+        .bind(company_id)
+        .bind(period_end_date)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DbError::from)?;
+    if has_later_year {
+        return Ok(());
+    }
+
+    let (current_month, fiscal_calendar) = sqlx::query_as::<_, (Option<i32>, Option<String>)>(
+        "SELECT fiscal_year_end_month, fiscal_calendar FROM companies WHERE id = $1 FOR UPDATE",
+    )
+    .bind(company_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DbError::from)?;
+
+    let month = fiscal_year_end_month(period_end_date);
+    match current_month {
+        Some(current) if current == month => Ok(()),
+        // First annual period seen: record the year end, nothing changed
+        None => {
+            sqlx::query(
+                "UPDATE companies SET fiscal_year_end_month = $2, updated_at = NOW() WHERE id = $1",
+            )
+            .bind(company_id)
+            .bind(month)
+            .execute(&mut *conn)
+            .await
+            .map_err(DbError::from)?;
+            Ok(())
+        }
+        Some(current) => {
+            sqlx::query(
+                r#"
+                UPDATE companies
+                SET fiscal_year_end_month = $2, fiscal_calendar = NULL, updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(company_id)
+            .bind(month)
+            .execute(&mut *conn)
+            .await
+            .map_err(DbError::from)?;
+
+            insert_data_event(
+                conn,
+                company_id,
+                "fiscal_year_end_change",
+                None,
+                serde_json::json!({
+                    "from_month": current,
+                    "to_month": month,
+                    "previous_fiscal_calendar": fiscal_calendar,
+                    "first_period_end_date": period_end_date,
+                }),
+            )
+            .await
+        }
+    }
+}
+
+/// Rows in `COPY`'s text format: tab-separated, `\N` for null
+fn copy_rows(rows: &[DailyPriceInsert]) -> String {
+    fn field<T: ToString>(value: &Option<T>) -> String {
+        value
+            .as_ref()
+            .map_or_else(|| "\\N".to_string(), ToString::to_string)
+    }
+
+    let mut out = String::new();
+    for row in rows {
+        let fields = [
+            row.company_id.to_string(),
+            row.price_date.to_string(),
+            field(&row.open),
+            field(&row.high),
+            field(&row.low),
+            field(&row.close),
+            field(&row.adjusted_close),
+            field(&row.volume),
+            field(&row.dividend_amount),
+            field(&row.split_coefficient),
+        ];
+        out.push_str(&fields.join("\t"));
+        out.push('\n');
+    }
+    out
 }
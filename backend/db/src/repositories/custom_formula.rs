@@ -0,0 +1,131 @@
+use crate::error::{DbError, DbResult};
+use crate::models::custom_formula::CustomFormula;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCustomFormula {
+    pub name: String,
+    pub expression: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCustomFormula {
+    pub name: Option<String>,
+    pub expression: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct CustomFormulaRepository {
+    pool: PgPool,
+}
+
+impl CustomFormulaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_by_user(&self, user_id: Uuid) -> DbResult<Vec<CustomFormula>> {
+        let formulas = sqlx::query_as::<_, CustomFormula>(
+            r#"
+            SELECT * FROM custom_formulas
+            WHERE user_id = $1
+            ORDER BY name ASC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(formulas)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid, user_id: Uuid) -> DbResult<Option<CustomFormula>> {
+        let formula = sqlx::query_as::<_, CustomFormula>(
+            r#"
+            SELECT * FROM custom_formulas
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(formula)
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        formula: CreateCustomFormula,
+    ) -> DbResult<CustomFormula> {
+        let created_formula = sqlx::query_as::<_, CustomFormula>(
+            r#"
+            INSERT INTO custom_formulas (user_id, name, expression, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(formula.name)
+        .bind(formula.expression)
+        .bind(formula.description)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(created_formula)
+    }
+
+    pub async fn update(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        formula: UpdateCustomFormula,
+    ) -> DbResult<CustomFormula> {
+        let updated_formula = sqlx::query_as::<_, CustomFormula>(
+            r#"
+            UPDATE custom_formulas
+            SET
+                name = COALESCE($3, name),
+                expression = COALESCE($4, expression),
+                description = COALESCE($5, description),
+                updated_at = NOW()
+            WHERE id = $1 AND user_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(formula.name)
+        .bind(formula.expression)
+        .bind(formula.description)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(updated_formula)
+    }
+
+    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM custom_formulas
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::error::{DbError, DbResult};
+use crate::models::data_event::{DataEvent, StatementRevision};
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct DataEventRepository {
+    pool: PgPool,
+}
+
+impl DataEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A company's data events, most recent first
+    pub async fn list_by_company(&self, company_id: Uuid, limit: i64) -> DbResult<Vec<DataEvent>> {
+        let events = sqlx::query_as::<_, DataEvent>(
+            r#"
+            SELECT * FROM data_events
+            WHERE company_id = $1
+            ORDER BY detected_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(company_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(events)
+    }
+
+    /// Every recorded version of one statement period, oldest first
+    pub async fn list_statement_revisions(
+        &self,
+        company_id: Uuid,
+        statement_type: &str,
+        period_end_date: NaiveDate,
+        period_type: &str,
+    ) -> DbResult<Vec<StatementRevision>> {
+        let revisions = sqlx::query_as::<_, StatementRevision>(
+            r#"
+            SELECT * FROM statement_revisions
+            WHERE company_id = $1 AND statement_type = $2
+              AND period_end_date = $3 AND period_type = $4
+            ORDER BY recorded_at ASC, revision_type ASC
+            "#,
+        )
+        .bind(company_id)
+        .bind(statement_type)
+        .bind(period_end_date)
+        .bind(period_type)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(revisions)
+    }
+}
+
+/// Log a data event as part of an ingestion transaction
+pub(crate) async fn insert_data_event(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    event_type: &str,
+    statement: Option<(&str, NaiveDate, &str)>,
+    details: serde_json::Value,
+) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO data_events (company_id, event_type, statement_type, period_end_date, period_type, details)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(company_id)
+    .bind(event_type)
+    .bind(statement.map(|(statement_type, _, _)| statement_type))
+    .bind(statement.map(|(_, period_end_date, _)| period_end_date))
+    .bind(statement.map(|(_, _, period_type)| period_type))
+    .bind(details)
+    .execute(conn)
+    .await
+    .map_err(DbError::from)?;
+
+    Ok(())
+}
+
+/// Record one version of a statement period's line items
+pub(crate) async fn insert_statement_revision(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    (statement_type, period_end_date, period_type): (&str, NaiveDate, &str),
+    revision_type: &str,
+    line_items: serde_json::Value,
+) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO statement_revisions (company_id, statement_type, period_end_date, period_type, revision_type, line_items)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(company_id)
+    .bind(statement_type)
+    .bind(period_end_date)
+    .bind(period_type)
+    .bind(revision_type)
+    .bind(line_items)
+    .execute(conn)
+    .await
+    .map_err(DbError::from)?;
+
+    Ok(())
+}
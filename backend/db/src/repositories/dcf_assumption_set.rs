@@ -0,0 +1,131 @@
+use crate::error::{DbError, DbResult};
+use crate::models::dcf_assumption_set::DcfAssumptionSet;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateDcfAssumptionSet {
+    pub name: String,
+    pub description: Option<String>,
+    pub assumptions: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateDcfAssumptionSet {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub assumptions: Option<serde_json::Value>,
+}
+
+#[derive(Clone)]
+pub struct DcfAssumptionSetRepository {
+    pool: PgPool,
+}
+
+impl DcfAssumptionSetRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_by_user(&self, user_id: Uuid) -> DbResult<Vec<DcfAssumptionSet>> {
+        let sets = sqlx::query_as::<_, DcfAssumptionSet>(
+            r#"
+            SELECT * FROM dcf_assumption_sets
+            WHERE user_id = $1
+            ORDER BY name ASC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(sets)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid, user_id: Uuid) -> DbResult<Option<DcfAssumptionSet>> {
+        let set = sqlx::query_as::<_, DcfAssumptionSet>(
+            r#"
+            SELECT * FROM dcf_assumption_sets
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(set)
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        set: CreateDcfAssumptionSet,
+    ) -> DbResult<DcfAssumptionSet> {
+        let created_set = sqlx::query_as::<_, DcfAssumptionSet>(
+            r#"
+            INSERT INTO dcf_assumption_sets (user_id, name, description, assumptions)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(set.name)
+        .bind(set.description)
+        .bind(set.assumptions)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(created_set)
+    }
+
+    pub async fn update(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        set: UpdateDcfAssumptionSet,
+    ) -> DbResult<DcfAssumptionSet> {
+        let updated_set = sqlx::query_as::<_, DcfAssumptionSet>(
+            r#"
+            UPDATE dcf_assumption_sets
+            SET
+                name = COALESCE($3, name),
+                description = COALESCE($4, description),
+                assumptions = COALESCE($5, assumptions),
+                updated_at = NOW()
+            WHERE id = $1 AND user_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(set.name)
+        .bind(set.description)
+        .bind(set.assumptions)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(updated_set)
+    }
+
+    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM dcf_assumption_sets
+            WHERE id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,5 +1,10 @@
+pub mod background_job;
 pub mod company;
+pub mod custom_formula;
+pub mod data_event;
+pub mod dcf_assumption_set;
 pub mod document;
+pub mod price_partition;
 pub mod screener_repository;
 pub mod tracker_repository;
 /// Database repositories
@@ -10,11 +15,19 @@ pub mod user;
 pub mod verdict;
 
 // Re-export commonly used repositories
+pub use background_job::BackgroundJobRepository;
 pub use company::{
-    BalanceSheetInsert, CashFlowStatementInsert, CompanyFilters, CompanyRepository,
-    DailyPriceInsert, IncomeStatementInsert, Pagination,
+    BalanceSheetInsert, CashFlowStatementInsert, CompanyFilters, CompanyInsert, CompanyRepository,
+    DailyPriceInsert, DerivedMetricFilters, IncomeStatementInsert, Pagination, PeerGroup,
+    PeerMetric, StatementPeriod,
+};
+pub use custom_formula::{CreateCustomFormula, CustomFormulaRepository, UpdateCustomFormula};
+pub use data_event::DataEventRepository;
+pub use dcf_assumption_set::{
+    CreateDcfAssumptionSet, DcfAssumptionSetRepository, UpdateDcfAssumptionSet,
 };
 pub use document::{CreateDocumentParams, DocumentRepository};
+pub use price_partition::{PricePartitionRepository, DEFAULT_PRICE_PARTITION};
 pub use screener_repository::{CreateScreener, ScreenerRepository, UpdateScreener};
 pub use tracker_repository::{
     Pagination as TrackerPagination, TrackerItem, TrackerRepository, TrackerSummary,
@@ -0,0 +1,117 @@
+use crate::error::{DbError, DbResult};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+/// Holds prices for years without a partition of their own
+pub const DEFAULT_PRICE_PARTITION: &str = "daily_prices_default";
+
+/// Maintains the yearly `daily_prices_<year>` range partitions
+#[derive(Clone)]
+pub struct PricePartitionRepository {
+    pool: PgPool,
+}
+
+impl PricePartitionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Years that have their own partition
+    pub async fn partition_years(&self) -> DbResult<Vec<i32>> {
+        let years = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT substring(child.relname FROM '^daily_prices_([0-9]{4})$')::int AS year
+            FROM pg_inherits i
+            JOIN pg_class child ON child.oid = i.inhrelid
+            JOIN pg_class parent ON parent.oid = i.inhparent
+            WHERE parent.relname = 'daily_prices'
+              AND child.relname ~ '^daily_prices_[0-9]{4}$'
+            ORDER BY year
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(years)
+    }
+
+    /// Years with prices sitting in the default partition
+    pub async fn default_partition_years(&self) -> DbResult<Vec<i32>> {
+        let years = sqlx::query_scalar::<_, i32>(&format!(
+            "SELECT DISTINCT EXTRACT(YEAR FROM price_date)::int AS year FROM {} ORDER BY year",
+            DEFAULT_PRICE_PARTITION
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(DbError::from)?;
+
+        Ok(years)
+    }
+
+    /// Create the partition for `year` unless it exists, moving that year's
+    /// rows out of the default partition. Returns whether it was created.
+    pub async fn ensure_year(&self, year: i32) -> DbResult<bool> {
+        let bounds =
+            NaiveDate::from_ymd_opt(year, 1, 1).zip(NaiveDate::from_ymd_opt(year + 1, 1, 1));
+        let (from, to) = match bounds {
+            Some(bounds) if (1000..=9998).contains(&year) => bounds,
+            _ => {
+                return Err(DbError::QueryError(format!(
+                    "Invalid price partition year {}",
+                    year
+                )))
+            }
+        };
+        let name = format!("daily_prices_{}", year);
+
+        let mut tx = self.pool.begin().await.map_err(DbError::from)?;
+        // Workers creating the same partition concurrently would otherwise race
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('daily_prices_partitions'))")
+            .execute(&mut *tx)
+            .await
+            .map_err(DbError::from)?;
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(&name)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DbError::from)?;
+        if exists {
+            return Ok(false);
+        }
+
+        // DDL takes no bind parameters; name and bounds derive from the year
+        sqlx::query(&format!(
+            "CREATE TABLE {} (LIKE daily_prices INCLUDING DEFAULTS INCLUDING CONSTRAINTS)",
+            name
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(DbError::from)?;
+        // The year's rows must leave the default partition before attaching
+        sqlx::query(&format!(
+            r#"
+            WITH moved AS (
+                DELETE FROM {} WHERE price_date >= $1 AND price_date < $2 RETURNING *
+            )
+            INSERT INTO {} SELECT * FROM moved
+            "#,
+            DEFAULT_PRICE_PARTITION, name
+        ))
+        .bind(from)
+        .bind(to)
+        .execute(&mut *tx)
+        .await
+        .map_err(DbError::from)?;
+        sqlx::query(&format!(
+            "ALTER TABLE daily_prices ATTACH PARTITION {} FOR VALUES FROM ('{}') TO ('{}')",
+            name, from, to
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(DbError::from)?;
+        tx.commit().await.map_err(DbError::from)?;
+
+        Ok(true)
+    }
+}
@@ -5,11 +5,16 @@ pub struct CompanyOverview {
     pub symbol: String,
     pub name: String,
     pub description: Option<String>,
+    pub cik: Option<String>,
     pub exchange: Option<String>,
     pub currency: Option<String>,
     pub country: Option<String>,
     pub sector: Option<String>,
     pub industry: Option<String>,
+    pub address: Option<String>,
+    /// Month name, e.g. "December"
+    pub fiscal_year_end: Option<String>,
+    pub latest_quarter: Option<chrono::NaiveDate>,
     pub market_capitalization: Option<i64>,
     pub ebitda: Option<i64>,
     pub pe_ratio: Option<f64>,
@@ -57,45 +62,90 @@ pub struct EarningsEvent {
     pub estimate: Option<f64>,
     pub currency: Option<String>,
 }
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Income statement for one period, mirroring the `income_statements` columns
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct IncomeStatement {
     pub period_end_date: chrono::NaiveDate,
     pub revenue: Option<bigdecimal::BigDecimal>,
+    pub cost_of_revenue: Option<bigdecimal::BigDecimal>,
     pub gross_profit: Option<bigdecimal::BigDecimal>,
+    pub operating_expenses: Option<bigdecimal::BigDecimal>,
     pub operating_income: Option<bigdecimal::BigDecimal>,
+    pub interest_income: Option<bigdecimal::BigDecimal>,
+    pub interest_expense: Option<bigdecimal::BigDecimal>,
+    pub income_before_tax: Option<bigdecimal::BigDecimal>,
+    pub income_tax_expense: Option<bigdecimal::BigDecimal>,
     pub net_income: Option<bigdecimal::BigDecimal>,
+    pub depreciation_amortization: Option<bigdecimal::BigDecimal>,
+    pub ebit: Option<bigdecimal::BigDecimal>,
+    pub ebitda: Option<bigdecimal::BigDecimal>,
+    /// Basic EPS
     pub eps: Option<bigdecimal::BigDecimal>,
+    pub diluted_eps: Option<bigdecimal::BigDecimal>,
+    pub shares_outstanding: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Balance sheet at one period end, mirroring the `balance_sheets` columns
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct BalanceSheet {
     pub period_end_date: chrono::NaiveDate,
     pub total_assets: Option<bigdecimal::BigDecimal>,
-    pub total_liabilities: Option<bigdecimal::BigDecimal>,
-    pub total_equity: Option<bigdecimal::BigDecimal>,
+    pub current_assets: Option<bigdecimal::BigDecimal>,
     pub cash_and_equivalents: Option<bigdecimal::BigDecimal>,
     pub short_term_investments: Option<bigdecimal::BigDecimal>,
+    pub inventory: Option<bigdecimal::BigDecimal>,
+    pub accounts_receivable: Option<bigdecimal::BigDecimal>,
+    pub non_current_assets: Option<bigdecimal::BigDecimal>,
+    pub property_plant_equipment: Option<bigdecimal::BigDecimal>,
+    pub goodwill: Option<bigdecimal::BigDecimal>,
+    pub intangible_assets: Option<bigdecimal::BigDecimal>,
+    pub total_liabilities: Option<bigdecimal::BigDecimal>,
+    pub current_liabilities: Option<bigdecimal::BigDecimal>,
+    pub accounts_payable: Option<bigdecimal::BigDecimal>,
     pub short_term_debt: Option<bigdecimal::BigDecimal>,
+    pub non_current_liabilities: Option<bigdecimal::BigDecimal>,
     pub long_term_debt: Option<bigdecimal::BigDecimal>,
+    pub total_equity: Option<bigdecimal::BigDecimal>,
+    pub retained_earnings: Option<bigdecimal::BigDecimal>,
+    pub common_stock: Option<bigdecimal::BigDecimal>,
+    /// Computed by the database from short and long term debt
+    pub total_debt: Option<bigdecimal::BigDecimal>,
+    /// Computed by the database as total debt less cash
     pub net_debt: Option<bigdecimal::BigDecimal>,
     pub common_stock_shares_outstanding: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Cash flow statement for one period, mirroring the `cash_flow_statements` columns
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct CashFlowStatement {
     pub period_end_date: chrono::NaiveDate,
     pub operating_cash_flow: Option<bigdecimal::BigDecimal>,
+    pub net_income: Option<bigdecimal::BigDecimal>,
+    pub depreciation_depletion: Option<bigdecimal::BigDecimal>,
+    pub change_in_receivables: Option<bigdecimal::BigDecimal>,
+    pub change_in_inventory: Option<bigdecimal::BigDecimal>,
+    pub change_in_payables: Option<bigdecimal::BigDecimal>,
+    pub investing_cash_flow: Option<bigdecimal::BigDecimal>,
     pub capital_expenditures: Option<bigdecimal::BigDecimal>,
+    pub investments: Option<bigdecimal::BigDecimal>,
+    pub financing_cash_flow: Option<bigdecimal::BigDecimal>,
+    pub dividend_payout: Option<bigdecimal::BigDecimal>,
+    pub stock_repurchase: Option<bigdecimal::BigDecimal>,
+    pub debt_repayment: Option<bigdecimal::BigDecimal>,
+    /// Computed by the database as operating cash flow less capex
     pub free_cash_flow: Option<bigdecimal::BigDecimal>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DailyPrice {
     pub date: chrono::NaiveDate,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
+    /// Close adjusted for splits and dividends
+    pub adjusted_close: Option<f64>,
+    pub volume: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -1,6 +1,8 @@
 pub mod domain;
 pub mod error;
+pub mod markets;
 pub mod metrics;
 pub mod periods;
 pub mod ports;
 pub mod services;
+pub mod valuation;
@@ -0,0 +1,371 @@
+//! Exchange trading calendars: which days an exchange holds a session and when
+//! the latest one closed, in the exchange's own timezone.
+//!
+//! The built-in calendars come from `trading_calendars.json`, which uses the
+//! same `days`/`market_close`/`holidays` keys as `exchanges.trading_days`;
+//! the `exchanges` table overrides them where it sets a value. Holiday lists
+//! only run as far ahead as the exchanges publish them, so the file needs a
+//! new year added each December. [`TradingCalendars::check_coverage`] fails
+//! for a year that hasn't been added, rather than guessing plain weekdays.
+
+use crate::error::AppError;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+
+const BUILTIN_CALENDARS: &str = include_str!("trading_calendars.json");
+
+static CALENDARS: OnceLock<TradingCalendars> = OnceLock::new();
+
+#[derive(Deserialize)]
+struct CalendarFileEntry {
+    timezone: String,
+    days: Vec<String>,
+    market_close: String,
+    #[serde(default)]
+    holidays: Vec<NaiveDate>,
+}
+
+/// `exchanges.trading_days`: any key left out keeps the calendar's value
+#[derive(Deserialize, Default)]
+struct TradingDaysEntry {
+    days: Option<Vec<String>>,
+    market_close: Option<String>,
+    holidays: Option<Vec<NaiveDate>>,
+}
+
+/// One exchange's sessions
+#[derive(Debug, Clone)]
+pub struct ExchangeCalendar {
+    timezone: Tz,
+    trading_days: Vec<Weekday>,
+    market_close: NaiveTime,
+    holidays: BTreeSet<NaiveDate>,
+}
+
+impl ExchangeCalendar {
+    /// Monday to Friday with no holidays, closing at the end of the UTC day.
+    /// Used for exchanges without a calendar.
+    pub fn weekdays() -> Self {
+        Self {
+            timezone: Tz::UTC,
+            trading_days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            market_close: NaiveTime::from_hms_opt(23, 59, 59).expect("valid time"),
+            holidays: BTreeSet::new(),
+        }
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        self.trading_days.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    /// The latest session on or before `date`, e.g. the Friday before a
+    /// period that ends on a weekend
+    pub fn session_on_or_before(&self, date: NaiveDate) -> NaiveDate {
+        let mut day = date;
+        while !self.is_trading_day(day) {
+            day -= Duration::days(1);
+        }
+        day
+    }
+
+    /// The latest session that had closed at `now`. Before today's close, or
+    /// on a day without a session, that is the previous session.
+    pub fn last_completed_session(&self, now: DateTime<Utc>) -> NaiveDate {
+        let local = now.with_timezone(&self.timezone);
+        let today = local.date_naive();
+        if self.is_trading_day(today) && local.time() >= self.market_close {
+            today
+        } else {
+            self.session_on_or_before(today - Duration::days(1))
+        }
+    }
+
+    /// Last year the holiday list covers; `None` for a calendar without
+    /// holidays, such as [`ExchangeCalendar::weekdays`]
+    pub fn holidays_through(&self) -> Option<i32> {
+        self.holidays.last().map(|date| date.year())
+    }
+
+    fn from_entry(code: &str, entry: CalendarFileEntry) -> Result<Self, AppError> {
+        Self::weekdays().with(
+            code,
+            Some(&entry.timezone),
+            TradingDaysEntry {
+                days: Some(entry.days),
+                market_close: Some(entry.market_close),
+                holidays: Some(entry.holidays),
+            },
+        )
+    }
+
+    /// This calendar with the values that are set replaced
+    fn with(
+        mut self,
+        code: &str,
+        timezone: Option<&str>,
+        entry: TradingDaysEntry,
+    ) -> Result<Self, AppError> {
+        let invalid = |what: String| {
+            AppError::ValidationError(format!("Trading calendar {}: {}", code, what))
+        };
+
+        if let Some(timezone) = timezone {
+            self.timezone = timezone
+                .parse::<Tz>()
+                .map_err(|_| invalid(format!("unknown timezone '{}'", timezone)))?;
+        }
+        if let Some(days) = entry.days {
+            self.trading_days = days
+                .iter()
+                .map(|day| {
+                    day.parse::<Weekday>()
+                        .map_err(|_| invalid(format!("unknown day '{}'", day)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if self.trading_days.is_empty() {
+                return Err(invalid("no trading days".to_string()));
+            }
+        }
+        if let Some(market_close) = entry.market_close {
+            self.market_close = NaiveTime::parse_from_str(&market_close, "%H:%M")
+                .map_err(|_| invalid(format!("invalid market_close '{}'", market_close)))?;
+        }
+        if let Some(holidays) = entry.holidays {
+            self.holidays = holidays.into_iter().collect();
+        }
+
+        Ok(self)
+    }
+}
+
+/// Calendars by exchange code
+#[derive(Debug, Clone)]
+pub struct TradingCalendars {
+    exchanges: HashMap<String, ExchangeCalendar>,
+    fallback: ExchangeCalendar,
+}
+
+impl TradingCalendars {
+    /// Parse calendars in the `trading_calendars.json` format
+    pub fn from_json(json: &str) -> Result<Self, AppError> {
+        let entries: HashMap<String, CalendarFileEntry> = serde_json::from_str(json)
+            .map_err(|e| AppError::ValidationError(format!("Invalid trading calendars: {}", e)))?;
+        let exchanges = entries
+            .into_iter()
+            .map(|(code, entry)| {
+                let calendar = ExchangeCalendar::from_entry(&code, entry)?;
+                Ok((code.to_uppercase(), calendar))
+            })
+            .collect::<Result<_, AppError>>()?;
+
+        Ok(Self {
+            exchanges,
+            fallback: ExchangeCalendar::weekdays(),
+        })
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            AppError::InternalError(format!(
+                "Failed to read trading calendars {:?}: {}",
+                path, e
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Apply an `exchanges` row: its timezone, and whatever its
+    /// `trading_days` sets. An exchange without a calendar starts from
+    /// weekdays.
+    pub fn with_exchange(
+        mut self,
+        code: &str,
+        timezone: &str,
+        trading_days: Option<&serde_json::Value>,
+    ) -> Result<Self, AppError> {
+        let entry = match trading_days {
+            Some(value) => TradingDaysEntry::deserialize(value).map_err(|e| {
+                AppError::ValidationError(format!(
+                    "Trading calendar {}: invalid trading_days: {}",
+                    code, e
+                ))
+            })?,
+            None => TradingDaysEntry::default(),
+        };
+        let code = code.to_uppercase();
+        let calendar = self
+            .exchanges
+            .remove(&code)
+            .unwrap_or_else(|| self.fallback.clone())
+            .with(&code, Some(timezone), entry)?;
+        self.exchanges.insert(code, calendar);
+        Ok(self)
+    }
+
+    /// Fail when an exchange's holiday list stops before `date`'s year, so
+    /// sessions are never guessed from weekdays alone
+    pub fn check_coverage(&self, date: NaiveDate) -> Result<(), AppError> {
+        let mut uncovered: Vec<&str> = self
+            .exchanges
+            .iter()
+            .filter(|(_, calendar)| {
+                calendar
+                    .holidays_through()
+                    .is_some_and(|year| year < date.year())
+            })
+            .map(|(code, _)| code.as_str())
+            .collect();
+        if uncovered.is_empty() {
+            return Ok(());
+        }
+        uncovered.sort();
+        Err(AppError::ValidationError(format!(
+            "Trading calendars have no {} holidays for {}",
+            date.year(),
+            uncovered.join(", ")
+        )))
+    }
+
+    /// The calendars shipped with the crate
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_CALENDARS).expect("built-in trading calendars are valid")
+    }
+
+    /// Use these calendars for the rest of the process. Fails once
+    /// [`TradingCalendars::global`] has been read or calendars were installed.
+    pub fn install(self) -> Result<(), AppError> {
+        CALENDARS.set(self).map_err(|_| {
+            AppError::InternalError("Trading calendars are already in use".to_string())
+        })
+    }
+
+    /// The installed calendars, or the built-in ones if none were installed
+    pub fn global() -> &'static TradingCalendars {
+        CALENDARS.get_or_init(Self::builtin)
+    }
+
+    /// The exchange's calendar; weekdays in UTC for an exchange without one
+    pub fn for_exchange(&self, code: &str) -> &ExchangeCalendar {
+        self.exchanges
+            .get(&code.to_uppercase())
+            .unwrap_or(&self.fallback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_calendars_skip_weekends_and_holidays() {
+        let calendars = TradingCalendars::builtin();
+        let nyse = calendars.for_exchange("NYSE");
+
+        // Good Friday 2026, then the weekend
+        assert!(!nyse.is_trading_day(date(2026, 4, 3)));
+        assert_eq!(
+            nyse.session_on_or_before(date(2026, 4, 5)),
+            date(2026, 4, 2)
+        );
+        // Holi closes the Indian exchanges but not New York
+        assert!(!calendars
+            .for_exchange("nse")
+            .is_trading_day(date(2026, 3, 3)));
+        assert!(nyse.is_trading_day(date(2026, 3, 3)));
+        // Unknown exchanges only skip weekends
+        let other = calendars.for_exchange("LSE");
+        assert!(other.is_trading_day(date(2026, 4, 3)));
+        assert_eq!(
+            other.session_on_or_before(date(2026, 4, 5)),
+            date(2026, 4, 3)
+        );
+    }
+
+    #[test]
+    fn test_last_completed_session_uses_exchange_close_and_timezone() {
+        let calendars = TradingCalendars::builtin();
+        let nyse = calendars.for_exchange("NYSE");
+        let nse = calendars.for_exchange("NSE");
+
+        // Tuesday 2026-03-10 15:00 UTC: 11:00 in New York, 20:30 in Mumbai
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 15, 0, 0).unwrap();
+        assert_eq!(nyse.last_completed_session(now), date(2026, 3, 9));
+        assert_eq!(nse.last_completed_session(now), date(2026, 3, 10));
+
+        // 21:00 UTC is after the New York close
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 21, 0, 0).unwrap();
+        assert_eq!(nyse.last_completed_session(now), date(2026, 3, 10));
+
+        // Monday after Good Friday, before the open: Thursday's session
+        let now = Utc.with_ymd_and_hms(2026, 4, 6, 12, 0, 0).unwrap();
+        assert_eq!(nyse.last_completed_session(now), date(2026, 4, 2));
+    }
+
+    #[test]
+    fn test_exchange_rows_override_builtin_calendars() {
+        let trading_days = serde_json::json!({
+            "days": ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday"],
+            "market_open": "09:15",
+            "market_close": "15:00"
+        });
+        let calendars = TradingCalendars::builtin()
+            .with_exchange("BSE", "Asia/Kolkata", Some(&trading_days))
+            .unwrap()
+            .with_exchange("LSE", "Europe/London", None)
+            .unwrap();
+
+        // The earlier close applies, holidays are kept from the built-in list
+        let bse = calendars.for_exchange("BSE");
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 9, 45, 0).unwrap();
+        assert_eq!(bse.last_completed_session(now), date(2026, 3, 10));
+        assert!(!bse.is_trading_day(date(2026, 3, 3)));
+
+        // An exchange without a built-in calendar gets its own timezone
+        let lse = calendars.for_exchange("LSE");
+        assert_eq!(lse.timezone(), chrono_tz::Europe::London);
+
+        let invalid = serde_json::json!({"days": ["Someday"]});
+        assert!(TradingCalendars::builtin()
+            .with_exchange("NYSE", "America/New_York", Some(&invalid))
+            .is_err());
+    }
+
+    #[test]
+    fn test_coverage_fails_for_years_without_holidays() {
+        let calendars = TradingCalendars::builtin();
+        assert!(calendars.check_coverage(date(2026, 12, 31)).is_ok());
+
+        let err = calendars
+            .check_coverage(date(2028, 1, 3))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("BSE, NASDAQ, NSE, NYSE"), "{}", err);
+    }
+
+    #[test]
+    fn test_invalid_calendar_is_rejected() {
+        let json =
+            r#"{"XYZ": {"timezone": "Mars/Olympus", "days": ["Monday"], "market_close": "16:00"}}"#;
+        assert!(TradingCalendars::from_json(json).is_err());
+    }
+}
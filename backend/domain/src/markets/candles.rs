@@ -0,0 +1,192 @@
+//! OHLCV candles resampled from daily prices for charting.
+//!
+//! A week runs Monday to Sunday and a month is the calendar month; each
+//! candle is dated by the first day of its bucket. Adjusted candles scale the
+//! whole session by `adjusted_close / close`, so splits and dividends don't
+//! show as gaps, and its volume by the inverse so the traded value is kept.
+
+use crate::domain::DailyPrice;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Width of one candle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CandleInterval {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl CandleInterval {
+    /// First day of the bucket `date` falls in
+    pub fn bucket_start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Self::Month => date.with_day(1).expect("first of month is valid"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Candle {
+    /// First day of the bucket
+    pub date: NaiveDate,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Null when no session in the bucket reports volume
+    pub volume: Option<i64>,
+}
+
+impl Candle {
+    /// One session, scaled by `factor`. Open, high and low missing from the
+    /// provider (stored as zero) fall back to the close.
+    fn from_price(price: &DailyPrice, factor: f64) -> Self {
+        let close = price.close;
+        let or_close = |v: f64| if v > 0.0 { v } else { close };
+        let open = or_close(price.open);
+        Self {
+            date: price.date,
+            open: open * factor,
+            high: or_close(price.high).max(open).max(close) * factor,
+            low: or_close(price.low).min(open).min(close) * factor,
+            close: close * factor,
+            volume: price.volume.map(|v| (v as f64 / factor).round() as i64),
+        }
+    }
+
+    fn merge(&mut self, next: Candle) {
+        self.high = self.high.max(next.high);
+        self.low = self.low.min(next.low);
+        self.close = next.close;
+        self.volume = match (self.volume, next.volume) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+    }
+}
+
+/// Candles for `prices`, which must be sorted by date. Sessions without a
+/// positive close are skipped. Adjusted candles are only built when every
+/// session has an adjusted close; `None` otherwise, rather than mixing
+/// adjusted and raw sessions in one chart.
+pub fn resample(
+    prices: &[DailyPrice],
+    interval: CandleInterval,
+    adjusted: bool,
+) -> Option<Vec<Candle>> {
+    let mut candles: Vec<Candle> = Vec::new();
+    for price in prices.iter().filter(|p| p.close > 0.0) {
+        let factor = match price.adjusted_close {
+            _ if !adjusted => 1.0,
+            Some(adjusted_close) if adjusted_close > 0.0 => adjusted_close / price.close,
+            _ => return None,
+        };
+        let mut candle = Candle::from_price(price, factor);
+        candle.date = interval.bucket_start(price.date);
+        match candles.last_mut() {
+            Some(last) if last.date == candle.date => last.merge(candle),
+            _ => candles.push(candle),
+        }
+    }
+    Some(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(date: &str, open: f64, high: f64, low: f64, close: f64, volume: i64) -> DailyPrice {
+        DailyPrice {
+            date: date.parse().unwrap(),
+            open,
+            high,
+            low,
+            close,
+            adjusted_close: None,
+            volume: Some(volume),
+        }
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_candles() {
+        let prices = vec![
+            // Thursday and Friday, then the next week's Monday
+            price("2024-01-25", 10.0, 12.0, 9.0, 11.0, 100),
+            price("2024-01-26", 11.0, 15.0, 10.0, 14.0, 200),
+            price("2024-01-29", 14.0, 14.5, 8.0, 9.0, 300),
+            price("2024-02-01", 9.0, 10.0, 8.5, 9.5, 400),
+        ];
+
+        let weeks = resample(&prices, CandleInterval::Week, false).unwrap();
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(
+            weeks[0],
+            Candle {
+                date: "2024-01-22".parse().unwrap(),
+                open: 10.0,
+                high: 15.0,
+                low: 9.0,
+                close: 14.0,
+                volume: Some(300),
+            }
+        );
+        assert_eq!(weeks[1].date, "2024-01-29".parse().unwrap());
+        assert_eq!(weeks[1].low, 8.0);
+        assert_eq!(weeks[1].volume, Some(700));
+
+        let months = resample(&prices, CandleInterval::Month, false).unwrap();
+        assert_eq!(months.len(), 2);
+        assert_eq!(months[0].close, 9.0);
+        assert_eq!(months[1].date, "2024-02-01".parse().unwrap());
+
+        assert_eq!(
+            resample(&prices, CandleInterval::Day, false).unwrap().len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_adjusted_candles_scale_the_session() {
+        let mut split = price("2024-03-01", 200.0, 210.0, 190.0, 200.0, 10);
+        split.adjusted_close = Some(100.0);
+        // Close-only rows get their open, high and low from the close
+        let mut close_only = price("2024-03-04", 0.0, 0.0, 0.0, 101.0, 20);
+        close_only.adjusted_close = Some(101.0);
+
+        let candles = resample(&[split, close_only.clone()], CandleInterval::Day, true).unwrap();
+        assert_eq!(
+            (
+                candles[0].open,
+                candles[0].high,
+                candles[0].low,
+                candles[0].close
+            ),
+            (100.0, 105.0, 95.0, 100.0)
+        );
+        assert_eq!(
+            (
+                candles[1].open,
+                candles[1].high,
+                candles[1].low,
+                candles[1].close
+            ),
+            (101.0, 101.0, 101.0, 101.0)
+        );
+        // Twice the shares at half the price
+        assert_eq!(candles[0].volume, Some(20));
+        assert_eq!(candles[1].volume, Some(20));
+
+        // A session without an adjusted close can't be charted adjusted
+        let unadjusted = price("2024-03-05", 101.0, 102.0, 100.0, 102.0, 30);
+        assert_eq!(
+            resample(&[close_only, unadjusted], CandleInterval::Day, true),
+            None
+        );
+    }
+}
@@ -1,9 +1,19 @@
 use crate::domain::{BalanceSheet, CashFlowStatement, DailyPrice, IncomeStatement};
+use crate::metrics::capital_allocation::{
+    market_cap, trailing_allocations, CapitalAllocation, ShareholderYield,
+};
 use crate::metrics::MetricValue;
+use crate::periods::PeriodType;
 use bigdecimal::ToPrimitive;
+use chrono::Datelike;
 
 pub struct MetricsCalculator;
 
+/// Smallest |denominator| (in percentage points) for which a sequential ratio
+/// is reported. Below this a tiny base such as a 0.1% margin would turn an
+/// ordinary move into a four-digit ratio, so the ratio is reported as N/A.
+pub const MIN_RATIO_DENOMINATOR: f64 = 0.5;
+
 pub struct ValuationMetrics {
     pub open_ratios: Vec<MetricValue>,
     pub high_ratios: Vec<MetricValue>,
@@ -12,6 +22,38 @@ pub struct ValuationMetrics {
     pub pe_ratios: Vec<MetricValue>,
 }
 
+/// Rolling compound annual growth rates, one value per annual period
+pub struct MultiYearGrowthMetrics {
+    pub revenue_cagr: Vec<MetricValue>,
+    pub eps_cagr: Vec<MetricValue>,
+    pub fcf_cagr: Vec<MetricValue>,
+}
+
+/// Trailing-window statistics on annual YoY revenue growth
+pub struct GrowthConsistencyMetrics {
+    pub positive_growth_periods: Vec<MetricValue>,
+    pub growth_std_dev: Vec<MetricValue>,
+}
+
+/// Working capital turnover in days, one value per period
+pub struct WorkingCapitalMetrics {
+    /// Receivables over revenue
+    pub days_sales_outstanding: Vec<MetricValue>,
+    /// Inventory over cost of revenue
+    pub days_inventory_outstanding: Vec<MetricValue>,
+    /// Payables over cost of revenue
+    pub days_payables_outstanding: Vec<MetricValue>,
+    /// DSO + DIO - DPO
+    pub cash_conversion_cycle: Vec<MetricValue>,
+}
+
+/// Shareholder returns against market cap at each period end
+pub struct ShareholderYieldMetrics {
+    pub dividend_yield: Vec<MetricValue>,
+    pub buyback_yield: Vec<MetricValue>,
+    pub shareholder_yield: Vec<MetricValue>,
+}
+
 impl MetricsCalculator {
     pub fn format_currency_value(value: f64, currency: &str) -> String {
         let abs_val = value.abs();
@@ -87,6 +129,24 @@ impl MetricsCalculator {
             .collect()
     }
 
+    /// Percentile rank (0-100) of `value` within `peer_values`.
+    ///
+    /// `peer_values` should include `value` itself. Ties count as half below,
+    /// so a group of identical values all rank at the 50th percentile.
+    pub fn calculate_peer_percentile(value: f64, peer_values: &[f64]) -> Option<f64> {
+        if peer_values.len() < 2 {
+            return None;
+        }
+        let below = peer_values.iter().filter(|&&v| v < value).count() as f64;
+        let equal = peer_values.iter().filter(|&&v| v == value).count() as f64;
+        Some((below + 0.5 * equal) / peer_values.len() as f64 * 100.0)
+    }
+
+    /// Map a percentile rank onto the 1-4 heat map quartile scale
+    pub fn percentile_to_quartile(percentile: f64) -> i32 {
+        ((percentile / 25.0).ceil() as i32).clamp(1, 4)
+    }
+
     pub fn calculate_revenue_metrics(
         incomes: &[IncomeStatement],
         prior_year_incomes: &[Option<IncomeStatement>],
@@ -208,6 +268,48 @@ impl MetricsCalculator {
         (gross_margins, operating_margins, net_margins)
     }
 
+    /// EBITDA as a percent of revenue. Falls back to operating income plus
+    /// D&A when EBITDA isn't reported.
+    pub fn calculate_ebitda_margin(incomes: &[IncomeStatement]) -> Vec<MetricValue> {
+        incomes
+            .iter()
+            .map(|income| {
+                let rev = income.revenue.as_ref().and_then(|v| v.to_f64());
+                let op = income.operating_income.as_ref().and_then(|v| v.to_f64());
+                let da = income
+                    .depreciation_amortization
+                    .as_ref()
+                    .and_then(|v| v.to_f64());
+                let ebitda = match income.ebitda.as_ref().and_then(|v| v.to_f64()) {
+                    Some(ebitda) => Some(ebitda),
+                    None => op.zip(da).map(|(op, da)| op + da),
+                };
+                let margin = match (ebitda, rev) {
+                    (Some(n), Some(d)) => Self::calculate_margin(n, d),
+                    _ => None,
+                };
+                Self::percent_value(margin)
+            })
+            .collect()
+    }
+
+    /// Income tax expense as a percent of pre-tax income. Undefined for a
+    /// pre-tax loss, where the rate has no meaningful sign.
+    pub fn calculate_effective_tax_rate(incomes: &[IncomeStatement]) -> Vec<MetricValue> {
+        incomes
+            .iter()
+            .map(|income| {
+                let tax = income.income_tax_expense.as_ref().and_then(|v| v.to_f64());
+                let pre_tax = income.income_before_tax.as_ref().and_then(|v| v.to_f64());
+                let rate = match (tax, pre_tax) {
+                    (Some(t), Some(p)) if p > 0.0 => Self::calculate_margin(t, p),
+                    _ => None,
+                };
+                Self::percent_value(rate)
+            })
+            .collect()
+    }
+
     pub fn calculate_expansion_metrics(margins: &[MetricValue]) -> Vec<MetricValue> {
         let mut expansions = Vec::new();
         expansions.push(MetricValue {
@@ -262,6 +364,53 @@ impl MetricsCalculator {
         accelerations
     }
 
+    /// Relative change `(current - prior) / |prior|` in percent.
+    ///
+    /// Returns `None` when `|prior|` is below [`MIN_RATIO_DENOMINATOR`].
+    pub fn calculate_sequential_ratio(current: f64, prior: f64) -> Option<f64> {
+        if prior.abs() < MIN_RATIO_DENOMINATOR {
+            return None;
+        }
+        Some((current - prior) / prior.abs() * 100.0)
+    }
+
+    fn sequential_ratios(values: &[MetricValue]) -> Vec<MetricValue> {
+        let mut ratios = Vec::with_capacity(values.len());
+
+        for i in 0..values.len() {
+            let ratio = if i == 0 {
+                None
+            } else {
+                match (values[i].value, values[i - 1].value) {
+                    (Some(curr), Some(prior)) => Self::calculate_sequential_ratio(curr, prior),
+                    _ => None,
+                }
+            };
+            ratios.push(MetricValue {
+                value: ratio,
+                formatted_value: ratio
+                    .map(|v| format!("{:.2}%", v))
+                    .unwrap_or_else(|| "N/A".to_string()),
+                unit: "%".to_string(),
+                heat_map_quartile: None,
+            });
+        }
+
+        ratios
+    }
+
+    /// Sequential OP margin expansion as defined in FR-SCR-005:
+    /// `(OPM[n] - OPM[n-1]) / |OPM[n-1]|`
+    pub fn calculate_margin_expansion_ratio(margins: &[MetricValue]) -> Vec<MetricValue> {
+        Self::sequential_ratios(margins)
+    }
+
+    /// Sequential YoY growth acceleration ratio as defined in FR-SCR-005:
+    /// `(YoY[n] - YoY[n-1]) / |YoY[n-1]|`
+    pub fn calculate_acceleration_ratio(yoy_growths: &[MetricValue]) -> Vec<MetricValue> {
+        Self::sequential_ratios(yoy_growths)
+    }
+
     pub fn calculate_cash_metrics(
         incomes: &[IncomeStatement],
         cash_flows: &[Option<CashFlowStatement>],
@@ -309,6 +458,201 @@ impl MetricsCalculator {
         (ocf_ratios, fcf_ratios)
     }
 
+    /// Days of flow a period's statements cover, for turning balances into days
+    pub fn period_days(period_type: PeriodType) -> f64 {
+        match period_type {
+            PeriodType::Annual => 365.0,
+            PeriodType::Quarterly => 365.0 / 4.0,
+        }
+    }
+
+    /// Balance sheet amount expressed as days of a period's flow
+    pub fn calculate_days(balance: f64, flow: f64, period_days: f64) -> Option<f64> {
+        if flow <= 0.0 {
+            return None;
+        }
+        Some(balance / flow * period_days)
+    }
+
+    /// DSO, DIO, DPO and the cash conversion cycle from period-end balances.
+    ///
+    /// Cost of revenue falls back to revenue less gross profit when it isn't
+    /// reported. The cycle needs all three components.
+    pub fn calculate_working_capital_metrics(
+        incomes: &[IncomeStatement],
+        balances: &[Option<BalanceSheet>],
+        period_type: PeriodType,
+    ) -> WorkingCapitalMetrics {
+        let days = Self::period_days(period_type);
+        let days_value = |value: Option<f64>| MetricValue {
+            value,
+            formatted_value: value
+                .map(|v| format!("{:.1} days", v))
+                .unwrap_or_else(|| "N/A".to_string()),
+            unit: "days".to_string(),
+            heat_map_quartile: None,
+        };
+
+        let mut dso = Vec::new();
+        let mut dio = Vec::new();
+        let mut dpo = Vec::new();
+        let mut ccc = Vec::new();
+
+        for (i, income) in incomes.iter().enumerate() {
+            let rev = income.revenue.as_ref().and_then(|v| v.to_f64());
+            let cost = income
+                .cost_of_revenue
+                .as_ref()
+                .and_then(|v| v.to_f64())
+                .or_else(|| Some(rev? - income.gross_profit.as_ref()?.to_f64()?));
+            let balance = balances.get(i).and_then(|opt| opt.as_ref());
+            let field = |f: fn(&BalanceSheet) -> &Option<bigdecimal::BigDecimal>| {
+                balance.and_then(|b| f(b).as_ref()).and_then(|v| v.to_f64())
+            };
+
+            let sales_days = match (field(|b| &b.accounts_receivable), rev) {
+                (Some(ar), Some(r)) => Self::calculate_days(ar, r, days),
+                _ => None,
+            };
+            let inventory_days = match (field(|b| &b.inventory), cost) {
+                (Some(inv), Some(c)) => Self::calculate_days(inv, c, days),
+                _ => None,
+            };
+            let payables_days = match (field(|b| &b.accounts_payable), cost) {
+                (Some(ap), Some(c)) => Self::calculate_days(ap, c, days),
+                _ => None,
+            };
+            let cycle = match (sales_days, inventory_days, payables_days) {
+                (Some(s), Some(inv), Some(p)) => Some(s + inv - p),
+                _ => None,
+            };
+
+            dso.push(days_value(sales_days));
+            dio.push(days_value(inventory_days));
+            dpo.push(days_value(payables_days));
+            ccc.push(days_value(cycle));
+        }
+
+        WorkingCapitalMetrics {
+            days_sales_outstanding: dso,
+            days_inventory_outstanding: dio,
+            days_payables_outstanding: dpo,
+            cash_conversion_cycle: ccc,
+        }
+    }
+
+    /// Net income not backed by operating cash flow, as a percent of total
+    /// assets. High or rising accruals flag lower-quality earnings.
+    pub fn calculate_accruals_ratio(
+        incomes: &[IncomeStatement],
+        balances: &[Option<BalanceSheet>],
+        cash_flows: &[Option<CashFlowStatement>],
+    ) -> Vec<MetricValue> {
+        incomes
+            .iter()
+            .enumerate()
+            .map(|(i, income)| {
+                let ni = income.net_income.as_ref().and_then(|v| v.to_f64());
+                let ocf = cash_flows
+                    .get(i)
+                    .and_then(|opt| opt.as_ref())
+                    .and_then(|c| c.operating_cash_flow.as_ref())
+                    .and_then(|v| v.to_f64());
+                let assets = balances
+                    .get(i)
+                    .and_then(|opt| opt.as_ref())
+                    .and_then(|b| b.total_assets.as_ref())
+                    .and_then(|v| v.to_f64());
+
+                let ratio = match (ni, ocf, assets) {
+                    (Some(n), Some(o), Some(a)) if a > 0.0 => Some((n - o) / a * 100.0),
+                    _ => None,
+                };
+                MetricValue {
+                    value: ratio,
+                    formatted_value: ratio
+                        .map(|v| format!("{:.2}%", v))
+                        .unwrap_or_else(|| "N/A".to_string()),
+                    unit: "%".to_string(),
+                    heat_map_quartile: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Capex and dividends plus buybacks as a percent of operating cash flow
+    pub fn calculate_capital_allocation_metrics(
+        incomes: &[IncomeStatement],
+        cash_flows: &[Option<CashFlowStatement>],
+    ) -> (Vec<MetricValue>, Vec<MetricValue>) {
+        let percent = |value: Option<f64>| MetricValue {
+            value,
+            formatted_value: value
+                .map(|v| format!("{:.2}%", v))
+                .unwrap_or_else(|| "N/A".to_string()),
+            unit: "%".to_string(),
+            heat_map_quartile: None,
+        };
+
+        (0..incomes.len())
+            .map(|i| {
+                let allocation = cash_flows
+                    .get(i)
+                    .and_then(|opt| opt.as_ref())
+                    .map(CapitalAllocation::from_cash_flow);
+                let capex = allocation
+                    .as_ref()
+                    .and_then(|a| a.capital_expenditures.pct_of_ocf);
+                let returns = allocation
+                    .as_ref()
+                    .and_then(|a| a.shareholder_returns().pct_of_ocf);
+                (percent(capex), percent(returns))
+            })
+            .unzip()
+    }
+
+    /// Dividend, buyback and total shareholder yield on the market cap at
+    /// each period end
+    pub fn calculate_shareholder_yield_metrics(
+        incomes: &[IncomeStatement],
+        balances: &[Option<BalanceSheet>],
+        cash_flows: &[Option<CashFlowStatement>],
+        prices: &[Option<DailyPrice>],
+        period_type: PeriodType,
+    ) -> ShareholderYieldMetrics {
+        let percent = |value: Option<f64>| MetricValue {
+            value,
+            formatted_value: value
+                .map(|v| format!("{:.2}%", v))
+                .unwrap_or_else(|| "N/A".to_string()),
+            unit: "%".to_string(),
+            heat_map_quartile: None,
+        };
+
+        let mut dividend_yield = Vec::new();
+        let mut buyback_yield = Vec::new();
+        let mut shareholder_yield = Vec::new();
+        for i in 0..incomes.len() {
+            // Quarterly yields are trailing-twelve-month, like annual ones
+            let yields = trailing_allocations(cash_flows, i, period_type).map(|window| {
+                let cap = market_cap(
+                    balances.get(i).and_then(|opt| opt.as_ref()),
+                    prices.get(i).and_then(|opt| opt.as_ref()),
+                );
+                ShareholderYield::over_periods(&window, cap)
+            });
+            dividend_yield.push(percent(yields.and_then(|y| y.dividend_yield)));
+            buyback_yield.push(percent(yields.and_then(|y| y.buyback_yield)));
+            shareholder_yield.push(percent(yields.and_then(|y| y.shareholder_yield)));
+        }
+
+        ShareholderYieldMetrics {
+            dividend_yield,
+            buyback_yield,
+            shareholder_yield,
+        }
+    }
+
     pub fn calculate_valuation_metrics(
         incomes: &[IncomeStatement],
         prices: &[Option<DailyPrice>],
@@ -414,6 +758,172 @@ impl MetricsCalculator {
         (revenue_minus_net_debt_ratios, shares_outstanding)
     }
 
+    /// Compound annual growth rate in percent.
+    ///
+    /// Undefined when the starting value is not positive or the ending value is
+    /// negative, since a growth rate cannot bridge a sign change.
+    pub fn calculate_cagr(begin: f64, end: f64, years: f64) -> Option<f64> {
+        if years <= 0.0 || begin <= 0.0 || end < 0.0 {
+            return None;
+        }
+        Some(((end / begin).powf(1.0 / years) - 1.0) * 100.0)
+    }
+
+    /// Sample standard deviation (n - 1 denominator)
+    pub fn calculate_std_dev(values: &[f64]) -> Option<f64> {
+        if values.len() < 2 {
+            return None;
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        Some(variance.sqrt())
+    }
+
+    /// Index of the statement ending `years` fiscal years before `incomes[idx]`.
+    ///
+    /// Matched by date rather than position so that a missing year in the
+    /// history does not silently stretch the lookback window.
+    fn find_years_prior(incomes: &[IncomeStatement], idx: usize, years: i32) -> Option<usize> {
+        let target = incomes[idx].period_end_date;
+        incomes.iter().position(|i| {
+            target.year() - i.period_end_date.year() == years
+                && (target.month() as i32 - i.period_end_date.month() as i32).abs() <= 1
+        })
+    }
+
+    /// Rolling `years`-year CAGRs of revenue, EPS and FCF.
+    ///
+    /// `annual_incomes` must be annual statements sorted by date ascending and
+    /// `annual_cash_flows` aligned to them by index.
+    pub fn calculate_multi_year_growth(
+        annual_incomes: &[IncomeStatement],
+        annual_cash_flows: &[Option<CashFlowStatement>],
+        years: i32,
+    ) -> MultiYearGrowthMetrics {
+        let mut revenue_cagr = Vec::new();
+        let mut eps_cagr = Vec::new();
+        let mut fcf_cagr = Vec::new();
+
+        let fcf_at = |idx: usize| {
+            annual_cash_flows
+                .get(idx)
+                .and_then(|opt| opt.as_ref())
+                .and_then(|c| c.free_cash_flow.as_ref())
+                .and_then(|v| v.to_f64())
+        };
+
+        for (i, income) in annual_incomes.iter().enumerate() {
+            let base = Self::find_years_prior(annual_incomes, i, years);
+            let base_income = base.map(|b| &annual_incomes[b]);
+
+            let rev = match (
+                base_income
+                    .and_then(|b| b.revenue.as_ref())
+                    .and_then(|v| v.to_f64()),
+                income.revenue.as_ref().and_then(|v| v.to_f64()),
+            ) {
+                (Some(b), Some(e)) => Self::calculate_cagr(b, e, years as f64),
+                _ => None,
+            };
+            revenue_cagr.push(Self::percent_value(rev));
+
+            let eps = match (
+                base_income
+                    .and_then(|b| b.eps.as_ref())
+                    .and_then(|v| v.to_f64()),
+                income.eps.as_ref().and_then(|v| v.to_f64()),
+            ) {
+                (Some(b), Some(e)) => Self::calculate_cagr(b, e, years as f64),
+                _ => None,
+            };
+            eps_cagr.push(Self::percent_value(eps));
+
+            let fcf = match (base.and_then(fcf_at), fcf_at(i)) {
+                (Some(b), Some(e)) => Self::calculate_cagr(b, e, years as f64),
+                _ => None,
+            };
+            fcf_cagr.push(Self::percent_value(fcf));
+        }
+
+        MultiYearGrowthMetrics {
+            revenue_cagr,
+            eps_cagr,
+            fcf_cagr,
+        }
+    }
+
+    /// Count of positive YoY revenue growth years and the standard deviation of
+    /// YoY growth over the trailing `window` annual periods.
+    pub fn calculate_growth_consistency(
+        annual_incomes: &[IncomeStatement],
+        window: usize,
+    ) -> GrowthConsistencyMetrics {
+        let yoy: Vec<Option<f64>> = (0..annual_incomes.len())
+            .map(|i| {
+                let prior = Self::find_years_prior(annual_incomes, i, 1)
+                    .and_then(|p| annual_incomes[p].revenue.as_ref())
+                    .and_then(|v| v.to_f64());
+                let curr = annual_incomes[i].revenue.as_ref().and_then(|v| v.to_f64());
+                match (curr, prior) {
+                    (Some(c), Some(p)) => Self::calculate_yoy_change(c, p),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let mut positive_growth_periods = Vec::new();
+        let mut growth_std_dev = Vec::new();
+
+        for i in 0..annual_incomes.len() {
+            let start = (i + 1).saturating_sub(window);
+            let observed: Vec<f64> = yoy[start..=i].iter().filter_map(|&v| v).collect();
+
+            if observed.is_empty() {
+                positive_growth_periods.push(MetricValue {
+                    value: None,
+                    formatted_value: "N/A".to_string(),
+                    unit: "periods".to_string(),
+                    heat_map_quartile: None,
+                });
+            } else {
+                let positive = observed.iter().filter(|&&g| g > 0.0).count();
+                positive_growth_periods.push(MetricValue {
+                    value: Some(positive as f64),
+                    formatted_value: format!("{} of {}", positive, observed.len()),
+                    unit: "periods".to_string(),
+                    heat_map_quartile: None,
+                });
+            }
+
+            let std_dev = Self::calculate_std_dev(&observed);
+            growth_std_dev.push(MetricValue {
+                value: std_dev,
+                formatted_value: std_dev
+                    .map(|v| format!("{:.2} pp", v))
+                    .unwrap_or_else(|| "N/A".to_string()),
+                unit: "pp".to_string(),
+                heat_map_quartile: None,
+            });
+        }
+
+        GrowthConsistencyMetrics {
+            positive_growth_periods,
+            growth_std_dev,
+        }
+    }
+
+    fn percent_value(value: Option<f64>) -> MetricValue {
+        MetricValue {
+            value,
+            formatted_value: value
+                .map(|v| format!("{:.2}%", v))
+                .unwrap_or_else(|| "N/A".to_string()),
+            unit: "%".to_string(),
+            heat_map_quartile: None,
+        }
+    }
+
     // Additional methods will be added here
 }
 
@@ -468,6 +978,7 @@ mod tests {
             operating_income: Some(BigDecimal::from_str("200").unwrap()),
             net_income: Some(BigDecimal::from_str("100").unwrap()),
             eps: Some(BigDecimal::from_str("1.0").unwrap()),
+            ..Default::default()
         }];
         let (gm, om, nm) = MetricsCalculator::calculate_margin_metrics(&incomes);
         assert_eq!(gm[0].value, Some(40.0));
@@ -475,6 +986,50 @@ mod tests {
         assert_eq!(nm[0].value, Some(10.0));
     }
 
+    #[test]
+    fn test_calculate_ebitda_margin_and_effective_tax_rate() {
+        use bigdecimal::BigDecimal;
+        let period_end_date = chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let incomes = vec![
+            IncomeStatement {
+                period_end_date,
+                revenue: Some(BigDecimal::from(1000)),
+                ebitda: Some(BigDecimal::from(300)),
+                income_before_tax: Some(BigDecimal::from(200)),
+                income_tax_expense: Some(BigDecimal::from(50)),
+                ..Default::default()
+            },
+            // EBITDA built from its parts; a pre-tax loss has no rate
+            IncomeStatement {
+                period_end_date,
+                revenue: Some(BigDecimal::from(1000)),
+                operating_income: Some(BigDecimal::from(150)),
+                depreciation_amortization: Some(BigDecimal::from(50)),
+                income_before_tax: Some(BigDecimal::from(-20)),
+                income_tax_expense: Some(BigDecimal::from(5)),
+                ..Default::default()
+            },
+            IncomeStatement {
+                period_end_date,
+                revenue: Some(BigDecimal::from(1000)),
+                operating_income: Some(BigDecimal::from(150)),
+                ..Default::default()
+            },
+        ];
+
+        let margins: Vec<_> = MetricsCalculator::calculate_ebitda_margin(&incomes)
+            .into_iter()
+            .map(|m| m.value)
+            .collect();
+        assert_eq!(margins, vec![Some(30.0), Some(20.0), None]);
+
+        let rates: Vec<_> = MetricsCalculator::calculate_effective_tax_rate(&incomes)
+            .into_iter()
+            .map(|m| m.value)
+            .collect();
+        assert_eq!(rates, vec![Some(25.0), None, None]);
+    }
+
     #[test]
     fn test_calculate_valuation_metrics() {
         use bigdecimal::BigDecimal;
@@ -482,10 +1037,8 @@ mod tests {
         let incomes = vec![IncomeStatement {
             period_end_date: chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
             revenue: Some(BigDecimal::from_str("1000").unwrap()),
-            gross_profit: None,
-            operating_income: None,
-            net_income: None,
             eps: Some(BigDecimal::from_str("5.0").unwrap()),
+            ..Default::default()
         }];
         let prices = vec![Some(DailyPrice {
             date: chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
@@ -493,6 +1046,7 @@ mod tests {
             high: 155.0,
             low: 135.0,
             close: 150.0,
+            ..Default::default()
         })];
         let metrics = MetricsCalculator::calculate_valuation_metrics(&incomes, &prices);
 
@@ -503,4 +1057,242 @@ mod tests {
         assert!((metrics.close_ratios[0].value.unwrap() - 15.0).abs() < eps);
         assert!((metrics.pe_ratios[0].value.unwrap() - 30.0).abs() < eps);
     }
+
+    #[test]
+    fn test_calculate_peer_percentile() {
+        let peers = [10.0, 20.0, 30.0, 40.0];
+        assert_eq!(
+            MetricsCalculator::calculate_peer_percentile(10.0, &peers),
+            Some(12.5)
+        );
+        assert_eq!(
+            MetricsCalculator::calculate_peer_percentile(40.0, &peers),
+            Some(87.5)
+        );
+        assert_eq!(
+            MetricsCalculator::calculate_peer_percentile(5.0, &[5.0, 5.0]),
+            Some(50.0)
+        );
+        assert_eq!(
+            MetricsCalculator::calculate_peer_percentile(5.0, &[5.0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_percentile_to_quartile() {
+        assert_eq!(MetricsCalculator::percentile_to_quartile(0.0), 1);
+        assert_eq!(MetricsCalculator::percentile_to_quartile(12.5), 1);
+        assert_eq!(MetricsCalculator::percentile_to_quartile(25.0), 1);
+        assert_eq!(MetricsCalculator::percentile_to_quartile(62.5), 3);
+        assert_eq!(MetricsCalculator::percentile_to_quartile(100.0), 4);
+    }
+
+    fn percents(values: &[Option<f64>]) -> Vec<MetricValue> {
+        values
+            .iter()
+            .map(|&value| MetricValue {
+                value,
+                formatted_value: String::new(),
+                unit: "%".to_string(),
+                heat_map_quartile: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calculate_sequential_ratio() {
+        assert_eq!(
+            MetricsCalculator::calculate_sequential_ratio(22.0, 20.0),
+            Some(10.0)
+        );
+        // Negative base: improvement from -10% to -5% is a +50% move
+        assert_eq!(
+            MetricsCalculator::calculate_sequential_ratio(-5.0, -10.0),
+            Some(50.0)
+        );
+        assert_eq!(
+            MetricsCalculator::calculate_sequential_ratio(5.0, 0.2),
+            None
+        );
+        assert_eq!(
+            MetricsCalculator::calculate_sequential_ratio(5.0, 0.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calculate_margin_expansion_ratio() {
+        let margins = percents(&[
+            Some(20.0),
+            Some(25.0),
+            None,
+            Some(30.0),
+            Some(0.1),
+            Some(5.0),
+        ]);
+        let ratios = MetricsCalculator::calculate_margin_expansion_ratio(&margins);
+
+        assert_eq!(ratios.len(), 6);
+        assert_eq!(ratios[0].value, None);
+        assert_eq!(ratios[1].value, Some(25.0));
+        assert_eq!(ratios[1].formatted_value, "25.00%");
+        assert_eq!(ratios[2].value, None);
+        assert_eq!(ratios[3].value, None);
+        assert_eq!(ratios[5].value, None);
+    }
+
+    #[test]
+    fn test_calculate_acceleration_ratio() {
+        let yoy = percents(&[Some(10.0), Some(15.0), Some(12.0)]);
+        let ratios = MetricsCalculator::calculate_acceleration_ratio(&yoy);
+
+        assert_eq!(ratios[1].value, Some(50.0));
+        assert_eq!(ratios[2].value, Some(-20.0));
+    }
+
+    fn annual_income(year: i32, revenue: &str, eps: &str) -> IncomeStatement {
+        use bigdecimal::BigDecimal;
+        use std::str::FromStr;
+        IncomeStatement {
+            period_end_date: chrono::NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+            revenue: Some(BigDecimal::from_str(revenue).unwrap()),
+            eps: Some(BigDecimal::from_str(eps).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calculate_cagr() {
+        let cagr = MetricsCalculator::calculate_cagr(100.0, 133.1, 3.0).unwrap();
+        assert!((cagr - 10.0).abs() < 1e-9);
+        assert_eq!(MetricsCalculator::calculate_cagr(0.0, 100.0, 3.0), None);
+        assert_eq!(MetricsCalculator::calculate_cagr(-50.0, 100.0, 3.0), None);
+        assert_eq!(MetricsCalculator::calculate_cagr(100.0, -10.0, 3.0), None);
+    }
+
+    #[test]
+    fn test_calculate_multi_year_growth_skips_missing_years() {
+        // 2020 is missing, so the 2-year CAGR for 2022 has no base period
+        let incomes = vec![
+            annual_income(2019, "100", "1.0"),
+            annual_income(2021, "121", "1.21"),
+            annual_income(2022, "133.1", "1.331"),
+        ];
+        let growth = MetricsCalculator::calculate_multi_year_growth(&incomes, &[], 2);
+
+        assert_eq!(growth.revenue_cagr[0].value, None);
+        assert!((growth.revenue_cagr[1].value.unwrap() - 10.0).abs() < 1e-9);
+        assert!((growth.eps_cagr[1].value.unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(growth.revenue_cagr[2].value, None);
+        assert_eq!(growth.fcf_cagr[1].value, None);
+    }
+
+    #[test]
+    fn test_calculate_growth_consistency() {
+        let incomes = vec![
+            annual_income(2019, "100", "1.0"),
+            annual_income(2020, "110", "1.0"),
+            annual_income(2021, "99", "1.0"),
+            annual_income(2022, "118.8", "1.0"),
+        ];
+        let consistency = MetricsCalculator::calculate_growth_consistency(&incomes, 5);
+
+        assert_eq!(consistency.positive_growth_periods[0].value, None);
+        assert_eq!(consistency.positive_growth_periods[3].value, Some(2.0));
+        assert_eq!(
+            consistency.positive_growth_periods[3].formatted_value,
+            "2 of 3"
+        );
+        // Growths are 10%, -10%, 20%: sample std dev is sqrt(233.33)
+        let std_dev = consistency.growth_std_dev[3].value.unwrap();
+        assert!((std_dev - (700.0_f64 / 3.0).sqrt()).abs() < 1e-6);
+        assert_eq!(consistency.growth_std_dev[1].value, None);
+    }
+
+    #[test]
+    fn test_calculate_working_capital_metrics() {
+        use bigdecimal::BigDecimal;
+        let incomes = vec![
+            IncomeStatement {
+                period_end_date: chrono::NaiveDate::from_ymd_opt(2023, 3, 31).unwrap(),
+                revenue: Some(BigDecimal::from(1000)),
+                cost_of_revenue: Some(BigDecimal::from(600)),
+                ..Default::default()
+            },
+            // No cost of revenue reported: derived from gross profit
+            IncomeStatement {
+                period_end_date: chrono::NaiveDate::from_ymd_opt(2023, 6, 30).unwrap(),
+                revenue: Some(BigDecimal::from(1000)),
+                gross_profit: Some(BigDecimal::from(400)),
+                ..Default::default()
+            },
+        ];
+        let balance = BalanceSheet {
+            accounts_receivable: Some(BigDecimal::from(500)),
+            inventory: Some(BigDecimal::from(300)),
+            accounts_payable: Some(BigDecimal::from(150)),
+            ..Default::default()
+        };
+        let balances = vec![
+            Some(balance.clone()),
+            Some(BalanceSheet {
+                inventory: None,
+                ..balance
+            }),
+        ];
+
+        let wc = MetricsCalculator::calculate_working_capital_metrics(
+            &incomes,
+            &balances,
+            PeriodType::Quarterly,
+        );
+        let days = 365.0 / 4.0;
+        assert!((wc.days_sales_outstanding[0].value.unwrap() - 0.5 * days).abs() < 1e-9);
+        assert!((wc.days_inventory_outstanding[0].value.unwrap() - 0.5 * days).abs() < 1e-9);
+        assert!((wc.days_payables_outstanding[0].value.unwrap() - 0.25 * days).abs() < 1e-9);
+        assert!((wc.cash_conversion_cycle[0].value.unwrap() - 0.75 * days).abs() < 1e-9);
+        assert_eq!(wc.days_sales_outstanding[0].formatted_value, "45.6 days");
+
+        assert!((wc.days_payables_outstanding[1].value.unwrap() - 0.25 * days).abs() < 1e-9);
+        assert_eq!(wc.days_inventory_outstanding[1].value, None);
+        assert_eq!(wc.cash_conversion_cycle[1].value, None);
+    }
+
+    #[test]
+    fn test_calculate_accruals_ratio() {
+        use bigdecimal::BigDecimal;
+        let incomes = vec![
+            IncomeStatement {
+                net_income: Some(BigDecimal::from(120)),
+                ..Default::default()
+            },
+            IncomeStatement {
+                net_income: Some(BigDecimal::from(80)),
+                ..Default::default()
+            },
+        ];
+        let balances = vec![
+            Some(BalanceSheet {
+                total_assets: Some(BigDecimal::from(1000)),
+                ..Default::default()
+            }),
+            Some(BalanceSheet {
+                total_assets: Some(BigDecimal::from(1000)),
+                ..Default::default()
+            }),
+        ];
+        let cash_flows = vec![
+            Some(CashFlowStatement {
+                operating_cash_flow: Some(BigDecimal::from(100)),
+                ..Default::default()
+            }),
+            None,
+        ];
+
+        let accruals =
+            MetricsCalculator::calculate_accruals_ratio(&incomes, &balances, &cash_flows);
+        assert!((accruals[0].value.unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(accruals[1].value, None);
+    }
 }
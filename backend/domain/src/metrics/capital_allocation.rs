@@ -0,0 +1,246 @@
+//! Capital allocation: where each period's operating cash flow went, and what
+//! the cash returned to shareholders amounts to against market cap.
+//!
+//! Sources disagree on the sign of outflows, so every use of cash is taken as
+//! a positive amount. Percentages are of operating cash flow and only reported
+//! while it is positive.
+
+use crate::domain::{BalanceSheet, CashFlowStatement, DailyPrice};
+use crate::periods::{is_previous_period, PeriodType};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Quarters a trailing-twelve-month yield sums
+pub const TTM_QUARTERS: usize = 4;
+
+/// One use of operating cash flow
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AllocationLine {
+    /// Outflow as a positive amount; null when not reported
+    pub amount: Option<f64>,
+    /// Share of operating cash flow, %
+    pub pct_of_ocf: Option<f64>,
+}
+
+/// Where one period's operating cash flow went
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CapitalAllocation {
+    pub period_end_date: NaiveDate,
+    pub operating_cash_flow: Option<f64>,
+    pub capital_expenditures: AllocationLine,
+    /// Acquisitions and other investments
+    pub investments: AllocationLine,
+    pub buybacks: AllocationLine,
+    pub dividends: AllocationLine,
+    pub debt_repayment: AllocationLine,
+}
+
+impl CapitalAllocation {
+    pub fn from_cash_flow(cash_flow: &CashFlowStatement) -> Self {
+        let ocf = cash_flow
+            .operating_cash_flow
+            .as_ref()
+            .and_then(|v| v.to_f64());
+        let line = |value: &Option<BigDecimal>| {
+            let amount = value.as_ref().and_then(|v| v.to_f64()).map(f64::abs);
+            AllocationLine {
+                amount,
+                pct_of_ocf: match (amount, ocf) {
+                    (Some(a), Some(o)) if o > 0.0 => Some(a / o * 100.0),
+                    _ => None,
+                },
+            }
+        };
+
+        Self {
+            period_end_date: cash_flow.period_end_date,
+            operating_cash_flow: ocf,
+            capital_expenditures: line(&cash_flow.capital_expenditures),
+            investments: line(&cash_flow.investments),
+            buybacks: line(&cash_flow.stock_repurchase),
+            dividends: line(&cash_flow.dividend_payout),
+            debt_repayment: line(&cash_flow.debt_repayment),
+        }
+    }
+
+    /// Dividends plus buybacks, counting whichever is reported; null if neither is
+    pub fn shareholder_returns(&self) -> AllocationLine {
+        let sum = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+        };
+        AllocationLine {
+            amount: sum(self.dividends.amount, self.buybacks.amount),
+            pct_of_ocf: sum(self.dividends.pct_of_ocf, self.buybacks.pct_of_ocf),
+        }
+    }
+}
+
+/// Cash returned to shareholders over a period against market cap at its end
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ShareholderYield {
+    pub market_cap: Option<f64>,
+    /// Dividends paid / market cap, %
+    pub dividend_yield: Option<f64>,
+    /// Buybacks / market cap, %
+    pub buyback_yield: Option<f64>,
+    /// Dividends plus buybacks / market cap, %
+    pub shareholder_yield: Option<f64>,
+}
+
+impl ShareholderYield {
+    pub fn new(allocation: &CapitalAllocation, market_cap: Option<f64>) -> Self {
+        Self::over_periods(std::slice::from_ref(allocation), market_cap)
+    }
+
+    /// Cash returned over all of `allocations`, e.g. the trailing four
+    /// quarters, against market cap at the end of the last
+    pub fn over_periods(allocations: &[CapitalAllocation], market_cap: Option<f64>) -> Self {
+        let market_cap = market_cap.filter(|m| *m > 0.0);
+        let yield_of = |amount: Option<f64>| Some(amount? / market_cap? * 100.0);
+        let total = |line: fn(&CapitalAllocation) -> AllocationLine| {
+            allocations.iter().map(|a| line(a).amount).fold(
+                None,
+                |sum: Option<f64>, amount| match (sum, amount) {
+                    (None, None) => None,
+                    (sum, amount) => Some(sum.unwrap_or(0.0) + amount.unwrap_or(0.0)),
+                },
+            )
+        };
+        Self {
+            market_cap,
+            dividend_yield: yield_of(total(|a| a.dividends)),
+            buyback_yield: yield_of(total(|a| a.buybacks)),
+            shareholder_yield: yield_of(total(|a| a.shareholder_returns())),
+        }
+    }
+}
+
+/// Periods a yield for `cash_flows[index]` is measured over: the period
+/// itself for annual data, the trailing four quarters for quarterly data, so
+/// quarterly yields stay comparable with annual ones. None when any of them is
+/// missing or the quarters aren't consecutive.
+pub fn trailing_allocations(
+    cash_flows: &[Option<CashFlowStatement>],
+    index: usize,
+    period_type: PeriodType,
+) -> Option<Vec<CapitalAllocation>> {
+    let periods = match period_type {
+        PeriodType::Annual => 1,
+        PeriodType::Quarterly => TTM_QUARTERS,
+    };
+    let window = cash_flows.get((index + 1).checked_sub(periods)?..=index)?;
+    let window: Vec<&CashFlowStatement> =
+        window.iter().map(Option::as_ref).collect::<Option<_>>()?;
+    if !window
+        .windows(2)
+        .all(|w| is_previous_period(w[0].period_end_date, w[1].period_end_date, period_type))
+    {
+        return None;
+    }
+    Some(
+        window
+            .into_iter()
+            .map(CapitalAllocation::from_cash_flow)
+            .collect(),
+    )
+}
+
+/// Closing price times the shares outstanding on the balance sheet
+pub fn market_cap(balance: Option<&BalanceSheet>, price: Option<&DailyPrice>) -> Option<f64> {
+    let shares = balance?.common_stock_shares_outstanding?;
+    Some(price?.close * shares as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocation_takes_outflows_as_positive_shares_of_ocf() {
+        let cash_flow = CashFlowStatement {
+            operating_cash_flow: Some(BigDecimal::from(1000)),
+            capital_expenditures: Some(BigDecimal::from(-200)),
+            dividend_payout: Some(BigDecimal::from(300)),
+            stock_repurchase: Some(BigDecimal::from(-100)),
+            ..Default::default()
+        };
+        let allocation = CapitalAllocation::from_cash_flow(&cash_flow);
+
+        assert_eq!(allocation.capital_expenditures.amount, Some(200.0));
+        assert_eq!(allocation.capital_expenditures.pct_of_ocf, Some(20.0));
+        assert_eq!(allocation.investments.amount, None);
+        assert_eq!(allocation.shareholder_returns().amount, Some(400.0));
+        assert_eq!(allocation.shareholder_returns().pct_of_ocf, Some(40.0));
+
+        let price = DailyPrice {
+            date: cash_flow.period_end_date,
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            ..Default::default()
+        };
+        let balance = BalanceSheet {
+            common_stock_shares_outstanding: Some(1000),
+            ..Default::default()
+        };
+        let yields = ShareholderYield::new(&allocation, market_cap(Some(&balance), Some(&price)));
+        assert_eq!(yields.market_cap, Some(10_000.0));
+        assert_eq!(yields.dividend_yield, Some(3.0));
+        assert_eq!(yields.buyback_yield, Some(1.0));
+        assert_eq!(yields.shareholder_yield, Some(4.0));
+    }
+
+    #[test]
+    fn test_quarterly_yield_sums_trailing_four_quarters() {
+        let quarter = |end: NaiveDate, dividends: i64| {
+            Some(CashFlowStatement {
+                period_end_date: end,
+                dividend_payout: Some(BigDecimal::from(dividends)),
+                ..Default::default()
+            })
+        };
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut cash_flows = vec![
+            quarter(date(2023, 3, 31), 10),
+            quarter(date(2023, 6, 30), 20),
+            quarter(date(2023, 9, 30), 30),
+            quarter(date(2023, 12, 31), 40),
+        ];
+
+        assert!(trailing_allocations(&cash_flows, 2, PeriodType::Quarterly).is_none());
+        let window = trailing_allocations(&cash_flows, 3, PeriodType::Quarterly).unwrap();
+        let yields = ShareholderYield::over_periods(&window, Some(1000.0));
+        assert_eq!(yields.dividend_yield, Some(10.0));
+        assert_eq!(yields.buyback_yield, None);
+        assert_eq!(yields.shareholder_yield, Some(10.0));
+
+        // A missing quarter leaves no trailing year to measure
+        cash_flows[1] = quarter(date(2022, 12, 31), 20);
+        assert!(trailing_allocations(&cash_flows, 3, PeriodType::Quarterly).is_none());
+        assert_eq!(
+            trailing_allocations(&cash_flows, 3, PeriodType::Annual).map(|w| w.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_negative_ocf_has_no_shares() {
+        let cash_flow = CashFlowStatement {
+            operating_cash_flow: Some(BigDecimal::from(-50)),
+            dividend_payout: Some(BigDecimal::from(10)),
+            ..Default::default()
+        };
+        let allocation = CapitalAllocation::from_cash_flow(&cash_flow);
+        assert_eq!(allocation.dividends.amount, Some(10.0));
+        assert_eq!(allocation.dividends.pct_of_ocf, None);
+        assert_eq!(allocation.shareholder_returns().pct_of_ocf, None);
+        assert_eq!(
+            ShareholderYield::new(&allocation, None).dividend_yield,
+            None
+        );
+    }
+}
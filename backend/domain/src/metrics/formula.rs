@@ -0,0 +1,751 @@
+//! Sandboxed expression language for user-defined metrics.
+//!
+//! A formula is arithmetic (`+ - * /`, unary minus, parentheses) over
+//! statement fields and registered metrics, plus two window functions:
+//!
+//! - `lag(expr, n)`: value of `expr` n periods earlier
+//! - `avg(expr, n)`: mean of `expr` over the current and n-1 prior periods
+//!
+//! Any missing input, division by zero or window reaching before the first
+//! period yields null for that period rather than an error. Formulas cannot
+//! call anything else, so evaluation is bounded by the formula size and the
+//! number of periods.
+
+use crate::domain::{BalanceSheet, CashFlowStatement, IncomeStatement};
+use crate::metrics::registry::{find_metric, MetricInputs};
+use crate::metrics::MetricValue;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Longest accepted formula source, in bytes
+pub const MAX_FORMULA_LENGTH: usize = 500;
+/// Deepest accepted expression nesting
+pub const MAX_FORMULA_DEPTH: usize = 32;
+/// Largest `n` accepted by `lag` and `avg`
+pub const MAX_FORMULA_WINDOW: usize = 20;
+
+/// Statement fields a formula can reference, aligned to each period
+pub const STATEMENT_FIELDS: &[&str] = &[
+    // Income statement
+    "revenue",
+    "cost_of_revenue",
+    "gross_profit",
+    "operating_expenses",
+    "operating_income",
+    "interest_income",
+    "interest_expense",
+    "income_before_tax",
+    "income_tax_expense",
+    "net_income",
+    "depreciation_amortization",
+    "ebit",
+    "ebitda",
+    "eps",
+    "diluted_eps",
+    // Balance sheet
+    "total_assets",
+    "current_assets",
+    "cash_and_equivalents",
+    "short_term_investments",
+    "inventory",
+    "accounts_receivable",
+    "non_current_assets",
+    "property_plant_equipment",
+    "goodwill",
+    "intangible_assets",
+    "total_liabilities",
+    "current_liabilities",
+    "accounts_payable",
+    "short_term_debt",
+    "non_current_liabilities",
+    "long_term_debt",
+    "total_equity",
+    "retained_earnings",
+    "common_stock",
+    "total_debt",
+    "net_debt",
+    "shares_outstanding",
+    // Cash flow statement
+    "operating_cash_flow",
+    "depreciation_depletion",
+    "change_in_receivables",
+    "change_in_inventory",
+    "change_in_payables",
+    "investing_cash_flow",
+    "capital_expenditures",
+    "investments",
+    "financing_cash_flow",
+    "dividend_payout",
+    "stock_repurchase",
+    "debt_repayment",
+    "free_cash_flow",
+    "price",
+];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum FormulaError {
+    #[error("Formula is empty")]
+    Empty,
+
+    #[error("Formula exceeds {MAX_FORMULA_LENGTH} characters")]
+    TooLong,
+
+    #[error("Formula nesting exceeds {MAX_FORMULA_DEPTH} levels")]
+    TooDeep,
+
+    #[error("Unexpected character '{0}' at position {1}")]
+    UnexpectedChar(char, usize),
+
+    #[error("Unexpected {0}")]
+    UnexpectedToken(String),
+
+    #[error("Unknown field or metric '{0}'")]
+    UnknownIdentifier(String),
+
+    #[error("Unknown function '{0}': expected lag or avg")]
+    UnknownFunction(String),
+
+    #[error("{0}() window must be an integer between 1 and {MAX_FORMULA_WINDOW}")]
+    InvalidWindow(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(n) => format!("number {}", n),
+            Token::Ident(s) => format!("identifier '{}'", s),
+            Token::Plus => "'+'".to_string(),
+            Token::Minus => "'-'".to_string(),
+            Token::Star => "'*'".to_string(),
+            Token::Slash => "'/'".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::Comma => "','".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Field(String),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Lag(Box<Expr>, usize),
+    Avg(Box<Expr>, usize),
+}
+
+/// A parsed, validated formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    expr: Expr,
+}
+
+impl Formula {
+    /// Parse and validate `source`, rejecting unknown identifiers up front
+    pub fn parse(source: &str) -> Result<Self, FormulaError> {
+        if source.chars().count() > MAX_FORMULA_LENGTH {
+            return Err(FormulaError::TooLong);
+        }
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err(FormulaError::Empty);
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expression(0)?;
+        if let Some(token) = parser.peek() {
+            return Err(FormulaError::UnexpectedToken(token.describe()));
+        }
+
+        let formula = Formula { expr };
+        for name in formula.identifiers() {
+            if !STATEMENT_FIELDS.contains(&name.as_str()) && find_metric(&name).is_none() {
+                return Err(FormulaError::UnknownIdentifier(name));
+            }
+        }
+        Ok(formula)
+    }
+
+    /// Distinct fields and metrics the formula references
+    pub fn identifiers(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        collect_identifiers(&self.expr, &mut names);
+        names
+    }
+
+    /// Evaluate once per period of `inputs.incomes`
+    pub fn evaluate(&self, inputs: &MetricInputs) -> Vec<MetricValue> {
+        let series: HashMap<String, Vec<Option<f64>>> = self
+            .identifiers()
+            .into_iter()
+            .map(|name| {
+                let values = input_series(inputs, &name);
+                (name, values)
+            })
+            .collect();
+
+        eval_series(&self.expr, &series, inputs.incomes.len())
+            .into_iter()
+            .map(|value| MetricValue {
+                value,
+                formatted_value: value
+                    .map(|v| format!("{:.2}", v))
+                    .unwrap_or_else(|| "N/A".to_string()),
+                unit: "".to_string(),
+                heat_map_quartile: None,
+            })
+            .collect()
+    }
+}
+
+fn collect_identifiers(expr: &Expr, names: &mut Vec<String>) {
+    match expr {
+        Expr::Number(_) => {}
+        Expr::Field(name) => {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        Expr::Neg(inner) | Expr::Lag(inner, _) | Expr::Avg(inner, _) => {
+            collect_identifiers(inner, names)
+        }
+        Expr::Binary(_, lhs, rhs) => {
+            collect_identifiers(lhs, names);
+            collect_identifiers(rhs, names);
+        }
+    }
+}
+
+/// Evaluate `expr` for every period at once, bottom-up, so nested windows
+/// cost O(nodes * periods * window) rather than multiplying per level.
+fn eval_series(
+    expr: &Expr,
+    series: &HashMap<String, Vec<Option<f64>>>,
+    len: usize,
+) -> Vec<Option<f64>> {
+    let values: Vec<Option<f64>> = match expr {
+        Expr::Number(n) => vec![Some(*n); len],
+        Expr::Field(name) => (0..len)
+            .map(|i| series.get(name).and_then(|s| s.get(i).copied().flatten()))
+            .collect(),
+        Expr::Neg(inner) => eval_series(inner, series, len)
+            .into_iter()
+            .map(|v| v.map(|x| -x))
+            .collect(),
+        Expr::Binary(op, lhs, rhs) => {
+            let l = eval_series(lhs, series, len);
+            let r = eval_series(rhs, series, len);
+            l.into_iter()
+                .zip(r)
+                .map(|(l, r)| {
+                    let (l, r) = (l?, r?);
+                    match op {
+                        BinaryOp::Add => Some(l + r),
+                        BinaryOp::Sub => Some(l - r),
+                        BinaryOp::Mul => Some(l * r),
+                        BinaryOp::Div if r == 0.0 => None,
+                        BinaryOp::Div => Some(l / r),
+                    }
+                })
+                .collect()
+        }
+        Expr::Lag(inner, n) => {
+            let inner = eval_series(inner, series, len);
+            (0..len)
+                .map(|i| i.checked_sub(*n).and_then(|j| inner[j]))
+                .collect()
+        }
+        Expr::Avg(inner, n) => {
+            let inner = eval_series(inner, series, len);
+            (0..len)
+                .map(|i| {
+                    let start = (i + 1).checked_sub(*n)?;
+                    let window: Option<Vec<f64>> = inner[start..=i].iter().copied().collect();
+                    window.map(|w| w.iter().sum::<f64>() / *n as f64)
+                })
+                .collect()
+        }
+    };
+    values
+        .into_iter()
+        .map(|v| v.filter(|x| x.is_finite()))
+        .collect()
+}
+
+/// Per-period values of a statement field or registered metric
+fn input_series(inputs: &MetricInputs, name: &str) -> Vec<Option<f64>> {
+    let to_f64 = |v: Option<&BigDecimal>| v.and_then(|d| d.to_f64());
+    let periods = 0..inputs.incomes.len();
+    let income = |f: fn(&IncomeStatement) -> Option<&BigDecimal>| -> Vec<Option<f64>> {
+        inputs.incomes.iter().map(|i| to_f64(f(i))).collect()
+    };
+    let balance = |f: fn(&BalanceSheet) -> Option<&BigDecimal>| -> Vec<Option<f64>> {
+        periods
+            .clone()
+            .map(|i| to_f64(inputs.balances.get(i).and_then(|b| b.as_ref()).and_then(f)))
+            .collect()
+    };
+    let cash_flow = |f: fn(&CashFlowStatement) -> Option<&BigDecimal>| -> Vec<Option<f64>> {
+        periods
+            .clone()
+            .map(|i| {
+                to_f64(
+                    inputs
+                        .cash_flows
+                        .get(i)
+                        .and_then(|c| c.as_ref())
+                        .and_then(f),
+                )
+            })
+            .collect()
+    };
+
+    match name {
+        "revenue" => income(|i| i.revenue.as_ref()),
+        "cost_of_revenue" => income(|i| i.cost_of_revenue.as_ref()),
+        "gross_profit" => income(|i| i.gross_profit.as_ref()),
+        "operating_expenses" => income(|i| i.operating_expenses.as_ref()),
+        "operating_income" => income(|i| i.operating_income.as_ref()),
+        "interest_income" => income(|i| i.interest_income.as_ref()),
+        "interest_expense" => income(|i| i.interest_expense.as_ref()),
+        "income_before_tax" => income(|i| i.income_before_tax.as_ref()),
+        "income_tax_expense" => income(|i| i.income_tax_expense.as_ref()),
+        "net_income" => income(|i| i.net_income.as_ref()),
+        "depreciation_amortization" => income(|i| i.depreciation_amortization.as_ref()),
+        "ebit" => income(|i| i.ebit.as_ref()),
+        "ebitda" => income(|i| i.ebitda.as_ref()),
+        "eps" => income(|i| i.eps.as_ref()),
+        "diluted_eps" => income(|i| i.diluted_eps.as_ref()),
+        "total_assets" => balance(|b| b.total_assets.as_ref()),
+        "current_assets" => balance(|b| b.current_assets.as_ref()),
+        "cash_and_equivalents" => balance(|b| b.cash_and_equivalents.as_ref()),
+        "short_term_investments" => balance(|b| b.short_term_investments.as_ref()),
+        "inventory" => balance(|b| b.inventory.as_ref()),
+        "accounts_receivable" => balance(|b| b.accounts_receivable.as_ref()),
+        "non_current_assets" => balance(|b| b.non_current_assets.as_ref()),
+        "property_plant_equipment" => balance(|b| b.property_plant_equipment.as_ref()),
+        "goodwill" => balance(|b| b.goodwill.as_ref()),
+        "intangible_assets" => balance(|b| b.intangible_assets.as_ref()),
+        "total_liabilities" => balance(|b| b.total_liabilities.as_ref()),
+        "current_liabilities" => balance(|b| b.current_liabilities.as_ref()),
+        "accounts_payable" => balance(|b| b.accounts_payable.as_ref()),
+        "short_term_debt" => balance(|b| b.short_term_debt.as_ref()),
+        "non_current_liabilities" => balance(|b| b.non_current_liabilities.as_ref()),
+        "long_term_debt" => balance(|b| b.long_term_debt.as_ref()),
+        "total_equity" => balance(|b| b.total_equity.as_ref()),
+        "retained_earnings" => balance(|b| b.retained_earnings.as_ref()),
+        "common_stock" => balance(|b| b.common_stock.as_ref()),
+        "total_debt" => balance(|b| b.total_debt.as_ref()),
+        "net_debt" => balance(|b| b.net_debt.as_ref()),
+        "shares_outstanding" => periods
+            .map(|i| {
+                inputs
+                    .balances
+                    .get(i)
+                    .and_then(|b| b.as_ref())
+                    .and_then(|b| b.common_stock_shares_outstanding)
+                    .map(|s| s as f64)
+            })
+            .collect(),
+        "operating_cash_flow" => cash_flow(|c| c.operating_cash_flow.as_ref()),
+        "depreciation_depletion" => cash_flow(|c| c.depreciation_depletion.as_ref()),
+        "change_in_receivables" => cash_flow(|c| c.change_in_receivables.as_ref()),
+        "change_in_inventory" => cash_flow(|c| c.change_in_inventory.as_ref()),
+        "change_in_payables" => cash_flow(|c| c.change_in_payables.as_ref()),
+        "investing_cash_flow" => cash_flow(|c| c.investing_cash_flow.as_ref()),
+        "capital_expenditures" => cash_flow(|c| c.capital_expenditures.as_ref()),
+        "investments" => cash_flow(|c| c.investments.as_ref()),
+        "financing_cash_flow" => cash_flow(|c| c.financing_cash_flow.as_ref()),
+        "dividend_payout" => cash_flow(|c| c.dividend_payout.as_ref()),
+        "stock_repurchase" => cash_flow(|c| c.stock_repurchase.as_ref()),
+        "debt_repayment" => cash_flow(|c| c.debt_repayment.as_ref()),
+        "free_cash_flow" => cash_flow(|c| c.free_cash_flow.as_ref()),
+        "price" => periods
+            .map(|i| {
+                inputs
+                    .prices
+                    .get(i)
+                    .and_then(|p| p.as_ref())
+                    .map(|p| p.close)
+            })
+            .collect(),
+        other => match find_metric(other) {
+            Some(metric) => (metric.compute)(inputs)
+                .into_iter()
+                .map(|v| v.value)
+                .collect(),
+            None => vec![None; inputs.incomes.len()],
+        },
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, FormulaError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' | '\n' | '\r' => i += 1,
+            '+' => {
+                tokens.push(Token::Plus);
+                i += 1;
+            }
+            // Accept the typographic minus as well, formulas get pasted from docs
+            '-' | '\u{2212}' => {
+                tokens.push(Token::Minus);
+                i += 1;
+            }
+            '*' => {
+                tokens.push(Token::Star);
+                i += 1;
+            }
+            '/' => {
+                tokens.push(Token::Slash);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| FormulaError::UnexpectedChar(c, start))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let ident: String = chars[start..i].iter().collect();
+                tokens.push(Token::Ident(ident.to_lowercase()));
+            }
+            other => return Err(FormulaError::UnexpectedChar(other, i)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), FormulaError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(FormulaError::UnexpectedToken(token.describe())),
+            None => Err(FormulaError::UnexpectedToken("end of formula".to_string())),
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self, depth: usize) -> Result<Expr, FormulaError> {
+        let mut lhs = self.term(depth)?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOp::Add,
+                Some(Token::Minus) => BinaryOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.term(depth)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self, depth: usize) -> Result<Expr, FormulaError> {
+        let mut lhs = self.unary(depth)?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => BinaryOp::Mul,
+                Some(Token::Slash) => BinaryOp::Div,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.unary(depth)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    // unary := '-' unary | primary
+    fn unary(&mut self, depth: usize) -> Result<Expr, FormulaError> {
+        if depth > MAX_FORMULA_DEPTH {
+            return Err(FormulaError::TooDeep);
+        }
+        if self.peek() == Some(&Token::Minus) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary(depth + 1)?)));
+        }
+        self.primary(depth)
+    }
+
+    // primary := number | ident | ident '(' expression ',' number ')' | '(' expression ')'
+    fn primary(&mut self, depth: usize) -> Result<Expr, FormulaError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::LParen) => {
+                let inner = self.expression(depth + 1)?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                if name != "lag" && name != "avg" {
+                    return Err(FormulaError::UnknownFunction(name));
+                }
+                let inner = self.expression(depth + 1)?;
+                self.expect(Token::Comma)?;
+                let window = match self.next() {
+                    Some(Token::Number(n))
+                        if n.fract() == 0.0 && n >= 1.0 && n <= MAX_FORMULA_WINDOW as f64 =>
+                    {
+                        n as usize
+                    }
+                    _ => return Err(FormulaError::InvalidWindow(name)),
+                };
+                self.expect(Token::RParen)?;
+                Ok(if name == "lag" {
+                    Expr::Lag(Box::new(inner), window)
+                } else {
+                    Expr::Avg(Box::new(inner), window)
+                })
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Field(name)),
+            Some(token) => Err(FormulaError::UnexpectedToken(token.describe())),
+            None => Err(FormulaError::UnexpectedToken("end of formula".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::periods::PeriodType;
+    use chrono::NaiveDate;
+
+    fn quarter(month: u32, revenue: i64, net_income: i64) -> IncomeStatement {
+        IncomeStatement {
+            period_end_date: NaiveDate::from_ymd_opt(2023, month, 28).unwrap(),
+            revenue: Some(BigDecimal::from(revenue)),
+            net_income: Some(BigDecimal::from(net_income)),
+            ..Default::default()
+        }
+    }
+
+    fn values(formula: &str, inputs: &MetricInputs) -> Vec<Option<f64>> {
+        Formula::parse(formula)
+            .unwrap()
+            .evaluate(inputs)
+            .into_iter()
+            .map(|v| v.value)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_formulas() {
+        assert_eq!(Formula::parse("  "), Err(FormulaError::Empty));
+        assert_eq!(
+            Formula::parse("revenue +"),
+            Err(FormulaError::UnexpectedToken("end of formula".to_string()))
+        );
+        assert_eq!(
+            Formula::parse("ebitdar / revenue"),
+            Err(FormulaError::UnknownIdentifier("ebitdar".to_string()))
+        );
+        assert_eq!(
+            Formula::parse("exp(revenue, 2)"),
+            Err(FormulaError::UnknownFunction("exp".to_string()))
+        );
+        assert_eq!(
+            Formula::parse("lag(revenue, 0)"),
+            Err(FormulaError::InvalidWindow("lag".to_string()))
+        );
+        assert_eq!(
+            Formula::parse("revenue; drop"),
+            Err(FormulaError::UnexpectedChar(';', 7))
+        );
+        assert_eq!(Formula::parse(&"(".repeat(40)), Err(FormulaError::TooDeep));
+        assert_eq!(
+            Formula::parse(&"1".repeat(MAX_FORMULA_LENGTH + 1)),
+            Err(FormulaError::TooLong)
+        );
+        // The limit counts characters, not bytes
+        assert_ne!(
+            Formula::parse(&"é".repeat(MAX_FORMULA_LENGTH)),
+            Err(FormulaError::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_arithmetic_lag_and_avg() {
+        let incomes = vec![quarter(3, 100, 10), quarter(6, 200, 30), quarter(9, 300, 0)];
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &[None, None, None],
+            balances: &[None, None, None],
+            cash_flows: &[None, None, None],
+            prices: &[None, None, None],
+            annual_incomes: &[],
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Quarterly,
+        };
+
+        assert_eq!(
+            values("net_income / revenue * 100", &inputs),
+            vec![Some(10.0), Some(15.0), Some(0.0)]
+        );
+        assert_eq!(
+            values("revenue - lag(revenue, 1)", &inputs),
+            vec![None, Some(100.0), Some(100.0)]
+        );
+        assert_eq!(
+            values("avg(revenue, 2)", &inputs),
+            vec![None, Some(150.0), Some(250.0)]
+        );
+        // Division by zero is null, not an error
+        assert_eq!(
+            values("revenue / net_income", &inputs),
+            vec![Some(10.0), Some(200.0 / 30.0), None]
+        );
+        // Registered metrics are usable alongside raw fields
+        assert_eq!(
+            values("net_margin - 5", &inputs),
+            vec![Some(5.0), Some(10.0), Some(-5.0)]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_reads_every_statement_line_item() {
+        let incomes = vec![IncomeStatement {
+            ebitda: Some(BigDecimal::from(30)),
+            income_before_tax: Some(BigDecimal::from(20)),
+            income_tax_expense: Some(BigDecimal::from(5)),
+            ..quarter(3, 100, 15)
+        }];
+        let balances = vec![Some(BalanceSheet {
+            period_end_date: incomes[0].period_end_date,
+            inventory: Some(BigDecimal::from(40)),
+            goodwill: Some(BigDecimal::from(10)),
+            ..Default::default()
+        })];
+        let cash_flows = vec![Some(CashFlowStatement {
+            period_end_date: incomes[0].period_end_date,
+            stock_repurchase: Some(BigDecimal::from(8)),
+            ..Default::default()
+        })];
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &[None],
+            balances: &balances,
+            cash_flows: &cash_flows,
+            prices: &[None],
+            annual_incomes: &[],
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Quarterly,
+        };
+
+        assert_eq!(values("ebitda / revenue * 100", &inputs), vec![Some(30.0)]);
+        assert_eq!(
+            values("income_tax_expense / income_before_tax", &inputs),
+            vec![Some(0.25)]
+        );
+        assert_eq!(values("inventory + goodwill", &inputs), vec![Some(50.0)]);
+        assert_eq!(values("stock_repurchase", &inputs), vec![Some(8.0)]);
+    }
+
+    #[test]
+    fn test_evaluate_propagates_missing_inputs() {
+        let incomes = vec![quarter(3, 100, 10), quarter(6, 200, 30)];
+        let cash_flows = vec![
+            Some(CashFlowStatement {
+                period_end_date: incomes[0].period_end_date,
+                operating_cash_flow: Some(BigDecimal::from(50)),
+                capital_expenditures: Some(BigDecimal::from(20)),
+                ..Default::default()
+            }),
+            None,
+        ];
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &[None, None],
+            balances: &[None, None],
+            cash_flows: &cash_flows,
+            prices: &[None, None],
+            annual_incomes: &[],
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Quarterly,
+        };
+
+        assert_eq!(
+            values(
+                "(operating_cash_flow − capital_expenditures) / revenue",
+                &inputs
+            ),
+            vec![Some(0.3), None]
+        );
+        // Equity is missing everywhere
+        assert_eq!(
+            values(
+                "(operating_cash_flow - capital_expenditures) / total_equity",
+                &inputs
+            ),
+            vec![None, None]
+        );
+    }
+}
@@ -1,4 +1,8 @@
 pub mod calculator;
+pub mod capital_allocation;
+pub mod formula;
+pub mod price_analytics;
+pub mod registry;
 
 use serde::{Deserialize, Serialize};
 
@@ -0,0 +1,366 @@
+//! Technical analytics over a company's stored daily closes: moving averages,
+//! 52-week range, realized volatility, drawdown, beta against a benchmark and
+//! relative strength against the sector.
+//!
+//! Series are `(date, close)` pairs sorted by date, using adjusted closes
+//! when the provider supplies them for the whole window so splits and
+//! dividends don't read as price moves. Year-long statistics look back 365 calendar days from the
+//! latest close and need at least [`MIN_RETURNS`] daily returns.
+
+use crate::domain::DailyPrice;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// One close per session, oldest first
+pub type PricePoint = (NaiveDate, f64);
+
+/// Benchmark used for beta when none is configured
+pub const DEFAULT_BENCHMARK_SYMBOL: &str = "SPY";
+/// Sessions per year, used to annualize daily volatility
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+/// Fewest daily returns a volatility or beta is computed from
+pub const MIN_RETURNS: usize = 20;
+/// Calendar days of history the analytics read: a year plus room for the
+/// 200-day average and the close a year back
+pub const LOOKBACK_DAYS: i64 = 400;
+
+/// Calendar days in the one-year window
+pub const YEAR_DAYS: i64 = 365;
+
+/// The benchmark symbol from `BENCHMARK_SYMBOL`, or [`DEFAULT_BENCHMARK_SYMBOL`]
+pub fn benchmark_symbol() -> String {
+    std::env::var("BENCHMARK_SYMBOL").unwrap_or_else(|_| DEFAULT_BENCHMARK_SYMBOL.to_string())
+}
+
+/// Price analytics as of a company's latest close. Distances are % of the
+/// reference level; every value is null when there is too little history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PriceAnalytics {
+    pub as_of: NaiveDate,
+    pub close: f64,
+    pub sma_50: Option<f64>,
+    pub sma_200: Option<f64>,
+    /// Close vs the 50-day average, %
+    pub pct_from_sma_50: Option<f64>,
+    /// Close vs the 200-day average, %
+    pub pct_from_sma_200: Option<f64>,
+    pub high_52w: Option<f64>,
+    pub low_52w: Option<f64>,
+    /// Close vs the 52-week high, % (zero or negative)
+    pub pct_from_high_52w: Option<f64>,
+    /// Close vs the 52-week low, % (zero or positive)
+    pub pct_from_low_52w: Option<f64>,
+    /// Annualized standard deviation of daily log returns over a year, %
+    pub volatility_1y: Option<f64>,
+    /// Largest peak-to-trough fall over a year, % (zero or negative)
+    pub max_drawdown_1y: Option<f64>,
+    /// Daily-return beta against the benchmark over a year
+    pub beta_1y: Option<f64>,
+    /// Total return over a year, %
+    pub return_1y: Option<f64>,
+    /// Mean one-year return of the company's sector peers, %
+    pub sector_return_1y: Option<f64>,
+    /// One-year return relative to the sector mean, %: positive when the
+    /// company outperformed its peers
+    pub relative_strength_1y: Option<f64>,
+}
+
+impl PriceAnalytics {
+    /// Analytics for `prices`; `benchmark` may be empty, and `peer_returns`
+    /// are the sector peers' one-year returns as fractions. None without a
+    /// close to anchor on.
+    pub fn compute(
+        prices: &[PricePoint],
+        benchmark: &[PricePoint],
+        peer_returns: &[f64],
+    ) -> Option<Self> {
+        let &(as_of, close) = prices.last()?;
+        let year_start = as_of - Duration::days(YEAR_DAYS);
+        let closes: Vec<f64> = prices.iter().map(|(_, c)| *c).collect();
+        let year: Vec<PricePoint> = prices
+            .iter()
+            .copied()
+            .filter(|(d, _)| *d > year_start)
+            .collect();
+
+        let sma_50 = simple_moving_average(&closes, 50);
+        let sma_200 = simple_moving_average(&closes, 200);
+        let (high_52w, low_52w) = if year.is_empty() {
+            (None, None)
+        } else {
+            let year_closes = year.iter().map(|(_, c)| *c);
+            (
+                year_closes.clone().reduce(f64::max),
+                year_closes.reduce(f64::min),
+            )
+        };
+
+        let return_1y = period_return(prices, year_start).map(|r| r * 100.0);
+        let sector_return = mean(peer_returns);
+        let relative_strength_1y = match (period_return(prices, year_start), sector_return) {
+            (Some(r), Some(s)) if s > -1.0 => Some(((1.0 + r) / (1.0 + s) - 1.0) * 100.0),
+            _ => None,
+        };
+
+        let benchmark_year: Vec<PricePoint> = benchmark
+            .iter()
+            .copied()
+            .filter(|(d, _)| *d > year_start && *d <= as_of)
+            .collect();
+
+        Some(Self {
+            as_of,
+            close,
+            sma_50,
+            sma_200,
+            pct_from_sma_50: sma_50.and_then(|s| pct_change(close, s)),
+            pct_from_sma_200: sma_200.and_then(|s| pct_change(close, s)),
+            high_52w,
+            low_52w,
+            pct_from_high_52w: high_52w.and_then(|h| pct_change(close, h)),
+            pct_from_low_52w: low_52w.and_then(|l| pct_change(close, l)),
+            volatility_1y: annualized_volatility(&year).map(|v| v * 100.0),
+            max_drawdown_1y: max_drawdown(&year).map(|d| d * 100.0),
+            beta_1y: beta(&year, &benchmark_year),
+            return_1y,
+            sector_return_1y: sector_return.map(|s| s * 100.0),
+            relative_strength_1y,
+        })
+    }
+
+    /// The values persisted to `derived_metrics`, by metric name
+    pub fn metrics(&self) -> Vec<(&'static str, Option<f64>)> {
+        PRICE_ANALYTICS_METRICS
+            .into_iter()
+            .zip([
+                self.pct_from_sma_50,
+                self.pct_from_sma_200,
+                self.pct_from_high_52w,
+                self.pct_from_low_52w,
+                self.volatility_1y,
+                self.max_drawdown_1y,
+                self.beta_1y,
+                self.relative_strength_1y,
+            ])
+            .collect()
+    }
+}
+
+/// `derived_metrics` names the analytics are persisted under, which the
+/// screener's technical filters accept
+pub const PRICE_ANALYTICS_METRICS: [&str; 8] = [
+    "price_vs_sma_50_pct",
+    "price_vs_sma_200_pct",
+    "pct_from_52w_high",
+    "pct_from_52w_low",
+    "volatility_1y_pct",
+    "max_drawdown_1y_pct",
+    "beta_1y",
+    "relative_strength_sector_1y_pct",
+];
+
+/// Series from stored prices, skipping sessions without a positive close.
+/// Adjusted closes are used only when every session has one: mixing them with
+/// raw closes would turn each split or dividend adjustment into a price move.
+pub fn price_series<'a>(prices: impl IntoIterator<Item = &'a DailyPrice>) -> Vec<PricePoint> {
+    let prices: Vec<&DailyPrice> = prices.into_iter().filter(|p| p.close > 0.0).collect();
+    let adjusted = prices
+        .iter()
+        .all(|p| p.adjusted_close.is_some_and(|c| c > 0.0));
+    prices
+        .into_iter()
+        .map(|p| match p.adjusted_close {
+            Some(close) if adjusted => (p.date, close),
+            _ => (p.date, p.close),
+        })
+        .collect()
+}
+
+/// Mean of the last `window` closes
+pub fn simple_moving_average(closes: &[f64], window: usize) -> Option<f64> {
+    if window == 0 || closes.len() < window {
+        return None;
+    }
+    mean(&closes[closes.len() - window..])
+}
+
+/// Return from the last close on or before `start` to the latest close, as a
+/// fraction
+pub fn period_return(prices: &[PricePoint], start: NaiveDate) -> Option<f64> {
+    let &(_, last) = prices.last()?;
+    let &(_, first) = prices.iter().rev().find(|(d, _)| *d <= start)?;
+    (first > 0.0).then(|| last / first - 1.0)
+}
+
+/// Annualized sample standard deviation of daily log returns, as a fraction
+pub fn annualized_volatility(prices: &[PricePoint]) -> Option<f64> {
+    let returns: Vec<f64> = log_returns(prices).into_iter().map(|(_, r)| r).collect();
+    if returns.len() < MIN_RETURNS {
+        return None;
+    }
+    let m = mean(&returns)?;
+    let variance =
+        returns.iter().map(|r| (r - m).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Some((variance * TRADING_DAYS_PER_YEAR).sqrt())
+}
+
+/// Largest fall from a running peak, as a fraction (zero or negative)
+pub fn max_drawdown(prices: &[PricePoint]) -> Option<f64> {
+    let mut peak = f64::MIN;
+    let mut worst: Option<f64> = None;
+    for &(_, close) in prices {
+        peak = peak.max(close);
+        if peak > 0.0 {
+            let drawdown = close / peak - 1.0;
+            worst = Some(worst.map_or(drawdown, |w| w.min(drawdown)));
+        }
+    }
+    worst
+}
+
+/// Covariance of daily returns with the benchmark's over the benchmark's
+/// variance, on the sessions both series have a return for
+pub fn beta(prices: &[PricePoint], benchmark: &[PricePoint]) -> Option<f64> {
+    let benchmark_returns: HashMap<NaiveDate, f64> = log_returns(benchmark).into_iter().collect();
+    let (company, market): (Vec<f64>, Vec<f64>) = log_returns(prices)
+        .into_iter()
+        .filter_map(|(d, r)| Some((r, *benchmark_returns.get(&d)?)))
+        .unzip();
+    if company.len() < MIN_RETURNS {
+        return None;
+    }
+
+    let (mc, mm) = (mean(&company)?, mean(&market)?);
+    let covariance: f64 = company
+        .iter()
+        .zip(&market)
+        .map(|(c, m)| (c - mc) * (m - mm))
+        .sum();
+    let variance: f64 = market.iter().map(|m| (m - mm).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// Log return into each session from the one before it
+fn log_returns(prices: &[PricePoint]) -> Vec<(NaiveDate, f64)> {
+    prices
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0 && w[1].1 > 0.0)
+        .map(|w| (w[1].0, (w[1].1 / w[0].1).ln()))
+        .collect()
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn pct_change(value: f64, reference: f64) -> Option<f64> {
+    (reference > 0.0).then(|| (value / reference - 1.0) * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Consecutive days from 2025-01-01 with the given closes
+    fn series(closes: &[f64]) -> Vec<PricePoint> {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (start + Duration::days(i as i64), *c))
+            .collect()
+    }
+
+    #[test]
+    fn test_moving_averages_and_52_week_range() {
+        let closes: Vec<f64> = (1..=250).map(f64::from).collect();
+        let analytics = PriceAnalytics::compute(&series(&closes), &[], &[]).unwrap();
+
+        assert_eq!(analytics.close, 250.0);
+        assert_eq!(analytics.sma_50, Some(225.5));
+        assert_eq!(analytics.sma_200, Some(150.5));
+        assert_eq!(analytics.high_52w, Some(250.0));
+        assert_eq!(analytics.low_52w, Some(1.0));
+        assert_eq!(analytics.pct_from_high_52w, Some(0.0));
+        assert_eq!(analytics.pct_from_low_52w, Some(24_900.0));
+        // Less than a year of history has no one-year return
+        assert_eq!(analytics.return_1y, None);
+        assert_eq!(analytics.beta_1y, None);
+
+        let short = PriceAnalytics::compute(&series(&closes[..40]), &[], &[]).unwrap();
+        assert_eq!(short.sma_50, None);
+        assert_eq!(short.pct_from_sma_50, None);
+    }
+
+    #[test]
+    fn test_drawdown_and_volatility() {
+        let prices = series(&[100.0, 120.0, 90.0, 110.0, 60.0, 130.0]);
+        let drawdown = max_drawdown(&prices).unwrap();
+        assert!((drawdown - -0.5).abs() < 1e-12);
+
+        // A constant daily move has no volatility; alternating moves do
+        let steady: Vec<f64> = (0..30).map(|i| 100.0 * 1.01f64.powi(i)).collect();
+        assert!(annualized_volatility(&series(&steady)).unwrap() < 1e-9);
+        let choppy: Vec<f64> = (0..30)
+            .map(|i| if i % 2 == 0 { 100.0 } else { 102.0 })
+            .collect();
+        assert!(annualized_volatility(&series(&choppy)).unwrap() > 0.2);
+        assert_eq!(annualized_volatility(&series(&choppy[..10])), None);
+    }
+
+    #[test]
+    fn test_beta_and_relative_strength() {
+        let market: Vec<f64> = (0..400)
+            .map(|i| 100.0 + 10.0 * ((i as f64) / 7.0).sin())
+            .collect();
+        // Twice the benchmark's log moves
+        let company: Vec<f64> = market.iter().map(|m| (m / 100.0).powi(2) * 50.0).collect();
+        let benchmark = series(&market);
+        let prices = series(&company);
+
+        let beta = beta(&prices, &benchmark).unwrap();
+        assert!((beta - 2.0).abs() < 1e-9);
+
+        // Up 21% against peers up 10% on average: 10% relative strength
+        let mut prices = series(&[100.0; 400]);
+        prices.last_mut().unwrap().1 = 121.0;
+        let analytics = PriceAnalytics::compute(&prices, &benchmark, &[0.05, 0.15]).unwrap();
+        assert!((analytics.return_1y.unwrap() - 21.0).abs() < 1e-9);
+        assert!((analytics.sector_return_1y.unwrap() - 10.0).abs() < 1e-9);
+        assert!((analytics.relative_strength_1y.unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_price_series_never_mixes_adjusted_and_raw_closes() {
+        let price = |day: u32, close: f64, adjusted_close: Option<f64>| DailyPrice {
+            date: NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+            close,
+            adjusted_close,
+            ..Default::default()
+        };
+        let adjusted = [price(2, 100.0, Some(50.0)), price(3, 102.0, Some(51.0))];
+        assert_eq!(
+            price_series(&adjusted)
+                .iter()
+                .map(|(_, c)| *c)
+                .collect::<Vec<_>>(),
+            vec![50.0, 51.0]
+        );
+
+        // One session without an adjusted close: raw closes throughout
+        let mixed = [
+            price(2, 100.0, Some(50.0)),
+            price(3, 102.0, None),
+            price(6, 0.0, None),
+        ];
+        assert_eq!(
+            price_series(&mixed)
+                .iter()
+                .map(|(_, c)| *c)
+                .collect::<Vec<_>>(),
+            vec![100.0, 102.0]
+        );
+    }
+}
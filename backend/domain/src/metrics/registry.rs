@@ -0,0 +1,768 @@
+//! Declarative catalog of the per-period metrics shown in the analyzer and
+//! persisted by the recalculation job.
+//!
+//! Adding a metric means adding one `MetricDefinition` to `METRIC_REGISTRY`;
+//! the API rows, the `derived_metrics` persistence and `/metrics/catalog` all
+//! iterate this list.
+
+use crate::domain::{BalanceSheet, CashFlowStatement, DailyPrice, IncomeStatement};
+use crate::metrics::calculator::{
+    MetricsCalculator, ShareholderYieldMetrics, WorkingCapitalMetrics,
+};
+use crate::metrics::MetricValue;
+use crate::periods::{is_previous_period, PeriodType};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Analyzer section a metric row is rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricSection {
+    GrowthAndMargins,
+    CashAndLeverage,
+    Valuation,
+}
+
+/// How a metric's value is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricFormat {
+    Currency,
+    Percent,
+    BasisPoints,
+    PercentagePoints,
+    Multiple,
+    Count,
+    Shares,
+    Days,
+}
+
+/// Which end of a metric's range is colored as favorable on the heat map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatMapDirection {
+    HigherIsBetter,
+    LowerIsBetter,
+    /// No heat map coloring
+    None,
+}
+
+impl HeatMapDirection {
+    /// Orient a raw quartile (1 = lowest values) so that 4 is always favorable
+    pub fn orient(self, quartile: i32) -> Option<i32> {
+        match self {
+            HeatMapDirection::HigherIsBetter => Some(quartile),
+            HeatMapDirection::LowerIsBetter => Some(5 - quartile),
+            HeatMapDirection::None => None,
+        }
+    }
+}
+
+/// Statements a metric is computed from, aligned by index to `incomes`.
+///
+/// `annual_incomes` / `annual_cash_flows` are the company's annual history
+/// (sorted ascending) used by the multi-year metrics regardless of the
+/// period type being displayed.
+pub struct MetricInputs<'a> {
+    pub incomes: &'a [IncomeStatement],
+    pub prior_year_incomes: &'a [Option<IncomeStatement>],
+    pub balances: &'a [Option<BalanceSheet>],
+    pub cash_flows: &'a [Option<CashFlowStatement>],
+    pub prices: &'a [Option<DailyPrice>],
+    pub annual_incomes: &'a [IncomeStatement],
+    pub annual_cash_flows: &'a [Option<CashFlowStatement>],
+    pub currency: &'a str,
+    /// Length of each entry in `incomes`, for metrics expressed in days
+    pub period_type: PeriodType,
+}
+
+pub struct MetricDefinition {
+    /// Row key in the metrics API response
+    pub name: &'static str,
+    /// `derived_metrics.metric_name` the worker persists it under, if any
+    pub stored_name: Option<&'static str>,
+    pub display_name: &'static str,
+    pub section: MetricSection,
+    pub unit: &'static str,
+    pub format: MetricFormat,
+    pub heat_map: HeatMapDirection,
+    /// Produces one value per entry in `MetricInputs::incomes`
+    pub compute: fn(&MetricInputs) -> Vec<MetricValue>,
+}
+
+impl MetricDefinition {
+    /// Compute the metric and assign heat map quartiles across the series
+    pub fn evaluate(&self, inputs: &MetricInputs) -> Vec<MetricValue> {
+        let mut values = (self.compute)(inputs);
+        if self.heat_map != HeatMapDirection::None {
+            let raw: Vec<Option<f64>> = values.iter().map(|v| v.value).collect();
+            let quartiles = MetricsCalculator::calculate_quartiles(&raw);
+            for (value, quartile) in values.iter_mut().zip(quartiles) {
+                value.heat_map_quartile = quartile.and_then(|q| self.heat_map.orient(q));
+            }
+        }
+        values
+    }
+}
+
+/// Look up a metric by its API row name
+pub fn find_metric(name: &str) -> Option<&'static MetricDefinition> {
+    METRIC_REGISTRY.iter().find(|m| m.name == name)
+}
+
+/// Every registered metric, in display order within each section
+pub static METRIC_REGISTRY: &[MetricDefinition] = &[
+    // Growth & margins
+    MetricDefinition {
+        name: "revenue",
+        stored_name: None,
+        display_name: "Revenue",
+        section: MetricSection::GrowthAndMargins,
+        unit: "currency",
+        format: MetricFormat::Currency,
+        heat_map: HeatMapDirection::None,
+        compute: |i| {
+            MetricsCalculator::calculate_revenue_metrics(
+                i.incomes,
+                i.prior_year_incomes,
+                i.currency,
+            )
+            .0
+        },
+    },
+    MetricDefinition {
+        name: "revenue_growth_yoy",
+        stored_name: Some("yoy_revenue_growth_pct"),
+        display_name: "Revenue Growth (YoY)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: yoy_growth,
+    },
+    MetricDefinition {
+        name: "revenue_growth_qoq",
+        stored_name: Some("qoq_revenue_growth_pct"),
+        display_name: "Revenue Growth (QoQ)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| {
+            consecutive_only(
+                i,
+                MetricsCalculator::calculate_revenue_metrics(
+                    i.incomes,
+                    i.prior_year_incomes,
+                    i.currency,
+                )
+                .2,
+            )
+        },
+    },
+    MetricDefinition {
+        name: "revenue_growth_acceleration",
+        stored_name: Some("growth_acceleration"),
+        display_name: "YoY Growth Acceleration",
+        section: MetricSection::GrowthAndMargins,
+        unit: "bps",
+        format: MetricFormat::BasisPoints,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| {
+            consecutive_only(
+                i,
+                MetricsCalculator::calculate_revenue_acceleration(&yoy_growth(i)),
+            )
+        },
+    },
+    MetricDefinition {
+        name: "growth_acceleration_ratio_pct",
+        stored_name: Some("growth_acceleration_ratio_pct"),
+        display_name: "YoY Growth Acceleration (Ratio)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| {
+            consecutive_only(
+                i,
+                MetricsCalculator::calculate_acceleration_ratio(&yoy_growth(i)),
+            )
+        },
+    },
+    MetricDefinition {
+        name: "gross_margin",
+        stored_name: Some("gross_margin_pct"),
+        display_name: "Gross Margin",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| MetricsCalculator::calculate_margin_metrics(i.incomes).0,
+    },
+    MetricDefinition {
+        name: "operating_margin",
+        stored_name: Some("operating_margin_pct"),
+        display_name: "Operating Margin",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: operating_margin,
+    },
+    MetricDefinition {
+        name: "op_margin_expansion_bps",
+        stored_name: Some("op_margin_expansion_bps"),
+        display_name: "OP Margin Expansion",
+        section: MetricSection::GrowthAndMargins,
+        unit: "bps",
+        format: MetricFormat::BasisPoints,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| {
+            consecutive_only(
+                i,
+                MetricsCalculator::calculate_expansion_metrics(&operating_margin(i)),
+            )
+        },
+    },
+    MetricDefinition {
+        name: "op_margin_expansion_pct",
+        stored_name: Some("op_margin_expansion_pct"),
+        display_name: "Sequential OP Margin Expansion",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| {
+            consecutive_only(
+                i,
+                MetricsCalculator::calculate_margin_expansion_ratio(&operating_margin(i)),
+            )
+        },
+    },
+    MetricDefinition {
+        name: "net_margin",
+        stored_name: Some("net_margin_pct"),
+        display_name: "Net Margin",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| MetricsCalculator::calculate_margin_metrics(i.incomes).2,
+    },
+    MetricDefinition {
+        name: "ebitda_margin",
+        stored_name: Some("ebitda_margin_pct"),
+        display_name: "EBITDA Margin",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| MetricsCalculator::calculate_ebitda_margin(i.incomes),
+    },
+    MetricDefinition {
+        name: "effective_tax_rate",
+        stored_name: Some("effective_tax_rate_pct"),
+        display_name: "Effective Tax Rate",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::None,
+        compute: |i| MetricsCalculator::calculate_effective_tax_rate(i.incomes),
+    },
+    MetricDefinition {
+        name: "revenue_cagr_3y",
+        stored_name: Some("revenue_cagr_3y_pct"),
+        display_name: "Revenue CAGR (3Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| align_to_periods(i, &multi_year_growth(i, 3).revenue_cagr),
+    },
+    MetricDefinition {
+        name: "revenue_cagr_5y",
+        stored_name: Some("revenue_cagr_5y_pct"),
+        display_name: "Revenue CAGR (5Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| align_to_periods(i, &multi_year_growth(i, 5).revenue_cagr),
+    },
+    MetricDefinition {
+        name: "eps_cagr_3y",
+        stored_name: Some("eps_cagr_3y_pct"),
+        display_name: "EPS CAGR (3Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| align_to_periods(i, &multi_year_growth(i, 3).eps_cagr),
+    },
+    MetricDefinition {
+        name: "eps_cagr_5y",
+        stored_name: Some("eps_cagr_5y_pct"),
+        display_name: "EPS CAGR (5Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| align_to_periods(i, &multi_year_growth(i, 5).eps_cagr),
+    },
+    MetricDefinition {
+        name: "fcf_cagr_3y",
+        stored_name: Some("fcf_cagr_3y_pct"),
+        display_name: "FCF CAGR (3Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| align_to_periods(i, &multi_year_growth(i, 3).fcf_cagr),
+    },
+    MetricDefinition {
+        name: "fcf_cagr_5y",
+        stored_name: Some("fcf_cagr_5y_pct"),
+        display_name: "FCF CAGR (5Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| align_to_periods(i, &multi_year_growth(i, 5).fcf_cagr),
+    },
+    MetricDefinition {
+        name: "positive_growth_years_5y",
+        stored_name: Some("positive_growth_years_5y"),
+        display_name: "Years of Positive Revenue Growth (5Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "periods",
+        format: MetricFormat::Count,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| {
+            let consistency = MetricsCalculator::calculate_growth_consistency(i.annual_incomes, 5);
+            align_to_periods(i, &consistency.positive_growth_periods)
+        },
+    },
+    MetricDefinition {
+        name: "revenue_growth_std_dev_5y",
+        stored_name: Some("revenue_growth_std_dev_5y"),
+        display_name: "Revenue Growth Std Dev (5Y)",
+        section: MetricSection::GrowthAndMargins,
+        unit: "pp",
+        format: MetricFormat::PercentagePoints,
+        heat_map: HeatMapDirection::LowerIsBetter,
+        compute: |i| {
+            let consistency = MetricsCalculator::calculate_growth_consistency(i.annual_incomes, 5);
+            align_to_periods(i, &consistency.growth_std_dev)
+        },
+    },
+    // Cash & leverage
+    MetricDefinition {
+        name: "ocf_margin",
+        stored_name: Some("ocf_revenue_pct"),
+        display_name: "OCF Margin",
+        section: MetricSection::CashAndLeverage,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| MetricsCalculator::calculate_cash_metrics(i.incomes, i.cash_flows).0,
+    },
+    MetricDefinition {
+        name: "fcf_margin",
+        stored_name: Some("fcf_revenue_pct"),
+        display_name: "FCF Margin",
+        section: MetricSection::CashAndLeverage,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| MetricsCalculator::calculate_cash_metrics(i.incomes, i.cash_flows).1,
+    },
+    MetricDefinition {
+        name: "leverage_ratio",
+        stored_name: Some("revenue_minus_net_debt_pct"),
+        display_name: "Leverage Ratio",
+        section: MetricSection::CashAndLeverage,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| MetricsCalculator::calculate_leverage_metrics(i.incomes, i.balances).0,
+    },
+    MetricDefinition {
+        name: "days_sales_outstanding",
+        stored_name: Some("dso_days"),
+        display_name: "Days Sales Outstanding",
+        section: MetricSection::CashAndLeverage,
+        unit: "days",
+        format: MetricFormat::Days,
+        heat_map: HeatMapDirection::LowerIsBetter,
+        compute: |i| working_capital(i).days_sales_outstanding,
+    },
+    MetricDefinition {
+        name: "days_inventory_outstanding",
+        stored_name: Some("dio_days"),
+        display_name: "Days Inventory Outstanding",
+        section: MetricSection::CashAndLeverage,
+        unit: "days",
+        format: MetricFormat::Days,
+        heat_map: HeatMapDirection::LowerIsBetter,
+        compute: |i| working_capital(i).days_inventory_outstanding,
+    },
+    MetricDefinition {
+        name: "days_payables_outstanding",
+        stored_name: Some("dpo_days"),
+        display_name: "Days Payables Outstanding",
+        section: MetricSection::CashAndLeverage,
+        unit: "days",
+        format: MetricFormat::Days,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| working_capital(i).days_payables_outstanding,
+    },
+    MetricDefinition {
+        name: "cash_conversion_cycle",
+        stored_name: Some("cash_conversion_cycle_days"),
+        display_name: "Cash Conversion Cycle",
+        section: MetricSection::CashAndLeverage,
+        unit: "days",
+        format: MetricFormat::Days,
+        heat_map: HeatMapDirection::LowerIsBetter,
+        compute: |i| working_capital(i).cash_conversion_cycle,
+    },
+    MetricDefinition {
+        name: "accruals_ratio",
+        stored_name: Some("accruals_ratio_pct"),
+        display_name: "Accruals Ratio",
+        section: MetricSection::CashAndLeverage,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::LowerIsBetter,
+        compute: |i| {
+            MetricsCalculator::calculate_accruals_ratio(i.incomes, i.balances, i.cash_flows)
+        },
+    },
+    MetricDefinition {
+        name: "capex_to_ocf",
+        stored_name: Some("capex_ocf_pct"),
+        display_name: "Capex % of OCF",
+        section: MetricSection::CashAndLeverage,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::None,
+        compute: |i| {
+            MetricsCalculator::calculate_capital_allocation_metrics(i.incomes, i.cash_flows).0
+        },
+    },
+    MetricDefinition {
+        name: "shareholder_returns_to_ocf",
+        stored_name: Some("shareholder_returns_ocf_pct"),
+        display_name: "Dividends + Buybacks % of OCF",
+        section: MetricSection::CashAndLeverage,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::None,
+        compute: |i| {
+            MetricsCalculator::calculate_capital_allocation_metrics(i.incomes, i.cash_flows).1
+        },
+    },
+    MetricDefinition {
+        name: "shares_outstanding",
+        stored_name: None,
+        display_name: "Shares Outstanding",
+        section: MetricSection::CashAndLeverage,
+        unit: "shares",
+        format: MetricFormat::Shares,
+        heat_map: HeatMapDirection::None,
+        compute: |i| MetricsCalculator::calculate_leverage_metrics(i.incomes, i.balances).1,
+    },
+    // Valuation
+    MetricDefinition {
+        name: "pe_ratio",
+        stored_name: Some("pe_ratio_historical"),
+        display_name: "P/E Ratio",
+        section: MetricSection::Valuation,
+        unit: "x",
+        format: MetricFormat::Multiple,
+        heat_map: HeatMapDirection::LowerIsBetter,
+        compute: |i| MetricsCalculator::calculate_valuation_metrics(i.incomes, i.prices).pe_ratios,
+    },
+    MetricDefinition {
+        name: "dividend_yield",
+        stored_name: Some("dividend_yield_pct"),
+        display_name: "Dividend Yield",
+        section: MetricSection::Valuation,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| shareholder_yields(i).dividend_yield,
+    },
+    MetricDefinition {
+        name: "buyback_yield",
+        stored_name: Some("buyback_yield_pct"),
+        display_name: "Buyback Yield",
+        section: MetricSection::Valuation,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| shareholder_yields(i).buyback_yield,
+    },
+    MetricDefinition {
+        name: "shareholder_yield",
+        stored_name: Some("shareholder_yield_pct"),
+        display_name: "Shareholder Yield",
+        section: MetricSection::Valuation,
+        unit: "%",
+        format: MetricFormat::Percent,
+        heat_map: HeatMapDirection::HigherIsBetter,
+        compute: |i| shareholder_yields(i).shareholder_yield,
+    },
+];
+
+fn yoy_growth(inputs: &MetricInputs) -> Vec<MetricValue> {
+    MetricsCalculator::calculate_revenue_metrics(
+        inputs.incomes,
+        inputs.prior_year_incomes,
+        inputs.currency,
+    )
+    .1
+}
+
+fn operating_margin(inputs: &MetricInputs) -> Vec<MetricValue> {
+    MetricsCalculator::calculate_margin_metrics(inputs.incomes).1
+}
+
+/// Blank the values of a sequential metric where a period doesn't directly
+/// follow the one before it: comparing across a missing quarter or year is
+/// not a sequential change
+fn consecutive_only(inputs: &MetricInputs, mut values: Vec<MetricValue>) -> Vec<MetricValue> {
+    for (i, value) in values.iter_mut().enumerate().skip(1) {
+        let (Some(prior), Some(current)) = (inputs.incomes.get(i - 1), inputs.incomes.get(i))
+        else {
+            continue;
+        };
+        if !is_previous_period(
+            prior.period_end_date,
+            current.period_end_date,
+            inputs.period_type,
+        ) {
+            value.value = None;
+            value.formatted_value = "N/A".to_string();
+        }
+    }
+    values
+}
+
+fn working_capital(inputs: &MetricInputs) -> WorkingCapitalMetrics {
+    MetricsCalculator::calculate_working_capital_metrics(
+        inputs.incomes,
+        inputs.balances,
+        inputs.period_type,
+    )
+}
+
+fn shareholder_yields(inputs: &MetricInputs) -> ShareholderYieldMetrics {
+    MetricsCalculator::calculate_shareholder_yield_metrics(
+        inputs.incomes,
+        inputs.balances,
+        inputs.cash_flows,
+        inputs.prices,
+        inputs.period_type,
+    )
+}
+
+fn multi_year_growth(
+    inputs: &MetricInputs,
+    years: i32,
+) -> crate::metrics::calculator::MultiYearGrowthMetrics {
+    MetricsCalculator::calculate_multi_year_growth(
+        inputs.annual_incomes,
+        inputs.annual_cash_flows,
+        years,
+    )
+}
+
+/// Map values computed per annual period onto `inputs.incomes`: each period
+/// shows the value of the latest fiscal year ending on or before it.
+fn align_to_periods(inputs: &MetricInputs, annual_values: &[MetricValue]) -> Vec<MetricValue> {
+    inputs
+        .incomes
+        .iter()
+        .map(|inc| {
+            inputs
+                .annual_incomes
+                .iter()
+                .rposition(|a| a.period_end_date <= inc.period_end_date)
+                .and_then(|idx| annual_values.get(idx))
+                .cloned()
+                .unwrap_or_else(|| MetricValue {
+                    value: None,
+                    formatted_value: "N/A".to_string(),
+                    unit: annual_values
+                        .first()
+                        .map(|v| v.unit.clone())
+                        .unwrap_or_default(),
+                    heat_map_quartile: None,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use std::collections::HashSet;
+
+    fn income(date: NaiveDate, revenue: i64) -> IncomeStatement {
+        IncomeStatement {
+            period_end_date: date,
+            revenue: Some(BigDecimal::from(revenue)),
+            ..Default::default()
+        }
+    }
+
+    fn annual(year: i32, revenue: i64) -> IncomeStatement {
+        income(NaiveDate::from_ymd_opt(year, 12, 31).unwrap(), revenue)
+    }
+
+    #[test]
+    fn test_registry_names_are_unique() {
+        let names: HashSet<_> = METRIC_REGISTRY.iter().map(|m| m.name).collect();
+        assert_eq!(names.len(), METRIC_REGISTRY.len());
+
+        let stored: Vec<_> = METRIC_REGISTRY
+            .iter()
+            .filter_map(|m| m.stored_name)
+            .collect();
+        let unique_stored: HashSet<_> = stored.iter().collect();
+        assert_eq!(unique_stored.len(), stored.len());
+    }
+
+    #[test]
+    fn test_evaluate_assigns_oriented_quartiles() {
+        let incomes = vec![
+            annual(2019, 100),
+            annual(2020, 110),
+            annual(2021, 130),
+            annual(2022, 160),
+        ];
+        let mut prior_year_incomes = vec![None];
+        prior_year_incomes.extend(incomes[..3].iter().cloned().map(Some));
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &prior_year_incomes,
+            balances: &[None, None, None, None],
+            cash_flows: &[None, None, None, None],
+            prices: &[None, None, None, None],
+            annual_incomes: &incomes,
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Annual,
+        };
+
+        let quartiles = |name: &str| -> Vec<Option<i32>> {
+            find_metric(name)
+                .unwrap()
+                .evaluate(&inputs)
+                .iter()
+                .map(|v| v.heat_map_quartile)
+                .collect()
+        };
+
+        // Accelerating growth ranks higher each year
+        assert_eq!(
+            quartiles("revenue_growth_yoy"),
+            vec![None, Some(1), Some(2), Some(3)]
+        );
+        // Raw revenue is not heat-mapped
+        assert_eq!(quartiles("revenue"), vec![None; 4]);
+        assert_eq!(HeatMapDirection::LowerIsBetter.orient(4), Some(1));
+    }
+
+    #[test]
+    fn test_sequential_metrics_skip_missing_periods() {
+        let quarter = |month: u32, day: u32| NaiveDate::from_ymd_opt(2023, month, day).unwrap();
+        // Q2 is missing: Q3 follows Q1
+        let incomes = vec![
+            income(quarter(3, 31), 100),
+            income(quarter(9, 30), 120),
+            income(quarter(12, 31), 150),
+        ];
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &[None, None, None],
+            balances: &[None, None, None],
+            cash_flows: &[None, None, None],
+            prices: &[None, None, None],
+            annual_incomes: &[],
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Quarterly,
+        };
+
+        let qoq: Vec<Option<f64>> = find_metric("revenue_growth_qoq")
+            .unwrap()
+            .evaluate(&inputs)
+            .iter()
+            .map(|v| v.value)
+            .collect();
+        assert_eq!(qoq, vec![None, None, Some(25.0)]);
+    }
+
+    #[test]
+    fn test_ebitda_margin_and_effective_tax_rate_rows() {
+        let incomes = vec![IncomeStatement {
+            ebitda: Some(BigDecimal::from(250)),
+            income_before_tax: Some(BigDecimal::from(160)),
+            income_tax_expense: Some(BigDecimal::from(40)),
+            ..annual(2023, 1000)
+        }];
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &[None],
+            balances: &[None],
+            cash_flows: &[None],
+            prices: &[None],
+            annual_incomes: &incomes,
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Annual,
+        };
+
+        let ebitda_margin = find_metric("ebitda_margin").unwrap();
+        assert_eq!(ebitda_margin.stored_name, Some("ebitda_margin_pct"));
+        assert_eq!(ebitda_margin.evaluate(&inputs)[0].value, Some(25.0));
+
+        let tax_rate = find_metric("effective_tax_rate").unwrap();
+        assert_eq!(tax_rate.stored_name, Some("effective_tax_rate_pct"));
+        assert_eq!(tax_rate.evaluate(&inputs)[0].formatted_value, "25.00%");
+    }
+
+    #[test]
+    fn test_multi_year_metrics_align_to_latest_annual() {
+        let annual_incomes = vec![annual(2019, 100), annual(2020, 110), annual(2021, 121)];
+        let incomes = vec![
+            income(NaiveDate::from_ymd_opt(2019, 6, 30).unwrap(), 50),
+            income(NaiveDate::from_ymd_opt(2022, 3, 31).unwrap(), 35),
+        ];
+        let inputs = MetricInputs {
+            incomes: &incomes,
+            prior_year_incomes: &[None, None],
+            balances: &[None, None],
+            cash_flows: &[None, None],
+            prices: &[None, None],
+            annual_incomes: &annual_incomes,
+            annual_cash_flows: &[],
+            currency: "$",
+            period_type: PeriodType::Annual,
+        };
+
+        let positive = find_metric("positive_growth_years_5y")
+            .unwrap()
+            .evaluate(&inputs);
+        // Before the first fiscal year ends nothing is in effect; Q1 2022
+        // shows FY2021's two growth years.
+        assert_eq!(positive[0].value, None);
+        assert_eq!(positive[1].value, Some(2.0));
+        assert_eq!(positive[1].formatted_value, "2 of 2");
+    }
+}
@@ -0,0 +1,348 @@
+//! Calendarization: restating fiscal periods on calendar quarters or years so
+//! companies with different fiscal year ends can be compared period for period.
+//!
+//! Flow items (revenue, income, cash flows) are apportioned by the share of
+//! each fiscal period's days that fall inside the calendar period. Balance
+//! sheet items are point-in-time and taken from the latest balance sheet at
+//! the calendar period end.
+
+use super::{
+    FiscalCalendar, FiscalPeriod, PeriodType, PeriodWindowGenerator, PERIOD_MATCH_TOLERANCE_DAYS,
+};
+use crate::domain::{BalanceSheet, CashFlowStatement, IncomeStatement};
+use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
+use chrono::{Duration, NaiveDate};
+
+/// A flow value reported over the inclusive range `start..=end`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowObservation {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub value: Option<f64>,
+}
+
+/// Day-weighted sum of the observations overlapping `start..=end`.
+///
+/// `None` if the observations leave part of the target uncovered (beyond the
+/// match tolerance) or an overlapping observation has no value.
+pub fn calendarize_flow(
+    observations: &[FlowObservation],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Option<f64> {
+    let target_days = (end - start).num_days() + 1;
+    let mut covered_days = 0;
+    let mut total = 0.0;
+
+    for obs in observations {
+        let overlap = (obs.end.min(end) - obs.start.max(start)).num_days() + 1;
+        if overlap <= 0 {
+            continue;
+        }
+        let value = obs.value?;
+        let obs_days = (obs.end - obs.start).num_days() + 1;
+        total += value * overlap as f64 / obs_days as f64;
+        covered_days += overlap;
+    }
+
+    if covered_days + PERIOD_MATCH_TOLERANCE_DAYS < target_days {
+        return None;
+    }
+    Some(total)
+}
+
+/// `count` calendar quarters or years ending with the one containing `as_of`,
+/// oldest first, labelled "CQ3 2024" / "CY2024"
+pub fn calendar_periods(
+    count: usize,
+    period_type: PeriodType,
+    as_of: NaiveDate,
+) -> Vec<FiscalPeriod> {
+    let generator = PeriodWindowGenerator::new(12);
+    let mut periods = generator.generate_periods(count, period_type, as_of);
+    periods.reverse();
+    for period in &mut periods {
+        period.display_label = match period.fiscal_quarter {
+            Some(q) if period_type == PeriodType::Quarterly => {
+                format!("CQ{} {}", q, period.fiscal_year)
+            }
+            _ => format!("CY{}", period.fiscal_year),
+        };
+    }
+    periods
+}
+
+impl FiscalCalendar {
+    /// Whether fiscal periods of this type already coincide with calendar
+    /// periods, so calendarizing would not change any values
+    pub fn is_calendar_aligned(&self, period_type: PeriodType) -> bool {
+        match (self, period_type) {
+            (Self::MonthEnd { month }, PeriodType::Quarterly) => month % 3 == 0,
+            (Self::MonthEnd { month }, PeriodType::Annual) => *month == 12,
+            _ => false,
+        }
+    }
+}
+
+/// Restates one company's fiscal statements onto calendar periods
+pub struct Calendarizer {
+    generator: PeriodWindowGenerator,
+    period_type: PeriodType,
+}
+
+impl Calendarizer {
+    pub fn new(calendar: FiscalCalendar, period_type: PeriodType) -> Self {
+        Self {
+            generator: PeriodWindowGenerator::with_calendar(calendar),
+            period_type,
+        }
+    }
+
+    /// Fiscal window each statement reports: from the start of its fiscal
+    /// period to the statement's own period end date
+    fn windows(&self, period_ends: impl Iterator<Item = NaiveDate>) -> Vec<(NaiveDate, NaiveDate)> {
+        period_ends
+            .map(|end| {
+                let period = self.generator.period_containing(end, self.period_type);
+                (period.period_start_date, end)
+            })
+            .collect()
+    }
+
+    fn flow(
+        windows: &[(NaiveDate, NaiveDate)],
+        target: &FiscalPeriod,
+        value: impl Fn(usize) -> Option<f64>,
+    ) -> Option<BigDecimal> {
+        let observations: Vec<FlowObservation> = windows
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, end))| FlowObservation {
+                start,
+                end,
+                value: value(i),
+            })
+            .collect();
+        calendarize_flow(
+            &observations,
+            target.period_start_date,
+            target.period_end_date,
+        )
+        .and_then(BigDecimal::from_f64)
+    }
+
+    /// Calendarized income statements, one per target; `None` where the fiscal
+    /// statements don't cover the target or report no revenue for it
+    pub fn incomes(
+        &self,
+        incomes: &[IncomeStatement],
+        targets: &[FiscalPeriod],
+    ) -> Vec<Option<IncomeStatement>> {
+        let windows = self.windows(incomes.iter().map(|i| i.period_end_date));
+        let field = |f: fn(&IncomeStatement) -> &Option<BigDecimal>| {
+            move |i: usize| f(&incomes[i]).as_ref().and_then(|v| v.to_f64())
+        };
+
+        targets
+            .iter()
+            .map(|target| {
+                let revenue = Self::flow(&windows, target, field(|i| &i.revenue))?;
+                let flow = |f| Self::flow(&windows, target, field(f));
+                // Share counts are point in time, like the balance sheet
+                let shares_outstanding = Self::latest_in(incomes, target, |i| i.period_end_date)
+                    .and_then(|i| i.shares_outstanding);
+                Some(IncomeStatement {
+                    period_end_date: target.period_end_date,
+                    revenue: Some(revenue),
+                    cost_of_revenue: flow(|i| &i.cost_of_revenue),
+                    gross_profit: flow(|i| &i.gross_profit),
+                    operating_expenses: flow(|i| &i.operating_expenses),
+                    operating_income: flow(|i| &i.operating_income),
+                    interest_income: flow(|i| &i.interest_income),
+                    interest_expense: flow(|i| &i.interest_expense),
+                    income_before_tax: flow(|i| &i.income_before_tax),
+                    income_tax_expense: flow(|i| &i.income_tax_expense),
+                    net_income: flow(|i| &i.net_income),
+                    depreciation_amortization: flow(|i| &i.depreciation_amortization),
+                    ebit: flow(|i| &i.ebit),
+                    ebitda: flow(|i| &i.ebitda),
+                    eps: flow(|i| &i.eps),
+                    diluted_eps: flow(|i| &i.diluted_eps),
+                    shares_outstanding,
+                })
+            })
+            .collect()
+    }
+
+    /// Calendarized cash flow statements, one per target
+    pub fn cash_flows(
+        &self,
+        cash_flows: &[CashFlowStatement],
+        targets: &[FiscalPeriod],
+    ) -> Vec<Option<CashFlowStatement>> {
+        let windows = self.windows(cash_flows.iter().map(|c| c.period_end_date));
+        let field = |f: fn(&CashFlowStatement) -> &Option<BigDecimal>| {
+            move |i: usize| f(&cash_flows[i]).as_ref().and_then(|v| v.to_f64())
+        };
+
+        targets
+            .iter()
+            .map(|target| {
+                let flow = |f| Self::flow(&windows, target, field(f));
+                let operating_cash_flow = flow(|c| &c.operating_cash_flow);
+                let free_cash_flow = flow(|c| &c.free_cash_flow);
+                if operating_cash_flow.is_none() && free_cash_flow.is_none() {
+                    return None;
+                }
+                Some(CashFlowStatement {
+                    period_end_date: target.period_end_date,
+                    operating_cash_flow,
+                    net_income: flow(|c| &c.net_income),
+                    depreciation_depletion: flow(|c| &c.depreciation_depletion),
+                    change_in_receivables: flow(|c| &c.change_in_receivables),
+                    change_in_inventory: flow(|c| &c.change_in_inventory),
+                    change_in_payables: flow(|c| &c.change_in_payables),
+                    investing_cash_flow: flow(|c| &c.investing_cash_flow),
+                    capital_expenditures: flow(|c| &c.capital_expenditures),
+                    investments: flow(|c| &c.investments),
+                    financing_cash_flow: flow(|c| &c.financing_cash_flow),
+                    dividend_payout: flow(|c| &c.dividend_payout),
+                    stock_repurchase: flow(|c| &c.stock_repurchase),
+                    debt_repayment: flow(|c| &c.debt_repayment),
+                    free_cash_flow,
+                })
+            })
+            .collect()
+    }
+
+    /// Latest balance sheet for each target, see [`Self::latest_in`]
+    pub fn balances(
+        &self,
+        balances: &[BalanceSheet],
+        targets: &[FiscalPeriod],
+    ) -> Vec<Option<BalanceSheet>> {
+        targets
+            .iter()
+            .map(|target| {
+                Self::latest_in(balances, target, |b| b.period_end_date).map(|b| BalanceSheet {
+                    period_end_date: target.period_end_date,
+                    ..b.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Latest point-in-time statement on or shortly after the target's end,
+    /// as long as it falls within the target period
+    fn latest_in<'a, T>(
+        statements: &'a [T],
+        target: &FiscalPeriod,
+        date: impl Fn(&T) -> NaiveDate,
+    ) -> Option<&'a T> {
+        let tolerance = Duration::days(PERIOD_MATCH_TOLERANCE_DAYS);
+        statements
+            .iter()
+            .filter(|s| {
+                date(s) >= target.period_start_date && date(s) <= target.period_end_date + tolerance
+            })
+            .max_by_key(|s| date(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn income(end: NaiveDate, revenue: i64) -> IncomeStatement {
+        IncomeStatement {
+            period_end_date: end,
+            revenue: Some(BigDecimal::from(revenue)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calendarize_flow_weights_by_days() {
+        // Two fiscal quarters ending Jan 31 and Apr 30 spread over CQ1 (Jan-Mar)
+        let observations = [
+            FlowObservation {
+                start: date(2023, 11, 1),
+                end: date(2024, 1, 31),
+                value: Some(92.0),
+            },
+            FlowObservation {
+                start: date(2024, 2, 1),
+                end: date(2024, 4, 30),
+                value: Some(90.0),
+            },
+        ];
+        // 31 of 92 days from the first, 60 of 90 from the second
+        let cq1 = calendarize_flow(&observations, date(2024, 1, 1), date(2024, 3, 31)).unwrap();
+        assert!((cq1 - (31.0 + 60.0)).abs() < 1e-9);
+
+        // Nothing reported after April, so CQ2 is not covered
+        assert_eq!(
+            calendarize_flow(&observations, date(2024, 4, 1), date(2024, 6, 30)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calendarizer_restates_march_year_end_onto_calendar_year() {
+        let calendar = FiscalCalendar::MonthEnd { month: 3 };
+        let calendarizer = Calendarizer::new(calendar, PeriodType::Annual);
+        let incomes = [
+            income(date(2023, 3, 31), 365_000),
+            income(date(2024, 3, 31), 732_000),
+        ];
+        let targets = calendar_periods(2, PeriodType::Annual, date(2023, 12, 31));
+        assert_eq!(targets[1].display_label, "CY2023");
+
+        let restated = calendarizer.incomes(&incomes, &targets);
+        // CY2022 needs Jan-Mar 2022, which no statement covers
+        assert!(restated[0].is_none());
+        // CY2023: Jan-Mar 2023 from FY2023 (90/365) + Apr-Dec 2023 from FY2024 (275/366)
+        let cy2023 = restated[1].as_ref().unwrap();
+        let expected = 365_000.0 * 90.0 / 365.0 + 732_000.0 * 275.0 / 366.0;
+        assert!((cy2023.revenue.as_ref().unwrap().to_f64().unwrap() - expected).abs() < 1e-6);
+        assert_eq!(cy2023.period_end_date, date(2023, 12, 31));
+    }
+
+    #[test]
+    fn test_calendarizer_apportions_every_cash_flow_line() {
+        let calendar = FiscalCalendar::MonthEnd { month: 3 };
+        let calendarizer = Calendarizer::new(calendar, PeriodType::Annual);
+        let cash_flow = |end, amount: i64| CashFlowStatement {
+            period_end_date: end,
+            operating_cash_flow: Some(BigDecimal::from(amount)),
+            dividend_payout: Some(BigDecimal::from(amount / 10)),
+            stock_repurchase: None,
+            ..Default::default()
+        };
+        let cash_flows = [
+            cash_flow(date(2023, 3, 31), 365_000),
+            cash_flow(date(2024, 3, 31), 732_000),
+        ];
+        let targets = calendar_periods(1, PeriodType::Annual, date(2023, 12, 31));
+
+        let cy2023 = calendarizer.cash_flows(&cash_flows, &targets)[0]
+            .clone()
+            .unwrap();
+        let expected = 36_500.0 * 90.0 / 365.0 + 73_200.0 * 275.0 / 366.0;
+        let dividends = cy2023.dividend_payout.unwrap().to_f64().unwrap();
+        assert!((dividends - expected).abs() < 1e-6);
+        // Lines no statement reports stay missing rather than becoming zero
+        assert_eq!(cy2023.stock_repurchase, None);
+    }
+
+    #[test]
+    fn test_calendar_alignment() {
+        assert!(FiscalCalendar::MonthEnd { month: 3 }.is_calendar_aligned(PeriodType::Quarterly));
+        assert!(!FiscalCalendar::MonthEnd { month: 3 }.is_calendar_aligned(PeriodType::Annual));
+        assert!(!FiscalCalendar::MonthEnd { month: 1 }.is_calendar_aligned(PeriodType::Quarterly));
+    }
+}
@@ -1,10 +1,191 @@
-use chrono::{Datelike, NaiveDate};
+pub mod calendarize;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 use utoipa::ToSchema;
 
+/// Statements from different sources (or a 52/53-week filer's balance sheet vs.
+/// its price history) can disagree on a period end by a few days. Dates within
+/// this many days are treated as the same period.
+pub const PERIOD_MATCH_TOLERANCE_DAYS: i64 = 7;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "mon"),
+    (Weekday::Tue, "tue"),
+    (Weekday::Wed, "wed"),
+    (Weekday::Thu, "thu"),
+    (Weekday::Fri, "fri"),
+    (Weekday::Sat, "sat"),
+    (Weekday::Sun, "sun"),
+];
+
+/// Rule for where a company's fiscal year ends.
+///
+/// Written as `month_end_dec`, `last_sat_jan` (last Saturday of January) or
+/// `nearest_sat_sep` (Saturday nearest September 30). Week-based calendars have
+/// 52-week years with an occasional 53rd week, and 13-week quarters with the
+/// extra week falling in Q4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FiscalCalendar {
+    MonthEnd { month: u32 },
+    LastWeekday { month: u32, weekday: Weekday },
+    NearestWeekday { month: u32, weekday: Weekday },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid fiscal calendar '{0}': expected e.g. 'month_end_dec', 'last_sat_jan' or 'nearest_sat_sep'")]
+pub struct FiscalCalendarParseError(String);
+
+impl FiscalCalendar {
+    /// Calendar for a company: the stored rule if it parses, otherwise month-end
+    /// of the fiscal year end month (December if unknown)
+    pub fn for_company(spec: Option<&str>, fiscal_year_end_month: Option<i32>) -> Self {
+        spec.and_then(|s| s.parse().ok()).unwrap_or(Self::MonthEnd {
+            month: fiscal_year_end_month
+                .filter(|m| (1..=12).contains(m))
+                .unwrap_or(12) as u32,
+        })
+    }
+
+    /// Month the fiscal year is named after. A `nearest_sat_dec` year can end
+    /// on Jan 2 and still be labelled with the December's year.
+    pub fn anchor_month(&self) -> u32 {
+        match *self {
+            Self::MonthEnd { month }
+            | Self::LastWeekday { month, .. }
+            | Self::NearestWeekday { month, .. } => month,
+        }
+    }
+
+    pub fn is_week_based(&self) -> bool {
+        !matches!(self, Self::MonthEnd { .. })
+    }
+
+    pub fn fiscal_year_end(&self, fiscal_year: i32) -> NaiveDate {
+        let month_end = last_day_of_month(fiscal_year, self.anchor_month());
+        match *self {
+            Self::MonthEnd { .. } => month_end,
+            Self::LastWeekday { weekday, .. } => {
+                let back = (month_end.weekday().num_days_from_monday() + 7
+                    - weekday.num_days_from_monday())
+                    % 7;
+                month_end - Duration::days(back as i64)
+            }
+            Self::NearestWeekday { weekday, .. } => {
+                let back = (month_end.weekday().num_days_from_monday() + 7
+                    - weekday.num_days_from_monday())
+                    % 7;
+                if back <= 3 {
+                    month_end - Duration::days(back as i64)
+                } else {
+                    month_end + Duration::days(7 - back as i64)
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for FiscalCalendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let month = MONTHS[(self.anchor_month() - 1) as usize];
+        let day = |weekday: Weekday| {
+            WEEKDAYS
+                .iter()
+                .find(|(w, _)| *w == weekday)
+                .map(|(_, name)| *name)
+                .unwrap_or("sat")
+        };
+        match *self {
+            Self::MonthEnd { .. } => write!(f, "month_end_{}", month),
+            Self::LastWeekday { weekday, .. } => write!(f, "last_{}_{}", day(weekday), month),
+            Self::NearestWeekday { weekday, .. } => {
+                write!(f, "nearest_{}_{}", day(weekday), month)
+            }
+        }
+    }
+}
+
+impl FromStr for FiscalCalendar {
+    type Err = FiscalCalendarParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || FiscalCalendarParseError(s.to_string());
+        let lower = s.trim().to_ascii_lowercase();
+        let (rule, month) = lower.rsplit_once('_').ok_or_else(err)?;
+        let month = MONTHS
+            .iter()
+            .position(|m| *m == month)
+            .map(|i| i as u32 + 1)
+            .ok_or_else(err)?;
+
+        if rule == "month_end" {
+            return Ok(Self::MonthEnd { month });
+        }
+        let (kind, day) = rule.split_once('_').ok_or_else(err)?;
+        let weekday = WEEKDAYS
+            .iter()
+            .find(|(_, name)| *name == day)
+            .map(|(w, _)| *w)
+            .ok_or_else(err)?;
+        match kind {
+            "last" => Ok(Self::LastWeekday { month, weekday }),
+            "nearest" => Ok(Self::NearestWeekday { month, weekday }),
+            _ => Err(err()),
+        }
+    }
+}
+
+fn last_day_of_month(year: i32, month: u32) -> NaiveDate {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1).unwrap() - Duration::days(1)
+}
+
+/// Whether two period end dates refer to the same fiscal period
+pub fn same_period(a: NaiveDate, b: NaiveDate) -> bool {
+    (a - b).num_days().abs() <= PERIOD_MATCH_TOLERANCE_DAYS
+}
+
+/// Whether `prior` ends one fiscal year before `current`, allowing for
+/// 53-week years and month-end drift
+pub fn is_prior_year_period(prior: NaiveDate, current: NaiveDate) -> bool {
+    ((current - prior).num_days() - 365).abs() <= PERIOD_MATCH_TOLERANCE_DAYS
+}
+
+/// Whether `prior` is the period immediately before `current`: a quarter ends
+/// 80 to 100 days earlier, a fiscal year about a year earlier. Anything wider
+/// means a period is missing in between.
+pub fn is_previous_period(prior: NaiveDate, current: NaiveDate, period_type: PeriodType) -> bool {
+    match period_type {
+        PeriodType::Quarterly => (80..=100).contains(&(current - prior).num_days()),
+        PeriodType::Annual => is_prior_year_period(prior, current),
+    }
+}
+
+/// The item whose period end is closest to `target`, within the match tolerance
+pub fn find_period_match<T>(
+    items: &[T],
+    target: NaiveDate,
+    period_end: impl Fn(&T) -> NaiveDate,
+) -> Option<&T> {
+    items
+        .iter()
+        .filter(|item| same_period(period_end(item), target))
+        .min_by_key(|item| (period_end(item) - target).num_days().abs())
+}
+
 /// Represents a fiscal period with consistent labeling
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct FiscalPeriod {
+    pub period_start_date: NaiveDate,
     pub period_end_date: NaiveDate,
     pub period_type: PeriodType,
     pub fiscal_year: i32,
@@ -12,6 +193,37 @@ pub struct FiscalPeriod {
     pub display_label: String,       // e.g., "FY2024", "Q3 2024"
 }
 
+impl FiscalPeriod {
+    /// Whether a statement dated `date` reports this period. The window is
+    /// shifted forward by the match tolerance so a statement dated a few days
+    /// after the nominal period end still lands here rather than in the next
+    /// period.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        let tolerance = Duration::days(PERIOD_MATCH_TOLERANCE_DAYS);
+        date >= self.period_start_date + tolerance && date <= self.period_end_date + tolerance
+    }
+}
+
+/// For each period, the index of the statement that reports it, or `None` for
+/// a gap. When several statements fall in one period the one closest to the
+/// period end wins.
+pub fn align_statements(
+    periods: &[FiscalPeriod],
+    statement_dates: &[NaiveDate],
+) -> Vec<Option<usize>> {
+    periods
+        .iter()
+        .map(|period| {
+            statement_dates
+                .iter()
+                .enumerate()
+                .filter(|(_, date)| period.contains(**date))
+                .min_by_key(|(_, date)| (**date - period.period_end_date).num_days().abs())
+                .map(|(i, _)| i)
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum PeriodType {
@@ -21,13 +233,21 @@ pub enum PeriodType {
 
 /// Generates consistent period windows for both metrics and documents
 pub struct PeriodWindowGenerator {
+    calendar: FiscalCalendar,
     fiscal_year_end_month: u32, // e.g., 3 for March, 12 for December
 }
 
 impl PeriodWindowGenerator {
     pub fn new(fiscal_year_end_month: u32) -> Self {
+        Self::with_calendar(FiscalCalendar::MonthEnd {
+            month: fiscal_year_end_month,
+        })
+    }
+
+    pub fn with_calendar(calendar: FiscalCalendar) -> Self {
         Self {
-            fiscal_year_end_month,
+            calendar,
+            fiscal_year_end_month: calendar.anchor_month(),
         }
     }
 
@@ -51,6 +271,48 @@ impl PeriodWindowGenerator {
         periods
     }
 
+    /// The period a statement dated `date` reports, using the same window as
+    /// [`FiscalPeriod::contains`]
+    pub fn period_containing(&self, date: NaiveDate, period_type: PeriodType) -> FiscalPeriod {
+        let shifted = date - Duration::days(PERIOD_MATCH_TOLERANCE_DAYS);
+        let (fiscal_year, quarter) = self.get_fiscal_quarter(shifted);
+        match period_type {
+            PeriodType::Annual => self.annual_period(fiscal_year),
+            PeriodType::Quarterly => self.quarterly_period(fiscal_year, quarter),
+        }
+    }
+
+    fn annual_period(&self, fiscal_year: i32) -> FiscalPeriod {
+        let mut period = FiscalPeriod {
+            period_start_date: self.get_fiscal_year_end(fiscal_year - 1) + Duration::days(1),
+            period_end_date: self.get_fiscal_year_end(fiscal_year),
+            period_type: PeriodType::Annual,
+            fiscal_year,
+            fiscal_quarter: None,
+            display_label: String::new(),
+        };
+        period.display_label = self.format_label(&period);
+        period
+    }
+
+    fn quarterly_period(&self, fiscal_year: i32, quarter: i32) -> FiscalPeriod {
+        let previous_end = if quarter == 1 {
+            self.get_quarter_end(fiscal_year - 1, 4)
+        } else {
+            self.get_quarter_end(fiscal_year, quarter - 1)
+        };
+        let mut period = FiscalPeriod {
+            period_start_date: previous_end + Duration::days(1),
+            period_end_date: self.get_quarter_end(fiscal_year, quarter),
+            period_type: PeriodType::Quarterly,
+            fiscal_year,
+            fiscal_quarter: Some(quarter),
+            display_label: String::new(),
+        };
+        period.display_label = self.format_label(&period);
+        period
+    }
+
     fn calculate_annual_period(&self, as_of_date: NaiveDate, offset: usize) -> FiscalPeriod {
         let mut year = as_of_date.year();
         let month = as_of_date.month();
@@ -62,18 +324,7 @@ impl PeriodWindowGenerator {
 
         let fiscal_year = year - (offset as i32);
 
-        // Calculate the end date of that fiscal year
-        let end_date = self.get_fiscal_year_end(fiscal_year);
-
-        let mut period = FiscalPeriod {
-            period_end_date: end_date,
-            period_type: PeriodType::Annual,
-            fiscal_year,
-            fiscal_quarter: None,
-            display_label: String::new(),
-        };
-        period.display_label = self.format_label(&period);
-        period
+        self.annual_period(fiscal_year)
     }
 
     fn calculate_quarterly_period(&self, as_of_date: NaiveDate, offset: usize) -> FiscalPeriod {
@@ -90,20 +341,13 @@ impl PeriodWindowGenerator {
             }
         }
 
-        let end_date = self.get_quarter_end(year, quarter);
-
-        let mut period = FiscalPeriod {
-            period_end_date: end_date,
-            period_type: PeriodType::Quarterly,
-            fiscal_year: year,
-            fiscal_quarter: Some(quarter),
-            display_label: String::new(),
-        };
-        period.display_label = self.format_label(&period);
-        period
+        self.quarterly_period(year, quarter)
     }
 
     fn get_fiscal_year_end(&self, fiscal_year: i32) -> NaiveDate {
+        if self.calendar.is_week_based() {
+            return self.calendar.fiscal_year_end(fiscal_year);
+        }
         let month = self.fiscal_year_end_month;
         let day = if month == 2 {
             if (fiscal_year % 4 == 0 && fiscal_year % 100 != 0) || (fiscal_year % 400 == 0) {
@@ -121,6 +365,9 @@ impl PeriodWindowGenerator {
     }
 
     fn get_fiscal_quarter(&self, date: NaiveDate) -> (i32, i32) {
+        if self.calendar.is_week_based() {
+            return self.get_week_based_quarter(date);
+        }
         let year = date.year();
         let month = date.month();
 
@@ -149,7 +396,35 @@ impl PeriodWindowGenerator {
         (f_year, f_quarter as i32)
     }
 
+    /// Fiscal quarter containing `date` on a 52/53-week calendar
+    fn get_week_based_quarter(&self, date: NaiveDate) -> (i32, i32) {
+        let mut f_year = date.year();
+        if date.month() > self.fiscal_year_end_month {
+            f_year += 1;
+        }
+        // The anchor-month guess is off by one near year ends that spill into
+        // the next month (e.g. a Dec year ending Jan 2)
+        while date > self.calendar.fiscal_year_end(f_year) {
+            f_year += 1;
+        }
+        while date <= self.calendar.fiscal_year_end(f_year - 1) {
+            f_year -= 1;
+        }
+        let quarter = (1..=4)
+            .find(|&q| date <= self.get_quarter_end(f_year, q))
+            .unwrap_or(4);
+        (f_year, quarter)
+    }
+
     fn get_quarter_end(&self, fiscal_year: i32, fiscal_quarter: i32) -> NaiveDate {
+        if self.calendar.is_week_based() {
+            // 13-week quarters; a 53rd week lands in Q4
+            if fiscal_quarter == 4 {
+                return self.calendar.fiscal_year_end(fiscal_year);
+            }
+            return self.calendar.fiscal_year_end(fiscal_year - 1)
+                + Duration::weeks(13 * fiscal_quarter as i64);
+        }
         // Let's redo it properly.
         let target_month =
             ((self.fiscal_year_end_month + (fiscal_quarter as u32 * 3) - 1) % 12) + 1;
@@ -195,3 +470,122 @@ impl PeriodWindowGenerator {
         date.month() == self.fiscal_year_end_month
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_fiscal_calendar_parse_round_trip() {
+        for spec in ["month_end_dec", "last_sat_sep", "nearest_sat_jan"] {
+            let calendar: FiscalCalendar = spec.parse().unwrap();
+            assert_eq!(calendar.to_string(), spec);
+        }
+        assert!("last_saturday_jan".parse::<FiscalCalendar>().is_err());
+        assert_eq!(
+            FiscalCalendar::for_company(Some("bogus"), Some(3)),
+            FiscalCalendar::MonthEnd { month: 3 }
+        );
+    }
+
+    #[test]
+    fn test_week_based_year_ends() {
+        let last_sat_sep: FiscalCalendar = "last_sat_sep".parse().unwrap();
+        assert_eq!(last_sat_sep.fiscal_year_end(2022), date(2022, 9, 24));
+        // 53-week year
+        assert_eq!(last_sat_sep.fiscal_year_end(2023), date(2023, 9, 30));
+
+        // Saturday nearest Jan 31 can fall in February
+        let nearest_sat_jan: FiscalCalendar = "nearest_sat_jan".parse().unwrap();
+        assert_eq!(nearest_sat_jan.fiscal_year_end(2024), date(2024, 2, 3));
+        assert_eq!(nearest_sat_jan.fiscal_year_end(2023), date(2023, 1, 28));
+    }
+
+    #[test]
+    fn test_week_based_quarters_keep_labels_across_53_week_year() {
+        let generator = PeriodWindowGenerator::with_calendar("last_sat_sep".parse().unwrap());
+        let periods = generator.generate_periods(5, PeriodType::Quarterly, date(2023, 10, 15));
+
+        let labels: Vec<&str> = periods.iter().map(|p| p.display_label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["Q1 2024", "FY2023", "Q3 2023", "Q2 2023", "Q1 2023"]
+        );
+        let ends: Vec<NaiveDate> = periods.iter().map(|p| p.period_end_date).collect();
+        assert_eq!(
+            ends,
+            vec![
+                date(2023, 12, 30),
+                date(2023, 9, 30),
+                date(2023, 6, 24),
+                date(2023, 3, 25),
+                date(2022, 12, 24),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_month_end_quarters_unchanged() {
+        let generator = PeriodWindowGenerator::new(12);
+        let periods = generator.generate_periods(2, PeriodType::Quarterly, date(2024, 5, 10));
+        assert_eq!(periods[0].period_end_date, date(2024, 6, 30));
+        assert_eq!(periods[0].display_label, "Q2 2024");
+        assert_eq!(periods[1].period_end_date, date(2024, 3, 31));
+    }
+
+    #[test]
+    fn test_align_statements_marks_gaps() {
+        let generator = PeriodWindowGenerator::new(12);
+        let mut periods = generator.generate_periods(4, PeriodType::Quarterly, date(2023, 12, 24));
+        periods.reverse();
+
+        // Q2 missing; Q4 reported a couple of days late
+        let dates = [date(2023, 3, 31), date(2023, 9, 30), date(2024, 1, 2)];
+        assert_eq!(
+            align_statements(&periods, &dates),
+            vec![Some(0), None, Some(1), Some(2)]
+        );
+        assert_eq!(periods[1].period_start_date, date(2023, 4, 1));
+        assert_eq!(
+            generator
+                .period_containing(date(2024, 1, 2), PeriodType::Quarterly)
+                .display_label,
+            "FY2023"
+        );
+    }
+
+    #[test]
+    fn test_period_matching_tolerance() {
+        let dates = [date(2023, 9, 24), date(2023, 9, 30), date(2023, 12, 31)];
+        assert_eq!(
+            find_period_match(&dates, date(2023, 9, 29), |d| *d),
+            Some(&date(2023, 9, 30))
+        );
+        assert_eq!(find_period_match(&dates, date(2023, 11, 15), |d| *d), None);
+
+        assert!(is_prior_year_period(date(2022, 9, 24), date(2023, 9, 30)));
+        assert!(is_prior_year_period(date(2022, 12, 31), date(2023, 12, 31)));
+        assert!(!is_prior_year_period(date(2023, 6, 30), date(2023, 12, 31)));
+
+        assert!(is_previous_period(
+            date(2023, 9, 30),
+            date(2023, 12, 31),
+            PeriodType::Quarterly
+        ));
+        // Q3 missing: Q2 isn't the quarter before Q4
+        assert!(!is_previous_period(
+            date(2023, 6, 30),
+            date(2023, 12, 31),
+            PeriodType::Quarterly
+        ));
+        assert!(is_previous_period(
+            date(2022, 12, 31),
+            date(2023, 12, 31),
+            PeriodType::Annual
+        ));
+    }
+}
@@ -11,6 +11,18 @@ pub trait MarketDataProvider: Send + Sync {
     async fn get_income_statement(&self, symbol: &str) -> Result<Vec<IncomeStatement>, AppError>;
     async fn get_balance_sheet(&self, symbol: &str) -> Result<Vec<BalanceSheet>, AppError>;
     async fn get_cash_flow(&self, symbol: &str) -> Result<Vec<CashFlowStatement>, AppError>;
+    async fn get_quarterly_income_statement(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<IncomeStatement>, AppError>;
+    async fn get_quarterly_balance_sheet(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<BalanceSheet>, AppError>;
+    async fn get_quarterly_cash_flow(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<CashFlowStatement>, AppError>;
     async fn get_daily_prices(
         &self,
         symbol: &str,
@@ -1,10 +1,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::HashMap;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::metrics::price_analytics::PRICE_ANALYTICS_METRICS;
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FilterCriteria {
@@ -17,6 +19,55 @@ pub struct FilterCriteria {
     pub momentum_6m_min: Option<f64>,
     pub has_verdict: Option<bool>,
     pub verdict_types: Option<Vec<String>>,
+    /// Bounds on the requesting user's saved formulas, applied to each
+    /// candidate's latest quarter after the SQL filters and before
+    /// [`SCREENER_RESULT_LIMIT`]
+    pub formula_filters: Option<Vec<FormulaFilter>>,
+    /// Bounds on each company's latest stored price analytics, one of
+    /// [`PRICE_ANALYTICS_METRICS`]
+    pub technical_filters: Option<Vec<TechnicalFilter>>,
+}
+
+impl FilterCriteria {
+    /// Reject technical filters on metrics the recalculation doesn't store
+    pub fn validate(&self) -> Result<(), AppError> {
+        for filter in self.technical_filters.iter().flatten() {
+            if !PRICE_ANALYTICS_METRICS.contains(&filter.metric.as_str()) {
+                return Err(AppError::ValidationError(format!(
+                    "Unknown technical metric '{}': expected one of {}",
+                    filter.metric,
+                    PRICE_ANALYTICS_METRICS.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FormulaFilter {
+    /// Name of a saved custom formula
+    pub name: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl FormulaFilter {
+    /// Whether a formula value passes; null values never match
+    pub fn matches(&self, value: Option<f64>) -> bool {
+        match value {
+            Some(v) => self.min.is_none_or(|min| v >= min) && self.max.is_none_or(|max| v <= max),
+            None => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TechnicalFilter {
+    /// Price analytics metric, e.g. "volatility_1y_pct"
+    pub metric: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
@@ -42,8 +93,15 @@ pub struct ScreenerResult {
     pub verdict: Option<String>,
     pub last_analyzed: Option<DateTime<Utc>>,
     pub guidance_summary: Option<String>,
+    /// Latest value of each formula in `FilterCriteria::formula_filters`
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub formula_values: HashMap<String, Option<f64>>,
 }
 
+/// Most companies a screen returns, largest market cap first
+pub const SCREENER_RESULT_LIMIT: usize = 100;
+
 pub struct ScreenerService {
     pool: PgPool,
 }
@@ -53,8 +111,26 @@ impl ScreenerService {
         Self { pool }
     }
 
+    /// The largest [`SCREENER_RESULT_LIMIT`] companies passing the SQL filters
     pub async fn execute(&self, criteria: FilterCriteria) -> Result<Vec<ScreenerResult>, AppError> {
-        let mut query_builder = Self::build_query(&criteria);
+        self.fetch(&criteria, Some(SCREENER_RESULT_LIMIT)).await
+    }
+
+    /// Every company passing the SQL filters, largest market cap first, for
+    /// callers that filter further before applying [`SCREENER_RESULT_LIMIT`]
+    pub async fn candidates(
+        &self,
+        criteria: &FilterCriteria,
+    ) -> Result<Vec<ScreenerResult>, AppError> {
+        self.fetch(criteria, None).await
+    }
+
+    async fn fetch(
+        &self,
+        criteria: &FilterCriteria,
+        limit: Option<usize>,
+    ) -> Result<Vec<ScreenerResult>, AppError> {
+        let mut query_builder = Self::build_query(criteria, limit);
         let query = query_builder.build_query_as::<ScreenerResult>();
 
         let mut results = query
@@ -70,7 +146,10 @@ impl ScreenerService {
         Ok(results)
     }
 
-    fn build_query(criteria: &FilterCriteria) -> QueryBuilder<'static, Postgres> {
+    fn build_query(
+        criteria: &FilterCriteria,
+        limit: Option<usize>,
+    ) -> QueryBuilder<'static, Postgres> {
         let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
             r#"
             SELECT 
@@ -127,6 +206,24 @@ impl ScreenerService {
 
         // Momentum filters ignored for now as columns are NULL
 
+        // Price analytics are stored against the latest statement period when
+        // they're computed; the newest row is the current value. Companies
+        // without one don't match.
+        for filter in criteria.technical_filters.iter().flatten() {
+            for (op, bound) in [(" >= ", filter.min), (" <= ", filter.max)] {
+                let Some(bound) = bound else {
+                    continue;
+                };
+                query_builder.push(
+                    " AND (SELECT dm.metric_value::float8 FROM derived_metrics dm WHERE dm.company_id = c.id AND dm.metric_name = ",
+                );
+                query_builder.push_bind(filter.metric.clone());
+                query_builder.push(" ORDER BY dm.period_end_date DESC LIMIT 1)");
+                query_builder.push(op);
+                query_builder.push_bind(bound);
+            }
+        }
+
         if let Some(has_verdict) = criteria.has_verdict {
             if has_verdict {
                 query_builder.push(" AND v.final_verdict IS NOT NULL");
@@ -143,7 +240,11 @@ impl ScreenerService {
             }
         }
 
-        query_builder.push(" ORDER BY c.market_cap DESC NULLS LAST LIMIT 100");
+        query_builder.push(" ORDER BY c.market_cap DESC NULLS LAST");
+        if let Some(limit) = limit {
+            query_builder.push(" LIMIT ");
+            query_builder.push_bind(limit as i64);
+        }
         query_builder
     }
 }
@@ -174,13 +275,73 @@ mod tests {
             momentum_6m_min: None,
             has_verdict: None,
             verdict_types: None,
+            formula_filters: None,
+            technical_filters: None,
         };
 
-        let query_builder = ScreenerService::build_query(&criteria);
+        let query_builder = ScreenerService::build_query(&criteria, Some(SCREENER_RESULT_LIMIT));
         let sql = query_builder.sql();
 
         assert!(sql.contains("AND c.exchange = ANY("));
         assert!(sql.contains("AND c.market_cap >= "));
         assert!(sql.contains("ORDER BY c.market_cap DESC"));
+        assert!(sql.contains("LIMIT"));
+
+        // Formula screens take every candidate and limit after filtering
+        let unlimited = ScreenerService::build_query(&criteria, None);
+        assert!(!unlimited.sql().contains("LIMIT"));
+    }
+
+    #[test]
+    fn test_technical_filters_read_stored_price_analytics() {
+        let mut criteria = FilterCriteria {
+            exchanges: None,
+            industries: None,
+            market_cap_min: None,
+            market_cap_max: None,
+            momentum_1m_min: None,
+            momentum_3m_min: None,
+            momentum_6m_min: None,
+            has_verdict: None,
+            verdict_types: None,
+            formula_filters: None,
+            technical_filters: Some(vec![TechnicalFilter {
+                metric: "volatility_1y_pct".to_string(),
+                min: None,
+                max: Some(30.0),
+            }]),
+        };
+        assert!(criteria.validate().is_ok());
+        let sql = ScreenerService::build_query(&criteria, None)
+            .sql()
+            .to_string();
+        assert!(sql.contains("FROM derived_metrics dm"));
+        assert!(sql.contains(" <= "));
+        assert!(!sql.contains(") >= "));
+
+        criteria.technical_filters = Some(vec![TechnicalFilter {
+            metric: "pe_ratio_ttm".to_string(),
+            min: Some(0.0),
+            max: None,
+        }]);
+        assert!(matches!(
+            criteria.validate(),
+            Err(AppError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_formula_filter_bounds() {
+        let filter = FormulaFilter {
+            name: "fcf_to_equity".to_string(),
+            min: Some(0.1),
+            max: Some(0.5),
+        };
+
+        assert!(filter.matches(Some(0.1)));
+        assert!(filter.matches(Some(0.5)));
+        assert!(!filter.matches(Some(0.05)));
+        assert!(!filter.matches(Some(0.6)));
+        assert!(!filter.matches(None));
     }
 }
@@ -0,0 +1,475 @@
+//! Discounted cash flow valuation.
+//!
+//! FCF is projected as revenue × FCF margin, with revenue compounding along a
+//! per-year growth path. All rates are percentages (9.0 = 9%), matching the
+//! rest of the metrics code. Paths shorter than the projection horizon repeat
+//! their last value.
+
+use crate::domain::{CashFlowStatement, IncomeStatement};
+use crate::error::AppError;
+use crate::metrics::calculator::MetricsCalculator;
+use bigdecimal::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const DEFAULT_PROJECTION_YEARS: usize = 5;
+pub const MAX_PROJECTION_YEARS: usize = 30;
+pub const DEFAULT_WACC: f64 = 9.0;
+pub const DEFAULT_TERMINAL_GROWTH: f64 = 2.5;
+
+/// Step between sensitivity grid rows / columns, in percentage points
+const WACC_STEP: f64 = 1.0;
+const TERMINAL_GROWTH_STEP: f64 = 0.5;
+
+/// Bounds for the reverse-DCF growth search, in percent per year
+const IMPLIED_GROWTH_MIN: f64 = -50.0;
+const IMPLIED_GROWTH_MAX: f64 = 100.0;
+
+/// Company figures the projection starts from
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DcfBase {
+    /// Latest annual revenue
+    pub revenue: f64,
+    /// Subtracted from enterprise value; negative when the company holds net cash
+    pub net_debt: f64,
+    pub shares_outstanding: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DcfAssumptions {
+    pub projection_years: usize,
+    /// Revenue growth per projected year, %
+    pub revenue_growth: Vec<f64>,
+    /// FCF as % of revenue per projected year
+    pub fcf_margin: Vec<f64>,
+    pub wacc: f64,
+    pub terminal_growth: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DcfProjectionYear {
+    pub year: usize,
+    pub revenue: f64,
+    pub revenue_growth: f64,
+    pub fcf_margin: f64,
+    pub fcf: f64,
+    pub discount_factor: f64,
+    pub present_value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DcfResult {
+    pub projections: Vec<DcfProjectionYear>,
+    pub pv_of_projected_fcf: f64,
+    pub terminal_value: f64,
+    pub pv_of_terminal_value: f64,
+    pub enterprise_value: f64,
+    pub equity_value: f64,
+    pub intrinsic_value_per_share: f64,
+}
+
+/// Intrinsic value per share across WACC (rows) × terminal growth (columns).
+/// Cells where WACC does not exceed terminal growth are null.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SensitivityGrid {
+    pub wacc_values: Vec<f64>,
+    pub terminal_growth_values: Vec<f64>,
+    pub values: Vec<Vec<Option<f64>>>,
+}
+
+/// Growth and margin paths derived from a company's annual history
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DerivedPaths {
+    /// Starting growth: 5y revenue CAGR, falling back to 3y then 1y
+    pub starting_growth: f64,
+    /// Mean FCF margin over the last three years
+    pub fcf_margin: f64,
+}
+
+/// Partially specified assumptions, as saved in an assumption set or passed
+/// on a request. Unset fields fall through to the next layer and finally to
+/// values derived from the company's history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DcfAssumptionOverrides {
+    pub projection_years: Option<usize>,
+    pub revenue_growth: Option<Vec<f64>>,
+    pub fcf_margin: Option<Vec<f64>>,
+    pub wacc: Option<f64>,
+    pub terminal_growth: Option<f64>,
+}
+
+impl DcfAssumptionOverrides {
+    /// Fill unset fields from `fallback`
+    pub fn or(self, fallback: DcfAssumptionOverrides) -> Self {
+        Self {
+            projection_years: self.projection_years.or(fallback.projection_years),
+            revenue_growth: self.revenue_growth.or(fallback.revenue_growth),
+            fcf_margin: self.fcf_margin.or(fallback.fcf_margin),
+            wacc: self.wacc.or(fallback.wacc),
+            terminal_growth: self.terminal_growth.or(fallback.terminal_growth),
+        }
+    }
+
+    /// Check the fields that are set; used before saving an assumption set
+    pub fn validate(&self) -> Result<(), AppError> {
+        let resolved = self.clone().or(DcfAssumptionOverrides {
+            projection_years: Some(DEFAULT_PROJECTION_YEARS),
+            revenue_growth: Some(vec![0.0]),
+            fcf_margin: Some(vec![0.0]),
+            wacc: Some(self.terminal_growth.unwrap_or(DEFAULT_TERMINAL_GROWTH) + 1.0),
+            terminal_growth: Some(self.wacc.unwrap_or(DEFAULT_WACC) - 1.0),
+        });
+        resolved.resolve(None).map(|_| ())
+    }
+
+    /// Complete the assumptions: WACC, terminal growth and horizon fall back to
+    /// the defaults; growth fades from the derived starting rate to terminal
+    /// growth; margin holds at the derived historical average.
+    pub fn resolve(self, derived: Option<&DerivedPaths>) -> Result<DcfAssumptions, AppError> {
+        let projection_years = self.projection_years.unwrap_or(DEFAULT_PROJECTION_YEARS);
+        let wacc = self.wacc.unwrap_or(DEFAULT_WACC);
+        let terminal_growth = self.terminal_growth.unwrap_or(DEFAULT_TERMINAL_GROWTH);
+
+        let revenue_growth = match (self.revenue_growth, derived) {
+            (Some(path), _) => path,
+            (None, Some(d)) => fade_path(d.starting_growth, terminal_growth, projection_years),
+            (None, None) => {
+                return Err(AppError::ValidationError(
+                    "revenue_growth is required: not enough revenue history to derive it"
+                        .to_string(),
+                ))
+            }
+        };
+        let fcf_margin = match (self.fcf_margin, derived) {
+            (Some(path), _) => path,
+            (None, Some(d)) => vec![d.fcf_margin],
+            (None, None) => {
+                return Err(AppError::ValidationError(
+                    "fcf_margin is required: not enough cash flow history to derive it".to_string(),
+                ))
+            }
+        };
+
+        let assumptions = DcfAssumptions {
+            projection_years,
+            revenue_growth,
+            fcf_margin,
+            wacc,
+            terminal_growth,
+        };
+        assumptions.validate()?;
+        Ok(assumptions)
+    }
+}
+
+impl DcfAssumptions {
+    fn value_at(path: &[f64], year: usize) -> f64 {
+        path.get(year)
+            .or_else(|| path.last())
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.projection_years == 0 || self.projection_years > MAX_PROJECTION_YEARS {
+            return Err(AppError::ValidationError(format!(
+                "projection_years must be between 1 and {}",
+                MAX_PROJECTION_YEARS
+            )));
+        }
+        if self.revenue_growth.is_empty() || self.fcf_margin.is_empty() {
+            return Err(AppError::ValidationError(
+                "revenue_growth and fcf_margin paths must not be empty".to_string(),
+            ));
+        }
+        if self.wacc <= self.terminal_growth {
+            return Err(AppError::ValidationError(
+                "wacc must exceed terminal_growth".to_string(),
+            ));
+        }
+        if self.wacc <= -100.0 {
+            return Err(AppError::ValidationError(
+                "wacc must be greater than -100%".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Growth path that fades linearly from `start` in year 1 to `terminal` in
+/// the final projected year
+pub fn fade_path(start: f64, terminal: f64, years: usize) -> Vec<f64> {
+    if years <= 1 {
+        return vec![start];
+    }
+    (0..years)
+        .map(|i| start + (terminal - start) * i as f64 / (years - 1) as f64)
+        .collect()
+}
+
+/// Derive starting growth and FCF margin from annual statements sorted ascending,
+/// with `annual_cash_flows` aligned by index. `None` if there is too little history.
+pub fn derive_paths(
+    annual_incomes: &[IncomeStatement],
+    annual_cash_flows: &[Option<CashFlowStatement>],
+) -> Option<DerivedPaths> {
+    let latest = annual_incomes.len().checked_sub(1)?;
+
+    let starting_growth = [5, 3, 1].iter().find_map(|&years| {
+        MetricsCalculator::calculate_multi_year_growth(annual_incomes, annual_cash_flows, years)
+            .revenue_cagr
+            .get(latest)
+            .and_then(|v| v.value)
+    })?;
+
+    let margins: Vec<f64> = annual_incomes
+        .iter()
+        .zip(annual_cash_flows)
+        .rev()
+        .take(3)
+        .filter_map(|(inc, cf)| {
+            let rev = inc.revenue.as_ref().and_then(|v| v.to_f64())?;
+            let fcf = cf
+                .as_ref()
+                .and_then(|c| c.free_cash_flow.as_ref())
+                .and_then(|v| v.to_f64())?;
+            MetricsCalculator::calculate_margin(fcf, rev)
+        })
+        .collect();
+    if margins.is_empty() {
+        return None;
+    }
+    let fcf_margin = margins.iter().sum::<f64>() / margins.len() as f64;
+
+    Some(DerivedPaths {
+        starting_growth,
+        fcf_margin,
+    })
+}
+
+pub fn run_dcf(base: &DcfBase, assumptions: &DcfAssumptions) -> Result<DcfResult, AppError> {
+    assumptions.validate()?;
+    if base.shares_outstanding <= 0.0 {
+        return Err(AppError::ValidationError(
+            "shares outstanding must be positive".to_string(),
+        ));
+    }
+
+    let wacc = assumptions.wacc / 100.0;
+    let terminal_growth = assumptions.terminal_growth / 100.0;
+
+    let mut projections = Vec::with_capacity(assumptions.projection_years);
+    let mut revenue = base.revenue;
+    let mut discount_factor = 1.0;
+    for year in 0..assumptions.projection_years {
+        let growth = DcfAssumptions::value_at(&assumptions.revenue_growth, year);
+        let margin = DcfAssumptions::value_at(&assumptions.fcf_margin, year);
+        revenue *= 1.0 + growth / 100.0;
+        discount_factor /= 1.0 + wacc;
+        let fcf = revenue * margin / 100.0;
+        projections.push(DcfProjectionYear {
+            year: year + 1,
+            revenue,
+            revenue_growth: growth,
+            fcf_margin: margin,
+            fcf,
+            discount_factor,
+            present_value: fcf * discount_factor,
+        });
+    }
+
+    let pv_of_projected_fcf: f64 = projections.iter().map(|p| p.present_value).sum();
+    let final_fcf = projections.last().map(|p| p.fcf).unwrap_or(0.0);
+    // Gordon growth on the year after the horizon
+    let terminal_value = final_fcf * (1.0 + terminal_growth) / (wacc - terminal_growth);
+    let pv_of_terminal_value = terminal_value * discount_factor;
+    let enterprise_value = pv_of_projected_fcf + pv_of_terminal_value;
+    let equity_value = enterprise_value - base.net_debt;
+
+    Ok(DcfResult {
+        projections,
+        pv_of_projected_fcf,
+        terminal_value,
+        pv_of_terminal_value,
+        enterprise_value,
+        equity_value,
+        intrinsic_value_per_share: equity_value / base.shares_outstanding,
+    })
+}
+
+/// Five-by-five grid centred on the assumed WACC and terminal growth
+pub fn sensitivity_grid(base: &DcfBase, assumptions: &DcfAssumptions) -> SensitivityGrid {
+    let offsets = [-2.0, -1.0, 0.0, 1.0, 2.0];
+    let wacc_values: Vec<f64> = offsets
+        .iter()
+        .map(|o| assumptions.wacc + o * WACC_STEP)
+        .collect();
+    let terminal_growth_values: Vec<f64> = offsets
+        .iter()
+        .map(|o| assumptions.terminal_growth + o * TERMINAL_GROWTH_STEP)
+        .collect();
+
+    let values = wacc_values
+        .iter()
+        .map(|&wacc| {
+            terminal_growth_values
+                .iter()
+                .map(|&terminal_growth| {
+                    let scenario = DcfAssumptions {
+                        wacc,
+                        terminal_growth,
+                        ..assumptions.clone()
+                    };
+                    run_dcf(base, &scenario)
+                        .ok()
+                        .map(|r| r.intrinsic_value_per_share)
+                })
+                .collect()
+        })
+        .collect();
+
+    SensitivityGrid {
+        wacc_values,
+        terminal_growth_values,
+        values,
+    }
+}
+
+/// Solve for the constant annual revenue growth that makes intrinsic value per
+/// share equal `price`, holding margins, WACC and terminal growth fixed.
+/// `None` if no growth rate in [-50%, 100%] reaches the price.
+pub fn reverse_dcf(base: &DcfBase, assumptions: &DcfAssumptions, price: f64) -> Option<f64> {
+    let value_at = |growth: f64| {
+        let scenario = DcfAssumptions {
+            revenue_growth: vec![growth],
+            ..assumptions.clone()
+        };
+        run_dcf(base, &scenario)
+            .ok()
+            .map(|r| r.intrinsic_value_per_share - price)
+    };
+
+    let (mut lo, mut hi) = (IMPLIED_GROWTH_MIN, IMPLIED_GROWTH_MAX);
+    let (f_lo, f_hi) = (value_at(lo)?, value_at(hi)?);
+    // Value is monotonic in growth only when margins are positive; with
+    // negative FCF the search still works, just in the opposite direction.
+    if f_lo.signum() == f_hi.signum() {
+        return None;
+    }
+    let increasing = f_hi > f_lo;
+
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        let f_mid = value_at(mid)?;
+        if f_mid.abs() < 1e-6 {
+            return Some(mid);
+        }
+        if (f_mid < 0.0) == increasing {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some((lo + hi) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> DcfBase {
+        DcfBase {
+            revenue: 1000.0,
+            net_debt: 100.0,
+            shares_outstanding: 10.0,
+        }
+    }
+
+    fn assumptions() -> DcfAssumptions {
+        DcfAssumptions {
+            projection_years: 2,
+            revenue_growth: vec![10.0],
+            fcf_margin: vec![20.0],
+            wacc: 10.0,
+            terminal_growth: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_run_dcf() {
+        let result = run_dcf(&base(), &assumptions()).unwrap();
+
+        // Year 1: revenue 1100, FCF 220, PV 200; year 2: revenue 1210, FCF 242, PV 200
+        assert_eq!(result.projections.len(), 2);
+        assert!((result.projections[0].present_value - 200.0).abs() < 1e-9);
+        assert!((result.projections[1].present_value - 200.0).abs() < 1e-9);
+        // TV = 242 * 1.02 / 0.08 = 3085.5, discounted by 1.21
+        assert!((result.terminal_value - 3085.5).abs() < 1e-9);
+        let expected_equity = 400.0 + 3085.5 / 1.21 - 100.0;
+        assert!((result.intrinsic_value_per_share - expected_equity / 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_run_dcf_rejects_wacc_below_terminal_growth() {
+        let bad = DcfAssumptions {
+            wacc: 2.0,
+            ..assumptions()
+        };
+        assert!(run_dcf(&base(), &bad).is_err());
+    }
+
+    #[test]
+    fn test_sensitivity_grid_is_centred_on_assumptions() {
+        let grid = sensitivity_grid(&base(), &assumptions());
+        let centre = run_dcf(&base(), &assumptions())
+            .unwrap()
+            .intrinsic_value_per_share;
+
+        assert_eq!(grid.wacc_values, vec![8.0, 9.0, 10.0, 11.0, 12.0]);
+        assert_eq!(grid.terminal_growth_values, vec![1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert_eq!(grid.values[2][2], Some(centre));
+        // Higher WACC, lower value
+        assert!(grid.values[4][2].unwrap() < centre);
+    }
+
+    #[test]
+    fn test_reverse_dcf_recovers_growth() {
+        let price = run_dcf(&base(), &assumptions())
+            .unwrap()
+            .intrinsic_value_per_share;
+        let implied = reverse_dcf(&base(), &assumptions(), price).unwrap();
+        assert!((implied - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_overrides_resolve_against_derived_paths() {
+        let derived = DerivedPaths {
+            starting_growth: 12.0,
+            fcf_margin: 18.0,
+        };
+        let request = DcfAssumptionOverrides {
+            wacc: Some(8.0),
+            ..Default::default()
+        };
+        let saved = DcfAssumptionOverrides {
+            wacc: Some(11.0),
+            projection_years: Some(3),
+            ..Default::default()
+        };
+
+        let resolved = request.or(saved).resolve(Some(&derived)).unwrap();
+        assert_eq!(resolved.wacc, 8.0);
+        assert_eq!(resolved.projection_years, 3);
+        assert_eq!(resolved.revenue_growth, vec![12.0, 7.25, 2.5]);
+        assert_eq!(resolved.fcf_margin, vec![18.0]);
+
+        assert!(DcfAssumptionOverrides::default().resolve(None).is_err());
+    }
+
+    #[test]
+    fn test_fade_path() {
+        assert_eq!(fade_path(10.0, 2.0, 5), vec![10.0, 8.0, 6.0, 4.0, 2.0]);
+        assert_eq!(fade_path(10.0, 2.0, 1), vec![10.0]);
+    }
+}
@@ -29,6 +29,24 @@ impl MarketDataProvider for AlphaVantageClient {
     async fn get_cash_flow(&self, _symbol: &str) -> Result<Vec<CashFlowStatement>, AppError> {
         unimplemented!()
     }
+    async fn get_quarterly_income_statement(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<IncomeStatement>, AppError> {
+        unimplemented!()
+    }
+    async fn get_quarterly_balance_sheet(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<BalanceSheet>, AppError> {
+        unimplemented!()
+    }
+    async fn get_quarterly_cash_flow(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<CashFlowStatement>, AppError> {
+        unimplemented!()
+    }
     async fn get_daily_prices(
         &self,
         _symbol: &str,
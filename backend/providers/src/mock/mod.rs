@@ -80,18 +80,39 @@ impl MockMarketDataProvider {
 #[serde(rename_all = "camelCase")]
 struct MockIncomeStatementResponse {
     annual_reports: Vec<serde_json::Value>,
+    #[serde(default)]
+    quarterly_reports: Vec<serde_json::Value>,
 }
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct MockBalanceSheetResponse {
     annual_reports: Vec<serde_json::Value>,
+    #[serde(default)]
+    quarterly_reports: Vec<serde_json::Value>,
 }
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct MockCashFlowResponse {
     annual_reports: Vec<serde_json::Value>,
+    #[serde(default)]
+    quarterly_reports: Vec<serde_json::Value>,
+}
+
+/// Maps camelCase reports onto a domain type through its helper struct
+fn convert_reports<H, T>(reports: Vec<serde_json::Value>) -> Result<Vec<T>, AppError>
+where
+    H: serde::de::DeserializeOwned + Into<T>,
+{
+    reports
+        .into_iter()
+        .map(|report| {
+            serde_json::from_value::<H>(report)
+                .map(Into::into)
+                .map_err(|e| AppError::InternalError(e.to_string()))
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -110,6 +131,8 @@ impl MarketDataProvider for MockMarketDataProvider {
                 "MSFT" => "Microsoft Corporation".to_string(),
                 _ => format!("Mock Company {}", symbol),
             };
+            // IBM's SEC identifier doesn't belong to anyone else
+            overview.cik = None;
         }
 
         Ok(overview)
@@ -157,6 +180,35 @@ impl MarketDataProvider for MockMarketDataProvider {
         Ok(sheets)
     }
 
+    async fn get_quarterly_income_statement(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<IncomeStatement>, AppError> {
+        self.simulate_delay().await;
+        let response: MockIncomeStatementResponse =
+            self.read_json("inome-statement-output.json").await?;
+        convert_reports::<IncomeStatementHelper, _>(response.quarterly_reports)
+    }
+
+    async fn get_quarterly_balance_sheet(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<BalanceSheet>, AppError> {
+        self.simulate_delay().await;
+        let response: MockBalanceSheetResponse =
+            self.read_json("balance-sheet-output.json").await?;
+        convert_reports::<BalanceSheetHelper, _>(response.quarterly_reports)
+    }
+
+    async fn get_quarterly_cash_flow(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<CashFlowStatement>, AppError> {
+        self.simulate_delay().await;
+        let response: MockCashFlowResponse = self.read_json("cash-flow-output.json").await?;
+        convert_reports::<CashFlowHelper, _>(response.quarterly_reports)
+    }
+
     async fn get_cash_flow(&self, _symbol: &str) -> Result<Vec<CashFlowStatement>, AppError> {
         self.simulate_delay().await;
         let response: MockCashFlowResponse = self.read_json("cash-flow-output.json").await?;
@@ -213,12 +265,19 @@ impl MarketDataProvider for MockMarketDataProvider {
                 .parse()
                 .unwrap_or(0.0);
 
+            let adjusted_close = values["5. adjusted close"]
+                .as_str()
+                .and_then(|v| v.parse().ok());
+            let volume = values["6. volume"].as_str().and_then(|v| v.parse().ok());
+
             prices.push(DailyPrice {
                 date,
                 open,
                 high,
                 low,
                 close,
+                adjusted_close,
+                volume,
             });
         }
 
@@ -291,11 +350,16 @@ struct MockCompanyOverviewResponse {
     symbol: String,
     name: String,
     description: Option<String>,
+    #[serde(rename = "CIK")]
+    cik: Option<String>,
     exchange: Option<String>,
     currency: Option<String>,
     country: Option<String>,
     sector: Option<String>,
     industry: Option<String>,
+    address: Option<String>,
+    fiscal_year_end: Option<String>,
+    latest_quarter: Option<String>,
     market_capitalization: Option<String>,
     #[serde(rename = "EBITDA")]
     ebitda: Option<String>,
@@ -362,11 +426,17 @@ impl From<MockCompanyOverviewResponse> for CompanyOverview {
             symbol: item.symbol,
             name: item.name,
             description: item.description,
+            cik: item.cik,
             exchange: item.exchange,
             currency: item.currency,
             country: item.country,
             sector: item.sector,
             industry: item.industry,
+            address: item.address,
+            fiscal_year_end: item.fiscal_year_end,
+            latest_quarter: item
+                .latest_quarter
+                .and_then(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok()),
             market_capitalization: item.market_capitalization.and_then(|s| s.parse().ok()),
             ebitda: item.ebitda.and_then(|s| s.parse().ok()),
             pe_ratio: item.pe_ratio.and_then(|s| s.parse().ok()),
@@ -415,40 +485,56 @@ impl From<MockCompanyOverviewResponse> for CompanyOverview {
     }
 }
 
+/// Statement amounts are strings, with "None" where the filing has no value
+fn decimal(value: Option<String>) -> Option<BigDecimal> {
+    value.and_then(|s| BigDecimal::from_str(&s).ok())
+}
+
+fn fiscal_date(value: &str) -> chrono::NaiveDate {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap_or_default()
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct IncomeStatementHelper {
     fiscal_date_ending: String,
     total_revenue: Option<String>,
+    cost_of_revenue: Option<String>,
     gross_profit: Option<String>,
+    operating_expenses: Option<String>,
     operating_income: Option<String>,
+    interest_income: Option<String>,
+    interest_expense: Option<String>,
+    income_before_tax: Option<String>,
+    income_tax_expense: Option<String>,
     net_income: Option<String>,
-    // eps is not in income statement reports typically? Wait, it is if we check file.
-    // The file has "netIncome", no "eps" in reports?
-    // Let's check inome-statement-output.json again...
-    // It has "totalRevenue", "grossProfit", "operatingIncome", "netIncome". No EPS.
-    // EPS is usually in Earnings. But domain::IncomeStatement has eps.
+    depreciation_and_amortization: Option<String>,
+    ebit: Option<String>,
+    ebitda: Option<String>,
+    // EPS and share counts come from the earnings and shares endpoints, not
+    // the income statement reports
 }
 
 impl From<IncomeStatementHelper> for IncomeStatement {
     fn from(item: IncomeStatementHelper) -> Self {
         IncomeStatement {
-            period_end_date: chrono::NaiveDate::parse_from_str(
-                &item.fiscal_date_ending,
-                "%Y-%m-%d",
-            )
-            .unwrap_or_default(),
-            revenue: item
-                .total_revenue
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            gross_profit: item
-                .gross_profit
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            operating_income: item
-                .operating_income
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            net_income: item.net_income.and_then(|s| BigDecimal::from_str(&s).ok()),
-            eps: None, // Not in mock data
+            period_end_date: fiscal_date(&item.fiscal_date_ending),
+            revenue: decimal(item.total_revenue),
+            cost_of_revenue: decimal(item.cost_of_revenue),
+            gross_profit: decimal(item.gross_profit),
+            operating_expenses: decimal(item.operating_expenses),
+            operating_income: decimal(item.operating_income),
+            interest_income: decimal(item.interest_income),
+            interest_expense: decimal(item.interest_expense),
+            income_before_tax: decimal(item.income_before_tax),
+            income_tax_expense: decimal(item.income_tax_expense),
+            net_income: decimal(item.net_income),
+            depreciation_amortization: decimal(item.depreciation_and_amortization),
+            ebit: decimal(item.ebit),
+            ebitda: decimal(item.ebitda),
+            eps: None,
+            diluted_eps: None,
+            shares_outstanding: None,
         }
     }
 }
@@ -458,45 +544,53 @@ impl From<IncomeStatementHelper> for IncomeStatement {
 struct BalanceSheetHelper {
     fiscal_date_ending: String,
     total_assets: Option<String>,
-    total_liabilities: Option<String>,
-    total_shareholder_equity: Option<String>,
+    total_current_assets: Option<String>,
     cash_and_cash_equivalents_at_carrying_value: Option<String>,
     short_term_investments: Option<String>,
+    inventory: Option<String>,
+    current_net_receivables: Option<String>,
+    total_non_current_assets: Option<String>,
+    property_plant_equipment: Option<String>,
+    goodwill: Option<String>,
+    intangible_assets_excluding_goodwill: Option<String>,
+    total_liabilities: Option<String>,
+    total_current_liabilities: Option<String>,
+    current_accounts_payable: Option<String>,
     short_term_debt: Option<String>,
+    total_non_current_liabilities: Option<String>,
     long_term_debt: Option<String>,
-    // net_debt not explicitly in file usually?
+    total_shareholder_equity: Option<String>,
+    retained_earnings: Option<String>,
+    common_stock: Option<String>,
     common_stock_shares_outstanding: Option<String>,
 }
 
 impl From<BalanceSheetHelper> for BalanceSheet {
     fn from(item: BalanceSheetHelper) -> Self {
         BalanceSheet {
-            period_end_date: chrono::NaiveDate::parse_from_str(
-                &item.fiscal_date_ending,
-                "%Y-%m-%d",
-            )
-            .unwrap_or_default(),
-            total_assets: item
-                .total_assets
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            total_liabilities: item
-                .total_liabilities
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            total_equity: item
-                .total_shareholder_equity
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            cash_and_equivalents: item
-                .cash_and_cash_equivalents_at_carrying_value
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            short_term_investments: item
-                .short_term_investments
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            short_term_debt: item
-                .short_term_debt
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            long_term_debt: item
-                .long_term_debt
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
+            period_end_date: fiscal_date(&item.fiscal_date_ending),
+            total_assets: decimal(item.total_assets),
+            current_assets: decimal(item.total_current_assets),
+            cash_and_equivalents: decimal(item.cash_and_cash_equivalents_at_carrying_value),
+            short_term_investments: decimal(item.short_term_investments),
+            inventory: decimal(item.inventory),
+            accounts_receivable: decimal(item.current_net_receivables),
+            non_current_assets: decimal(item.total_non_current_assets),
+            property_plant_equipment: decimal(item.property_plant_equipment),
+            goodwill: decimal(item.goodwill),
+            // Goodwill has its own field
+            intangible_assets: decimal(item.intangible_assets_excluding_goodwill),
+            total_liabilities: decimal(item.total_liabilities),
+            current_liabilities: decimal(item.total_current_liabilities),
+            accounts_payable: decimal(item.current_accounts_payable),
+            short_term_debt: decimal(item.short_term_debt),
+            non_current_liabilities: decimal(item.total_non_current_liabilities),
+            long_term_debt: decimal(item.long_term_debt),
+            total_equity: decimal(item.total_shareholder_equity),
+            retained_earnings: decimal(item.retained_earnings),
+            common_stock: decimal(item.common_stock),
+            // Derived by the database on insert
+            total_debt: None,
             net_debt: None,
             common_stock_shares_outstanding: item
                 .common_stock_shares_outstanding
@@ -510,26 +604,37 @@ impl From<BalanceSheetHelper> for BalanceSheet {
 struct CashFlowHelper {
     fiscal_date_ending: String,
     operating_cashflow: Option<String>,
+    net_income: Option<String>,
+    depreciation_depletion_and_amortization: Option<String>,
+    change_in_receivables: Option<String>,
+    change_in_inventory: Option<String>,
+    cashflow_from_investment: Option<String>,
     capital_expenditures: Option<String>,
-    // free_cash_flow usually calculated or field? "freeCashFlow"?
-    // I'll check file content conceptually or just assume minimal
+    cashflow_from_financing: Option<String>,
+    dividend_payout: Option<String>,
+    payments_for_repurchase_of_common_stock: Option<String>,
 }
 
 impl From<CashFlowHelper> for CashFlowStatement {
     fn from(item: CashFlowHelper) -> Self {
         CashFlowStatement {
-            period_end_date: chrono::NaiveDate::parse_from_str(
-                &item.fiscal_date_ending,
-                "%Y-%m-%d",
-            )
-            .unwrap_or_default(),
-            operating_cash_flow: item
-                .operating_cashflow
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            capital_expenditures: item
-                .capital_expenditures
-                .and_then(|s| BigDecimal::from_str(&s).ok()),
-            free_cash_flow: None, // Calculate if needed: operating - capex
+            period_end_date: fiscal_date(&item.fiscal_date_ending),
+            operating_cash_flow: decimal(item.operating_cashflow),
+            net_income: decimal(item.net_income),
+            depreciation_depletion: decimal(item.depreciation_depletion_and_amortization),
+            change_in_receivables: decimal(item.change_in_receivables),
+            change_in_inventory: decimal(item.change_in_inventory),
+            // Not broken out in the reports
+            change_in_payables: None,
+            investing_cash_flow: decimal(item.cashflow_from_investment),
+            capital_expenditures: decimal(item.capital_expenditures),
+            investments: None,
+            financing_cash_flow: decimal(item.cashflow_from_financing),
+            dividend_payout: decimal(item.dividend_payout),
+            stock_repurchase: decimal(item.payments_for_repurchase_of_common_stock),
+            debt_repayment: None,
+            // Derived by the database on insert
+            free_cash_flow: None,
         }
     }
 }
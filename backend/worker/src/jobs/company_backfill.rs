@@ -0,0 +1,149 @@
+use crate::jobs::fundamentals_refresh::{ingest_quarterly_statements, ingest_statements};
+use crate::jobs::price_partitions::ensure_price_partitions;
+use crate::jobs::Job;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Datelike;
+use db::models::background_job::COMPANY_BACKFILL_JOB;
+use db::models::{BackgroundJob, Company};
+use db::repositories::{BackgroundJobRepository, CompanyRepository, DailyPriceInsert};
+use domain::domain::OutputSize;
+use domain::periods::FiscalCalendar;
+use domain::ports::market_data::MarketDataProvider;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+
+/// Ingests the full history of companies added through the API. Each run
+/// drains the queued `company_backfill` jobs, recording per-step progress on
+/// the job row as it goes.
+pub struct CompanyBackfillJob {
+    db: PgPool,
+    provider: Arc<dyn MarketDataProvider>,
+}
+
+impl CompanyBackfillJob {
+    pub fn new(db: PgPool, provider: Arc<dyn MarketDataProvider>) -> Self {
+        Self { db, provider }
+    }
+
+    async fn backfill(&self, jobs: &BackgroundJobRepository, job: &BackgroundJob) -> Result<()> {
+        let company_id = job
+            .company_id
+            .context("company_backfill job has no company")?;
+        let company = CompanyRepository::new(self.db.clone())
+            .find_by_id(company_id)
+            .await?
+            .with_context(|| format!("Company {} not found", company_id))?;
+
+        let mut steps = job.steps();
+        let mut failed = Vec::new();
+        for i in 0..steps.len() {
+            steps[i].status = "running".to_string();
+            jobs.update_progress(job.id, &steps).await?;
+
+            let outcome = match steps[i].step.as_str() {
+                "statements" => self.backfill_statements(&company).await,
+                "prices" => self.backfill_prices(&company).await,
+                // Nothing implements DocumentProvider yet, so the step can't
+                // succeed; fail it rather than report a backfill that never ran
+                "documents" => Err(anyhow::anyhow!(
+                    "No document provider configured; filings and transcripts were not backfilled"
+                )),
+                other => Err(anyhow::anyhow!("Unknown backfill step '{}'", other)),
+            };
+            let step = &mut steps[i];
+            match outcome {
+                Ok(records) => {
+                    step.status = "completed".to_string();
+                    step.records = records;
+                }
+                Err(e) => {
+                    error!(
+                        "Backfill step {} failed for {}: {:?}",
+                        step.step, company.symbol, e
+                    );
+                    step.status = "failed".to_string();
+                    step.message = Some(e.to_string());
+                    failed.push(step.step.clone());
+                }
+            }
+            jobs.update_progress(job.id, &steps).await?;
+        }
+
+        if !failed.is_empty() {
+            anyhow::bail!("Failed steps: {}", failed.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Every annual and quarterly statement the provider reports; returns
+    /// rows written
+    async fn backfill_statements(&self, company: &Company) -> Result<i32> {
+        let calendar = FiscalCalendar::for_company(
+            company.fiscal_calendar.as_deref(),
+            company.fiscal_year_end_month,
+        );
+        let annual = ingest_statements(
+            &self.db,
+            self.provider.as_ref(),
+            company.id,
+            &company.symbol,
+            calendar,
+        )
+        .await?;
+        let quarterly = ingest_quarterly_statements(
+            &self.db,
+            self.provider.as_ref(),
+            company.id,
+            &company.symbol,
+            calendar,
+        )
+        .await?;
+
+        Ok((annual + quarterly) as i32)
+    }
+
+    /// The full daily price history; returns rows written
+    async fn backfill_prices(&self, company: &Company) -> Result<i32> {
+        let prices = self
+            .provider
+            .get_daily_prices(&company.symbol, OutputSize::Full)
+            .await?;
+        let rows: Vec<DailyPriceInsert> = prices
+            .into_iter()
+            .map(|p| DailyPriceInsert::from_domain(company.id, p))
+            .collect();
+        ensure_price_partitions(&self.db, rows.iter().map(|r| r.price_date.year())).await?;
+        let records = CompanyRepository::new(self.db.clone())
+            .upsert_daily_prices(&rows)
+            .await?;
+
+        Ok(records as i32)
+    }
+}
+
+#[async_trait]
+impl Job for CompanyBackfillJob {
+    fn name(&self) -> &str {
+        COMPANY_BACKFILL_JOB
+    }
+
+    #[instrument(skip(self, _pool))]
+    async fn run(&self, _pool: &PgPool) -> Result<()> {
+        let jobs = BackgroundJobRepository::new(self.db.clone());
+
+        while let Some(job) = jobs.claim_next(COMPANY_BACKFILL_JOB).await? {
+            info!(job_id = %job.id, company_id = ?job.company_id, "Starting company backfill");
+            match self.backfill(&jobs, &job).await {
+                Ok(()) => jobs.complete(job.id).await?,
+                Err(e) => {
+                    error!(job_id = %job.id, "Company backfill failed: {:?}", e);
+                    jobs.fail(job.id, &e.to_string()).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use crate::jobs::Job;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{NaiveDate, Utc};
+use db::repositories::CompanyRepository;
 use domain::ports::market_data::MarketDataProvider;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
@@ -65,6 +66,8 @@ impl Job for EarningsPollingJob {
         let mut updated = 0;
         let mut errors = 0;
 
+        let repo = CompanyRepository::new(self.db.clone());
+
         // 2c. Fetch earnings calendar
         info!("Fetching earnings calendar...");
         let calendar_result = self.provider.get_earnings_calendar().await;
@@ -86,6 +89,15 @@ impl Job for EarningsPollingJob {
                         for company in companies {
                             processed += 1;
 
+                            // Keep every report date for charting, not just the latest quarter
+                            for event in events.iter().filter(|e| e.symbol == company.symbol) {
+                                if let Err(e) = repo.upsert_earnings_event(company.id, event).await
+                                {
+                                    error!(company_id = %company.id, error = %e, "Failed to store earnings event");
+                                    errors += 1;
+                                }
+                            }
+
                             // 3. Handle rate limiting (simple delay per active company processing to share load if we did fetches here)
                             // Since we fetched calendar already, this loop is fast. No delay needed.
 
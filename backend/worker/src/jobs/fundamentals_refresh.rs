@@ -0,0 +1,286 @@
+use crate::jobs::metrics_recalc::recalculate_company;
+use crate::jobs::Job;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use db::repositories::{
+    BalanceSheetInsert, CashFlowStatementInsert, CompanyRepository, IncomeStatementInsert,
+};
+use domain::domain::{BalanceSheet, CashFlowStatement, IncomeStatement};
+use domain::periods::{FiscalCalendar, PeriodType, PeriodWindowGenerator};
+use domain::ports::market_data::MarketDataProvider;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+/// Statements are refetched at least this often even without a new quarter,
+/// to pick up late corrections
+const STALE_AFTER_DAYS: i32 = 7;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JobResult {
+    processed: usize,
+    updated: usize,
+    errors: usize,
+}
+
+#[derive(FromRow)]
+struct CompanyRow {
+    id: Uuid,
+    symbol: String,
+    fiscal_year_end_month: Option<i32>,
+    fiscal_calendar: Option<String>,
+}
+
+pub struct FundamentalsRefreshJob {
+    db: PgPool,
+    provider: Arc<dyn MarketDataProvider>,
+}
+
+impl FundamentalsRefreshJob {
+    pub fn new(db: PgPool, provider: Arc<dyn MarketDataProvider>) -> Self {
+        Self { db, provider }
+    }
+
+    /// Refetch, store and recalculate one company
+    async fn refresh_company(&self, company: &CompanyRow) -> Result<usize> {
+        let calendar = FiscalCalendar::for_company(
+            company.fiscal_calendar.as_deref(),
+            company.fiscal_year_end_month,
+        );
+        let written = ingest_statements(
+            &self.db,
+            self.provider.as_ref(),
+            company.id,
+            &company.symbol,
+            calendar,
+        )
+        .await?;
+        recalculate_company(&self.db, company.id).await?;
+
+        Ok(written)
+    }
+}
+
+#[async_trait]
+impl Job for FundamentalsRefreshJob {
+    fn name(&self) -> &str {
+        "fundamentals_refresh"
+    }
+
+    #[instrument(skip(self, _pool))]
+    async fn run(&self, _pool: &PgPool) -> Result<()> {
+        info!("Starting fundamentals_refresh job");
+        let start_time = Utc::now();
+        let job_id = Uuid::new_v4();
+
+        // Track job run
+        let _ = sqlx::query(
+            "INSERT INTO job_runs (id, job_name, status, started_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(job_id)
+        .bind(self.name())
+        .bind("running")
+        .bind(start_time)
+        .execute(&self.db)
+        .await
+        .map_err(|e| warn!("Failed to create job_run record: {}", e));
+
+        let mut processed = 0;
+        let mut updated = 0;
+        let mut errors = 0;
+
+        // Never fetched, stale, or a quarter reported since the last fetch
+        let companies_result = sqlx::query_as::<_, CompanyRow>(
+            r#"
+            SELECT id, symbol, fiscal_year_end_month, fiscal_calendar
+            FROM companies
+            WHERE is_active = true
+              AND (
+                fundamentals_refreshed_at IS NULL
+                OR fundamentals_refreshed_at < NOW() - make_interval(days => $1)
+                OR latest_quarter > COALESCE(fundamentals_quarter, '-infinity')
+              )
+            "#,
+        )
+        .bind(STALE_AFTER_DAYS)
+        .fetch_all(&self.db)
+        .await;
+
+        match companies_result {
+            Ok(companies) => {
+                for company in companies {
+                    processed += 1;
+                    match self.refresh_company(&company).await {
+                        Ok(written) => {
+                            info!("Stored {} statements for {}", written, company.symbol);
+                            updated += 1;
+                        }
+                        Err(e) => {
+                            error!(
+                                "Failed to refresh fundamentals for {}: {:?}",
+                                company.symbol, e
+                            );
+                            errors += 1;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to fetch companies: {}", e);
+                errors += 1;
+            }
+        }
+
+        // Finish job run
+        let end_time = Utc::now();
+        let result = JobResult {
+            processed,
+            updated,
+            errors,
+        };
+        let result_json = serde_json::to_value(&result).unwrap_or(serde_json::Value::Null);
+        let status = if errors > 0 && updated == 0 {
+            "failed"
+        } else {
+            "completed"
+        };
+
+        let _ = sqlx::query(
+            "UPDATE job_runs SET status = $1, ended_at = $2, result = $3 WHERE id = $4",
+        )
+        .bind(status)
+        .bind(end_time)
+        .bind(result_json)
+        .bind(job_id)
+        .execute(&self.db)
+        .await;
+
+        info!("Fundamentals refresh job finished: {:?}", result);
+
+        Ok(())
+    }
+}
+
+/// Fetch a company's income statements, balance sheets and cash flows, store
+/// them in one transaction and mark the company refreshed. Returns rows
+/// written.
+pub(crate) async fn ingest_statements(
+    pool: &PgPool,
+    provider: &dyn MarketDataProvider,
+    company_id: Uuid,
+    symbol: &str,
+    calendar: FiscalCalendar,
+) -> Result<usize> {
+    let incomes = provider.get_income_statement(symbol).await?;
+    let balances = provider.get_balance_sheet(symbol).await?;
+    let cash_flows = provider.get_cash_flow(symbol).await?;
+
+    let written = store_statements(
+        pool,
+        company_id,
+        calendar,
+        PeriodType::Annual,
+        incomes,
+        balances,
+        cash_flows,
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE companies SET fundamentals_refreshed_at = NOW(), fundamentals_quarter = latest_quarter WHERE id = $1",
+    )
+    .bind(company_id)
+    .execute(pool)
+    .await?;
+
+    Ok(written)
+}
+
+/// Fetch and store a company's quarterly statements. Returns rows written.
+pub(crate) async fn ingest_quarterly_statements(
+    pool: &PgPool,
+    provider: &dyn MarketDataProvider,
+    company_id: Uuid,
+    symbol: &str,
+    calendar: FiscalCalendar,
+) -> Result<usize> {
+    let incomes = provider.get_quarterly_income_statement(symbol).await?;
+    let balances = provider.get_quarterly_balance_sheet(symbol).await?;
+    let cash_flows = provider.get_quarterly_cash_flow(symbol).await?;
+
+    store_statements(
+        pool,
+        company_id,
+        calendar,
+        PeriodType::Quarterly,
+        incomes,
+        balances,
+        cash_flows,
+    )
+    .await
+}
+
+/// Store one period type's statements, labelled with the fiscal period each
+/// falls in
+async fn store_statements(
+    pool: &PgPool,
+    company_id: Uuid,
+    calendar: FiscalCalendar,
+    period_type: PeriodType,
+    mut incomes: Vec<IncomeStatement>,
+    balances: Vec<BalanceSheet>,
+    cash_flows: Vec<CashFlowStatement>,
+) -> Result<usize> {
+    // Shares are stored with the income statement but the provider reports
+    // them on the balance sheet
+    for income in incomes
+        .iter_mut()
+        .filter(|i| i.shares_outstanding.is_none())
+    {
+        income.shares_outstanding = balances
+            .iter()
+            .find(|b| b.period_end_date == income.period_end_date)
+            .and_then(|b| b.common_stock_shares_outstanding);
+    }
+
+    let periods = PeriodWindowGenerator::with_calendar(calendar);
+    let period_label = match period_type {
+        PeriodType::Annual => "annual",
+        PeriodType::Quarterly => "quarterly",
+    };
+    let fiscal_period = |date| {
+        let period = periods.period_containing(date, period_type);
+        (Some(period.fiscal_year), period.fiscal_quarter)
+    };
+
+    let written = CompanyRepository::new(pool.clone())
+        .upsert_statements(
+            incomes
+                .into_iter()
+                .map(|s| {
+                    let (year, quarter) = fiscal_period(s.period_end_date);
+                    IncomeStatementInsert::from_domain(company_id, period_label, year, quarter, s)
+                })
+                .collect(),
+            balances
+                .into_iter()
+                .map(|s| {
+                    let (year, quarter) = fiscal_period(s.period_end_date);
+                    BalanceSheetInsert::from_domain(company_id, period_label, year, quarter, s)
+                })
+                .collect(),
+            cash_flows
+                .into_iter()
+                .map(|s| {
+                    let (year, quarter) = fiscal_period(s.period_end_date);
+                    CashFlowStatementInsert::from_domain(company_id, period_label, year, quarter, s)
+                })
+                .collect(),
+        )
+        .await?;
+
+    Ok(written)
+}
@@ -2,21 +2,57 @@ use crate::jobs::Job;
 use anyhow::Result;
 use async_trait::async_trait;
 use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
-use chrono::{Datelike, NaiveDate};
+use chrono::NaiveDate;
 use db::models::financials::{
     BalanceSheet as DbBalance, CashFlowStatement as DbCashFlow, IncomeStatement as DbIncome,
 };
-use db::models::DailyPrice as DbPrice;
+use db::repositories::CompanyRepository;
 use domain::domain::{
     BalanceSheet as DomainBalance, CashFlowStatement as DomainCashFlow, DailyPrice as DomainPrice,
     IncomeStatement as DomainIncome,
 };
-use domain::metrics::calculator::MetricsCalculator;
+use domain::markets::calendar::{ExchangeCalendar, TradingCalendars};
+use domain::metrics::price_analytics::{
+    benchmark_symbol, price_series, PriceAnalytics, LOOKBACK_DAYS, YEAR_DAYS,
+};
+use domain::metrics::registry::{MetricInputs, METRIC_REGISTRY};
+use domain::periods::calendarize::{calendar_periods, Calendarizer};
+use domain::periods::{find_period_match, is_prior_year_period, FiscalCalendar, PeriodType};
 use sqlx::PgPool;
 use std::collections::HashMap;
 use tracing::{error, info};
 
-pub struct MetricsRecalculationJob;
+/// Recomputes derived metrics for companies whose statements or prices
+/// changed since their last recalculation, as marked in
+/// `metrics_recalc_queue` by the ingestion triggers
+#[derive(Debug, Default)]
+pub struct MetricsRecalculationJob {
+    /// Recompute every active company, marked or not
+    full_rebuild: bool,
+}
+
+impl MetricsRecalculationJob {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A run recomputing every active company, e.g. after a metric definition
+    /// changed
+    pub fn full_rebuild() -> Self {
+        Self { full_rebuild: true }
+    }
+}
+
+/// A company to recalculate, with the queue revision it was read at
+struct RecalcCompany {
+    id: uuid::Uuid,
+    symbol: String,
+    exchange: String,
+    currency: Option<String>,
+    fiscal_year_end_month: Option<i32>,
+    fiscal_calendar: Option<String>,
+    revision: Option<i64>,
+}
 
 #[async_trait]
 impl Job for MetricsRecalculationJob {
@@ -25,22 +61,34 @@ impl Job for MetricsRecalculationJob {
     }
 
     async fn run(&self, pool: &PgPool) -> Result<()> {
-        info!("Starting metrics recalculation job");
+        info!(
+            "Starting metrics recalculation job (full rebuild: {})",
+            self.full_rebuild
+        );
 
-        // Fetch active companies
-        let companies: Vec<_> =
-            sqlx::query!("SELECT id, symbol, currency FROM companies WHERE is_active = true")
-                .fetch_all(pool)
-                .await?;
+        // Fetch active companies with changed data, or all of them for a full
+        // rebuild
+        let companies: Vec<RecalcCompany> = sqlx::query_as!(
+            RecalcCompany,
+            r#"
+            SELECT c.id, c.symbol, c.exchange, c.currency, c.fiscal_year_end_month,
+                   c.fiscal_calendar, q.revision AS "revision?"
+            FROM companies c
+            LEFT JOIN metrics_recalc_queue q ON q.company_id = c.id
+            WHERE c.is_active = true AND (q.company_id IS NOT NULL OR $1)
+            "#,
+            self.full_rebuild
+        )
+        .fetch_all(pool)
+        .await?;
 
-        info!("Found {} active companies to process", companies.len());
+        info!("Found {} companies to process", companies.len());
 
         let mut success_count = 0;
         let mut fail_count = 0;
 
         for company in companies {
-            let currency = company.currency.unwrap_or_else(|| "USD".to_string());
-            match process_company(pool, company.id, &company.symbol, &currency).await {
+            match recalculate(pool, &company).await {
                 Ok(_) => {
                     success_count += 1;
                 }
@@ -63,16 +111,71 @@ impl Job for MetricsRecalculationJob {
     }
 }
 
+/// Recalculate a single company's metrics, e.g. right after its statements
+/// were refreshed
+pub async fn recalculate_company(pool: &PgPool, company_id: uuid::Uuid) -> Result<()> {
+    let company = sqlx::query_as!(
+        RecalcCompany,
+        r#"
+        SELECT c.id, c.symbol, c.exchange, c.currency, c.fiscal_year_end_month,
+               c.fiscal_calendar, q.revision AS "revision?"
+        FROM companies c
+        LEFT JOIN metrics_recalc_queue q ON q.company_id = c.id
+        WHERE c.id = $1
+        "#,
+        company_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    recalculate(pool, &company).await
+}
+
+/// Process a company, then take it off the queue unless it was marked again
+/// in the meantime
+async fn recalculate(pool: &PgPool, company: &RecalcCompany) -> Result<()> {
+    let currency = company.currency.as_deref().unwrap_or("USD");
+    let calendar = FiscalCalendar::for_company(
+        company.fiscal_calendar.as_deref(),
+        company.fiscal_year_end_month,
+    );
+    let sessions = TradingCalendars::global().for_exchange(&company.exchange);
+    process_company(
+        pool,
+        company.id,
+        &company.symbol,
+        currency,
+        calendar,
+        sessions,
+    )
+    .await?;
+
+    if let Some(revision) = company.revision {
+        sqlx::query!(
+            "DELETE FROM metrics_recalc_queue WHERE company_id = $1 AND revision = $2",
+            company.id,
+            revision
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
 async fn process_company(
     pool: &PgPool,
     company_id: uuid::Uuid,
     _symbol: &str,
     currency: &str,
+    calendar: FiscalCalendar,
+    sessions: &ExchangeCalendar,
 ) -> Result<()> {
-    // 1. Fetch all financial statements sorted by period_end_date ASC
+    // 1. Fetch the current version of every financial statement, sorted by
+    // period_end_date ASC
     let incomes: Vec<DbIncome> = sqlx::query_as!(
         DbIncome,
-        "SELECT * FROM income_statements WHERE company_id = $1 ORDER BY period_end_date ASC",
+        "SELECT * FROM income_statements WHERE company_id = $1 AND known_to IS NULL ORDER BY period_end_date ASC",
         company_id
     )
     .fetch_all(pool)
@@ -84,7 +187,7 @@ async fn process_company(
 
     let balances: Vec<DbBalance> = sqlx::query_as!(
         DbBalance,
-        "SELECT * FROM balance_sheets WHERE company_id = $1 ORDER BY period_end_date ASC",
+        "SELECT * FROM balance_sheets WHERE company_id = $1 AND known_to IS NULL ORDER BY period_end_date ASC",
         company_id
     )
     .fetch_all(pool)
@@ -92,22 +195,23 @@ async fn process_company(
 
     let cash_flows: Vec<DbCashFlow> = sqlx::query_as!(
         DbCashFlow,
-        "SELECT * FROM cash_flow_statements WHERE company_id = $1 ORDER BY period_end_date ASC",
+        "SELECT * FROM cash_flow_statements WHERE company_id = $1 AND known_to IS NULL ORDER BY period_end_date ASC",
         company_id
     )
     .fetch_all(pool)
     .await?;
 
-    // Create lookups for balance and cashflow
-    let bal_map: HashMap<(NaiveDate, String), DbBalance> = balances
-        .into_iter()
-        .map(|b| ((b.period_end_date, b.period_type.clone()), b))
-        .collect();
+    // Create lookups for balance and cashflow, grouped by period type. Dates are
+    // matched within a tolerance since 52/53-week filers' statements can drift.
+    let mut bal_map: HashMap<String, Vec<DbBalance>> = HashMap::new();
+    for b in balances {
+        bal_map.entry(b.period_type.clone()).or_default().push(b);
+    }
 
-    let cf_map: HashMap<(NaiveDate, String), DbCashFlow> = cash_flows
-        .into_iter()
-        .map(|c| ((c.period_end_date, c.period_type.clone()), c))
-        .collect();
+    let mut cf_map: HashMap<String, Vec<DbCashFlow>> = HashMap::new();
+    for c in cash_flows {
+        cf_map.entry(c.period_type.clone()).or_default().push(c);
+    }
 
     // 2. Align data
     let mut domain_incomes = Vec::new();
@@ -116,32 +220,18 @@ async fn process_company(
     let mut dates = Vec::new();
 
     for income in &incomes {
-        let key = (income.period_end_date, income.period_type.clone());
         dates.push(income.period_end_date);
 
         // Convert Income
-        domain_incomes.push(DomainIncome {
-            period_end_date: income.period_end_date,
-            revenue: income.total_revenue.clone(),
-            gross_profit: income.gross_profit.clone(),
-            operating_income: income.operating_income.clone(),
-            net_income: income.net_income.clone(),
-            eps: income.basic_eps.clone(),
-        });
+        domain_incomes.push(DomainIncome::from(income));
 
         // Align Balance
-        let mut domain_bal = bal_map.get(&key).map(|b| DomainBalance {
-            period_end_date: b.period_end_date,
-            total_assets: b.total_assets.clone(),
-            total_liabilities: b.total_liabilities.clone(),
-            total_equity: b.total_equity.clone(),
-            cash_and_equivalents: b.cash_and_equivalents.clone(),
-            short_term_investments: b.short_term_investments.clone(),
-            short_term_debt: b.short_term_debt.clone(),
-            long_term_debt: b.long_term_debt.clone(),
-            net_debt: b.net_debt.clone(),
-            common_stock_shares_outstanding: None,
-        });
+        let mut domain_bal = bal_map
+            .get(&income.period_type)
+            .and_then(|group| {
+                find_period_match(group, income.period_end_date, |b| b.period_end_date)
+            })
+            .map(DomainBalance::from);
 
         // Try to patch shares from income if available
         if let Some(ref mut db) = domain_bal {
@@ -152,135 +242,192 @@ async fn process_company(
         aligned_balances.push(domain_bal);
 
         // Align CashFlow
-        let cf_opt = cf_map.get(&key).map(|c| DomainCashFlow {
-            period_end_date: c.period_end_date,
-            operating_cash_flow: c.operating_cash_flow.clone(),
-            capital_expenditures: c.capital_expenditures.clone(),
-            free_cash_flow: c.free_cash_flow.clone(),
-        });
+        let cf_opt = cf_map
+            .get(&income.period_type)
+            .and_then(|group| {
+                find_period_match(group, income.period_end_date, |c| c.period_end_date)
+            })
+            .map(DomainCashFlow::from);
         aligned_cash_flows.push(cf_opt);
     }
 
-    // 3. Prepare prior year incomes
+    // 3. Prepare prior year incomes. Stub periods from a fiscal year-end
+    // change have no like-for-like prior year and aren't one for others.
     let mut prior_year_incomes = Vec::new();
     for income in &incomes {
         let target_date = income.period_end_date;
         let prior = incomes.iter().find(|i| {
             i.period_type == income.period_type
-                && i.period_end_date < target_date
-                && (target_date.year() - i.period_end_date.year() == 1)
-                && (target_date.month() as i32 - i.period_end_date.month() as i32).abs() <= 1
+                && !income.is_transitional
+                && !i.is_transitional
+                && is_prior_year_period(i.period_end_date, target_date)
         });
 
-        let prior_domain = prior.map(|p| DomainIncome {
-            period_end_date: p.period_end_date,
-            revenue: p.total_revenue.clone(),
-            gross_profit: p.gross_profit.clone(),
-            operating_income: p.operating_income.clone(),
-            net_income: p.net_income.clone(),
-            eps: p.basic_eps.clone(),
-        });
-        prior_year_incomes.push(prior_domain);
+        prior_year_incomes.push(prior.map(DomainIncome::from));
     }
 
-    // 4. Fetch Prices
-    let mut aligned_prices = Vec::new();
-    for date in &dates {
-        let price: Option<DbPrice> = sqlx::query_as!(
-            DbPrice,
-            "SELECT * FROM daily_prices WHERE company_id = $1 AND price_date <= $2 ORDER BY price_date DESC LIMIT 1",
-            company_id,
-            *date
-        )
-        .fetch_optional(pool)
-        .await?;
+    // 4. Fetch Prices, as of the exchange's last session on or before each
+    // period end, in one query. A session without a close gets no price
+    // rather than an older one.
+    let repo = CompanyRepository::new(pool.clone());
+    let sessions_by_period: Vec<NaiveDate> = dates
+        .iter()
+        .map(|date| sessions.session_on_or_before(*date))
+        .collect();
+    let prices_as_of: HashMap<NaiveDate, DomainPrice> = repo
+        .get_prices_as_of(company_id, &sessions_by_period)
+        .await?
+        .iter()
+        .filter(|p| !p.is_stale())
+        .map(|p| (p.as_of, DomainPrice::from(&p.price)))
+        .collect();
+    let aligned_prices: Vec<Option<DomainPrice>> = sessions_by_period
+        .iter()
+        .map(|session| prices_as_of.get(session).cloned())
+        .collect();
 
-        let domain_price = price.map(|p| DomainPrice {
-            date: p.price_date,
-            open: p.open.and_then(|v: BigDecimal| v.to_f64()).unwrap_or(0.0),
-            high: p.high.and_then(|v: BigDecimal| v.to_f64()).unwrap_or(0.0),
-            low: p.low.and_then(|v: BigDecimal| v.to_f64()).unwrap_or(0.0),
-            close: p.close.and_then(|v: BigDecimal| v.to_f64()).unwrap_or(0.0),
-        });
-        aligned_prices.push(domain_price);
+    // 5. Evaluate the metric registry once per period type, so sequential
+    // metrics (QoQ, margin expansion) only compare like periods. Multi-year
+    // metrics always read the annual history.
+    let annual_indices: Vec<usize> = incomes
+        .iter()
+        .enumerate()
+        .filter(|(_, inc)| inc.period_type == "annual" && !inc.is_transitional)
+        .map(|(i, _)| i)
+        .collect();
+    let annual_incomes = select(&domain_incomes, &annual_indices);
+    let annual_cash_flows = select(&aligned_cash_flows, &annual_indices);
+
+    let mut period_types: Vec<String> = incomes.iter().map(|i| i.period_type.clone()).collect();
+    period_types.sort();
+    period_types.dedup();
+
+    for period_type in &period_types {
+        let domain_period_type = match period_type.as_str() {
+            "quarterly" => PeriodType::Quarterly,
+            _ => PeriodType::Annual,
+        };
+        let indices: Vec<usize> = incomes
+            .iter()
+            .enumerate()
+            .filter(|(_, inc)| &inc.period_type == period_type)
+            .map(|(i, _)| i)
+            .collect();
+        let period_incomes = select(&domain_incomes, &indices);
+        let period_prior_incomes = select(&prior_year_incomes, &indices);
+        let period_balances = select(&aligned_balances, &indices);
+        let period_cash_flows = select(&aligned_cash_flows, &indices);
+        let period_prices = select(&aligned_prices, &indices);
+
+        let inputs = MetricInputs {
+            incomes: &period_incomes,
+            prior_year_incomes: &period_prior_incomes,
+            balances: &period_balances,
+            cash_flows: &period_cash_flows,
+            prices: &period_prices,
+            annual_incomes: &annual_incomes,
+            annual_cash_flows: &annual_cash_flows,
+            currency,
+            period_type: domain_period_type,
+        };
+
+        // 6. Save Metrics
+        for metric in METRIC_REGISTRY {
+            let Some(stored_name) = metric.stored_name else {
+                continue;
+            };
+            let values = (metric.compute)(&inputs);
+            for (income, value) in period_incomes.iter().zip(values) {
+                let date = income.period_end_date;
+                match value.value {
+                    Some(val) => {
+                        insert_metric(pool, company_id, date, period_type, stored_name, val).await?
+                    }
+                    None => clear_metric(pool, company_id, date, period_type, stored_name).await?,
+                }
+            }
+        }
     }
 
-    // 5. Run Calculations
-
-    // Revenue Metrics
-    let (_, yoy_growths, qoq_growths) = MetricsCalculator::calculate_revenue_metrics(
-        &domain_incomes,
-        &prior_year_incomes,
-        currency,
-    );
-
-    // Margins
-    let (gross_margins, op_margins, net_margins) =
-        MetricsCalculator::calculate_margin_metrics(&domain_incomes);
-
-    // Acceleration
-    let accels = MetricsCalculator::calculate_revenue_acceleration(&yoy_growths);
-
-    // Cash Metrics
-    let (ocf_ratios, fcf_ratios) =
-        MetricsCalculator::calculate_cash_metrics(&domain_incomes, &aligned_cash_flows);
-
-    // Leverage
-    let (rev_net_debt, _shares) =
-        MetricsCalculator::calculate_leverage_metrics(&domain_incomes, &aligned_balances);
-
-    // Valuation (Point-in-time)
-    let val_metrics =
-        MetricsCalculator::calculate_valuation_metrics(&domain_incomes, &aligned_prices);
-
-    // 6. Save Metrics
-    for i in 0..domain_incomes.len() {
-        let period_end = domain_incomes[i].period_end_date;
-        let period_type = incomes[i].period_type.clone();
-
-        let mut metrics_to_save: HashMap<String, Option<f64>> = HashMap::new();
-
-        metrics_to_save.insert("yoy_revenue_growth_pct".to_string(), yoy_growths[i].value);
-        metrics_to_save.insert("qoq_revenue_growth_pct".to_string(), qoq_growths[i].value);
-        metrics_to_save.insert("growth_acceleration".to_string(), accels[i].value);
-
-        metrics_to_save.insert("gross_margin_pct".to_string(), gross_margins[i].value);
-        metrics_to_save.insert("operating_margin_pct".to_string(), op_margins[i].value);
-        metrics_to_save.insert("net_margin_pct".to_string(), net_margins[i].value);
-
-        metrics_to_save.insert("ocf_revenue_pct".to_string(), ocf_ratios[i].value);
-        metrics_to_save.insert("fcf_revenue_pct".to_string(), fcf_ratios[i].value);
-
-        metrics_to_save.insert(
-            "revenue_minus_net_debt_pct".to_string(),
-            rev_net_debt[i].value,
-        );
-
-        metrics_to_save.insert(
-            "pe_ratio_historical".to_string(),
-            val_metrics.pe_ratios[i].value,
-        );
-
-        for (name, val_opt) in metrics_to_save {
-            if let Some(val) = val_opt {
-                insert_metric(pool, company_id, period_end, &period_type, &name, val).await?;
-            }
+    // 7. Calendarized metrics, so peers on other fiscal calendars can be
+    // ranked against the same calendar period
+    for period_type in &period_types {
+        let domain_period_type = match period_type.as_str() {
+            "quarterly" => PeriodType::Quarterly,
+            "annual" => PeriodType::Annual,
+            _ => continue,
+        };
+        if calendar.is_calendar_aligned(domain_period_type) {
+            continue;
         }
+        let indices: Vec<usize> = incomes
+            .iter()
+            .enumerate()
+            .filter(|(_, inc)| &inc.period_type == period_type && !inc.is_transitional)
+            .map(|(i, _)| i)
+            .collect();
+        // Stub periods can't be apportioned like a whole fiscal period
+        let period_incomes = select(&domain_incomes, &indices);
+        let period_balances: Vec<DomainBalance> = bal_map
+            .get(period_type)
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|b| DomainBalance {
+                        common_stock_shares_outstanding: find_period_match(
+                            &incomes,
+                            b.period_end_date,
+                            |i| i.period_end_date,
+                        )
+                        .and_then(|i| i.shares_outstanding),
+                        ..b.into()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let period_cash_flows: Vec<DomainCashFlow> = cf_map
+            .get(period_type)
+            .map(|group| {
+                group
+                    .iter()
+                    .filter(|c| !c.is_transitional)
+                    .map(DomainCashFlow::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let fiscal_annual_cash_flows: Vec<DomainCashFlow> = cf_map
+            .get("annual")
+            .map(|group| {
+                group
+                    .iter()
+                    .filter(|c| !c.is_transitional)
+                    .map(DomainCashFlow::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        store_calendarized_metrics(
+            pool,
+            company_id,
+            calendar,
+            domain_period_type,
+            &period_incomes,
+            &period_balances,
+            &period_cash_flows,
+            &annual_incomes,
+            &fiscal_annual_cash_flows,
+            currency,
+        )
+        .await?;
     }
 
-    // 7. Latest Price Metrics
+    // 8. Latest Price Metrics
     if let Some(latest_idx) = domain_incomes.len().checked_sub(1) {
         let latest_income = &domain_incomes[latest_idx];
         let latest_period_end = latest_income.period_end_date;
         let latest_period_type = incomes[latest_idx].period_type.clone();
 
-        let latest_price_row: Option<DbPrice> = sqlx::query_as!(
-            DbPrice,
-            "SELECT * FROM daily_prices WHERE company_id = $1 ORDER BY price_date DESC LIMIT 1",
-            company_id
-        )
-        .fetch_optional(pool)
-        .await?;
+        let latest_price_row = repo.get_latest_price(company_id).await?;
 
         if let Some(lp) = latest_price_row {
             let close = lp.close.and_then(|v: BigDecimal| v.to_f64()).unwrap_or(0.0);
@@ -305,6 +452,15 @@ async fn process_company(
                 }
             }
 
+            // One window of closes serves momentum and the technical analytics
+            let start = lp.price_date - chrono::Duration::days(LOOKBACK_DAYS);
+            let prices: Vec<DomainPrice> = repo
+                .get_daily_prices(company_id, start, lp.price_date)
+                .await?
+                .iter()
+                .map(DomainPrice::from)
+                .collect();
+
             // Momentum
             let dates = [
                 ("momentum_1m", 30),
@@ -313,27 +469,53 @@ async fn process_company(
             ];
 
             for (name, days) in dates {
-                let target_date = lp.price_date - chrono::Duration::days(days);
-                let hist_price: Option<DbPrice> = sqlx::query_as!(
-                    DbPrice,
-                    "SELECT * FROM daily_prices WHERE company_id = $1 AND price_date <= $2 ORDER BY price_date DESC LIMIT 1",
+                let target_date =
+                    sessions.session_on_or_before(lp.price_date - chrono::Duration::days(days));
+                let hist_price = prices
+                    .iter()
+                    .rev()
+                    .find(|p| p.date <= target_date && p.close > 0.0);
+
+                if let Some(hp) = hist_price {
+                    let mom = (close / hp.close - 1.0) * 100.0;
+                    insert_metric(
+                        pool,
+                        company_id,
+                        latest_period_end,
+                        &latest_period_type,
+                        name,
+                        mom,
+                    )
+                    .await?;
+                }
+            }
+
+            // Technical analytics over the last year of closes
+            let benchmark = repo
+                .get_daily_prices_by_symbol(&benchmark_symbol(), start, lp.price_date)
+                .await?;
+            let peer_returns = repo
+                .get_sector_peer_returns(
                     company_id,
-                    target_date
+                    lp.price_date - chrono::Duration::days(YEAR_DAYS),
+                    lp.price_date,
                 )
-                .fetch_optional(pool)
                 .await?;
-
-                if let Some(hp) = hist_price {
-                    let hp_close = hp.close.and_then(|v: BigDecimal| v.to_f64()).unwrap_or(0.0);
-                    if hp_close > 0.0 {
-                        let mom = (close / hp_close - 1.0) * 100.0;
+            let benchmark: Vec<DomainPrice> = benchmark.iter().map(DomainPrice::from).collect();
+            if let Some(analytics) = PriceAnalytics::compute(
+                &price_series(&prices),
+                &price_series(&benchmark),
+                &peer_returns,
+            ) {
+                for (name, value) in analytics.metrics() {
+                    if let Some(value) = value {
                         insert_metric(
                             pool,
                             company_id,
                             latest_period_end,
                             &latest_period_type,
                             name,
-                            mom,
+                            value,
                         )
                         .await?;
                     }
@@ -345,6 +527,105 @@ async fn process_company(
     Ok(())
 }
 
+/// Restate a company's fiscal statements of one period type onto calendar
+/// periods and store the registry metrics as `calendar_<period_type>` rows.
+/// Calendar periods the statements don't fully cover are skipped.
+#[allow(clippy::too_many_arguments)]
+async fn store_calendarized_metrics(
+    pool: &PgPool,
+    company_id: uuid::Uuid,
+    calendar: FiscalCalendar,
+    period_type: PeriodType,
+    incomes: &[DomainIncome],
+    balances: &[DomainBalance],
+    cash_flows: &[DomainCashFlow],
+    annual_incomes: &[DomainIncome],
+    annual_cash_flows: &[DomainCashFlow],
+    currency: &str,
+) -> Result<()> {
+    let Some(latest) = incomes.last() else {
+        return Ok(());
+    };
+    let (stored_type, lookback) = match period_type {
+        PeriodType::Quarterly => ("calendar_quarterly", 4),
+        PeriodType::Annual => ("calendar_annual", 1),
+    };
+
+    let calendarizer = Calendarizer::new(calendar, period_type);
+    let targets = calendar_periods(incomes.len() + 1, period_type, latest.period_end_date);
+    let restated = calendarizer.incomes(incomes, &targets);
+    let restated_balances = calendarizer.balances(balances, &targets);
+    let restated_cash_flows = calendarizer.cash_flows(cash_flows, &targets);
+
+    let mut period_incomes = Vec::new();
+    let mut period_prior_incomes = Vec::new();
+    let mut period_balances = Vec::new();
+    let mut period_cash_flows = Vec::new();
+    for (i, income) in restated.iter().enumerate() {
+        let Some(income) = income else {
+            continue;
+        };
+        period_incomes.push(income.clone());
+        period_prior_incomes.push(i.checked_sub(lookback).and_then(|j| restated[j].clone()));
+        period_balances.push(restated_balances[i].clone());
+        period_cash_flows.push(restated_cash_flows[i].clone());
+    }
+    let period_prices = vec![None; period_incomes.len()];
+
+    let annual_calendarizer = Calendarizer::new(calendar, PeriodType::Annual);
+    let annual_targets = annual_incomes
+        .last()
+        .map(|latest| {
+            calendar_periods(
+                annual_incomes.len() + 1,
+                PeriodType::Annual,
+                latest.period_end_date,
+            )
+        })
+        .unwrap_or_default();
+    let (calendar_annual_incomes, calendar_annual_cash_flows): (Vec<_>, Vec<_>) =
+        annual_calendarizer
+            .incomes(annual_incomes, &annual_targets)
+            .into_iter()
+            .zip(annual_calendarizer.cash_flows(annual_cash_flows, &annual_targets))
+            .filter_map(|(income, cash_flow)| Some((income?, cash_flow)))
+            .unzip();
+
+    let inputs = MetricInputs {
+        incomes: &period_incomes,
+        prior_year_incomes: &period_prior_incomes,
+        balances: &period_balances,
+        cash_flows: &period_cash_flows,
+        prices: &period_prices,
+        annual_incomes: &calendar_annual_incomes,
+        annual_cash_flows: &calendar_annual_cash_flows,
+        currency,
+        period_type,
+    };
+
+    for metric in METRIC_REGISTRY {
+        let Some(stored_name) = metric.stored_name else {
+            continue;
+        };
+        let values = (metric.compute)(&inputs);
+        for (income, value) in period_incomes.iter().zip(values) {
+            let date = income.period_end_date;
+            match value.value {
+                Some(val) => {
+                    insert_metric(pool, company_id, date, stored_type, stored_name, val).await?
+                }
+                None => clear_metric(pool, company_id, date, stored_type, stored_name).await?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn select<T: Clone>(items: &[T], indices: &[usize]) -> Vec<T> {
+    indices.iter().map(|&i| items[i].clone()).collect()
+}
+
 async fn insert_metric(
     pool: &PgPool,
     company_id: uuid::Uuid,
@@ -370,3 +651,28 @@ async fn insert_metric(
     .await?;
     Ok(())
 }
+
+/// Null out a stored value the metric no longer yields, e.g. once a missing
+/// prior period or a restatement leaves it undefined, so peer ranking and the
+/// screener stop reading it
+async fn clear_metric(
+    pool: &PgPool,
+    company_id: uuid::Uuid,
+    date: NaiveDate,
+    ptype: &str,
+    name: &str,
+) -> Result<()> {
+    sqlx::query!(
+        "UPDATE derived_metrics
+        SET metric_value = NULL, created_at = NOW()
+        WHERE company_id = $1 AND period_end_date = $2 AND period_type = $3
+          AND metric_name = $4 AND metric_value IS NOT NULL",
+        company_id,
+        date,
+        ptype,
+        name
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -9,9 +9,18 @@ pub trait Job: Send + Sync {
     async fn run(&self, pool: &PgPool) -> Result<()>;
 }
 
+pub mod company_backfill;
+pub use company_backfill::CompanyBackfillJob;
+
 pub mod earnings_poll;
 pub use earnings_poll::EarningsPollingJob;
 
+pub mod fundamentals_refresh;
+pub use fundamentals_refresh::FundamentalsRefreshJob;
+
+pub mod price_partitions;
+pub use price_partitions::PricePartitionJob;
+
 pub mod price_refresh;
 pub use price_refresh::PriceRefreshJob;
 
@@ -0,0 +1,58 @@
+use crate::jobs::Job;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Datelike, Utc};
+use db::repositories::PricePartitionRepository;
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use tracing::info;
+
+/// Years past the current one that get a partition before any price lands
+const PARTITION_YEARS_AHEAD: i32 = 1;
+
+/// Keeps `daily_prices` partitioned by year: creates partitions ahead of time
+/// and splits out any years that have collected in the default partition.
+pub struct PricePartitionJob;
+
+#[async_trait]
+impl Job for PricePartitionJob {
+    fn name(&self) -> &str {
+        "price_partitions"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<()> {
+        info!("Starting price partition maintenance");
+
+        let this_year = Utc::now().year();
+        let mut years: Vec<i32> = (this_year..=this_year + PARTITION_YEARS_AHEAD).collect();
+        years.extend(
+            PricePartitionRepository::new(pool.clone())
+                .default_partition_years()
+                .await?,
+        );
+        let created = ensure_price_partitions(pool, years).await?;
+
+        info!("Price partition maintenance created {} partitions", created);
+        Ok(())
+    }
+}
+
+/// Make sure each year has its own price partition, e.g. for the history a
+/// backfill is about to write. Returns partitions created.
+pub(crate) async fn ensure_price_partitions(
+    pool: &PgPool,
+    years: impl IntoIterator<Item = i32>,
+) -> Result<usize> {
+    let repo = PricePartitionRepository::new(pool.clone());
+    let existing: BTreeSet<i32> = repo.partition_years().await?.into_iter().collect();
+
+    let mut created = 0;
+    for year in years.into_iter().collect::<BTreeSet<_>>() {
+        if !existing.contains(&year) && repo.ensure_year(year).await? {
+            info!("Created daily_prices partition for {}", year);
+            created += 1;
+        }
+    }
+
+    Ok(created)
+}
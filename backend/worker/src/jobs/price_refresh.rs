@@ -1,8 +1,11 @@
+use crate::jobs::price_partitions::ensure_price_partitions;
 use crate::jobs::Job;
 use anyhow::Result;
 use async_trait::async_trait;
-use chrono::{Datelike, NaiveDate, Utc, Weekday};
+use chrono::{Datelike, NaiveDate, Utc};
+use db::repositories::{CompanyRepository, DailyPriceInsert};
 use domain::domain::OutputSize;
+use domain::markets::calendar::TradingCalendars;
 use domain::ports::market_data::MarketDataProvider;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
@@ -21,6 +24,7 @@ pub struct JobResult {
 struct CompanyRow {
     id: Uuid,
     symbol: String,
+    exchange: String,
     shares_outstanding: Option<i64>,
 }
 
@@ -33,12 +37,6 @@ impl PriceRefreshJob {
     pub fn new(db: PgPool, provider: Arc<dyn MarketDataProvider>) -> Self {
         Self { db, provider }
     }
-
-    // Helper to check if a date is a trading day
-    fn is_trading_day(&self, date: NaiveDate) -> bool {
-        let weekday = date.weekday();
-        weekday != Weekday::Sat && weekday != Weekday::Sun
-    }
 }
 
 #[async_trait]
@@ -53,6 +51,11 @@ impl Job for PriceRefreshJob {
         let start_time = Utc::now();
         let job_id = Uuid::new_v4();
 
+        // Without the year's holidays a holiday would look like a missed
+        // session and every company would be refetched
+        let calendars = TradingCalendars::global();
+        calendars.check_coverage(start_time.date_naive())?;
+
         // Track job run
         let _ = sqlx::query(
             "INSERT INTO job_runs (id, job_name, status, started_at) VALUES ($1, $2, $3, $4)",
@@ -69,9 +72,11 @@ impl Job for PriceRefreshJob {
         let mut updated = 0;
         let mut errors = 0;
 
+        let repo = CompanyRepository::new(self.db.clone());
+
         // Fetch active companies
         let companies_result = sqlx::query_as::<_, CompanyRow>(
-            "SELECT id, symbol, shares_outstanding FROM companies WHERE is_active = true",
+            "SELECT id, symbol, exchange, shares_outstanding FROM companies WHERE is_active = true",
         )
         .fetch_all(&self.db)
         .await;
@@ -81,7 +86,6 @@ impl Job for PriceRefreshJob {
                 for company in companies {
                     processed += 1;
 
-                    // Check if we need update
                     // Check if we need update
                     let max_date_result = sqlx::query_scalar::<_, Option<NaiveDate>>(
                         "SELECT MAX(price_date) FROM daily_prices WHERE company_id = $1",
@@ -94,15 +98,12 @@ impl Job for PriceRefreshJob {
                     let should_update = match max_date_result {
                         Ok(Some(last_date)) => {
                             has_existing_data = true;
-                            let today = Utc::now().date_naive();
-
-                            if !self.is_trading_day(today) {
-                                // Skip weekends
-                                false
-                            } else {
-                                // If today is trading day, check if we are behind
-                                last_date < today
-                            }
+                            // Behind only once a session has closed since the
+                            // last stored price; weekends and holidays never are
+                            let last_session = calendars
+                                .for_exchange(&company.exchange)
+                                .last_completed_session(start_time);
+                            last_date < last_session
                         }
                         Ok(None) => true,
                         Err(e) => {
@@ -128,37 +129,32 @@ impl Job for PriceRefreshJob {
                             .await
                         {
                             Ok(prices) => {
-                                // Upsert prices
-                                let mut batch_updated = 0;
-                                for price in prices {
-                                    // Insert
-                                    // Note: daily_prices has volume, but domain DailyPrice might not
-                                    let r = sqlx::query(
-                                         r#"
-                                         INSERT INTO daily_prices (company_id, price_date, open, high, low, close, volume)
-                                         VALUES ($1, $2, $3, $4, $5, $6, NULL) 
-                                         ON CONFLICT (company_id, price_date) 
-                                         DO UPDATE SET open = EXCLUDED.open, high = EXCLUDED.high, low = EXCLUDED.low, close = EXCLUDED.close
-                                         "#
-                                     )
-                                     .bind(company.id)
-                                     .bind(price.date)
-                                     .bind(price.open)
-                                     .bind(price.high)
-                                     .bind(price.low)
-                                     .bind(price.close)
-                                     .execute(&self.db)
-                                     .await;
-
-                                    if let Err(e) = r {
-                                        error!(
-                                            "Failed to upsert price for {}: {}",
+                                let rows: Vec<DailyPriceInsert> = prices
+                                    .into_iter()
+                                    .map(|p| DailyPriceInsert::from_domain(company.id, p))
+                                    .collect();
+                                // A first fetch is the full history, which can
+                                // reach back past every existing partition
+                                if !has_existing_data {
+                                    let years = rows.iter().map(|r| r.price_date.year());
+                                    if let Err(e) = ensure_price_partitions(&self.db, years).await {
+                                        warn!(
+                                            "Failed to create price partitions for {}: {:?}",
                                             company.symbol, e
                                         );
-                                    } else {
-                                        batch_updated += 1;
                                     }
                                 }
+                                let batch_updated = match repo.upsert_daily_prices(&rows).await {
+                                    Ok(written) => written,
+                                    Err(e) => {
+                                        error!(
+                                            "Failed to upsert prices for {}: {}",
+                                            company.symbol, e
+                                        );
+                                        errors += 1;
+                                        0
+                                    }
+                                };
 
                                 if batch_updated > 0 {
                                     updated += 1;
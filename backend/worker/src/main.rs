@@ -4,10 +4,14 @@ use sqlx::postgres::PgPoolOptions;
 use std::env;
 use tracing::{error, info};
 
+use chrono::Utc;
+use db::repositories::CompanyRepository;
+use domain::markets::calendar::TradingCalendars;
 use providers::mock::MockMarketDataProvider;
 use std::sync::Arc;
 use worker::jobs::{
-    DocumentRefresh, EarningsPollingJob, FxRefresh, Job, MetricsRecalculationJob, PriceRefreshJob,
+    CompanyBackfillJob, DocumentRefresh, EarningsPollingJob, FundamentalsRefreshJob, FxRefresh,
+    Job, MetricsRecalculationJob, PricePartitionJob, PriceRefreshJob,
 };
 use worker::scheduler::Scheduler;
 
@@ -29,17 +33,28 @@ struct Args {
     /// Run in scheduler mode (continuous loop)
     #[arg(long)]
     schedule: bool,
+
+    /// Recompute metrics for every active company, not only those whose
+    /// statements or prices changed
+    #[arg(long)]
+    full_rebuild: bool,
 }
 
 #[derive(Debug)]
 struct Config {
     database_url: String,
+    /// Trading calendars file replacing the built-in one
+    trading_calendars_path: Option<String>,
 }
 
 impl Config {
     fn from_env() -> Result<Self> {
         let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
-        Ok(Self { database_url })
+        let trading_calendars_path = env::var("TRADING_CALENDARS_PATH").ok();
+        Ok(Self {
+            database_url,
+            trading_calendars_path,
+        })
     }
 }
 
@@ -72,17 +87,54 @@ async fn main() -> Result<()> {
         .await
         .context("Failed to connect to database")?;
 
+    // The calendars file (or the built-in one), with the timezone and
+    // schedule of each row in the exchanges table applied over it
+    let mut calendars = match &config.trading_calendars_path {
+        Some(path) => {
+            TradingCalendars::from_path(path).context("Failed to load trading calendars")?
+        }
+        None => TradingCalendars::builtin(),
+    };
+    for exchange in CompanyRepository::new(pool.clone())
+        .list_exchanges()
+        .await
+        .context("Failed to load exchanges")?
+    {
+        calendars = calendars
+            .with_exchange(
+                &exchange.code,
+                &exchange.timezone,
+                exchange.trading_days.as_ref(),
+            )
+            .context("Failed to load trading calendars")?;
+    }
+    if let Err(e) = calendars.check_coverage(Utc::now().date_naive()) {
+        error!("{}; the price refresh will fail until they are added", e);
+    }
+    calendars
+        .install()
+        .context("Failed to load trading calendars")?;
+
     // Initialize provider (using Mock for now as per build plan context)
     let provider = Arc::new(MockMarketDataProvider::new());
 
+    let metrics_job = if args.full_rebuild {
+        MetricsRecalculationJob::full_rebuild()
+    } else {
+        MetricsRecalculationJob::new()
+    };
+
     // Helper to create job list
     let create_jobs = |pool: &sqlx::PgPool| -> Vec<Box<dyn Job>> {
         vec![
+            Box::new(PricePartitionJob),
+            Box::new(CompanyBackfillJob::new(pool.clone(), provider.clone())),
             Box::new(EarningsPollingJob::new(pool.clone(), provider.clone())),
+            Box::new(FundamentalsRefreshJob::new(pool.clone(), provider.clone())),
             Box::new(PriceRefreshJob::new(pool.clone(), provider.clone())),
             Box::new(FxRefresh),
             Box::new(DocumentRefresh),
-            Box::new(MetricsRecalculationJob),
+            Box::new(metrics_job),
         ]
     };
 
@@ -1,11 +1,16 @@
 use async_trait::async_trait;
 use bigdecimal::BigDecimal;
-use chrono::{NaiveDate, Utc};
+use chrono::{Datelike, NaiveDate, Utc};
+use db::models::background_job::{COMPANY_BACKFILL_JOB, COMPANY_BACKFILL_STEPS};
+use db::repositories::{
+    BackgroundJobRepository, CompanyInsert, CompanyRepository, DailyPriceInsert,
+};
 use domain::domain::{
     BalanceSheet, CashFlowStatement, CompanyOverview, DailyPrice, EarningsEvent, IncomeStatement,
     OutputSize,
 };
 use domain::error::AppError;
+use domain::markets::calendar::TradingCalendars;
 use domain::ports::market_data::MarketDataProvider;
 use providers::mock::MockMarketDataProvider;
 use sqlx::postgres::PgPoolOptions;
@@ -13,7 +18,10 @@ use std::env;
 use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
-use worker::jobs::{EarningsPollingJob, Job, MetricsRecalculationJob, PriceRefreshJob};
+use worker::jobs::{
+    CompanyBackfillJob, EarningsPollingJob, FundamentalsRefreshJob, Job, MetricsRecalculationJob,
+    PricePartitionJob, PriceRefreshJob,
+};
 
 async fn setup_db() -> sqlx::PgPool {
     let database_url = env::var("DATABASE_URL")
@@ -73,6 +81,46 @@ async fn test_earnings_poll_updates_calendar() {
         "latest_quarter should be updated for {}",
         symbol
     );
+
+    // The report itself is kept for charting
+    let report_dates: Vec<NaiveDate> =
+        sqlx::query_scalar("SELECT report_date FROM earnings_events WHERE company_id = $1")
+            .bind(company_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert!(!report_dates.is_empty());
+
+    // A rescheduled report moves the date of its fiscal period's event
+    let repo = CompanyRepository::new(pool.clone());
+    let report = |report_date: &str, fiscal_date_ending: Option<&str>| EarningsEvent {
+        symbol: symbol.to_string(),
+        name: "PNC".to_string(),
+        report_date: report_date.parse().unwrap(),
+        fiscal_date_ending: fiscal_date_ending.map(|d| d.parse().unwrap()),
+        estimate: None,
+        currency: Some("USD".to_string()),
+    };
+    repo.upsert_earnings_event(company_id, &report("2030-04-20", None))
+        .await
+        .unwrap();
+    repo.upsert_earnings_event(company_id, &report("2030-04-20", Some("2030-03-31")))
+        .await
+        .unwrap();
+    repo.upsert_earnings_event(company_id, &report("2030-04-27", Some("2030-03-31")))
+        .await
+        .unwrap();
+    let report_dates: Vec<NaiveDate> = sqlx::query_scalar(
+        "SELECT report_date FROM earnings_events WHERE company_id = $1 AND report_date >= '2030-01-01'",
+    )
+    .bind(company_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        report_dates,
+        vec![NaiveDate::from_ymd_opt(2030, 4, 27).unwrap()]
+    );
 }
 
 #[tokio::test]
@@ -93,6 +141,127 @@ async fn test_price_refresh_updates_prices() {
         .unwrap();
 
     assert!(count > 0, "daily_prices should have records for {}", symbol);
+
+    // Volume and adjusted close come through from the provider
+    let (volume, adjusted_close): (Option<i64>, Option<BigDecimal>) = sqlx::query_as(
+        "SELECT volume, adjusted_close FROM daily_prices WHERE company_id = $1 AND price_date = '2026-01-15'",
+    )
+    .bind(company_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(volume, Some(4_932_480));
+    assert_eq!(
+        adjusted_close,
+        Some(BigDecimal::from_str("297.95").unwrap())
+    );
+}
+
+#[tokio::test]
+async fn test_price_refresh_skips_companies_current_to_last_session() {
+    let pool = setup_db().await;
+    let nasdaq = TradingCalendars::global().for_exchange("NASDAQ");
+    let last_session = nasdaq.last_completed_session(Utc::now());
+    let previous_session = nasdaq.session_on_or_before(last_session - chrono::Duration::days(1));
+    let repo = CompanyRepository::new(pool.clone());
+
+    let mut companies = Vec::new();
+    for latest in [last_session, previous_session] {
+        let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+        let company_id = seed_company(&pool, &symbol).await;
+        repo.upsert_daily_prices(&[DailyPriceInsert::from_domain(
+            company_id,
+            DailyPrice {
+                date: latest,
+                close: 100.0,
+                ..Default::default()
+            },
+        )])
+        .await
+        .unwrap();
+        companies.push(company_id);
+    }
+
+    let provider = Arc::new(MockMarketDataProvider::new());
+    PriceRefreshJob::new(pool.clone(), provider)
+        .run(&pool)
+        .await
+        .expect("Job failed");
+
+    let count = |company_id: Uuid| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT count(*) FROM daily_prices WHERE company_id = $1")
+                .bind(company_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+    // Current to the last closed session: nothing to fetch
+    assert_eq!(count(companies[0]).await, 1);
+    // A session behind: refreshed from the provider
+    assert!(count(companies[1]).await > 1);
+}
+
+#[tokio::test]
+async fn test_bulk_price_upsert_merges_into_existing_rows() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+    let repo = CompanyRepository::new(pool.clone());
+    let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+    let price = |date, close: f64, volume| {
+        DailyPriceInsert::from_domain(
+            company_id,
+            DailyPrice {
+                date,
+                open: close,
+                high: close,
+                low: close,
+                close,
+                adjusted_close: Some(close),
+                volume: Some(volume),
+            },
+        )
+    };
+
+    // A date repeated within a batch keeps its last row
+    let written = repo
+        .upsert_daily_prices(&[
+            price(day(4), 10.0, 100),
+            price(day(5), 11.0, 200),
+            price(day(5), 12.0, 300),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(written, 2);
+
+    let written = repo
+        .upsert_daily_prices(&[price(day(4), 10.5, 150), price(day(6), 13.0, 400)])
+        .await
+        .unwrap();
+    assert_eq!(written, 2);
+
+    let rows: Vec<(NaiveDate, BigDecimal, i64)> = sqlx::query_as(
+        "SELECT price_date, close, volume FROM daily_prices WHERE company_id = $1 ORDER BY price_date",
+    )
+    .bind(company_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let rows: Vec<(NaiveDate, String, i64)> = rows
+        .into_iter()
+        .map(|(d, c, v)| (d, c.normalized().to_string(), v))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (day(4), "10.5".to_string(), 150),
+            (day(5), "12".to_string(), 300),
+            (day(6), "13".to_string(), 400),
+        ]
+    );
 }
 
 #[tokio::test]
@@ -117,6 +286,138 @@ async fn test_price_refresh_updates_market_cap() {
     assert!(market_cap.unwrap() > 0, "market_cap should be positive");
 }
 
+#[tokio::test]
+async fn test_price_partition_job_moves_years_out_of_default_partition() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+
+    // Years without a partition of their own land in the default partition
+    for date in ["1998-06-01", "2041-06-01"] {
+        sqlx::query(
+            "INSERT INTO daily_prices (company_id, price_date, close) VALUES ($1, $2::date, 10)",
+        )
+        .bind(company_id)
+        .bind(date)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    PricePartitionJob.run(&pool).await.expect("Job failed");
+
+    let partitions: Vec<(NaiveDate, String)> = sqlx::query_as(
+        "SELECT price_date, tableoid::regclass::text FROM daily_prices WHERE company_id = $1 ORDER BY price_date",
+    )
+    .bind(company_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        partitions,
+        vec![
+            (
+                NaiveDate::from_ymd_opt(1998, 6, 1).unwrap(),
+                "daily_prices_1998".to_string()
+            ),
+            (
+                NaiveDate::from_ymd_opt(2041, 6, 1).unwrap(),
+                "daily_prices_2041".to_string()
+            ),
+        ]
+    );
+
+    // Next year's partition exists before any of its prices do
+    let next_year: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(format!("daily_prices_{}", Utc::now().year() + 1))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(next_year);
+}
+
+#[tokio::test]
+async fn test_company_backfill_ingests_history_and_records_steps() {
+    let pool = setup_db().await;
+    let company = CompanyRepository::new(pool.clone())
+        .create(CompanyInsert {
+            symbol: format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase()),
+            exchange: "NYSE".to_string(),
+            name: "Backfill Inc".to_string(),
+            fiscal_year_end_month: Some(12),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let jobs = BackgroundJobRepository::new(pool.clone());
+    let queued = jobs
+        .enqueue(
+            COMPANY_BACKFILL_JOB,
+            Some(company.id),
+            &COMPANY_BACKFILL_STEPS,
+        )
+        .await
+        .unwrap();
+
+    let provider = Arc::new(MockMarketDataProvider::new());
+    let job = CompanyBackfillJob::new(pool.clone(), provider);
+    job.run(&pool).await.expect("Job failed");
+
+    let finished = jobs.find_by_id(queued.id).await.unwrap().unwrap();
+    // Without a document provider the documents step fails the job
+    assert_eq!(finished.status, "failed");
+    assert_eq!(
+        finished.error_message.as_deref(),
+        Some("Failed steps: documents")
+    );
+    let statuses: Vec<(String, String)> = finished
+        .steps()
+        .into_iter()
+        .map(|s| (s.step, s.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("statements".to_string(), "completed".to_string()),
+            ("prices".to_string(), "completed".to_string()),
+            ("documents".to_string(), "failed".to_string()),
+        ]
+    );
+
+    let statements: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM income_statements WHERE company_id = $1 AND period_type = 'annual' AND fiscal_year IS NOT NULL",
+    )
+    .bind(company.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let quarters: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM income_statements WHERE company_id = $1 AND period_type = 'quarterly' AND fiscal_quarter IS NOT NULL",
+    )
+    .bind(company.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let prices: i64 = sqlx::query_scalar("SELECT count(*) FROM daily_prices WHERE company_id = $1")
+        .bind(company.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(
+        statements > 0,
+        "annual income statements should be ingested"
+    );
+    assert!(
+        quarters > 0,
+        "quarterly income statements should be ingested"
+    );
+    assert!(prices > 0, "price history should be ingested");
+    assert_eq!(
+        finished.records_updated.unwrap_or(0),
+        finished.steps().iter().map(|s| s.records).sum::<i32>()
+    );
+}
+
 #[tokio::test]
 async fn test_metrics_recalc_creates_derived_metrics() {
     let pool = setup_db().await;
@@ -145,7 +446,7 @@ async fn test_metrics_recalc_creates_derived_metrics() {
         .await
         .unwrap();
 
-    let job = MetricsRecalculationJob;
+    let job = MetricsRecalculationJob::new();
     job.run(&pool).await.expect("Job failed");
 
     let count: i64 =
@@ -158,6 +459,387 @@ async fn test_metrics_recalc_creates_derived_metrics() {
     assert!(count > 0, "derived_metrics should be created");
 }
 
+#[tokio::test]
+async fn test_metrics_recalc_only_processes_changed_companies() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+
+    sqlx::query(
+        "INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue, net_income) VALUES ($1, $2, '2023-12-31', 'annual', 1000000, 100000)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let queued = || {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM metrics_recalc_queue WHERE company_id = $1)",
+            )
+            .bind(company_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    let metric_count = || {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT count(*) FROM derived_metrics WHERE company_id = $1",
+            )
+            .bind(company_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    assert!(queued().await, "a new statement should mark the company");
+
+    MetricsRecalculationJob::new()
+        .run(&pool)
+        .await
+        .expect("Job failed");
+    assert!(!queued().await);
+    assert!(metric_count().await > 0);
+
+    // Unchanged companies are skipped unless a full rebuild is asked for
+    sqlx::query("DELETE FROM derived_metrics WHERE company_id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    MetricsRecalculationJob::new()
+        .run(&pool)
+        .await
+        .expect("Job failed");
+    assert_eq!(metric_count().await, 0);
+
+    MetricsRecalculationJob::full_rebuild()
+        .run(&pool)
+        .await
+        .expect("Job failed");
+    assert!(metric_count().await > 0);
+
+    // Bulk-loaded prices mark it again
+    let repo = CompanyRepository::new(pool.clone());
+    repo.upsert_daily_prices(&[DailyPriceInsert::from_domain(
+        company_id,
+        DailyPrice {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            open: 0.0,
+            high: 0.0,
+            low: 0.0,
+            close: 150.0,
+            adjusted_close: None,
+            volume: None,
+        },
+    )])
+    .await
+    .unwrap();
+    assert!(queued().await, "new prices should mark the company");
+}
+
+#[tokio::test]
+async fn test_metrics_recalc_persists_multi_year_growth() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+
+    // Revenue compounding at 10% a year from 2019 to 2023
+    for (year, revenue) in [
+        (2019, "1000"),
+        (2020, "1100"),
+        (2021, "1210"),
+        (2022, "1331"),
+        (2023, "1464.1"),
+    ] {
+        sqlx::query(
+            "INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue) VALUES ($1, $2, $3, 'annual', $4)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(company_id)
+        .bind(NaiveDate::from_ymd_opt(year, 12, 31).unwrap())
+        .bind(BigDecimal::from_str(revenue).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let job = MetricsRecalculationJob::new();
+    job.run(&pool).await.expect("Job failed");
+
+    let cagr: BigDecimal = sqlx::query_scalar(
+        "SELECT metric_value FROM derived_metrics WHERE company_id = $1 AND metric_name = 'revenue_cagr_3y_pct' AND period_end_date = '2023-12-31'",
+    )
+    .bind(company_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(cagr.round(2), BigDecimal::from_str("10.00").unwrap());
+
+    let positive_years: BigDecimal = sqlx::query_scalar(
+        "SELECT metric_value FROM derived_metrics WHERE company_id = $1 AND metric_name = 'positive_growth_years_5y' AND period_end_date = '2023-12-31'",
+    )
+    .bind(company_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(positive_years, BigDecimal::from(4));
+}
+
+#[tokio::test]
+async fn test_metrics_recalc_persists_expansion_and_acceleration_ratios() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+
+    // YoY growth goes from 10% to 21% while the operating margin goes from
+    // 10% to 12%
+    for (year, month, day, revenue, operating_income) in [
+        (2022, 3, 31, "100", "10"),
+        (2022, 6, 30, "100", "10"),
+        (2022, 9, 30, "100", "10"),
+        (2022, 12, 31, "100", "10"),
+        (2023, 3, 31, "110", "11"),
+        (2023, 6, 30, "121", "14.52"),
+    ] {
+        sqlx::query(
+            "INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue, operating_income) VALUES ($1, $2, $3, 'quarterly', $4, $5)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(company_id)
+        .bind(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+        .bind(BigDecimal::from_str(revenue).unwrap())
+        .bind(BigDecimal::from_str(operating_income).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let job = MetricsRecalculationJob::new();
+    job.run(&pool).await.expect("Job failed");
+
+    for (metric_name, expected) in [
+        ("growth_acceleration_ratio_pct", "110.00"),
+        ("op_margin_expansion_bps", "200.00"),
+        ("op_margin_expansion_pct", "20.00"),
+    ] {
+        let value: BigDecimal = sqlx::query_scalar(
+            "SELECT metric_value FROM derived_metrics WHERE company_id = $1 AND metric_name = $2 AND period_end_date = '2023-06-30'",
+        )
+        .bind(company_id)
+        .bind(metric_name)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            value.round(2),
+            BigDecimal::from_str(expected).unwrap(),
+            "{}",
+            metric_name
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_metrics_recalc_skips_sequential_metrics_across_missing_quarters() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+
+    // Q2 2023 was never reported
+    for (month, day, revenue) in [(3, 31, "100"), (9, 30, "120"), (12, 31, "150")] {
+        sqlx::query(
+            "INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue) VALUES ($1, $2, $3, 'quarterly', $4)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(company_id)
+        .bind(NaiveDate::from_ymd_opt(2023, month, day).unwrap())
+        .bind(BigDecimal::from_str(revenue).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    // Computed across the gap before sequential metrics checked adjacency
+    sqlx::query(
+        "INSERT INTO derived_metrics (company_id, period_end_date, period_type, metric_name, metric_value) VALUES ($1, '2023-09-30', 'quarterly', 'qoq_revenue_growth_pct', 20)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let job = MetricsRecalculationJob::new();
+    job.run(&pool).await.expect("Job failed");
+
+    let qoq: Vec<(NaiveDate, Option<BigDecimal>)> = sqlx::query_as(
+        "SELECT period_end_date, metric_value FROM derived_metrics WHERE company_id = $1 AND metric_name = 'qoq_revenue_growth_pct' ORDER BY period_end_date",
+    )
+    .bind(company_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        qoq,
+        vec![
+            (NaiveDate::from_ymd_opt(2023, 9, 30).unwrap(), None),
+            (
+                NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+                Some(BigDecimal::from_str("25").unwrap())
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_metrics_recalc_leaves_periods_without_a_session_close_unpriced() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+
+    // Both fiscal years end on a Friday session; only the first one's close
+    // was stored
+    for date in ["2022-12-30", "2023-12-29"] {
+        sqlx::query(
+            "INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue, basic_eps) VALUES ($1, $2, $3, 'annual', 1000, 5)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(company_id)
+        .bind(NaiveDate::from_str(date).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query(
+        "INSERT INTO daily_prices (company_id, price_date, close) VALUES ($1, '2022-12-30', 100)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let job = MetricsRecalculationJob::new();
+    job.run(&pool).await.expect("Job failed");
+
+    // The 2022 close is a year out of date for FY2023
+    let priced: Vec<NaiveDate> = sqlx::query_scalar(
+        "SELECT period_end_date FROM derived_metrics WHERE company_id = $1 AND metric_name = 'pe_ratio_historical'",
+    )
+    .bind(company_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(priced, vec![NaiveDate::from_ymd_opt(2022, 12, 30).unwrap()]);
+}
+
+#[tokio::test]
+async fn test_metrics_recalc_persists_price_analytics() {
+    let pool = setup_db().await;
+    let repo = CompanyRepository::new(pool.clone());
+    let sector_id: Uuid = sqlx::query_scalar("INSERT INTO sectors (name) VALUES ($1) RETURNING id")
+        .bind(format!("Sector {}", Uuid::new_v4()))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    let mut companies = Vec::new();
+    for _ in 0..2 {
+        let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+        let company_id = seed_company(&pool, &symbol).await;
+        sqlx::query("UPDATE companies SET sector_id = $1 WHERE id = $2")
+            .bind(sector_id)
+            .bind(company_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        companies.push(company_id);
+    }
+    let (company_id, peer_id) = (companies[0], companies[1]);
+    sqlx::query(
+        "INSERT INTO income_statements (id, company_id, period_end_date, period_type, total_revenue) VALUES ($1, $2, '2024-12-31', 'annual', 1000)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // The benchmark oscillates and the company moves twice as much in log
+    // terms, while its peer is up 10% over the year
+    sqlx::query(
+        "INSERT INTO companies (symbol, name, exchange, is_active) VALUES ('SPY', 'SPDR S&P 500 ETF', 'NYSE', true) ON CONFLICT (symbol, exchange) DO NOTHING",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let benchmark_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM companies WHERE symbol = 'SPY' ORDER BY is_active DESC, created_at LIMIT 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let days = 400;
+    let last = days - 1;
+    let year_ago = last - 365;
+    let market = |i: i64| 100.0 + 10.0 * (i as f64 / 7.0).sin();
+    let row = |company_id, i: i64, close: f64| {
+        DailyPriceInsert::from_domain(
+            company_id,
+            DailyPrice {
+                date: start + chrono::Duration::days(i),
+                close,
+                ..Default::default()
+            },
+        )
+    };
+    let mut rows = Vec::new();
+    for i in 0..days {
+        rows.push(row(benchmark_id, i, market(i)));
+        rows.push(row(company_id, i, (market(i) / 100.0).powi(2) * 50.0));
+        rows.push(row(peer_id, i, if i <= year_ago { 100.0 } else { 110.0 }));
+    }
+    repo.upsert_daily_prices(&rows).await.unwrap();
+    let expected_return = (market(last) / market(year_ago)).powi(2) - 1.0;
+
+    MetricsRecalculationJob::new()
+        .run(&pool)
+        .await
+        .expect("Job failed");
+
+    let metric = |name: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let value: BigDecimal = sqlx::query_scalar(
+                "SELECT metric_value FROM derived_metrics WHERE company_id = $1 AND metric_name = $2 AND period_end_date = '2024-12-31'",
+            )
+            .bind(company_id)
+            .bind(name)
+            .fetch_one(&pool)
+            .await
+            .unwrap_or_else(|_| panic!("{} should be stored", name));
+            value.to_string().parse::<f64>().unwrap()
+        }
+    };
+    assert!((metric("beta_1y").await - 2.0).abs() < 0.001);
+    let relative_strength = ((1.0 + expected_return) / 1.1 - 1.0) * 100.0;
+    assert!((metric("relative_strength_sector_1y_pct").await - relative_strength).abs() < 0.001);
+    assert!(metric("volatility_1y_pct").await > 0.0);
+    assert!(metric("max_drawdown_1y_pct").await < 0.0);
+    assert!(metric("pct_from_52w_high").await <= 0.0);
+    for name in [
+        "price_vs_sma_50_pct",
+        "price_vs_sma_200_pct",
+        "pct_from_52w_low",
+    ] {
+        metric(name).await;
+    }
+}
+
 #[tokio::test]
 async fn test_job_records_success_in_database() {
     let pool = setup_db().await;
@@ -203,6 +885,24 @@ impl MarketDataProvider for FailingProvider {
     async fn get_cash_flow(&self, _symbol: &str) -> Result<Vec<CashFlowStatement>, AppError> {
         Err(AppError::InternalError("Provider error".into()))
     }
+    async fn get_quarterly_income_statement(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<IncomeStatement>, AppError> {
+        Err(AppError::InternalError("Provider error".into()))
+    }
+    async fn get_quarterly_balance_sheet(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<BalanceSheet>, AppError> {
+        Err(AppError::InternalError("Provider error".into()))
+    }
+    async fn get_quarterly_cash_flow(
+        &self,
+        _symbol: &str,
+    ) -> Result<Vec<CashFlowStatement>, AppError> {
+        Err(AppError::InternalError("Provider error".into()))
+    }
     async fn get_daily_prices(
         &self,
         _symbol: &str,
@@ -215,6 +915,267 @@ impl MarketDataProvider for FailingProvider {
     }
 }
 
+/// Serves mock statements for one symbol only, so a job run over every
+/// company leaves other tests' companies untouched
+struct SingleSymbolProvider {
+    symbol: String,
+    inner: MockMarketDataProvider,
+}
+
+impl SingleSymbolProvider {
+    fn check(&self, symbol: &str) -> Result<(), AppError> {
+        if symbol == self.symbol {
+            Ok(())
+        } else {
+            Err(AppError::InternalError(format!(
+                "Unknown symbol {}",
+                symbol
+            )))
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for SingleSymbolProvider {
+    async fn get_company_overview(&self, symbol: &str) -> Result<CompanyOverview, AppError> {
+        self.check(symbol)?;
+        self.inner.get_company_overview(symbol).await
+    }
+    async fn get_income_statement(&self, symbol: &str) -> Result<Vec<IncomeStatement>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_income_statement(symbol).await
+    }
+    async fn get_balance_sheet(&self, symbol: &str) -> Result<Vec<BalanceSheet>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_balance_sheet(symbol).await
+    }
+    async fn get_cash_flow(&self, symbol: &str) -> Result<Vec<CashFlowStatement>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_cash_flow(symbol).await
+    }
+    async fn get_quarterly_income_statement(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<IncomeStatement>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_quarterly_income_statement(symbol).await
+    }
+    async fn get_quarterly_balance_sheet(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<BalanceSheet>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_quarterly_balance_sheet(symbol).await
+    }
+    async fn get_quarterly_cash_flow(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<CashFlowStatement>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_quarterly_cash_flow(symbol).await
+    }
+    async fn get_daily_prices(
+        &self,
+        symbol: &str,
+        output_size: OutputSize,
+    ) -> Result<Vec<DailyPrice>, AppError> {
+        self.check(symbol)?;
+        self.inner.get_daily_prices(symbol, output_size).await
+    }
+    async fn get_earnings_calendar(&self) -> Result<Vec<EarningsEvent>, AppError> {
+        self.inner.get_earnings_calendar().await
+    }
+}
+
+#[tokio::test]
+async fn test_fundamentals_refresh_ingests_new_or_stale_statements() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+    sqlx::query("UPDATE companies SET latest_quarter = '2024-12-31' WHERE id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let provider = Arc::new(SingleSymbolProvider {
+        symbol: symbol.clone(),
+        inner: MockMarketDataProvider::new(),
+    });
+    let job = FundamentalsRefreshJob::new(pool.clone(), provider);
+    let count = |table: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>(&format!(
+                "SELECT count(*) FROM {} WHERE company_id = $1 AND known_to IS NULL",
+                table
+            ))
+            .bind(company_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+
+    // Never fetched: all three statements are stored and metrics follow
+    job.run(&pool).await.expect("Job failed");
+    let incomes = count("income_statements").await;
+    assert!(incomes > 0);
+    assert!(count("balance_sheets").await > 0);
+    assert!(count("cash_flow_statements").await > 0);
+    let metrics: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM derived_metrics WHERE company_id = $1")
+            .bind(company_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(
+        metrics > 0,
+        "metrics should be recalculated for the company"
+    );
+
+    // Fresh and no new quarter: skipped
+    sqlx::query("DELETE FROM income_statements WHERE company_id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    job.run(&pool).await.expect("Job failed");
+    assert_eq!(count("income_statements").await, 0);
+
+    // A newer quarter was reported since the last fetch
+    sqlx::query("UPDATE companies SET latest_quarter = '2025-03-31' WHERE id = $1")
+        .bind(company_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    job.run(&pool).await.expect("Job failed");
+    assert_eq!(count("income_statements").await, incomes);
+}
+
+#[tokio::test]
+async fn test_ingested_statements_keep_full_detail() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+    let provider = Arc::new(SingleSymbolProvider {
+        symbol: symbol.clone(),
+        inner: MockMarketDataProvider::new(),
+    });
+    FundamentalsRefreshJob::new(pool.clone(), provider)
+        .run(&pool)
+        .await
+        .expect("Job failed");
+
+    // Read back through the domain conversions the metrics are computed from
+    let repo = CompanyRepository::new(pool.clone());
+    let fy2024 = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+    let income: IncomeStatement = repo
+        .get_income_statements(company_id, "annual", 10)
+        .await
+        .unwrap()
+        .iter()
+        .find(|i| i.period_end_date == fy2024)
+        .expect("FY2024 income statement")
+        .into();
+    assert_eq!(income.ebitda, Some(BigDecimal::from(12_176_000_000i64)));
+    assert_eq!(
+        income.income_tax_expense,
+        Some(BigDecimal::from(-218_000_000i64))
+    );
+
+    let balance: BalanceSheet = repo
+        .get_balance_sheets(company_id, "annual", 10)
+        .await
+        .unwrap()
+        .iter()
+        .find(|b| b.period_end_date == fy2024)
+        .expect("FY2024 balance sheet")
+        .into();
+    assert_eq!(balance.inventory, Some(BigDecimal::from(1_289_000_000i64)));
+    assert_eq!(
+        balance.accounts_receivable,
+        Some(BigDecimal::from(14_010_000_000i64))
+    );
+    assert_eq!(balance.goodwill, Some(BigDecimal::from(60_706_000_000i64)));
+    assert!(balance.total_debt.is_some(), "computed by the database");
+
+    let cash_flow: CashFlowStatement = repo
+        .get_cash_flow_statements(company_id, "annual", 10)
+        .await
+        .unwrap()
+        .iter()
+        .find(|c| c.period_end_date == fy2024)
+        .expect("FY2024 cash flow statement")
+        .into();
+    assert_eq!(
+        cash_flow.dividend_payout,
+        Some(BigDecimal::from(6_147_000_000i64))
+    );
+    // Reported as "None" by the provider
+    assert_eq!(cash_flow.stock_repurchase, None);
+}
+
+#[tokio::test]
+async fn test_fundamentals_refresh_persists_working_capital_and_yield_metrics() {
+    let pool = setup_db().await;
+    let symbol = format!("T-{}", Uuid::new_v4().to_string()[..8].to_uppercase());
+    let company_id = seed_company(&pool, &symbol).await;
+    let provider = Arc::new(SingleSymbolProvider {
+        symbol: symbol.clone(),
+        inner: MockMarketDataProvider::new(),
+    });
+    sqlx::query(
+        "INSERT INTO daily_prices (company_id, price_date, close) VALUES ($1, '2024-12-31', 200)",
+    )
+    .bind(company_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    FundamentalsRefreshJob::new(pool.clone(), provider)
+        .run(&pool)
+        .await
+        .expect("Job failed");
+
+    let metric = |name: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, BigDecimal>(
+                "SELECT metric_value FROM derived_metrics WHERE company_id = $1 AND period_type = 'annual' AND period_end_date = '2024-12-31' AND metric_name = $2",
+            )
+            .bind(company_id)
+            .bind(name)
+            .fetch_one(&pool)
+            .await
+            .unwrap_or_else(|e| panic!("{} not stored: {}", name, e))
+            .to_string()
+            .parse::<f64>()
+            .unwrap()
+        }
+    };
+
+    // FY2024: receivables 14.01B on revenue 62.753B; payables 4.032B and
+    // inventory 1.289B on cost of revenue 27.201B
+    let dso = 14_010.0 / 62_753.0 * 365.0;
+    let dio = 1_289.0 / 27_201.0 * 365.0;
+    let dpo = 4_032.0 / 27_201.0 * 365.0;
+    assert!((metric("dso_days").await - dso).abs() < 0.01);
+    assert!((metric("dio_days").await - dio).abs() < 0.01);
+    assert!((metric("dpo_days").await - dpo).abs() < 0.01);
+    assert!((metric("cash_conversion_cycle_days").await - (dso + dio - dpo)).abs() < 0.01);
+    // Net income 6.023B against operating cash flow 13.445B on 137.175B of assets
+    let accruals = (6_023.0 - 13_445.0) / 137_175.0 * 100.0;
+    assert!((metric("accruals_ratio_pct").await - accruals).abs() < 0.01);
+
+    // Capex 1.685B and dividends 6.147B; no buybacks reported. Shares come
+    // from the balance sheet: 937.2M at 200.
+    let capex = 1_685.0 / 13_445.0 * 100.0;
+    assert!((metric("capex_ocf_pct").await - capex).abs() < 0.01);
+    let dividend_yield = 6_147.0 / (937.2 * 200.0) * 100.0;
+    assert!((metric("dividend_yield_pct").await - dividend_yield).abs() < 0.01);
+    assert!((metric("shareholder_yield_pct").await - dividend_yield).abs() < 0.01);
+}
+
 #[tokio::test]
 async fn test_job_handles_provider_errors_gracefully() {
     let pool = setup_db().await;